@@ -246,7 +246,7 @@ export type Edge = { from_id: string, to_id: string, from_pt: [number, number],
 /**
  * A renderable element in the scene
  */
-export type Element = { "Rect": Rect } | { "Circle": Circle } | { "Ellipse": Ellipse } | { "Line": Line } | { "Path": Path } | { "Polygon": Polygon } | { "Text": TextShape } | { "Image": ImageShape } | { "Diamond": Diamond } | { "Node": GraphNodeShape } | { "Edge": Edge } | { "Group": [Array<Element>, string | null] } | { "Graph": GraphContainer } | { "Use": Use };
+export type Element = { "Rect": Rect } | { "Circle": Circle } | { "Ellipse": Ellipse } | { "Line": Line } | { "Path": Path } | { "Polygon": Polygon } | { "Text": TextShape } | { "Image": ImageShape } | { "Diamond": Diamond } | { "Node": GraphNodeShape } | { "Edge": Edge } | { "Group": [Array<Element>, string | null, number] } | { "Graph": GraphContainer } | { "Use": Use };
 
 /**
  * Container for graph elements with layout info
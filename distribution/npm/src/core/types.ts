@@ -36,6 +36,18 @@ export function getSizePixels(size: CanvasSize): { width: number; height: number
   return CANVAS_SIZES[size];
 }
 
+/**
+ * Pixel dimensions for a size and `w:h` aspect ratio; the size tier is the shorter
+ * side and the longer one is rounded half up (mirrors Rust `AstCanvas::width`/`height`)
+ */
+export function canvasDimensions(size: CanvasSize, aspect: { w: number; h: number } = { w: 1, h: 1 }): { width: number; height: number } {
+  const base = (CANVAS_SIZES[size] ?? CANVAS_SIZES.medium).width;
+  const { w, h } = aspect;
+  return w >= h
+    ? { width: Math.floor((base * w + Math.floor(h / 2)) / h), height: base }
+    : { width: base, height: Math.floor((base * h + Math.floor(w / 2)) / w) };
+}
+
 /** Check if a string is a valid canvas size */
 export function isValidSize(name: string): name is CanvasSize {
   return name in CANVAS_SIZES;
@@ -32,6 +32,9 @@ function toWasmStyle(style: Record<string, unknown>): WasmStyle {
     opacity: (style.opacity as number) ?? 1,
     corner: (style.corner as number) ?? 0,
     filter: style.filter as string | undefined,
+    dash: (style.dash as number[] | undefined)?.length ? (style.dash as number[]).join(' ') : undefined,
+    dash_offset: (style.dashOffset as number) ?? 0,
+    fill_rule: style.fillRule as string | undefined,
   };
 }
 
@@ -61,7 +64,10 @@ function buildTransform(tf: Record<string, unknown>): string | undefined {
   }
   if (tf.scale) {
     const [sx, sy] = tf.scale as [number, number];
-    parts.push(`scale(${sx},${sy})`);
+    // Scale about the origin so negative factors mirror in place
+    const [ox, oy] = (tf.origin as [number, number] | undefined) ?? [0, 0];
+    if (ox || oy) parts.push(`translate(${ox},${oy}) scale(${sx},${sy}) translate(${-ox},${-oy})`);
+    else parts.push(`scale(${sx},${sy})`);
   }
   return parts.length ? parts.join(' ') : undefined;
 }
@@ -111,12 +117,17 @@ export function renderShapeWasm(s: ShapeDict, wasm: WasmCore, offset: [number, n
       const weight = (style.fontWeight as string) ?? 'normal';
       const anchor = (style.textAnchor as string) ?? 'start';
       const fill = (style.fill as string) ?? '#000';
-      return wasm.render_text(x, y, content, font, size, weight, anchor, fill, tf);
+      const baseline = style.baseline as string | undefined;
+      const decoration = style.decoration as string | undefined;
+      const direction = style.direction as string | undefined;
+      const letterSpacing = style.letterSpacing as number | undefined;
+      const wordSpacing = style.wordSpacing as number | undefined;
+      return wasm.render_text(x, y, content, font, size, weight, anchor, fill, tf, baseline, decoration, direction, letterSpacing, wordSpacing);
     }
     case 'image': {
       const [w, h] = (props.size as [number, number]) ?? [100, 100];
       const href = (props.href as string) ?? '';
-      return wasm.render_image(x, y, w, h, href, tf);
+      return wasm.render_image(x, y, w, h, href, tf, props.fit as string | undefined);
     }
     case 'group': {
       const inner = children.map(c => renderShapeWasm(c, wasm)).join('');
@@ -56,13 +56,19 @@ export type { CanvasOptions, CanvasFit } from './canvas';
 // ─────────────────────────────────────────────────────────────────────────────
 
 import { getWasm, tryGetWasm, initWasm } from './wasm/bridge';
-import { renderShapeWasm, renderSceneWasm, type ShapeDict } from './core/wasm-renderer';
-import { CANVAS_SIZES, type Canvas, type CanvasSize } from './core/types';
+import { renderGradientWasm, renderShapeWasm, renderSceneWasm, type ShapeDict } from './core/wasm-renderer';
+import { canvasDimensions, isValidSize, type Canvas } from './core/types';
 
 /** Parsed AST node (from Rust parser) */
 export interface AstNode {
   Scene?: AstNode[];
-  Canvas?: { size: string; fill: string };
+  Canvas?: {
+    size: string;
+    aspect?: { w: number; h: number };
+    fill: string;
+    gradient?: { gtype: string; from: string; to: string; angle: number } | null;
+    image?: string | null;
+  };
   Shape?: AstShape;
   Variable?: { name: string; value: unknown };
 }
@@ -87,6 +93,11 @@ export interface AstStyle {
   font_size: number;
   font_weight: string;
   text_anchor: string;
+  letter_spacing?: number;
+  word_spacing?: number;
+  dash?: number[];
+  dash_offset?: number;
+  fill_rule?: string;
 }
 
 export interface AstTransform {
@@ -94,9 +105,11 @@ export interface AstTransform {
   rotate: number;
   scale?: [number, number];
   origin?: [number, number];
+  origin_pct?: [number, number];
 }
 
 export interface ParseResult {
+  version: number;
   ast: AstNode;
   errors: Array<{ message: string; line: number; col: number }>;
 }
@@ -120,6 +133,14 @@ export function parseAst(source: string): AstNode {
   return JSON.parse(json) as AstNode;
 }
 
+/**
+ * Load persisted AST JSON. Blobs from a newer format version come back with an error.
+ */
+export function loadAst(json: string): ParseResult {
+  const wasm = getWasm();
+  return JSON.parse(wasm.load_ast(json)) as ParseResult;
+}
+
 /**
  * Tokenize DSL source.
  */
@@ -146,12 +167,18 @@ function astShapeToDict(shape: AstShape): ShapeDict {
       fontSize: shape.style.font_size,
       fontWeight: shape.style.font_weight,
       textAnchor: shape.style.text_anchor,
+      letterSpacing: shape.style.letter_spacing,
+      wordSpacing: shape.style.word_spacing,
+      dash: shape.style.dash,
+      dashOffset: shape.style.dash_offset,
+      fillRule: shape.style.fill_rule ?? undefined,
     },
     transform: {
       translate: shape.transform.translate ?? undefined,
       rotate: shape.transform.rotate,
       scale: shape.transform.scale ?? undefined,
       origin: shape.transform.origin ?? undefined,
+      originPct: shape.transform.origin_pct ?? undefined,
     },
     children: shape.children.map(astShapeToDict),
   };
@@ -180,6 +207,7 @@ export function render(source: string): string {
   
   // Extract canvas and shapes from AST
   let canvas: Canvas = { size: 'medium', fill: '#fff', width: 64, height: 64 };
+  let defs = '';
   const shapes: ShapeDict[] = [];
   
   const nodes = ast.Scene ?? [ast];
@@ -187,8 +215,10 @@ export function render(source: string): string {
     if (node.Canvas) {
       // Map AstCanvas to Canvas with computed dimensions
       const astCanvas = node.Canvas;
-      const dims = CANVAS_SIZES[astCanvas.size.toLowerCase() as CanvasSize] ?? { width: 64, height: 64 };
-      canvas = { size: astCanvas.size.toLowerCase() as CanvasSize, fill: astCanvas.fill, ...dims };
+      const size = astCanvas.size.toLowerCase();
+      const dims = isValidSize(size) ? canvasDimensions(size, astCanvas.aspect) : { width: 64, height: 64 };
+      canvas = { size: isValidSize(size) ? size : 'medium', fill: astCanvas.fill, ...dims };
+      defs = backgroundDefs(wasm, canvas, astCanvas.gradient, astCanvas.image);
     } else if (node.Shape) {
       shapes.push(astShapeToDict(node.Shape) as ShapeDict);
     }
@@ -198,7 +228,31 @@ export function render(source: string): string {
   const elementsSvg = shapes.map(s => renderShapeWasm(s, wasm)).join('');
   
   // Generate full SVG
-  return renderSceneWasm(wasm, canvas, '', elementsSvg);
+  return renderSceneWasm(wasm, canvas, defs, elementsSvg);
+}
+
+/** Background gradient/image defs; points canvas.fill at the topmost paint (mirrors Scene::background_defs) */
+function backgroundDefs(
+  wasm: ReturnType<typeof getWasm>,
+  canvas: Canvas,
+  gradient?: { gtype: string; from: string; to: string; angle: number } | null,
+  image?: string | null
+): string {
+  let defs = '';
+  if (gradient) {
+    const type = gradient.gtype === 'radial' ? 'radial' : 'linear';
+    defs += renderGradientWasm(wasm, 'canvas-gradient', { type, from: gradient.from, to: gradient.to, angle: gradient.angle });
+    canvas.fill = 'url(#canvas-gradient)';
+  }
+  if (image) {
+    const { width: w, height: h } = canvas;
+    const href = image.replace(/&/g, '&amp;').replace(/</g, '&lt;').replace(/>/g, '&gt;').replace(/"/g, '&quot;');
+    defs += `<pattern id="canvas-image" patternUnits="userSpaceOnUse" width="${w}" height="${h}">`
+      + `<rect width="${w}" height="${h}" fill="${canvas.fill}"/>`
+      + `<image width="${w}" height="${h}" href="${href}" preserveAspectRatio="xMidYMid slice"/></pattern>`;
+    canvas.fill = 'url(#canvas-image)';
+  }
+  return defs;
 }
 
 /**
@@ -284,6 +284,12 @@ describe('WASM Bridge', () => {
       expect(anchors.to).toHaveLength(2);
     });
 
+    it('compute_edge_anchors sides mode picks adjacent sides for diagonals', () => {
+      const anchors = wasm.compute_edge_anchors(50, 50, 80, 40, 170, 190, 60, 40, 'sides');
+      expect(anchors.from).toEqual([90, 50]);
+      expect(anchors.to).toEqual([170, 170]);
+    });
+
     it('layout_hierarchical positions nodes', () => {
       const nodes = [
         { id: 'a', w: 80, h: 40 },
@@ -474,6 +480,12 @@ describe('WASM Bridge', () => {
       expect(start[0]).toBeGreaterThanOrEqual(middle[0]);
       expect(middle[0]).toBeGreaterThanOrEqual(end[0]);
     });
+
+    it('compute_text_bounds flips start and end for rtl', () => {
+      const [x, , w] = wasm.compute_text_bounds(100, 50, 'Test', 'Arial', 16, 'start', 'rtl');
+      expect(x + w).toBeCloseTo(100);
+      expect(wasm.compute_text_bounds(100, 50, 'Test', 'Arial', 16, 'end', 'rtl')[0]).toBeCloseTo(100);
+    });
   });
 
   // ─────────────────────────────────────────────────────────────────────────
@@ -18,11 +18,16 @@ export interface WasmStyle {
   opacity: number;
   corner: number;
   filter?: string;
+  /** stroke-dasharray value, e.g. "5 3" */
+  dash?: string;
+  dash_offset?: number;
+  /** fill-rule value: "nonzero" or "evenodd" */
+  fill_rule?: string;
 }
 
 // Diff operation types
 export interface WasmDiffOp {
-  type: 'none' | 'full_redraw' | 'add' | 'remove' | 'update' | 'move' | 'update_defs';
+  type: 'none' | 'full_redraw' | 'add' | 'remove' | 'update' | 'move' | 'update_defs' | 'add_def' | 'update_def' | 'remove_def';
   id?: string;
   idx?: number;
   svg?: string;
@@ -94,7 +99,8 @@ export interface WasmCore {
   // DSL Processing (single source of truth)
   tokenize(source: string): string;  // Returns JSON array of tokens
   parse(source: string): string;  // Returns JSON AST
-  parse_with_errors(source: string): string;  // Returns {ast, errors} JSON
+  parse_with_errors(source: string): string;  // Returns {version, ast, errors} JSON
+  load_ast(json: string): string;  // Loads persisted AST JSON, same shape as parse_with_errors
   
   // Canvas Size System - returns native JS values via serde-wasm-bindgen
   size_to_pixels(name: string): [number, number] | null;
@@ -113,15 +119,15 @@ export interface WasmCore {
   render_line(x1: number, y1: number, x2: number, y2: number, stroke: string, strokeWidth: number, transform?: string): string;
   render_path(d: string, style: WasmStyle, transform?: string): string;
   render_polygon(points: [number, number][], style: WasmStyle, transform?: string): string;
-  render_text(x: number, y: number, content: string, font: string, size: number, weight: string, anchor: string, fill: string, transform?: string): string;
-  render_image(x: number, y: number, w: number, h: number, href: string, transform?: string): string;
+  render_text(x: number, y: number, content: string, font: string, size: number, weight: string, anchor: string, fill: string, transform?: string, baseline?: string, decoration?: string, direction?: string, letterSpacing?: number, wordSpacing?: number): string;
+  render_image(x: number, y: number, w: number, h: number, href: string, transform?: string, fit?: string): string;
   
   // Graph/Flowchart rendering - native JS objects via serde-wasm-bindgen
   render_diamond(cx: number, cy: number, w: number, h: number, style: WasmStyle, transform?: string): string;
-  render_node(id: string, shape: string, cx: number, cy: number, w: number, h: number, label: string | undefined, style: WasmStyle): string;
-  render_edge(fromX: number, fromY: number, toX: number, toY: number, edgeStyle: string, arrow: string, label: string | undefined, stroke: string, strokeWidth: number): string;
-  render_arrow_markers(color: string): string;
-  compute_edge_anchors(fromCx: number, fromCy: number, fromW: number, fromH: number, toCx: number, toCy: number, toW: number, toH: number): EdgeAnchors;
+  render_node(id: string, shape: string, cx: number, cy: number, w: number, h: number, label: string | undefined, style: WasmStyle, truncate?: boolean): string;
+  render_edge(fromX: number, fromY: number, toX: number, toY: number, edgeStyle: string, arrow: string, label: string | undefined, stroke: string, strokeWidth: number, arrowHead?: string, arrowSize?: number, labelBg?: string): string;
+  render_arrow_markers(color: string, head?: string, size?: number): string;
+  compute_edge_anchors(fromCx: number, fromCy: number, fromW: number, fromH: number, toCx: number, toCy: number, toW: number, toH: number, mode?: string): EdgeAnchors;
   layout_hierarchical(nodes: NodeInput[], direction: string, spacing: number): NodePosition[];
   layout_grid(nodes: NodeInput[], spacing: number): NodePosition[];
   
@@ -141,14 +147,14 @@ export interface WasmCore {
   
   // Text metrics - returns native JS object
   measure_text(content: string, font: string, size: number): TextMetrics;
-  compute_text_bounds(x: number, y: number, content: string, font: string, size: number, anchor: string): [number, number, number, number];
+  compute_text_bounds(x: number, y: number, content: string, font: string, size: number, anchor: string, direction?: string): [number, number, number, number];
   
   // Graph/Flowchart primitives - native JS objects
   render_diamond(cx: number, cy: number, w: number, h: number, style: WasmStyle, transform?: string): string;
-  render_node(id: string, shape: string, cx: number, cy: number, w: number, h: number, label: string | null, style: WasmStyle): string;
-  render_edge(fromX: number, fromY: number, toX: number, toY: number, edgeStyle: string, arrow: string, label: string | null, stroke: string, strokeWidth: number): string;
-  render_arrow_markers(color: string): string;
-  compute_edge_anchors(fromCx: number, fromCy: number, fromW: number, fromH: number, toCx: number, toCy: number, toW: number, toH: number): EdgeAnchors;
+  render_node(id: string, shape: string, cx: number, cy: number, w: number, h: number, label: string | null, style: WasmStyle, truncate?: boolean): string;
+  render_edge(fromX: number, fromY: number, toX: number, toY: number, edgeStyle: string, arrow: string, label: string | null, stroke: string, strokeWidth: number, arrowHead?: string, arrowSize?: number, labelBg?: string): string;
+  render_arrow_markers(color: string, head?: string, size?: number): string;
+  compute_edge_anchors(fromCx: number, fromCy: number, fromW: number, fromH: number, toCx: number, toCy: number, toW: number, toH: number, mode?: string): EdgeAnchors;
   layout_hierarchical(nodes: NodeLayoutInput[], direction: string, spacing: number): NodeLayoutOutput[];
   layout_grid(nodes: NodeLayoutInput[], spacing: number): NodeLayoutOutput[];
 }
@@ -3,4 +3,4 @@
 /**
  * Properties that can be animated
  */
-export type AnimatableProperty = { "Opacity": number } | { "Fill": string } | { "Stroke": string } | { "StrokeWidth": number } | { "Transform": string } | { "Translate": [number, number] } | { "Rotate": number } | { "Scale": [number, number] } | { "PathD": string } | { "X": number } | { "Y": number } | { "Cx": number } | { "Cy": number } | { "R": number } | { "Width": number } | { "Height": number };
+export type AnimatableProperty = { "Opacity": number } | { "Fill": string } | { "Stroke": string } | { "StrokeWidth": number } | { "StrokeDashOffset": number } | { "Transform": string } | { "Translate": [number, number] } | { "Rotate": number } | { "Scale": [number, number] } | { "PathD": string } | { "X": number } | { "Y": number } | { "Cx": number } | { "Cy": number } | { "R": number } | { "Width": number } | { "Height": number };
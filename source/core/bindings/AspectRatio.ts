@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Canvas aspect ratio (`2:1`, `wide`, `tall`); square by default
+ */
+export type AspectRatio = { w: number, h: number, };
@@ -1,7 +1,17 @@
 // This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { AspectRatio } from "./AspectRatio";
 import type { CanvasSize } from "./CanvasSize";
+import type { GradientDef } from "./GradientDef";
 
 /**
  * Canvas definition using standardized sizes
  */
-export type AstCanvas = { size: CanvasSize, fill: string, };
+export type AstCanvas = { size: CanvasSize, aspect: AspectRatio, fill: string, 
+/**
+ * Background gradient, painted over `fill`
+ */
+gradient: GradientDef | null, 
+/**
+ * Background image href, scaled to cover the canvas
+ */
+image: string | null, };
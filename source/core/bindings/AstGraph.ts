@@ -5,4 +5,4 @@ import type { GraphNode } from "./GraphNode";
 /**
  * Graph container with layout
  */
-export type AstGraph = { layout: string, direction: string, spacing: number, nodes: Array<GraphNode>, edges: Array<GraphEdge>, };
+export type AstGraph = { layout: string, direction: string, spacing: number, anchors: string, nodes: Array<GraphNode>, edges: Array<GraphEdge>, };
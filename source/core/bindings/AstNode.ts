@@ -5,9 +5,10 @@ import type { AstShape } from "./AstShape";
 import type { AstSymbol } from "./AstSymbol";
 import type { AstUse } from "./AstUse";
 import type { Keyframes } from "./Keyframes";
+import type { Span } from "./Span";
 import type { TokenValue } from "./TokenValue";
 
 /**
  * AST node types
  */
-export type AstNode = { "Scene": Array<AstNode> } | { "Canvas": AstCanvas } | { "Shape": AstShape } | { "Graph": AstGraph } | { "Symbol": AstSymbol } | { "Use": AstUse } | { "Variable": { name: string, value: TokenValue | null, } } | { "Keyframes": Keyframes };
+export type AstNode = { "Scene": Array<AstNode> } | { "Canvas": AstCanvas } | { "Shape": AstShape } | { "Graph": AstGraph } | { "Symbol": AstSymbol } | { "Use": AstUse } | { "Variable": { name: string, value: TokenValue | null, span: Span, } } | { "Keyframes": Keyframes } | { "Include": string };
@@ -5,8 +5,18 @@ import type { AstTransform } from "./AstTransform";
 import type { GradientDef } from "./GradientDef";
 import type { PropValue } from "./PropValue";
 import type { ShadowDef } from "./ShadowDef";
+import type { Span } from "./Span";
+import type { TokenValue } from "./TokenValue";
 
 /**
  * Shape in the AST
  */
-export type AstShape = { kind: string, props: { [key in string]?: PropValue }, style: AstStyle, shadow: ShadowDef | null, gradient: GradientDef | null, transform: AstTransform, animation: AnimationState | null, children: Array<AstShape>, };
+export type AstShape = { kind: string, props: { [key in string]?: PropValue }, style: AstStyle, shadow: ShadowDef | null, gradient: GradientDef | null, transform: AstTransform, animation: AnimationState | null, children: Array<AstShape>, 
+/**
+ * Block-local variables (`$fg = #000` inside a group), shadowing outer ones
+ */
+vars: Array<[string, TokenValue, Span]>, 
+/**
+ * Non-rendering editor metadata (`meta layer "bg"`)
+ */
+meta: { [key in string]?: string }, };
@@ -3,4 +3,45 @@
 /**
  * Style properties for shapes
  */
-export type AstStyle = { fill: string | null, stroke: string | null, stroke_width: number, opacity: number, corner: number, font: string | null, font_size: number, font_weight: string, text_anchor: string, };
+export type AstStyle = { fill: string | null, stroke: string | null, stroke_width: number, opacity: number, corner: number, font: string | null, font_size: number, font_weight: string, text_anchor: string, 
+/**
+ * Text `dominant-baseline` (alphabetic, middle, hanging)
+ */
+baseline: string | null, 
+/**
+ * Stroke dash pattern (dash, gap, ...); empty for a solid stroke
+ */
+dash: Array<number>, 
+/**
+ * Offset into the dash pattern
+ */
+dash_offset: number, 
+/**
+ * Stroke width as a percent of the viewBox's shorter side; layout
+ * resolution replaces `stroke_width` with it
+ */
+stroke_width_pct: number | null, 
+/**
+ * Font size as a percent of the viewBox's shorter side, like `stroke_width_pct`
+ */
+font_size_pct: number | null, 
+/**
+ * Extra space between glyphs (`letter-spacing`)
+ */
+letter_spacing: number, 
+/**
+ * Extra space per space character (`word-spacing`)
+ */
+word_spacing: number, 
+/**
+ * Text `text-decoration` (underline, line-through, overline)
+ */
+decoration: string | null, 
+/**
+ * Text writing direction (ltr, rtl)
+ */
+direction: string | null, 
+/**
+ * `fill-rule` (nonzero, evenodd); unset leaves the SVG default, nonzero
+ */
+fill_rule: string | null, };
@@ -1,7 +1,17 @@
 // This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
 import type { AstShape } from "./AstShape";
+import type { AstUse } from "./AstUse";
+import type { Span } from "./Span";
 
 /**
  * Symbol definition for reusable components (SVG <symbol>)
  */
-export type AstSymbol = { id: string, viewbox: [number, number, number, number] | null, children: Array<AstShape>, };
+export type AstSymbol = { id: string, viewbox: [number, number, number, number] | null, children: Array<AstShape>, 
+/**
+ * Nested symbol instances, drawn after `children`
+ */
+uses: Array<AstUse>, 
+/**
+ * Location of the id string, for diagnostics
+ */
+span: Span, };
@@ -3,4 +3,9 @@
 /**
  * Transform properties
  */
-export type AstTransform = { translate: [number, number] | null, rotate: number, scale: [number, number] | null, origin: [number, number] | null, };
+export type AstTransform = { translate: [number, number] | null, rotate: number, scale: [number, number] | null, origin: [number, number] | null, 
+/**
+ * Origin as percents of the shape's own bounds (`origin 50% 50%` is its
+ * center); lowering resolves it into `origin` once the bounds are known
+ */
+origin_pct: [number, number] | null, };
@@ -1,8 +1,13 @@
 // This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
 import type { AstStyle } from "./AstStyle";
 import type { AstTransform } from "./AstTransform";
+import type { Span } from "./Span";
 
 /**
  * Use reference for symbol instances (SVG <use>)
  */
-export type AstUse = { href: string, at: [number, number] | null, size: [number, number] | null, transform: AstTransform, style: AstStyle, };
+export type AstUse = { href: string, at: [number, number] | null, size: [number, number] | null, transform: AstTransform, style: AstStyle, 
+/**
+ * Location of the href string, for diagnostics
+ */
+span: Span, };
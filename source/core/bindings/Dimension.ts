@@ -3,4 +3,4 @@
 /**
  * Dimension value type for flexible sizing
  */
-export type Dimension = { "Px": number } | { "Percent": number } | "Auto";
+export type Dimension = { "Px": number } | { "Percent": number } | "Auto" | { "Calc": { percent: number, px: number, } };
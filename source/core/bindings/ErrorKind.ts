@@ -3,4 +3,4 @@
 /**
  * Error categories for structured diagnostics
  */
-export type ErrorKind = "UnexpectedToken" | "UnknownCommand" | "InvalidValue" | "MissingToken" | "InvalidIndentation" | "UnterminatedBlock" | "InvalidProperty" | "UndefinedVariable" | "DuplicateVariable";
+export type ErrorKind = "UnexpectedToken" | "UnknownCommand" | "InvalidValue" | "MissingToken" | "InvalidIndentation" | "UnterminatedBlock" | "InvalidProperty" | "UndefinedVariable" | "DuplicateVariable" | "DuplicateSymbol" | "UndefinedSymbol" | "CyclicSymbol" | "UnsupportedVersion" | "UnusedVariable" | "UnusedSymbol" | "CyclicInclude" | "MissingInclude" | "NestingTooDeep" | "MissingGeometry" | "UnterminatedString";
@@ -3,4 +3,8 @@
 /**
  * Gradient definition
  */
-export type GradientDef = { gtype: string, from: string, to: string, angle: number, };
+export type GradientDef = { gtype: string, from: string, to: string, angle: number, 
+/**
+ * One gradient across every shape using it (`gradient ... shared`)
+ */
+shared: boolean, };
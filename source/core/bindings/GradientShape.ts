@@ -3,4 +3,8 @@
 /**
  * Gradient definition
  */
-export type GradientShape = { id: string, kind: string, from_color: string, to_color: string, angle: number, };
+export type GradientShape = { id: string, kind: string, from_color: string, to_color: string, angle: number, 
+/**
+ * Span every shape that uses it (`gradientUnits="userSpaceOnUse"`) rather than restarting per shape
+ */
+shared: boolean, };
@@ -5,4 +5,13 @@ import type { GraphNodeShape } from "./GraphNodeShape";
 /**
  * Container for graph elements with layout info
  */
-export type GraphContainer = { layout: string, direction: string, spacing: number, nodes: Array<GraphNodeShape>, edges: Array<Edge>, };
+export type GraphContainer = { layout: string, direction: string, spacing: number, 
+/**
+ * Edge anchor selection: `axis`, `sides`, or `corners`
+ */
+anchors: string, 
+/**
+ * Nodes and edges in declaration order. Layouts place them in this order
+ * and `to_svg` emits them in it, so the same graph renders byte-identically.
+ */
+nodes: Array<GraphNodeShape>, edges: Array<Edge>, };
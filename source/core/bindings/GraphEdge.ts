@@ -3,4 +3,4 @@
 /**
  * Edge/connector between nodes
  */
-export type GraphEdge = { from: string, to: string, style: string, arrow: string, label: string | null, stroke: string | null, stroke_width: number, };
+export type GraphEdge = { from: string, to: string, style: string, arrow: string, arrow_head: string, arrow_size: number, label: string | null, label_bg: string | null, stroke: string | null, stroke_width: number, };
@@ -3,4 +3,8 @@
 /**
  * Image primitive
  */
-export type ImageShape = { x: number, y: number, w: number, h: number, href: string, transform: string | null, };
+export type ImageShape = { x: number, y: number, w: number, h: number, href: string, transform: string | null, 
+/**
+ * Fit mode: `cover` (crop to fill) or `contain` (letterbox)
+ */
+fit: string | null, hidden: boolean, layer: string | null, z: number, };
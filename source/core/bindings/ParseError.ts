@@ -6,4 +6,8 @@ import type { Span } from "./Span";
 /**
  * Parse error with recovery context
  */
-export type ParseError = { message: string, line: number, col: number, kind: ErrorKind, severity: ErrorSeverity, span: Span, suggestion: string | null, recovered: boolean, };
+export type ParseError = { message: string, line: number, col: number, kind: ErrorKind, 
+/**
+ * Stable diagnostic code (mirrors `kind.code()`) for tooling
+ */
+code: string, severity: ErrorSeverity, span: Span, suggestion: string | null, recovered: boolean, };
@@ -6,4 +6,4 @@ import type { LayoutProps } from "./LayoutProps";
 /**
  * Property value types
  */
-export type PropValue = "None" | { "Str": string } | { "Num": number } | { "Pair": [number, number] } | { "Points": Array<[number, number]> } | { "Dim": Dimension } | { "DimPair": DimensionPair } | { "PercentPair": [number, number] } | { "Layout": LayoutProps } | { "VarRef": [string, number, number] };
+export type PropValue = "None" | { "Str": string } | { "Num": number } | { "Pair": [number, number] } | { "Points": Array<[number, number]> } | { "Dim": Dimension } | { "DimPair": DimensionPair } | { "PercentPair": [number, number] } | { "Layout": LayoutProps } | { "VarRef": [string, number, number] } | { "List": Array<PropValue> };
@@ -4,7 +4,35 @@
  * Style properties for shapes
  */
 export type ShapeStyle = { fill: string | null, stroke: string | null, stroke_width: number, opacity: number, corner: number, filter: string | null, 
+/**
+ * `fill-rule` (nonzero, evenodd), deciding which overlaps and holes fill
+ */
+fill_rule: string | null, 
 /**
  * Animation class name (references CSS animation)
  */
-animation_class: string | null, };
+animation_class: string | null, 
+/**
+ * `stroke-dasharray` value, e.g. "5 3"
+ */
+dash: string | null, dash_offset: number, 
+/**
+ * Arrowheads on a standalone line or path: `forward`, `backward`, or `both`
+ */
+arrow: string | null, 
+/**
+ * Kept in the scene (and the diff) but rendered with `display="none"`
+ */
+hidden: boolean, 
+/**
+ * Editor metadata from `meta`, rendered as `data-*` attributes in key order
+ */
+meta: { [key in string]?: string }, 
+/**
+ * Named layer the element renders in (see `Scene::layers`)
+ */
+layer: string | null, 
+/**
+ * Stacking order among top-level elements (see `Element::z`)
+ */
+z: number, };
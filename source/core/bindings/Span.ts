@@ -3,4 +3,8 @@
 /**
  * Source span for error locations
  */
-export type Span = { start_line: number, start_col: number, end_line: number, end_col: number, };
+export type Span = { start_line: number, start_col: number, end_line: number, end_col: number, 
+/**
+ * Start and end byte offsets in the source, when built from tokens
+ */
+bytes: [number, number] | null, };
@@ -4,4 +4,24 @@ import type { ShapeStyle } from "./ShapeStyle";
 /**
  * Text primitive
  */
-export type TextShape = { x: number, y: number, content: string, font: string, size: number, weight: string, anchor: string, style: ShapeStyle, transform: string | null, };
+export type TextShape = { x: number, y: number, content: string, font: string, size: number, weight: string, anchor: string, style: ShapeStyle, transform: string | null, 
+/**
+ * `dominant-baseline` value (alphabetic, middle, hanging)
+ */
+baseline: string | null, 
+/**
+ * Extra space between glyphs (`letter-spacing`)
+ */
+letter_spacing: number, 
+/**
+ * Extra space per space character (`word-spacing`)
+ */
+word_spacing: number, 
+/**
+ * `text-decoration` value (underline, line-through, overline)
+ */
+decoration: string | null, 
+/**
+ * Writing `direction` (ltr, rtl); `rtl` flips which side `start` anchors
+ */
+direction: string | null, };
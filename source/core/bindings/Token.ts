@@ -5,4 +5,12 @@ import type { TokenValue } from "./TokenValue";
 /**
  * A single token from the lexer
  */
-export type Token = { ttype: TokenType, value: TokenValue, line: number, col: number, };
+export type Token = { ttype: TokenType, value: TokenValue, line: number, col: number, 
+/**
+ * Exact source text (`#FFF`, `1.50`); empty for synthetic indent/dedent/EOF tokens
+ */
+raw: string, 
+/**
+ * Byte offset of the token's start in the source
+ */
+offset: number, };
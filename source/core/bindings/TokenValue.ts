@@ -3,4 +3,4 @@
 /**
  * Token value variants
  */
-export type TokenValue = "None" | { "Str": string } | { "Num": number } | { "Pair": [number, number] } | { "PercentPair": [number, number] };
+export type TokenValue = "None" | { "Str": string } | { "Num": number } | { "Pair": [number, number] } | { "PercentPair": [number, number] } | { "List": Array<TokenValue> };
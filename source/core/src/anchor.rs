@@ -0,0 +1,96 @@
+//! Edge anchor selection
+//!
+//! Scene graphs and the WASM `compute_edge_anchors` primitive both choose
+//! where an edge meets its nodes through `best_anchors`, so a graph routes the
+//! same way whichever path draws it.
+
+/// Node box as center x, center y, width, height
+pub(crate) type NodeBox = (f32, f32, f32, f32);
+
+const SIDES: [&str; 4] = ["top", "right", "bottom", "left"];
+const CORNERS: [&str; 4] = ["top-right", "bottom-right", "bottom-left", "top-left"];
+
+/// Extra length charged for each edge already attached at a candidate anchor,
+/// so edges sharing a node spread over its sides
+const ANCHOR_REUSE_PENALTY: f32 = 12.0;
+
+/// Point on the box edge for a side (`top`, or `n`) or corner (`top-right`, or
+/// `ne`); the center for anything else
+pub(crate) fn anchor_point((cx, cy, w, h): NodeBox, anchor: &str) -> (f32, f32) {
+    let (dx, dy) = outward(anchor);
+    (cx + dx * w / 2.0, cy + dy * h / 2.0)
+}
+
+/// Direction an edge leaves the box at `anchor`
+fn outward(anchor: &str) -> (f32, f32) {
+    match anchor {
+        "top" | "n" => (0.0, -1.0),
+        "bottom" | "s" => (0.0, 1.0),
+        "left" | "w" => (-1.0, 0.0),
+        "right" | "e" => (1.0, 0.0),
+        "top-right" | "ne" => (1.0, -1.0),
+        "bottom-right" | "se" => (1.0, 1.0),
+        "bottom-left" | "sw" => (-1.0, 1.0),
+        "top-left" | "nw" => (-1.0, -1.0),
+        _ => (0.0, 0.0),
+    }
+}
+
+/// Anchors on `from` and `to` for an edge between them under `mode`.
+///
+/// `uses(i, anchor)` counts the edges already attached at `anchor` on `from`
+/// (`i == 0`) or `to` (`i == 1`); only the searching modes consult it. They
+/// fall back to `axis` when no pair leaves both nodes, as with overlapping boxes.
+pub(crate) fn best_anchors(from: NodeBox, to: NodeBox, mode: &str, uses: impl Fn(usize, &str) -> usize) -> (&'static str, &'static str) {
+    let candidates: Vec<&'static str> = match mode {
+        "sides" => SIDES.to_vec(),
+        "corners" => SIDES.iter().chain(&CORNERS).copied().collect(),
+        _ => return axis_anchors(from, to),
+    };
+    let mut best: Option<(f32, &'static str, &'static str)> = None;
+    for &fa in &candidates {
+        for &ta in &candidates {
+            let (p, q) = (anchor_point(from, fa), anchor_point(to, ta));
+            let (dx, dy) = (q.0 - p.0, q.1 - p.1);
+            let ((fx, fy), (tx, ty)) = (outward(fa), outward(ta));
+            if dx * fx + dy * fy <= 0.0 || -dx * tx - dy * ty <= 0.0 { continue; }
+            let cost = dx.hypot(dy) + ANCHOR_REUSE_PENALTY * (uses(0, fa) + uses(1, ta)) as f32;
+            if best.is_none_or(|(c, _, _)| cost < c) { best = Some((cost, fa, ta)); }
+        }
+    }
+    best.map_or_else(|| axis_anchors(from, to), |(_, fa, ta)| (fa, ta))
+}
+
+fn axis_anchors(from: NodeBox, to: NodeBox) -> (&'static str, &'static str) {
+    let dx = to.0 - from.0;
+    let dy = to.1 - from.1;
+    if dy.abs() > dx.abs() {
+        if dy > 0.0 { ("bottom", "top") } else { ("top", "bottom") }
+    } else if dx > 0.0 { ("right", "left") } else { ("left", "right") }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FROM: NodeBox = (50.0, 50.0, 80.0, 40.0);
+    const TO: NodeBox = (170.0, 190.0, 60.0, 40.0);
+
+    #[test]
+    fn test_diagonal_picks_adjacent_sides() {
+        let none = |_: usize, _: &str| 0;
+        assert_eq!(best_anchors(FROM, TO, "axis", none), ("bottom", "top"));
+        assert_eq!(best_anchors(FROM, TO, "sides", none), ("right", "top"));
+        assert_eq!(best_anchors(FROM, TO, "corners", none), ("bottom-right", "top-left"));
+        // Overlapping boxes have no outward pair and keep the axis choice
+        assert_eq!(best_anchors(FROM, (60.0, 50.0, 80.0, 40.0), "sides", none), ("right", "left"));
+    }
+
+    #[test]
+    fn test_reused_anchor_is_penalized() {
+        let busy = |i: usize, a: &str| usize::from(i == 1 && a == "top") * 2;
+        assert_eq!(best_anchors(FROM, TO, "sides", busy), ("right", "left"));
+        assert_eq!(anchor_point(TO, "left"), (140.0, 190.0));
+        assert_eq!(anchor_point(TO, "sw"), (140.0, 210.0));
+    }
+}
@@ -8,7 +8,10 @@
 
 use wasm_bindgen::prelude::*;
 use serde::{Deserialize, Serialize};
-use crate::CanvasSize;
+use crate::{CanvasSize, ElementId, ElementKind};
+use crate::path::Affine;
+use crate::fmt::num;
+use crate::svg::{arrow_marker_suffix, arrow_markers, preserve_aspect_ratio, Attrs, ARROW_HEADS, ARROW_SIZE, EDGE_LABEL_PADDING, EDGE_LABEL_SIZE};
 
 // Initialize panic hook for better error messages in WASM
 #[wasm_bindgen(start)]
@@ -46,6 +49,18 @@ pub fn get_all_sizes() -> JsValue {
     serde_wasm_bindgen::to_value(&CanvasSize::all_names()).unwrap_or(JsValue::NULL)
 }
 
+/// Name of the size exactly `px` pixels wide, or undefined
+#[wasm_bindgen]
+pub fn size_from_pixels(px: u32) -> Option<String> {
+    CanvasSize::from_pixels(px).map(|s| s.to_string())
+}
+
+/// Name of the size closest to `px` (ties go to the smaller size)
+#[wasm_bindgen]
+pub fn nearest_size(px: u32) -> String {
+    CanvasSize::nearest(px).to_string()
+}
+
 /// Get size info as object: {name, width, height}
 #[wasm_bindgen]
 pub fn get_size_info(name: &str) -> JsValue {
@@ -79,33 +94,26 @@ pub fn fnv1a_hash(data: &str) -> String {
     format!("{:016x}", hash)
 }
 
-/// Compute stable element ID from order, kind, and key properties
+/// Compute stable element ID from order, kind, and key properties. Known
+/// kinds seed the hash by discriminant, as the core's `ElementId` does.
 #[wasm_bindgen]
 pub fn compute_element_id(order: u32, kind: &str, key: JsValue) -> String {
-    let mut hash = FNV_OFFSET;
-    
-    // Hash order
-    for byte in order.to_le_bytes() {
-        hash ^= byte as u64;
-        hash = hash.wrapping_mul(FNV_PRIME);
-    }
-    
-    // Hash kind
-    for byte in kind.bytes() {
-        hash ^= byte as u64;
-        hash = hash.wrapping_mul(FNV_PRIME);
-    }
+    let mut h = match kind.parse::<ElementKind>() {
+        Ok(kind) => ElementId::hasher(order as u64, kind.as_u8()),
+        Err(_) => {
+            let mut h = ElementId::hasher(order as u64, u8::MAX);
+            h.write_str(kind);
+            h
+        }
+    };
     
     // Hash key properties (serialize to JSON for consistent hashing)
     let key_str = js_sys::JSON::stringify(&key)
         .map(|s| s.as_string().unwrap_or_default())
         .unwrap_or_default();
-    for byte in key_str.bytes() {
-        hash ^= byte as u64;
-        hash = hash.wrapping_mul(FNV_PRIME);
-    }
+    h.write_str(&key_str);
     
-    format!("{:016x}", hash)
+    format!("{:016x}", ElementId::from_hasher(h).0)
 }
 
 // ─────────────────────────────────────────────────────────────────────────────
@@ -120,6 +128,16 @@ pub struct WasmStyle {
     pub opacity: f32,
     pub corner: f32,
     pub filter: Option<String>,
+    #[serde(default)]
+    pub dash: Option<String>,
+    #[serde(default)]
+    pub dash_offset: f32,
+    /// `forward`, `backward`, or `both`: arrowheads referencing `render_arrow_markers` defs
+    #[serde(default)]
+    pub arrow: Option<String>,
+    /// `nonzero` or `evenodd`
+    #[serde(default)]
+    pub fill_rule: Option<String>,
 }
 
 impl WasmStyle {
@@ -127,21 +145,26 @@ impl WasmStyle {
         serde_wasm_bindgen::from_value(v).unwrap_or_default()
     }
 
+    /// Same attributes, in the same canonical order, as the scene's `Style::to_svg_attrs`
     fn to_svg_attrs(&self) -> String {
-        let mut attrs = Vec::with_capacity(4);
-        if let Some(ref fill) = self.fill {
-            attrs.push(format!(r#"fill="{}""#, fill));
-        }
-        if let Some(ref stroke) = self.stroke {
-            attrs.push(format!(r#"stroke="{}" stroke-width="{}""#, stroke, self.stroke_width));
-        }
-        if self.opacity < 1.0 {
-            attrs.push(format!(r#"opacity="{}""#, self.opacity));
-        }
-        if let Some(ref filter) = self.filter {
-            attrs.push(format!(r#"filter="url(#{})""#, filter));
+        let mut attrs = Attrs::new();
+        if let Some(ref fill) = self.fill { attrs.set("fill", fill); }
+        if let Some(ref rule) = self.fill_rule { attrs.set("fill-rule", rule); }
+        if let Some(ref stroke) = self.stroke { attrs.set("stroke", stroke).set("stroke-width", num(self.stroke_width)); }
+        if let Some(ref dash) = self.dash { attrs.set("stroke-dasharray", dash); }
+        if self.dash_offset != 0.0 { attrs.set("stroke-dashoffset", num(self.dash_offset)); }
+        if self.opacity < 1.0 { attrs.set("opacity", num(self.opacity)); }
+        if let Some(ref filter) = self.filter { attrs.set("filter", format!("url(#{})", filter)); }
+        attrs.render()
+    }
+
+    fn marker_attrs(&self) -> &'static str {
+        match self.arrow.as_deref() {
+            Some("forward") => r#" marker-end="url(#arrow-end)""#,
+            Some("backward") => r#" marker-start="url(#arrow-start)""#,
+            Some("both") => r#" marker-start="url(#arrow-start)" marker-end="url(#arrow-end)""#,
+            _ => "",
         }
-        if attrs.is_empty() { String::new() } else { format!(" {}", attrs.join(" ")) }
     }
 }
 
@@ -152,54 +175,66 @@ impl WasmStyle {
 #[wasm_bindgen]
 pub fn render_rect(x: f32, y: f32, w: f32, h: f32, rx: f32, style: JsValue, transform: Option<String>) -> String {
     let style = WasmStyle::from_js(style);
-    let rx_attr = if rx > 0.0 { format!(r#" rx="{}""#, rx) } else { String::new() };
+    let rx_attr = if rx > 0.0 { format!(r#" rx="{}""#, num(rx)) } else { String::new() };
     let tf = transform.map_or(String::new(), |t| format!(r#" transform="{}""#, t));
-    format!(r#"<rect x="{}" y="{}" width="{}" height="{}"{}{}{}/>"#, x, y, w, h, rx_attr, style.to_svg_attrs(), tf)
+    format!(r#"<rect x="{}" y="{}" width="{}" height="{}"{}{}{}/>"#, num(x), num(y), num(w), num(h), rx_attr, style.to_svg_attrs(), tf)
 }
 
 #[wasm_bindgen]
 pub fn render_circle(cx: f32, cy: f32, r: f32, style: JsValue, transform: Option<String>) -> String {
     let style = WasmStyle::from_js(style);
     let tf = transform.map_or(String::new(), |t| format!(r#" transform="{}""#, t));
-    format!(r#"<circle cx="{}" cy="{}" r="{}"{}{}/>"#, cx, cy, r, style.to_svg_attrs(), tf)
+    format!(r#"<circle cx="{}" cy="{}" r="{}"{}{}/>"#, num(cx), num(cy), num(r), style.to_svg_attrs(), tf)
 }
 
 #[wasm_bindgen]
 pub fn render_ellipse(cx: f32, cy: f32, rx: f32, ry: f32, style: JsValue, transform: Option<String>) -> String {
     let style = WasmStyle::from_js(style);
     let tf = transform.map_or(String::new(), |t| format!(r#" transform="{}""#, t));
-    format!(r#"<ellipse cx="{}" cy="{}" rx="{}" ry="{}"{}{}/>"#, cx, cy, rx, ry, style.to_svg_attrs(), tf)
+    format!(r#"<ellipse cx="{}" cy="{}" rx="{}" ry="{}"{}{}/>"#, num(cx), num(cy), num(rx), num(ry), style.to_svg_attrs(), tf)
 }
 
 #[wasm_bindgen]
 pub fn render_line(x1: f32, y1: f32, x2: f32, y2: f32, stroke: &str, stroke_width: f32, transform: Option<String>) -> String {
     let tf = transform.map_or(String::new(), |t| format!(r#" transform="{}""#, t));
-    format!(r#"<line x1="{}" y1="{}" x2="{}" y2="{}" stroke="{}" stroke-width="{}"{}/>"#, x1, y1, x2, y2, stroke, stroke_width, tf)
+    format!(r#"<line x1="{}" y1="{}" x2="{}" y2="{}" stroke="{}" stroke-width="{}"{}/>"#, num(x1), num(y1), num(x2), num(y2), stroke, num(stroke_width), tf)
 }
 
 #[wasm_bindgen]
 pub fn render_path(d: &str, style: JsValue, transform: Option<String>) -> String {
     let style = WasmStyle::from_js(style);
     let tf = transform.map_or(String::new(), |t| format!(r#" transform="{}""#, t));
-    format!(r#"<path d="{}"{}{}/>"#, d, style.to_svg_attrs(), tf)
+    format!(r#"<path d="{}"{}{}{}/>"#, d, style.to_svg_attrs(), style.marker_attrs(), tf)
 }
 
 #[wasm_bindgen]
 pub fn render_polygon(points: JsValue, style: JsValue, transform: Option<String>) -> String {
     let points: Vec<(f32, f32)> = serde_wasm_bindgen::from_value(points).unwrap_or_default();
-    let style = WasmStyle::from_js(style);
-    let pts: String = points.iter().map(|(x, y)| format!("{},{}", x, y)).collect::<Vec<_>>().join(" ");
+    polygon_svg(&points, &WasmStyle::from_js(style), transform)
+}
+
+/// `<polygon>`, or a `<path>` with rounded vertices when `style.corner > 0`
+fn polygon_svg(points: &[(f32, f32)], style: &WasmStyle, transform: Option<String>) -> String {
     let tf = transform.map_or(String::new(), |t| format!(r#" transform="{}""#, t));
+    if style.corner > 0.0 {
+        return format!(r#"<path d="{}"{}{}/>"#, crate::path::round_corners(points, style.corner), style.to_svg_attrs(), tf);
+    }
+    let pts: String = points.iter().map(|(x, y)| format!("{},{}", num(*x), num(*y))).collect::<Vec<_>>().join(" ");
     format!(r#"<polygon points="{}"{}{}/>"#, pts, style.to_svg_attrs(), tf)
 }
 
 #[wasm_bindgen]
-pub fn render_text(x: f32, y: f32, content: &str, font: &str, size: f32, weight: &str, anchor: &str, fill: &str, transform: Option<String>) -> String {
+pub fn render_text(x: f32, y: f32, content: &str, font: &str, size: f32, weight: &str, anchor: &str, fill: &str, transform: Option<String>, baseline: Option<String>, decoration: Option<String>, direction: Option<String>, letter_spacing: Option<f32>, word_spacing: Option<f32>) -> String {
     let tf = transform.map_or(String::new(), |t| format!(r#" transform="{}""#, t));
+    let mut attrs = baseline.map_or(String::new(), |b| format!(r#" dominant-baseline="{}""#, b));
+    if let Some(s) = letter_spacing.filter(|s| *s != 0.0) { attrs.push_str(&format!(r#" letter-spacing="{}""#, num(s))); }
+    if let Some(s) = word_spacing.filter(|s| *s != 0.0) { attrs.push_str(&format!(r#" word-spacing="{}""#, num(s))); }
+    if let Some(d) = decoration { attrs.push_str(&format!(r#" text-decoration="{}""#, d)); }
+    if let Some(d) = direction { attrs.push_str(&format!(r#" direction="{}""#, d)); }
     let escaped = html_escape(content);
     format!(
-        r#"<text x="{}" y="{}" font-family="{}" font-size="{}" font-weight="{}" text-anchor="{}" fill="{}"{}>{}</text>"#,
-        x, y, font, size, weight, anchor, fill, tf, escaped
+        r#"<text x="{}" y="{}" font-family="{}" font-size="{}" font-weight="{}" text-anchor="{}"{} fill="{}"{}>{}</text>"#,
+        num(x), num(y), font, num(size), weight, anchor, attrs, fill, tf, escaped
     )
 }
 
@@ -215,23 +250,24 @@ pub fn measure_text(content: &str, font: &str, size: f32) -> JsValue {
     }).unwrap_or(JsValue::NULL)
 }
 
-/// Compute text bounding box accounting for anchor position
+/// Compute text bounding box accounting for anchor position and direction
 /// Returns [x, y, width, height]
 #[wasm_bindgen]
-pub fn compute_text_bounds(x: f32, y: f32, content: &str, font: &str, size: f32, anchor: &str) -> JsValue {
+pub fn compute_text_bounds(x: f32, y: f32, content: &str, font: &str, size: f32, anchor: &str, direction: Option<String>) -> JsValue {
+    serde_wasm_bindgen::to_value(&text_bounds(x, y, content, font, size, anchor, direction.as_deref() == Some("rtl"))).unwrap_or(JsValue::NULL)
+}
+
+fn text_bounds(x: f32, y: f32, content: &str, font: &str, size: f32, anchor: &str, rtl: bool) -> [f32; 4] {
     let m = crate::font::measure_text(content, font, size);
-    let adj_x = match anchor {
-        "middle" => x - m.width / 2.0,
-        "end" => x - m.width,
-        _ => x,
-    };
-    serde_wasm_bindgen::to_value(&[adj_x, y - m.ascender, m.width, m.height]).unwrap_or(JsValue::NULL)
+    [crate::font::anchored_left(x, m.width, anchor, rtl), y - m.ascender, m.width, m.height]
 }
 
 #[wasm_bindgen]
-pub fn render_image(x: f32, y: f32, w: f32, h: f32, href: &str, transform: Option<String>) -> String {
+pub fn render_image(x: f32, y: f32, w: f32, h: f32, href: &str, transform: Option<String>, fit: Option<String>) -> String {
     let tf = transform.map_or(String::new(), |t| format!(r#" transform="{}""#, t));
-    format!(r#"<image x="{}" y="{}" width="{}" height="{}" href="{}"{}/>"#, x, y, w, h, html_escape(href), tf)
+    let par = fit.as_deref().and_then(preserve_aspect_ratio)
+        .map_or(String::new(), |p| format!(r#" preserveAspectRatio="{}""#, p));
+    format!(r#"<image x="{}" y="{}" width="{}" height="{}" href="{}"{}{}/>"#, num(x), num(y), num(w), num(h), html_escape(href), par, tf)
 }
 
 fn html_escape(s: &str) -> String {
@@ -244,12 +280,11 @@ fn html_escape(s: &str) -> String {
 
 #[wasm_bindgen]
 pub fn render_linear_gradient(id: &str, from_color: &str, to_color: &str, angle: f32) -> String {
-    let rad = (angle - 90.0_f32).to_radians();
-    let x2 = 50.0 + 50.0 * rad.cos();
-    let y2 = 50.0 + 50.0 * rad.sin();
+    let (x1, y1, x2, y2) = crate::svg::gradient_vector(angle, (0.0, 0.0, 100.0, 100.0));
+    let pct = |v: f32| crate::fmt::coord(v as f64, 1);
     format!(
-        r#"<linearGradient id="{}" x1="0%" y1="0%" x2="{:.1}%" y2="{:.1}%"><stop offset="0%" stop-color="{}"/><stop offset="100%" stop-color="{}"/></linearGradient>"#,
-        id, x2, y2, from_color, to_color
+        r#"<linearGradient id="{}" x1="{}%" y1="{}%" x2="{}%" y2="{}%"><stop offset="0%" stop-color="{}"/><stop offset="100%" stop-color="{}"/></linearGradient>"#,
+        id, pct(x1), pct(y1), pct(x2), pct(y2), from_color, to_color
     )
 }
 
@@ -405,22 +440,65 @@ pub fn diff_scenes(old: JsValue, new: JsValue) -> JsValue {
         }
     }
 
-    // Defs changed
-    if old.defs != new.defs {
-        ops.push(DiffOp {
-            op_type: "update_defs".into(),
-            id: None,
-            idx: None,
-            svg: Some(new.defs),
-            from_idx: None,
-            to_idx: None,
-        });
-    }
+    ops.extend(diff_defs(&old.defs, &new.defs));
 
     serde_wasm_bindgen::to_value(&DiffResult { ops, canvas_changed: false })
         .unwrap_or_else(|_| full_redraw_result())
 }
 
+/// Per-def `add_def`/`update_def`/`remove_def` ops keyed by def id. When either
+/// side can't be split into uniquely identified defs, all defs are replaced with
+/// one `update_defs`.
+fn diff_defs(old: &str, new: &str) -> Vec<DiffOp> {
+    if old == new { return Vec::new(); }
+    let op = |op_type: &str, id: Option<&str>, svg: Option<&str>| DiffOp {
+        op_type: op_type.into(), id: id.map(Into::into), idx: None, svg: svg.map(Into::into), from_idx: None, to_idx: None,
+    };
+    let (Some(old_defs), Some(new_defs)) = (split_defs(old), split_defs(new)) else {
+        return vec![op("update_defs", None, Some(new))];
+    };
+    let before: std::collections::HashMap<&str, &str> = old_defs.iter().copied().collect();
+    let after: std::collections::HashSet<&str> = new_defs.iter().map(|&(id, _)| id).collect();
+    let mut ops: Vec<DiffOp> = new_defs.iter().filter_map(|&(id, svg)| match before.get(id) {
+        None => Some(op("add_def", Some(id), Some(svg))),
+        Some(&prev) if prev != svg => Some(op("update_def", Some(id), Some(svg))),
+        _ => None,
+    }).collect();
+    ops.extend(old_defs.iter().filter(|(id, _)| !after.contains(id)).map(|&(id, _)| op("remove_def", Some(id), None)));
+    ops
+}
+
+/// Top-level elements of a defs string as `(id, markup)`, or `None` when one has
+/// no id, two share an id, or the tags don't balance
+fn split_defs(defs: &str) -> Option<Vec<(&str, &str)>> {
+    let (mut out, mut depth, mut start, mut i) = (Vec::new(), 0usize, 0, 0);
+    while let Some(offset) = defs[i..].find('<') {
+        let open = i + offset;
+        let close = open + defs[open..].find('>')?;
+        if depth == 0 {
+            if !defs[i..open].trim().is_empty() { return None; }
+            start = open;
+        }
+        let tag = &defs[open..=close];
+        if tag.starts_with("</") { depth = depth.checked_sub(1)?; }
+        else if !tag.ends_with("/>") { depth += 1; }
+        if depth == 0 {
+            let el = &defs[start..=close];
+            out.push((def_id(el)?, el));
+        }
+        i = close + 1;
+    }
+    let unique = out.iter().map(|&(id, _)| id).collect::<std::collections::HashSet<_>>().len() == out.len();
+    (depth == 0 && unique && defs[i..].trim().is_empty()).then_some(out)
+}
+
+/// `id` attribute of an element's opening tag
+fn def_id(el: &str) -> Option<&str> {
+    let head = &el[..el.find('>')?];
+    let start = head.find(" id=\"")? + 5;
+    Some(&head[start..start + head[start..].find('"')?])
+}
+
 fn full_redraw_result() -> JsValue {
     let result = DiffResult {
         ops: vec![DiffOp { op_type: "full_redraw".into(), id: None, idx: None, svg: None, from_idx: None, to_idx: None }],
@@ -451,7 +529,8 @@ pub fn scenes_equal(old: JsValue, new: JsValue) -> bool {
 // Scene Rendering (Full SVG output)
 // ─────────────────────────────────────────────────────────────────────────────
 
-/// Render complete scene SVG using standardized size
+/// Render complete scene SVG using standardized size. A `none` or
+/// `transparent` background leaves out the background rect.
 #[wasm_bindgen]
 pub fn render_scene(size_name: &str, background: &str, defs: &str, elements_svg: &str) -> String {
     let (width, height) = CanvasSize::from_str(size_name)
@@ -459,7 +538,7 @@ pub fn render_scene(size_name: &str, background: &str, defs: &str, elements_svg:
         .unwrap_or((64, 64)); // Default to medium if invalid
     
     let mut svg = format!(r#"<svg xmlns="http://www.w3.org/2000/svg" width="{}" height="{}">"#, width, height);
-    svg.push_str(&format!(r#"<rect width="100%" height="100%" fill="{}"/>"#, background));
+    svg.push_str(&crate::svg::background_rect(background));
     
     if !defs.is_empty() {
         svg.push_str("<defs>");
@@ -472,6 +551,134 @@ pub fn render_scene(size_name: &str, background: &str, defs: &str, elements_svg:
     svg
 }
 
+/// One scene for `render_scenes_batch`; mirrors the `render_scene` arguments
+#[derive(Serialize, Deserialize, Default)]
+#[serde(default)]
+struct SceneInput {
+    size: String,
+    background: String,
+    defs: String,
+    elements: String,
+}
+
+fn render_batch(inputs: &[SceneInput]) -> Vec<String> {
+    inputs.iter().map(|s| render_scene(&s.size, &s.background, &s.defs, &s.elements)).collect()
+}
+
+/// Render many scenes in one boundary crossing (e.g. a sprite sheet)
+/// Input: array of {size, background, defs, elements}
+/// Output: array of SVG strings, in input order
+#[wasm_bindgen]
+pub fn render_scenes_batch(inputs: JsValue) -> JsValue {
+    let inputs: Vec<SceneInput> = serde_wasm_bindgen::from_value(inputs).unwrap_or_default();
+    serde_wasm_bindgen::to_value(&render_batch(&inputs)).unwrap_or(JsValue::NULL)
+}
+
+/// Scene element whose hit region is read back from its own `svg`
+#[derive(Serialize, Deserialize)]
+struct BoundedElement {
+    id: String,
+    svg: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+struct ElementBounds {
+    id: String,
+    x: f32,
+    y: f32,
+    w: f32,
+    h: f32,
+}
+
+#[derive(Serialize, Deserialize)]
+struct BoundedScene {
+    svg: String,
+    bounds: Vec<ElementBounds>,
+}
+
+fn scene_with_bounds(size_name: &str, background: &str, defs: &str, elements: &[BoundedElement]) -> BoundedScene {
+    let body: String = elements.iter().map(|e| e.svg.as_str()).collect();
+    let bounds = elements.iter().map(|e| {
+        let (x, y, w, h) = svg_bounds(&e.svg);
+        ElementBounds { id: e.id.clone(), x, y, w, h }
+    }).collect();
+    BoundedScene { svg: render_scene(size_name, background, defs, &body), bounds }
+}
+
+/// Box enclosing an SVG fragment's shapes, each mapped through its own `transform`
+/// and those of the `<g>` elements around it
+fn svg_bounds(svg: &str) -> (f32, f32, f32, f32) {
+    let mut groups = vec![Affine::IDENTITY];
+    let mut extent: Option<(f32, f32, f32, f32)> = None;
+    let mut rest = svg;
+    while let Some(open) = rest.find('<') {
+        let Some(close) = rest[open..].find('>') else { break };
+        let tag = &rest[open + 1..open + close];
+        rest = &rest[open + close + 1..];
+        if tag.starts_with("/g") { if groups.len() > 1 { groups.pop(); } continue; }
+        if tag.starts_with(['/', '!', '?']) { continue; }
+        let name = tag.split(|c: char| c.is_whitespace() || c == '/').next().unwrap_or("");
+        let tf = groups[groups.len() - 1].then(tag_attr(tag, "transform").map_or(Affine::IDENTITY, Affine::parse));
+        if name == "g" {
+            if !tag.ends_with('/') { groups.push(tf); }
+            continue;
+        }
+        let text = &rest[..rest.find('<').unwrap_or(rest.len())];
+        let Some(b) = tag_bounds(name, tag, text) else { continue };
+        let (x, y, w, h) = tf.map_bounds(b);
+        extent = Some(extent.map_or((x, y, w, h), |(ex, ey, ew, eh)| {
+            let (x0, y0) = (ex.min(x), ey.min(y));
+            (x0, y0, (ex + ew).max(x + w) - x0, (ey + eh).max(y + h) - y0)
+        }));
+    }
+    extent.unwrap_or_default()
+}
+
+/// Untransformed `(x, y, w, h)` of one SVG start tag; `text` is the character data after it
+fn tag_bounds(name: &str, tag: &str, text: &str) -> Option<(f32, f32, f32, f32)> {
+    let n = |key: &str| tag_attr(tag, key).and_then(|v| v.parse::<f32>().ok()).unwrap_or(0.0);
+    let span = |pts: &[(f32, f32)]| {
+        let (x0, y0, x1, y1) = pts.iter().fold((f32::MAX, f32::MAX, f32::MIN, f32::MIN), |(a, b, c, d), &(x, y)| (a.min(x), b.min(y), c.max(x), d.max(y)));
+        (!pts.is_empty()).then_some((x0, y0, x1 - x0, y1 - y0))
+    };
+    match name {
+        "rect" | "image" | "use" => Some((n("x"), n("y"), n("width"), n("height"))),
+        "circle" => Some((n("cx") - n("r"), n("cy") - n("r"), 2.0 * n("r"), 2.0 * n("r"))),
+        "ellipse" => Some((n("cx") - n("rx"), n("cy") - n("ry"), 2.0 * n("rx"), 2.0 * n("ry"))),
+        "line" => span(&[(n("x1"), n("y1")), (n("x2"), n("y2"))]),
+        "polygon" | "polyline" => {
+            let coords: Vec<f32> = tag_attr(tag, "points")?.split([',', ' ']).filter_map(|v| v.parse().ok()).collect();
+            span(&coords.chunks_exact(2).map(|p| (p[0], p[1])).collect::<Vec<_>>())
+        }
+        "path" => Some(crate::path::parse_path_bounds(tag_attr(tag, "d")?)),
+        "text" if !text.is_empty() => {
+            let content = text.replace("&lt;", "<").replace("&gt;", ">").replace("&quot;", "\"").replace("&#39;", "'").replace("&amp;", "&");
+            // 16 is SVG's default font size
+            let size = tag_attr(tag, "font-size").and_then(|v| v.parse().ok()).unwrap_or(16.0);
+            let [x, y, w, h] = text_bounds(n("x"), n("y"), &content, tag_attr(tag, "font-family").unwrap_or("sans-serif"), size,
+                tag_attr(tag, "text-anchor").unwrap_or("start"), tag_attr(tag, "direction") == Some("rtl"));
+            Some((x, y, w, h))
+        }
+        _ => None,
+    }
+}
+
+/// Value of attribute `name` in an SVG start tag
+fn tag_attr<'a>(tag: &'a str, name: &str) -> Option<&'a str> {
+    let key = format!(r#" {}=""#, name);
+    let start = tag.find(&key)? + key.len();
+    tag[start..].split('"').next()
+}
+
+/// Render a scene and report each element's transformed bounding box, measured from its SVG
+/// Input: array of {id, svg}
+/// Output: {svg, bounds: [{id, x, y, w, h}]}
+#[wasm_bindgen]
+pub fn render_scene_with_bounds(size_name: &str, background: &str, defs: &str, elements: JsValue) -> JsValue {
+    let elements: Vec<BoundedElement> = serde_wasm_bindgen::from_value(elements).unwrap_or_default();
+    serde_wasm_bindgen::to_value(&scene_with_bounds(size_name, background, defs, &elements)).unwrap_or(JsValue::NULL)
+}
+
 // ─────────────────────────────────────────────────────────────────────────────
 // Path Bounds Calculation
 // ─────────────────────────────────────────────────────────────────────────────
@@ -489,117 +696,150 @@ pub fn compute_path_bounds(d: &str) -> JsValue {
 /// Render a diamond shape (rotated rectangle for flowcharts)
 #[wasm_bindgen]
 pub fn render_diamond(cx: f32, cy: f32, w: f32, h: f32, style: JsValue, transform: Option<String>) -> String {
-    let style = WasmStyle::from_js(style);
-    let pts = format!("{},{} {},{} {},{} {},{}",
-        cx, cy - h / 2.0, cx + w / 2.0, cy, cx, cy + h / 2.0, cx - w / 2.0, cy);
-    let tf = transform.map_or(String::new(), |t| format!(r#" transform="{}""#, t));
-    format!(r#"<polygon points="{}"{}{}/>"#, pts, style.to_svg_attrs(), tf)
+    let points = [(cx, cy - h / 2.0), (cx + w / 2.0, cy), (cx, cy + h / 2.0), (cx - w / 2.0, cy)];
+    polygon_svg(&points, &WasmStyle::from_js(style), transform)
 }
 
 /// Render a graph node (shape + label)
+///
+/// Labels wider than the node wrap onto centered `<tspan>` lines. With
+/// `truncate`, lines that overflow the node height are dropped and the last
+/// kept line ends in `…`.
 #[wasm_bindgen]
-pub fn render_node(id: &str, shape: &str, cx: f32, cy: f32, w: f32, h: f32, label: Option<String>, style: JsValue) -> String {
+pub fn render_node(id: &str, shape: &str, cx: f32, cy: f32, w: f32, h: f32, label: Option<String>, style: JsValue, truncate: Option<bool>) -> String {
     let style = WasmStyle::from_js(style);
+    let mut anchor = (cx, cy);
     
     let shape_svg = match shape {
         "circle" => {
             let r = w.min(h) / 2.0;
-            format!(r#"<circle cx="{}" cy="{}" r="{}"{}/>"#, cx, cy, r, style.to_svg_attrs())
+            format!(r#"<circle cx="{}" cy="{}" r="{}"{}/>"#, num(cx), num(cy), num(r), style.to_svg_attrs())
         }
         "ellipse" => {
-            format!(r#"<ellipse cx="{}" cy="{}" rx="{}" ry="{}"{}/>"#, cx, cy, w / 2.0, h / 2.0, style.to_svg_attrs())
+            format!(r#"<ellipse cx="{}" cy="{}" rx="{}" ry="{}"{}/>"#, num(cx), num(cy), num(w / 2.0), num(h / 2.0), style.to_svg_attrs())
         }
         "diamond" => {
-            let pts = format!("{},{} {},{} {},{} {},{}",
-                cx, cy - h / 2.0, cx + w / 2.0, cy, cx, cy + h / 2.0, cx - w / 2.0, cy);
+            let outline = [(cx, cy - h / 2.0), (cx + w / 2.0, cy), (cx, cy + h / 2.0), (cx - w / 2.0, cy)];
+            anchor = crate::path::label_anchor(&outline);
+            let pts = outline.iter().map(|(x, y)| format!("{},{}", num(*x), num(*y))).collect::<Vec<_>>().join(" ");
             format!(r#"<polygon points="{}"{}/>"#, pts, style.to_svg_attrs())
         }
         _ => { // rect
             let x = cx - w / 2.0;
             let y = cy - h / 2.0;
-            let rx = if style.corner > 0.0 { format!(r#" rx="{}""#, style.corner) } else { String::new() };
-            format!(r#"<rect x="{}" y="{}" width="{}" height="{}"{}{}/>"#, x, y, w, h, rx, style.to_svg_attrs())
+            let rx = if style.corner > 0.0 { format!(r#" rx="{}""#, num(style.corner)) } else { String::new() };
+            format!(r#"<rect x="{}" y="{}" width="{}" height="{}"{}{}/>"#, num(x), num(y), num(w), num(h), rx, style.to_svg_attrs())
         }
     };
     
-    let label_svg = label.map_or(String::new(), |lbl| {
-        format!(r##"<text x="{}" y="{}" text-anchor="middle" dominant-baseline="middle" fill="#000">{}</text>"##, 
-            cx, cy, html_escape(&lbl))
-    });
+    let label_svg = label.map_or(String::new(), |lbl| node_label_svg(anchor.0, anchor.1, w, h, &lbl, truncate.unwrap_or(false)));
     
     format!(r##"<g id="node-{}">{}{}</g>"##, html_escape(id), shape_svg, label_svg)
 }
 
+/// SVG's default font size, which node labels inherit
+const NODE_LABEL_SIZE: f32 = 16.0;
+/// Room kept clear between a node label and each side of the node
+const NODE_LABEL_PADDING: f32 = 4.0;
+
+fn node_label_svg(cx: f32, cy: f32, w: f32, h: f32, label: &str, truncate: bool) -> String {
+    use crate::font::{get_metrics, truncate_text, wrap_text};
+    let max_w = (w - 2.0 * NODE_LABEL_PADDING).max(0.0);
+    let line_h = get_metrics("sans-serif").line_height(NODE_LABEL_SIZE);
+    let mut lines = wrap_text(label, "sans-serif", NODE_LABEL_SIZE, max_w);
+    if truncate {
+        let fit = ((h / line_h).floor() as usize).max(1);
+        if lines.len() > fit {
+            let rest = lines.split_off(fit - 1).join(" ");
+            lines.push(rest);
+        }
+        for line in &mut lines { *line = truncate_text(line, "sans-serif", NODE_LABEL_SIZE, max_w); }
+    }
+
+    let open = format!(r##"<text x="{}" y="{}" text-anchor="middle" dominant-baseline="middle" fill="#000">"##, num(cx), num(cy));
+    if let [line] = lines.as_slice() { return format!("{}{}</text>", open, html_escape(line)); }
+    let first_dy = -((lines.len() - 1) as f32) * line_h / 2.0;
+    let tspans: String = lines.iter().enumerate()
+        .map(|(i, line)| format!(r#"<tspan x="{}" dy="{}">{}</tspan>"#, num(cx), num(if i == 0 { first_dy } else { line_h }), html_escape(line)))
+        .collect();
+    format!("{}{}</text>", open, tspans)
+}
+
 /// Render an edge (connector with optional arrow)
+///
+/// `arrow_head` and `arrow_size` pick the markers from the matching
+/// `render_arrow_markers` call, defaulting to the 10px triangle. `label_bg`
+/// fills a rounded rect sized to the measured label behind it.
 #[wasm_bindgen]
-pub fn render_edge(from_x: f32, from_y: f32, to_x: f32, to_y: f32, edge_style: &str, arrow: &str, label: Option<String>, stroke: &str, stroke_width: f32) -> String {
+pub fn render_edge(from_x: f32, from_y: f32, to_x: f32, to_y: f32, edge_style: &str, arrow: &str, label: Option<String>, stroke: &str, stroke_width: f32, arrow_head: Option<String>, arrow_size: Option<f32>, label_bg: Option<String>) -> String {
     let path_d = match edge_style {
         "curved" => {
             let mx = (from_x + to_x) / 2.0;
             let my = (from_y + to_y) / 2.0;
             if (to_y - from_y).abs() > (to_x - from_x).abs() {
-                format!("M{},{} C{},{} {},{} {},{}", from_x, from_y, from_x, my, to_x, my, to_x, to_y)
+                format!("M{},{} C{},{} {},{} {},{}", num(from_x), num(from_y), num(from_x), num(my), num(to_x), num(my), num(to_x), num(to_y))
             } else {
                 let offset = ((to_x - from_x).abs().max((to_y - from_y).abs())) * 0.3;
-                format!("M{},{} C{},{} {},{} {},{}", from_x, from_y, mx, from_y + offset, mx, to_y - offset, to_x, to_y)
+                format!("M{},{} C{},{} {},{} {},{}", num(from_x), num(from_y), num(mx), num(from_y + offset), num(mx), num(to_y - offset), num(to_x), num(to_y))
             }
         }
         "orthogonal" => {
             let mx = (from_x + to_x) / 2.0;
-            format!("M{},{} L{},{} L{},{} L{},{}", from_x, from_y, mx, from_y, mx, to_y, to_x, to_y)
+            format!("M{},{} L{},{} L{},{} L{},{}", num(from_x), num(from_y), num(mx), num(from_y), num(mx), num(to_y), num(to_x), num(to_y))
         }
-        _ => format!("M{},{} L{},{}", from_x, from_y, to_x, to_y), // straight
+        _ => format!("M{},{} L{},{}", num(from_x), num(from_y), num(to_x), num(to_y)), // straight
     };
     
+    let suffix = arrow_marker_suffix(arrow_head.as_deref().unwrap_or(ARROW_HEADS[0]), arrow_size.unwrap_or(ARROW_SIZE));
     let markers = match arrow {
-        "forward" => r#" marker-end="url(#arrow-end)""#,
-        "backward" => r#" marker-start="url(#arrow-start)""#,
-        "both" => r#" marker-start="url(#arrow-start)" marker-end="url(#arrow-end)""#,
-        _ => "",
+        "forward" => format!(r#" marker-end="url(#arrow{}-end)""#, suffix),
+        "backward" => format!(r#" marker-start="url(#arrow{}-start)""#, suffix),
+        "both" => format!(r#" marker-start="url(#arrow{}-start)" marker-end="url(#arrow{}-end)""#, suffix, suffix),
+        _ => String::new(),
     };
     
     let label_svg = label.map_or(String::new(), |lbl| {
         let mx = (from_x + to_x) / 2.0;
-        let my = (from_y + to_y) / 2.0;
-        format!(r##"<text x="{}" y="{}" text-anchor="middle" dominant-baseline="middle" font-size="12" fill="#666">{}</text>"##, 
-            mx, my - 8.0, html_escape(&lbl))
+        let my = (from_y + to_y) / 2.0 - 8.0;
+        let bg = label_bg.map_or(String::new(), |bg| {
+            let m = crate::font::measure_text(&lbl, "sans-serif", EDGE_LABEL_SIZE);
+            let (w, h) = (m.width + 2.0 * EDGE_LABEL_PADDING, m.height + 2.0 * EDGE_LABEL_PADDING);
+            format!(r#"<rect x="{}" y="{}" width="{}" height="{}" rx="3" fill="{}"/>"#, num(mx - w / 2.0), num(my - h / 2.0), num(w), num(h), html_escape(&bg))
+        });
+        format!(r##"{}<text x="{}" y="{}" text-anchor="middle" dominant-baseline="middle" font-size="{}" fill="#666">{}</text>"##, 
+            bg, num(mx), num(my), num(EDGE_LABEL_SIZE), html_escape(&lbl))
     });
     
-    format!(r##"<path d="{}" fill="none" stroke="{}" stroke-width="{}"{}/>{}"##, path_d, stroke, stroke_width, markers, label_svg)
+    format!(r##"<path d="{}" fill="none" stroke="{}" stroke-width="{}"{}/>{}"##, path_d, stroke, num(stroke_width), markers, label_svg)
 }
 
-/// Render arrow marker definitions (call once per SVG if using edges)
+/// Render arrow marker definitions (call once per SVG if using edges, and
+/// once more for each other `head`/`size` pair the edges use)
+///
+/// `head` is `triangle` (default), `open`, `diamond`, or `circle`; `size` is
+/// the marker width, 10 by default.
 #[wasm_bindgen]
-pub fn render_arrow_markers(color: &str) -> String {
-    format!(
-        r#"<marker id="arrow-start" markerWidth="10" markerHeight="7" refX="0" refY="3.5" orient="auto-start-reverse"><polygon points="10 0, 10 7, 0 3.5" fill="{color}"/></marker><marker id="arrow-end" markerWidth="10" markerHeight="7" refX="10" refY="3.5" orient="auto"><polygon points="0 0, 10 3.5, 0 7" fill="{color}"/></marker>"#,
-        color = color
-    )
+pub fn render_arrow_markers(color: &str, head: Option<String>, size: Option<f32>) -> String {
+    let (head, size) = (head.as_deref().unwrap_or(ARROW_HEADS[0]), size.unwrap_or(ARROW_SIZE));
+    let suffix = arrow_marker_suffix(head, size);
+    arrow_markers(&format!("arrow{}-start", suffix), &format!("arrow{}-end", suffix), color, head, size)
 }
 
 /// Compute best anchor points for an edge between two nodes
 /// Returns {from: [x, y], to: [x, y]}
+///
+/// `mode` is `axis` (default: facing sides along the dominant axis), `sides`
+/// (shortest outward pair of side midpoints), or `corners` (sides and corners).
 #[wasm_bindgen]
-pub fn compute_edge_anchors(from_cx: f32, from_cy: f32, from_w: f32, from_h: f32, to_cx: f32, to_cy: f32, to_w: f32, to_h: f32) -> JsValue {
+pub fn compute_edge_anchors(from_cx: f32, from_cy: f32, from_w: f32, from_h: f32, to_cx: f32, to_cy: f32, to_w: f32, to_h: f32, mode: Option<String>) -> JsValue {
     #[derive(Serialize)]
     struct EdgeAnchors { from: [f32; 2], to: [f32; 2] }
     
-    let dx = to_cx - from_cx;
-    let dy = to_cy - from_cy;
-    
-    let (from_pt, to_pt) = if dy.abs() > dx.abs() {
-        if dy > 0.0 {
-            ([from_cx, from_cy + from_h / 2.0], [to_cx, to_cy - to_h / 2.0])
-        } else {
-            ([from_cx, from_cy - from_h / 2.0], [to_cx, to_cy + to_h / 2.0])
-        }
-    } else if dx > 0.0 {
-        ([from_cx + from_w / 2.0, from_cy], [to_cx - to_w / 2.0, to_cy])
-    } else {
-        ([from_cx - from_w / 2.0, from_cy], [to_cx + to_w / 2.0, to_cy])
-    };
+    let (from, to) = ((from_cx, from_cy, from_w, from_h), (to_cx, to_cy, to_w, to_h));
+    let (from_side, to_side) = crate::anchor::best_anchors(from, to, mode.as_deref().unwrap_or("axis"), |_, _| 0);
+    let (from_pt, to_pt) = (crate::anchor::anchor_point(from, from_side), crate::anchor::anchor_point(to, to_side));
     
-    serde_wasm_bindgen::to_value(&EdgeAnchors { from: from_pt, to: to_pt }).unwrap_or(JsValue::NULL)
+    serde_wasm_bindgen::to_value(&EdgeAnchors { from: [from_pt.0, from_pt.1], to: [to_pt.0, to_pt.1] }).unwrap_or(JsValue::NULL)
 }
 
 #[derive(Deserialize)]
@@ -665,7 +905,7 @@ pub fn layout_grid(nodes: JsValue, spacing: f32) -> JsValue {
 pub fn render_symbol(id: &str, content: &str, viewbox: JsValue) -> String {
     let vb: Option<[f32; 4]> = serde_wasm_bindgen::from_value(viewbox).ok();
     let viewbox_attr = vb.map_or(String::new(), |[x, y, w, h]| 
-        format!(r#" viewBox="{} {} {} {}""#, x, y, w, h));
+        format!(r#" viewBox="{} {} {} {}""#, num(x), num(y), num(w), num(h)));
     format!(r#"<symbol id="{}"{}>{}</symbol>"#, html_escape(id), viewbox_attr, content)
 }
 
@@ -676,14 +916,14 @@ pub fn render_use(href: &str, x: f32, y: f32, width: JsValue, height: JsValue, s
     let w: Option<f32> = serde_wasm_bindgen::from_value(width).ok();
     let h: Option<f32> = serde_wasm_bindgen::from_value(height).ok();
     let size = match (w, h) {
-        (Some(wv), Some(hv)) => format!(r#" width="{}" height="{}""#, wv, hv),
-        (Some(wv), None) => format!(r#" width="{}""#, wv),
-        (None, Some(hv)) => format!(r#" height="{}""#, hv),
+        (Some(wv), Some(hv)) => format!(r#" width="{}" height="{}""#, num(wv), num(hv)),
+        (Some(wv), None) => format!(r#" width="{}""#, num(wv)),
+        (None, Some(hv)) => format!(r#" height="{}""#, num(hv)),
         _ => String::new(),
     };
     let tf = transform.map_or(String::new(), |t| format!(r#" transform="{}""#, t));
     format!("<use href=\"#{}\" x=\"{}\" y=\"{}\"{}{}{}/>" , 
-        html_escape(href), x, y, size, style.to_svg_attrs(), tf)
+        html_escape(href), num(x), num(y), size, style.to_svg_attrs(), tf)
 }
 
 // ─────────────────────────────────────────────────────────────────────────────
@@ -759,6 +999,20 @@ pub fn flatten_svg_path(d: &str, tolerance: f64) -> JsValue {
     serde_wasm_bindgen::to_value(&coords).unwrap_or(JsValue::NULL)
 }
 
+/// Smallest circle enclosing a flattened SVG path
+/// Returns [cx, cy, r]
+#[wasm_bindgen]
+pub fn path_bounding_circle(d: &str, tolerance: f64) -> JsValue {
+    let (cx, cy, r) = crate::path::bounding_circle(d, tolerance);
+    serde_wasm_bindgen::to_value(&[cx, cy, r]).unwrap_or(JsValue::NULL)
+}
+
+/// Tween between two SVG paths (`t = 0` is `a`, `t = 1` is `b`)
+#[wasm_bindgen]
+pub fn morph_paths(path_a: &str, path_b: &str, t: f64, tolerance: f64) -> String {
+    crate::path::morph(path_a, path_b, t, tolerance)
+}
+
 // ─────────────────────────────────────────────────────────────────────────────
 // Tests (native - no JsValue)
 // ─────────────────────────────────────────────────────────────────────────────
@@ -766,9 +1020,10 @@ pub fn flatten_svg_path(d: &str, tolerance: f64) -> JsValue {
 #[cfg(test)]
 mod tests {
     use super::{
-        fnv1a_hash, render_line, render_text, render_linear_gradient, render_radial_gradient,
-        render_shadow_filter, render_blur_filter, render_edge, render_arrow_markers, 
-        render_scene, WasmStyle, html_escape,
+        fnv1a_hash, render_line, render_text, render_image, render_linear_gradient, render_radial_gradient,
+        render_shadow_filter, render_blur_filter, render_edge, node_label_svg, render_arrow_markers, 
+        render_scene, render_batch, SceneInput, scene_with_bounds, svg_bounds, BoundedElement, ElementBounds, WasmStyle, html_escape, polygon_svg,
+        diff_defs, split_defs, text_bounds,
     };
     use crate::path::parse_path_bounds;
 
@@ -800,6 +1055,13 @@ mod tests {
     // Shape Rendering Tests (no JsValue)
     // ─────────────────────────────────────────────────────────────────────────
 
+    #[test]
+    fn test_render_line_rounds_coords() {
+        let third = 1.0_f32 / 3.0;
+        let svg = render_line(third, 0.0, 100.0, 2.0 * third, "#000", third, None);
+        assert_eq!(svg, r##"<line x1="0.333" y1="0" x2="100" y2="0.667" stroke="#000" stroke-width="0.333"/>"##);
+    }
+
     #[test]
     fn test_render_line() {
         let svg = render_line(0.0, 0.0, 100.0, 100.0, "#000", 2.0, None);
@@ -810,20 +1072,43 @@ mod tests {
 
     #[test]
     fn test_render_text() {
-        let svg = render_text(50.0, 50.0, "Hello", "Arial", 16.0, "bold", "middle", "#000", None);
+        let svg = render_text(50.0, 50.0, "Hello", "Arial", 16.0, "bold", "middle", "#000", None, None, None, None, None, None);
         assert!(svg.contains("<text"));
         assert!(svg.contains("Hello"));
         assert!(svg.contains(r#"font-family="Arial""#));
         assert!(svg.contains(r#"font-size="16""#));
+        assert!(!svg.contains("text-decoration"));
+        let underlined = render_text(0.0, 0.0, "Hi", "Arial", 12.0, "normal", "start", "#000", None, None, Some("underline".into()), None, None, None);
+        assert!(underlined.contains(r##" text-decoration="underline" fill="#000""##), "{}", underlined);
+        let spaced = render_text(0.0, 0.0, "Hi all", "Arial", 12.0, "normal", "start", "#000", None, None, Some("underline".into()), None, Some(1.5), Some(4.0));
+        assert!(spaced.contains(r#" letter-spacing="1.5" word-spacing="4" text-decoration="underline""#), "{}", spaced);
     }
 
     #[test]
     fn test_render_text_escapes_html() {
-        let svg = render_text(0.0, 0.0, "<script>&", "Arial", 12.0, "normal", "start", "#000", None);
+        let svg = render_text(0.0, 0.0, "<script>&", "Arial", 12.0, "normal", "start", "#000", None, None, None, None, None, None);
         assert!(svg.contains("&lt;script&gt;&amp;"));
         assert!(!svg.contains("<script>"));
     }
 
+    #[test]
+    fn test_text_bounds_rtl() {
+        let [x, _, w, _] = text_bounds(100.0, 50.0, "Test", "Arial", 16.0, "start", true);
+        assert!(x < 100.0 && (x + w - 100.0).abs() < 1e-4);
+        assert_eq!(text_bounds(100.0, 50.0, "Test", "Arial", 16.0, "end", true)[0], 100.0);
+        assert_eq!(text_bounds(100.0, 50.0, "Test", "Arial", 16.0, "start", false)[0], 100.0);
+        let svg = render_text(0.0, 0.0, "Hi", "Arial", 12.0, "normal", "start", "#000", None, None, None, Some("rtl".into()), None, None);
+        assert!(svg.contains(r#" direction="rtl""#));
+    }
+
+    #[test]
+    fn test_render_image_fit() {
+        let cover = render_image(0.0, 0.0, 10.0, 10.0, "a.png", None, Some("cover".into()));
+        assert!(cover.contains(r#" preserveAspectRatio="xMidYMid slice""#), "{}", cover);
+        let plain = render_image(0.0, 0.0, 10.0, 10.0, "a.png", None, Some("stretch".into()));
+        assert!(!plain.contains("preserveAspectRatio"), "{}", plain);
+    }
+
     // ─────────────────────────────────────────────────────────────────────────
     // Gradient & Filter Tests
     // ─────────────────────────────────────────────────────────────────────────
@@ -832,6 +1117,8 @@ mod tests {
     fn test_render_linear_gradient() {
         let svg = render_linear_gradient("grad1", "#ff0000", "#0000ff", 90.0);
         assert!(svg.contains("<linearGradient"));
+        assert!(svg.contains(r#"x1="50%" y1="0%" x2="50%" y2="100%""#), "{}", svg);
+        assert!(render_linear_gradient("g", "#000", "#fff", 0.0).contains(r#"x1="0%" y1="50%" x2="100%" y2="50%""#));
         assert!(svg.contains(r#"id="grad1""#));
         assert!(svg.contains("#ff0000"));
         assert!(svg.contains("#0000ff"));
@@ -900,33 +1187,92 @@ mod tests {
 
     #[test]
     fn test_render_edge_straight() {
-        let svg = render_edge(0.0, 0.0, 100.0, 100.0, "straight", "none", None, "#333", 2.0);
+        let svg = render_edge(0.0, 0.0, 100.0, 100.0, "straight", "none", None, "#333", 2.0, None, None, None);
         assert!(svg.contains("<path"));
         assert!(svg.contains("M0,0 L100,100"));
     }
 
     #[test]
     fn test_render_edge_curved() {
-        let svg = render_edge(0.0, 0.0, 100.0, 0.0, "curved", "forward", None, "#333", 2.0);
+        let svg = render_edge(0.0, 0.0, 100.0, 0.0, "curved", "forward", None, "#333", 2.0, None, None, None);
         assert!(svg.contains("C"));  // Bezier curve command
         assert!(svg.contains("marker-end"));
     }
 
     #[test]
     fn test_render_edge_orthogonal() {
-        let svg = render_edge(0.0, 0.0, 100.0, 100.0, "orthogonal", "both", Some("->".into()), "#333", 2.0);
+        let svg = render_edge(0.0, 0.0, 100.0, 100.0, "orthogonal", "both", Some("->".into()), "#333", 2.0, None, None, None);
         assert!(svg.contains("marker-start"));
         assert!(svg.contains("marker-end"));
     }
 
+    #[test]
+    fn test_render_edge_label_bg() {
+        let svg = render_edge(0.0, 0.0, 100.0, 0.0, "straight", "none", Some("yes".into()), "#333", 2.0, None, None, Some("#fff".into()));
+        let m = crate::font::measure_text("yes", "sans-serif", 12.0);
+        assert!(svg.contains(&format!(r##"width="{}" height="{}" rx="3" fill="#fff"/><text"##, m.width + 8.0, m.height + 8.0)), "{}", svg);
+    }
+
+    #[test]
+    fn test_node_label_wraps_and_truncates() {
+        let label = "Validate the incoming payment request";
+        let wrapped = node_label_svg(50.0, 50.0, 80.0, 200.0, label, false);
+        assert!(wrapped.matches("<tspan").count() >= 2 && !wrapped.contains('…'), "{}", wrapped);
+        assert!(wrapped.contains(r#"<tspan x="50" dy="-"#), "{}", wrapped);
+
+        let cut = node_label_svg(50.0, 50.0, 80.0, 40.0, label, true);
+        assert!(cut.contains('…'), "{}", cut);
+        assert!(cut.matches("<tspan").count() < wrapped.matches("<tspan").count(), "{}", cut);
+
+        let short = node_label_svg(50.0, 50.0, 80.0, 40.0, "Label", true);
+        assert!(short.ends_with(r##"fill="#000">Label</text>"##), "{}", short);
+    }
+
     #[test]
     fn test_render_arrow_markers() {
-        let svg = render_arrow_markers("#333");
+        let svg = render_arrow_markers("#333", None, None);
         assert!(svg.contains("<marker"));
         assert!(svg.contains(r#"id="arrow-start""#));
         assert!(svg.contains(r#"id="arrow-end""#));
     }
 
+    #[test]
+    fn test_render_edge_arrow_head() {
+        let edge = render_edge(0.0, 0.0, 100.0, 0.0, "straight", "forward", None, "#333", 2.0, Some("open".into()), Some(12.0), None);
+        assert!(edge.contains(r#"marker-end="url(#arrow-open-12-end)""#), "{}", edge);
+        let defs = render_arrow_markers("#333", Some("open".into()), Some(12.0));
+        assert!(defs.contains(r#"<marker id="arrow-open-12-end""#), "{}", defs);
+        assert!(defs.contains("<path"), "{}", defs);
+    }
+
+    // ─────────────────────────────────────────────────────────────────────────
+    // Defs Diffing Tests
+    // ─────────────────────────────────────────────────────────────────────────
+
+    #[test]
+    fn test_diff_defs_single_gradient_update() {
+        let grad = |id: &str, color: &str| render_linear_gradient(id, color, "#fff", 0.0);
+        let old = [grad("g1", "#f00"), grad("g2", "#0f0"), grad("g3", "#00f"), render_blur_filter("blur", 2.0)].concat();
+        let new = [grad("g1", "#f00"), grad("g2", "#ff0"), grad("g3", "#00f"), render_blur_filter("blur", 2.0)].concat();
+        assert_eq!(split_defs(&old).map(|d| d.len()), Some(4));
+        let ops = diff_defs(&old, &new);
+        assert_eq!(ops.len(), 1);
+        assert_eq!((ops[0].op_type.as_str(), ops[0].id.as_deref()), ("update_def", Some("g2")));
+        assert_eq!(ops[0].svg.as_deref(), Some(grad("g2", "#ff0").as_str()));
+        assert!(diff_defs(&old, &old).is_empty());
+    }
+
+    #[test]
+    fn test_diff_defs_add_remove_and_fallback() {
+        let (a, b) = (render_blur_filter("a", 1.0), render_blur_filter("b", 1.0));
+        let ops = diff_defs(&a, &b);
+        let kinds: Vec<_> = ops.iter().map(|o| (o.op_type.as_str(), o.id.as_deref())).collect();
+        assert_eq!(kinds, vec![("add_def", Some("b")), ("remove_def", Some("a"))]);
+        // A def without an id can't be tracked, so everything is replaced
+        let ops = diff_defs(&a, "<filter><feGaussianBlur stdDeviation=\"1\"/></filter>");
+        assert_eq!((ops.len(), ops[0].op_type.as_str()), (1, "update_defs"));
+    }
+
     // ─────────────────────────────────────────────────────────────────────────
     // Scene Rendering Tests
     // ─────────────────────────────────────────────────────────────────────────
@@ -941,6 +1287,15 @@ mod tests {
         assert!(svg.contains("<rect"));
     }
 
+    #[test]
+    fn test_render_scene_transparent_background() {
+        for bg in ["none", "transparent"] {
+            let svg = render_scene("medium", bg, "", "");
+            assert!(!svg.contains("<rect"), "{}", svg);
+        }
+        assert!(render_scene("medium", "#fff", "", "").contains(r##"<rect width="100%" height="100%" fill="#fff"/>"##));
+    }
+
     #[test]
     fn test_render_scene_with_defs() {
         let svg = render_scene("small", "#000", "<linearGradient id=\"g1\"/>", "");
@@ -965,6 +1320,39 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_render_batch_preserves_order() {
+        let inputs: Vec<SceneInput> = ["nano", "large", "giant"].iter().enumerate().map(|(i, size)| SceneInput {
+            size: size.to_string(), background: "#fff".into(), elements: format!("<rect id=\"r{}\"/>", i), ..Default::default()
+        }).collect();
+        let out = render_batch(&inputs);
+        assert_eq!(out.len(), 3);
+        for (svg, s) in out.iter().zip(&inputs) {
+            assert_eq!(svg, &render_scene(&s.size, &s.background, &s.defs, &s.elements));
+        }
+        assert!(out[1].contains("width=\"96\"") && out[1].contains("id=\"r1\""));
+        assert!(render_batch(&[]).is_empty());
+    }
+
+    #[test]
+    fn test_scene_with_bounds_transformed_rect() {
+        let rect = BoundedElement {
+            id: "r".into(), svg: r#"<rect x="10" y="10" width="20" height="10" transform="translate(5 5) scale(2)"/>"#.into(),
+        };
+        let out = scene_with_bounds("medium", "#fff", "", &[rect]);
+        assert!(out.svg.contains("<rect x=\"10\""));
+        assert_eq!(out.bounds, vec![ElementBounds { id: "r".into(), x: 25.0, y: 25.0, w: 40.0, h: 20.0 }]);
+    }
+
+    #[test]
+    fn test_svg_bounds_unions_group_children() {
+        let svg = r#"<g transform="translate(10 0)"><circle cx="5" cy="5" r="5"/><line x1="0" y1="0" x2="20" y2="4"/></g><polygon points="0,30 4,30 2,32"/>"#;
+        assert_eq!(svg_bounds(svg), (0.0, 0.0, 30.0, 32.0));
+        let text = render_text(50.0, 20.0, "A&B", "sans-serif", 12.0, "400", "middle", "#000", None, None, None, None, None, None);
+        let [x, y, w, h] = text_bounds(50.0, 20.0, "A&B", "sans-serif", 12.0, "middle", false);
+        assert_eq!(svg_bounds(&text), (x, y, w, h));
+    }
+
     // ─────────────────────────────────────────────────────────────────────────
     // Style Tests
     // ─────────────────────────────────────────────────────────────────────────
@@ -986,6 +1374,10 @@ mod tests {
             opacity: 0.5,
             corner: 0.0,
             filter: None,
+            dash: None,
+            dash_offset: 0.0,
+            arrow: None,
+            fill_rule: None,
         };
         let attrs = style.to_svg_attrs();
         assert!(attrs.contains("fill=\"#ff0\""));
@@ -994,6 +1386,27 @@ mod tests {
         assert!(attrs.contains("opacity=\"0.5\""));
     }
 
+    #[test]
+    fn test_wasm_polygon_matches_scene_output() {
+        let style = WasmStyle {
+            fill: Some("#f00".into()),
+            stroke: Some("#000".into()),
+            stroke_width: 2.0,
+            opacity: 0.5,
+            corner: 0.0,
+            filter: Some("s".into()),
+            dash: Some("4 2".into()),
+            dash_offset: 0.0,
+            arrow: None,
+            fill_rule: None,
+        };
+        // Same bytes as the scene's `Polygon::to_svg` (scene::shape `test_canonical_attr_order`)
+        assert_eq!(
+            polygon_svg(&[(0.0, 0.0), (10.0, 0.0), (5.0, 8.0)], &style, Some("rotate(45)".into())),
+            r##"<polygon points="0,0 10,0 5,8" fill="#f00" stroke="#000" stroke-width="2" stroke-dasharray="4 2" opacity="0.5" filter="url(#s)" transform="rotate(45)"/>"##
+        );
+    }
+
     #[test]
     fn test_wasm_style_with_filter() {
         let style = WasmStyle {
@@ -1003,6 +1416,10 @@ mod tests {
             opacity: 1.0,
             corner: 0.0,
             filter: Some("shadow1".into()),
+            dash: None,
+            dash_offset: 0.0,
+            arrow: None,
+            fill_rule: None,
         };
         let attrs = style.to_svg_attrs();
         assert!(attrs.contains("filter=\"url(#shadow1)\""));
@@ -0,0 +1,124 @@
+//! RGBA colors, shared by scene rendering and DSL style interpolation
+
+#[cfg(feature = "python")]
+use pyo3::prelude::*;
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+
+/// RGBA color representation
+#[derive(Clone, Debug, Default, Serialize, Deserialize, TS)]
+#[ts(export)]
+#[cfg_attr(feature = "python", pyclass(get_all, set_all))]
+pub struct Color {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+    pub a: f32,
+}
+
+#[cfg(feature = "python")]
+#[pymethods]
+impl Color {
+    #[new]
+    #[pyo3(signature = (r=0, g=0, b=0, a=1.0))]
+    fn py_new(r: u8, g: u8, b: u8, a: f32) -> Self { Self { r, g, b, a } }
+
+    #[staticmethod]
+    fn from_hex(hex: &str) -> PyResult<Self> { Ok(Self::parse_hex(hex)) }
+    fn to_css(&self) -> String { self.css() }
+    #[pyo3(name = "to_hex")]
+    fn py_to_hex(&self) -> String { self.to_hex() }
+    #[pyo3(name = "luminance")]
+    fn py_luminance(&self) -> f32 { self.luminance() }
+    #[pyo3(name = "contrast_ratio")]
+    fn py_contrast_ratio(&self, other: &Color) -> f32 { self.contrast_ratio(other) }
+    #[pyo3(name = "best_text_color")]
+    fn py_best_text_color(&self, candidates: Vec<Color>) -> Color { self.best_text_color(&candidates) }
+}
+
+impl Color {
+    pub fn parse_hex(hex: &str) -> Self {
+        let hex = hex.trim_start_matches('#');
+        let (r, g, b) = match hex.len() {
+            3 => (
+                u8::from_str_radix(&hex[0..1].repeat(2), 16).unwrap_or(0),
+                u8::from_str_radix(&hex[1..2].repeat(2), 16).unwrap_or(0),
+                u8::from_str_radix(&hex[2..3].repeat(2), 16).unwrap_or(0),
+            ),
+            6 => (
+                u8::from_str_radix(&hex[0..2], 16).unwrap_or(0),
+                u8::from_str_radix(&hex[2..4], 16).unwrap_or(0),
+                u8::from_str_radix(&hex[4..6], 16).unwrap_or(0),
+            ),
+            _ => (0, 0, 0),
+        };
+        Self { r, g, b, a: 1.0 }
+    }
+    pub fn css(&self) -> String { format!("rgba({},{},{},{})", self.r, self.g, self.b, self.a) }
+
+    /// Parse `#rgb`/`#rrggbb`, rejecting anything else (named colors, `url(...)`, vars)
+    pub fn try_parse_hex(s: &str) -> Option<Self> {
+        let hex = s.strip_prefix('#')?;
+        (matches!(hex.len(), 3 | 6) && hex.chars().all(|c| c.is_ascii_hexdigit())).then(|| Self::parse_hex(hex))
+    }
+
+    /// Linear blend toward `other` (`t = 0` is `self`, `t = 1` is `other`)
+    pub fn mix(&self, other: &Color, t: f32) -> Color {
+        let t = t.clamp(0.0, 1.0);
+        let ch = |a: u8, b: u8| (a as f32 + (b as f32 - a as f32) * t).round() as u8;
+        Color { r: ch(self.r, other.r), g: ch(self.g, other.g), b: ch(self.b, other.b), a: self.a + (other.a - self.a) * t }
+    }
+
+    /// `#rrggbb`, with a trailing alpha byte when translucent
+    pub fn to_hex(&self) -> String {
+        let rgb = format!("#{:02x}{:02x}{:02x}", self.r, self.g, self.b);
+        if self.a >= 1.0 { rgb } else { format!("{}{:02x}", rgb, (self.a.clamp(0.0, 1.0) * 255.0).round() as u8) }
+    }
+
+    /// WCAG relative luminance, 0 for black to 1 for white. Alpha is ignored.
+    pub fn luminance(&self) -> f32 {
+        let linear = |c: u8| {
+            let c = c as f32 / 255.0;
+            if c <= 0.03928 { c / 12.92 } else { ((c + 0.055) / 1.055).powf(2.4) }
+        };
+        0.2126 * linear(self.r) + 0.7152 * linear(self.g) + 0.0722 * linear(self.b)
+    }
+
+    /// WCAG contrast ratio against `other`, from 1 (identical) to 21 (black on white)
+    pub fn contrast_ratio(&self, other: &Color) -> f32 {
+        let (a, b) = (self.luminance(), other.luminance());
+        (a.max(b) + 0.05) / (a.min(b) + 0.05)
+    }
+
+    /// The candidate most readable on top of `self`; the first wins ties, and
+    /// opaque black stands in for an empty list
+    pub fn best_text_color(&self, candidates: &[Color]) -> Color {
+        candidates.iter()
+            .fold(None::<&Color>, |best, c| match best {
+                Some(b) if b.contrast_ratio(self) >= c.contrast_ratio(self) => Some(b),
+                _ => Some(c),
+            })
+            .cloned()
+            .unwrap_or(Color { r: 0, g: 0, b: 0, a: 1.0 })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[test] fn test_color_mix() {
+        let mid = Color::parse_hex("#000").mix(&Color::parse_hex("#fff"), 0.5);
+        assert_eq!(mid.to_hex(), "#808080");
+        assert!(Color::try_parse_hex("red").is_none() && Color::try_parse_hex("#12345").is_none());
+    }
+    #[test] fn test_color_contrast() {
+        let (black, white) = (Color::parse_hex("#000"), Color::parse_hex("#fff"));
+        assert!((black.contrast_ratio(&white) - 21.0).abs() < 0.01);
+        assert_eq!(white.contrast_ratio(&black), black.contrast_ratio(&white));
+        assert!((Color::parse_hex("#777").contrast_ratio(&Color::parse_hex("#777")) - 1.0).abs() < 1e-6);
+        let candidates = [black.clone(), white.clone()];
+        assert_eq!(Color::parse_hex("#1e293b").best_text_color(&candidates).to_hex(), "#ffffff");
+        assert_eq!(Color::parse_hex("#fde047").best_text_color(&candidates).to_hex(), "#000000");
+        assert_eq!(white.best_text_color(&[]).to_hex(), "#000000");
+    }
+}
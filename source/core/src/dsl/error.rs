@@ -0,0 +1,157 @@
+//! Diagnostics shared by the lexer and parser: error kinds, severities and source spans
+
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+
+#[cfg(feature = "python")]
+use pyo3::prelude::*;
+
+/// Error severity levels
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[ts(export)]
+#[cfg_attr(feature = "python", pyclass)]
+pub enum ErrorSeverity {
+    Error,
+    Warning,
+    Hint,
+}
+
+/// Error categories for structured diagnostics
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[ts(export)]
+#[cfg_attr(feature = "python", pyclass)]
+pub enum ErrorKind {
+    UnexpectedToken,
+    UnknownCommand,
+    InvalidValue,
+    MissingToken,
+    InvalidIndentation,
+    UnterminatedBlock,
+    InvalidProperty,
+    UndefinedVariable,
+    DuplicateVariable,
+    DuplicateSymbol,
+    UndefinedSymbol,
+    CyclicSymbol,
+    UnsupportedVersion,
+    UnusedVariable,
+    UnusedSymbol,
+    CyclicInclude,
+    MissingInclude,
+    NestingTooDeep,
+    MissingGeometry,
+    UnterminatedString,
+}
+
+impl ErrorKind {
+    pub fn code(self) -> &'static str {
+        match self {
+            Self::UnexpectedToken => "E001",
+            Self::UnknownCommand => "E002",
+            Self::InvalidValue => "E003",
+            Self::MissingToken => "E004",
+            Self::InvalidIndentation => "E005",
+            Self::UnterminatedBlock => "E006",
+            Self::InvalidProperty => "E007",
+            Self::UndefinedVariable => "E008",
+            Self::DuplicateVariable => "E009",
+            Self::DuplicateSymbol => "E010",
+            Self::UndefinedSymbol => "E011",
+            Self::CyclicSymbol => "E012",
+            Self::UnsupportedVersion => "E013",
+            Self::UnusedVariable => "E014",
+            Self::UnusedSymbol => "E015",
+            Self::CyclicInclude => "E016",
+            Self::MissingInclude => "E017",
+            Self::NestingTooDeep => "E018",
+            Self::MissingGeometry => "E019",
+            Self::UnterminatedString => "E020",
+        }
+    }
+}
+
+/// Source span for error locations
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize, TS)]
+#[ts(export)]
+#[cfg_attr(feature = "python", pyclass(get_all))]
+pub struct Span {
+    pub start_line: usize,
+    pub start_col: usize,
+    pub end_line: usize,
+    pub end_col: usize,
+    /// Start and end byte offsets in the source, when built from tokens
+    #[serde(default)]
+    pub bytes: Option<(usize, usize)>,
+}
+
+impl Span {
+    pub fn point(line: usize, col: usize) -> Self {
+        Self { start_line: line, start_col: col, end_line: line, end_col: col + 1, bytes: None }
+    }
+
+    pub fn range(start_line: usize, start_col: usize, end_line: usize, end_col: usize) -> Self {
+        Self { start_line, start_col, end_line, end_col, bytes: None }
+    }
+
+    pub fn with_bytes(mut self, start: usize, end: usize) -> Self { self.bytes = Some((start, end)); self }
+}
+
+/// Parse error with recovery context
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, TS)]
+#[ts(export)]
+#[cfg_attr(feature = "python", pyclass(get_all))]
+pub struct ParseError {
+    pub message: String,
+    pub line: usize,
+    pub col: usize,
+    pub kind: ErrorKind,
+    /// Stable diagnostic code (mirrors `kind.code()`) for tooling
+    pub code: String,
+    pub severity: ErrorSeverity,
+    pub span: Span,
+    pub suggestion: Option<String>,
+    pub recovered: bool,
+}
+
+impl ParseError {
+    pub fn new(message: impl Into<String>, kind: ErrorKind, line: usize, col: usize) -> Self {
+        Self {
+            message: message.into(),
+            line, col,
+            kind,
+            code: kind.code().into(),
+            severity: ErrorSeverity::Error,
+            span: Span::point(line, col),
+            suggestion: None,
+            recovered: false,
+        }
+    }
+
+    /// Error covering `span`, located at its start
+    pub fn spanning(message: impl Into<String>, kind: ErrorKind, span: Span) -> Self {
+        Self { span: span.clone(), ..Self::new(message, kind, span.start_line, span.start_col) }
+    }
+
+    pub fn with_span(mut self, span: Span) -> Self { self.span = span; self }
+    pub fn with_suggestion(mut self, s: impl Into<String>) -> Self { self.suggestion = Some(s.into()); self }
+    pub fn with_severity(mut self, sev: ErrorSeverity) -> Self { self.severity = sev; self }
+    pub fn as_recovered(mut self) -> Self { self.recovered = true; self }
+
+    /// Serialize as a machine-readable diagnostic (code, span, suggestion included)
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).unwrap_or_else(|_| "null".to_string())
+    }
+}
+
+#[cfg(feature = "python")]
+#[pymethods]
+impl ParseError {
+    fn __repr__(&self) -> String {
+        format!("ParseError[{}]({:?}, {}:{}-{}:{}{})",
+            self.kind.code(), self.message, self.span.start_line, self.span.start_col, self.span.end_line, self.span.end_col,
+            self.suggestion.as_ref().map(|s| format!(", suggestion={:?}", s)).unwrap_or_default())
+    }
+
+    #[pyo3(name = "to_json")]
+    fn py_to_json(&self) -> String { self.to_json() }
+}
@@ -6,6 +6,7 @@ use lazy_static::lazy_static;
 use regex_lite::Regex;
 use serde::{Deserialize, Serialize};
 use ts_rs::TS;
+use super::error::{ErrorKind, ParseError, Span};
 
 #[cfg(feature = "python")]
 use pyo3::prelude::*;
@@ -76,7 +77,30 @@ impl CanvasSize {
         }
     }
     pub fn pixels(self) -> u32 { self as u32 }
+
+    /// Every tier, smallest first
+    pub const ALL: [Self; 10] = [
+        Self::Nano, Self::Micro, Self::Tiny, Self::Small, Self::Medium,
+        Self::Large, Self::XLarge, Self::Huge, Self::Massive, Self::Giant,
+    ];
+
+    /// The tier exactly `px` pixels wide, if there is one
+    pub fn from_pixels(px: u32) -> Option<Self> { Self::ALL.into_iter().find(|s| s.pixels() == px) }
+
+    /// The tier closest to `px`; ties go to the smaller tier
+    pub fn nearest(px: u32) -> Self {
+        Self::ALL.into_iter().min_by_key(|s| s.pixels().abs_diff(px)).unwrap_or(Self::Medium)
+    }
     pub fn dimensions(self) -> (u32, u32) { let p = self.pixels(); (p, p) }
+
+    /// Dimensions under an aspect ratio; the size tier fixes the shorter side,
+    /// and the longer one saturates at `u32::MAX` for absurd ratios
+    pub fn dimensions_with(self, aspect: AspectRatio) -> (u32, u32) {
+        let p = u64::from(self.pixels());
+        let (w, h) = (u64::from(aspect.w.max(1)), u64::from(aspect.h.max(1)));
+        let long = |long: u64, short: u64| u32::try_from((p * long + short / 2) / short).unwrap_or(u32::MAX);
+        if w >= h { (long(w, h), self.pixels()) } else { (self.pixels(), long(h, w)) }
+    }
     
     /// All valid size names for error messages
     pub fn all_names() -> &'static [&'static str] {
@@ -96,11 +120,52 @@ impl std::fmt::Display for CanvasSize {
     }
 }
 
+/// Canvas aspect ratio (`2:1`, `wide`, `tall`); square by default
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize, TS)]
+#[ts(export)]
+#[cfg_attr(feature = "python", pyclass(get_all))]
+pub struct AspectRatio {
+    pub w: u32,
+    pub h: u32,
+}
+
+impl Default for AspectRatio {
+    fn default() -> Self { Self::SQUARE }
+}
+
+impl AspectRatio {
+    pub const SQUARE: Self = Self { w: 1, h: 1 };
+
+    pub fn new(w: u32, h: u32) -> Option<Self> { (w > 0 && h > 0).then_some(Self { w, h }) }
+
+    /// Named ratio keywords: `square` (1:1), `wide` (2:1), `tall` (1:2)
+    pub fn from_name(s: &str) -> Option<Self> {
+        match s.to_lowercase().as_str() {
+            "square" => Some(Self::SQUARE),
+            "wide" => Some(Self { w: 2, h: 1 }),
+            "tall" => Some(Self { w: 1, h: 2 }),
+            _ => None,
+        }
+    }
+
+    pub fn is_square(self) -> bool { self.w == self.h }
+}
+
+impl std::fmt::Display for AspectRatio {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result { write!(f, "{}:{}", self.w, self.h) }
+}
+
 #[cfg(feature = "python")]
 #[pymethods]
 impl CanvasSize {
     #[staticmethod]
     fn from_name(name: &str) -> Option<Self> { Self::from_str(name) }
+    #[staticmethod]
+    #[pyo3(name = "from_pixels")]
+    fn py_from_pixels(px: u32) -> Option<Self> { Self::from_pixels(px) }
+    #[staticmethod]
+    #[pyo3(name = "nearest")]
+    fn py_nearest(px: u32) -> Self { Self::nearest(px) }
     fn to_pixels(&self) -> u32 { self.pixels() }
     fn to_dimensions(&self) -> (u32, u32) { self.dimensions() }
     fn __repr__(&self) -> String { format!("CanvasSize.{} ({}px)", self, self.pixels()) }
@@ -112,6 +177,12 @@ impl TokenType {
     fn __repr__(&self) -> String {
         format!("{:?}", self)
     }
+
+    /// Variant name, e.g. `"Ident"`
+    #[getter]
+    fn name(&self) -> String { format!("{:?}", self) }
+
+    fn __hash__(&self) -> u64 { *self as u64 }
 }
 
 /// Token value variants
@@ -124,12 +195,28 @@ pub enum TokenValue {
     Pair(f64, f64),
     /// Percentage pair (both values are percentages 0-100)
     PercentPair(f64, f64),
+    /// Bracketed list (`[4 2 1]`) held by a variable; the lexer itself only emits the brackets
+    List(Vec<TokenValue>),
 }
 
 impl Default for TokenValue {
     fn default() -> Self { Self::None }
 }
 
+#[cfg(feature = "python")]
+impl TokenValue {
+    /// Python object: str, float, tuple, list, or None
+    pub fn to_py(&self, py: Python<'_>) -> PyObject {
+        match self {
+            TokenValue::None => py.None(),
+            TokenValue::Str(s) => s.clone().into_py(py),
+            TokenValue::Num(n) => n.into_py(py),
+            TokenValue::Pair(a, b) | TokenValue::PercentPair(a, b) => (*a, *b).into_py(py),
+            TokenValue::List(items) => pyo3::types::PyList::new(py, items.iter().map(|v| v.to_py(py))).into(),
+        }
+    }
+}
+
 /// A single token from the lexer
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize, TS)]
 #[ts(export)]
@@ -139,6 +226,12 @@ pub struct Token {
     pub value: TokenValue,
     pub line: usize,
     pub col: usize,
+    /// Exact source text (`#FFF`, `1.50`); empty for synthetic indent/dedent/EOF tokens
+    #[serde(default)]
+    pub raw: String,
+    /// Byte offset of the token's start in the source
+    #[serde(default)]
+    pub offset: usize,
 }
 
 #[cfg(feature = "python")]
@@ -153,6 +246,12 @@ impl Token {
     #[getter]
     fn get_col(&self) -> usize { self.col }
 
+    #[getter]
+    fn get_raw(&self) -> &str { &self.raw }
+
+    #[getter]
+    fn get_offset(&self) -> usize { self.offset }
+
     #[getter]
     fn value_str(&self) -> Option<String> {
         match &self.value {
@@ -177,25 +276,50 @@ impl Token {
         }
     }
 
-    /// Get value as Python object (str, float, tuple, or None)
+    /// Get value as Python object (str, float, tuple, list, or None)
     #[getter]
-    fn value(&self, py: Python<'_>) -> PyObject {
-        match &self.value {
-            TokenValue::None => py.None(),
-            TokenValue::Str(s) => s.clone().into_py(py),
-            TokenValue::Num(n) => n.into_py(py),
-            TokenValue::Pair(a, b) | TokenValue::PercentPair(a, b) => (*a, *b).into_py(py),
-        }
+    fn value(&self, py: Python<'_>) -> PyObject { self.value.to_py(py) }
+
+    fn __repr__(&self) -> String { self.to_string() }
+
+    fn __eq__(&self, other: &Self) -> bool { self == other }
+
+    fn __hash__(&self) -> u64 {
+        use std::hash::{Hash, Hasher};
+        let mut hasher = std::collections::hash_map::DefaultHasher::new();
+        (self.ttype, self.line, self.col, &self.raw).hash(&mut hasher);
+        hasher.finish()
     }
+}
 
-    fn __repr__(&self) -> String {
-        format!("Token({:?}, {:?}, {}:{})", self.ttype, self.value, self.line, self.col)
+/// `Token(Ident "rect" @1:1)`, with a 1-based line and column
+impl std::fmt::Display for Token {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Token({:?}", self.ttype)?;
+        match &self.value {
+            TokenValue::None => {}
+            TokenValue::Str(s) => write!(f, " {:?}", s)?,
+            TokenValue::Num(n) => write!(f, " {}", n)?,
+            TokenValue::Pair(a, b) => write!(f, " {},{}", a, b)?,
+            TokenValue::PercentPair(a, b) => write!(f, " {}%,{}%", a, b)?,
+            TokenValue::List(items) => write!(f, " [{} items]", items.len())?,
+        }
+        write!(f, " @{}:{})", self.line + 1, self.col + 1)
     }
 }
 
 impl Token {
     pub fn new(ttype: TokenType, value: TokenValue, line: usize, col: usize) -> Self {
-        Self { ttype, value, line, col }
+        Self { ttype, value, line, col, raw: String::new(), offset: 0 }
+    }
+
+    pub fn with_raw(mut self, raw: impl Into<String>) -> Self { self.raw = raw.into(); self }
+    pub fn with_offset(mut self, offset: usize) -> Self { self.offset = offset; self }
+
+    /// Source range the token covers; synthetic tokens cover one column
+    pub fn span(&self) -> Span {
+        let width = self.raw.chars().count().max(1);
+        Span::range(self.line, self.col, self.line, self.col + width).with_bytes(self.offset, self.offset + self.raw.len().max(1))
     }
 }
 
@@ -213,6 +337,9 @@ lazy_static! {
         Pattern { regex: Regex::new(r"^@keyframes\b").unwrap(), ttype: Some(TokenType::AtKeyframes) },
         Pattern { regex: Regex::new(r"^\$[a-zA-Z_][a-zA-Z0-9_]*").unwrap(), ttype: Some(TokenType::Var) },
         Pattern { regex: Regex::new(r"^#[0-9a-fA-F]{3,8}\b").unwrap(), ttype: Some(TokenType::Color) },
+        // Theme colors resolved by the embedding page, passed through verbatim
+        Pattern { regex: Regex::new(r"^currentColor\b").unwrap(), ttype: Some(TokenType::Color) },
+        Pattern { regex: Regex::new(r"^var\(\s*--[\w-]+\s*(,[^)\n]*)?\)").unwrap(), ttype: Some(TokenType::Color) },
         // Percent pairs must come before regular pairs (50%,50% or 50%x50%)
         Pattern { regex: Regex::new(r"^-?\d+\.?\d*%[,x]-?\d+\.?\d*%").unwrap(), ttype: Some(TokenType::PercentPair) },
         Pattern { regex: Regex::new(r"^-?\d+\.?\d*[,x]-?\d+\.?\d*").unwrap(), ttype: Some(TokenType::Pair) },
@@ -228,67 +355,154 @@ lazy_static! {
         Pattern { regex: Regex::new(r"^->").unwrap(), ttype: Some(TokenType::Arrow) },
         Pattern { regex: Regex::new(r"^:").unwrap(), ttype: Some(TokenType::Colon) },
         Pattern { regex: Regex::new(r"^=").unwrap(), ttype: Some(TokenType::Equals) },
+        // `calc(...)` stays one identifier so its operators survive for the parser
+        Pattern { regex: Regex::new(r"^calc\([^)\n]*\)").unwrap(), ttype: Some(TokenType::Ident) },
         // Size keywords before general identifiers
         Pattern { regex: Regex::new(r"^(nano|micro|tiny|small|medium|large|xlarge|xl|huge|massive|giant)\b").unwrap(), ttype: Some(TokenType::Size) },
         Pattern { regex: Regex::new(r"^[a-zA-Z_][a-zA-Z0-9_-]*").unwrap(), ttype: Some(TokenType::Ident) },
     ];
 }
 
+/// Default columns a tab advances indentation to (the next multiple of)
+pub const DEFAULT_TAB_WIDTH: usize = 4;
+
+/// Indentation settings for a `Lexer`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LexerOptions {
+    /// Columns a tab advances to (the next multiple of)
+    pub tab_width: usize,
+    /// Columns per block level. `None` accepts any consistent indent (each deeper
+    /// column opens a block); `Some(n)` requires multiples of `n`, one level at a time.
+    pub indent_unit: Option<usize>,
+}
+
+impl Default for LexerOptions {
+    fn default() -> Self { Self { tab_width: DEFAULT_TAB_WIDTH, indent_unit: None } }
+}
+
 /// Lexer for tokenizing DSL source
 #[cfg_attr(feature = "python", pyclass)]
 pub struct Lexer {
     lines: Vec<String>,
     indent_stack: Vec<usize>,
     line_idx: usize,
+    options: LexerOptions,
+    /// Indent character of the first indented line, and that line
+    indent_style: Option<(char, usize)>,
+    /// Indentation problems (mixed tabs and spaces), in source order
+    pub errors: Vec<ParseError>,
 }
 
 impl Lexer {
     /// Create a new lexer for the given source
     pub fn new(source: &str) -> Self {
+        Self::with_options(source, LexerOptions::default())
+    }
+
+    /// Create a lexer with explicit tab width and indent unit (zero widths are treated as 1)
+    pub fn with_options(source: &str, options: LexerOptions) -> Self {
         Self {
             lines: source.split('\n').map(String::from).collect(),
             indent_stack: vec![0],
             line_idx: 0,
+            options: LexerOptions { tab_width: options.tab_width.max(1), indent_unit: options.indent_unit.map(|u| u.max(1)) },
+            indent_style: None,
+            errors: Vec::new(),
         }
     }
 
+    /// Set how many columns a tab indents to (minimum 1)
+    pub fn with_tab_width(mut self, width: usize) -> Self { self.options.tab_width = width.max(1); self }
+
     /// Tokenize the source and return all tokens
     pub fn tokenize(&mut self) -> Vec<Token> {
         let mut tokens = Vec::new();
         let num_lines = self.lines.len();
+        let mut line_start = 0;
 
         for lineno in 0..num_lines {
             self.line_idx = lineno;
             // Clone the line to avoid borrowing self.lines while mutating self
             let line = self.lines[lineno].clone();
             let stripped = line.trim_start();
+            let start = line_start;
+            line_start += line.len() + 1;
 
             // Skip empty and comment-only lines
             if stripped.is_empty() || stripped.starts_with("//") {
                 continue;
             }
 
-            let indent = line.len() - stripped.len();
+            let width = self.measure_indent(&line[..line.len() - stripped.len()], lineno);
+            let indent = self.indent_level(width, lineno);
             let line_len = line.len();
-            tokens.extend(self.handle_indent(indent, lineno));
-            tokens.extend(self.tokenize_line(stripped, lineno));
-            tokens.push(Token::new(TokenType::Newline, TokenValue::Str("\n".into()), lineno, line_len));
+            tokens.extend(self.handle_indent(indent, width, lineno).into_iter().map(|t| t.with_offset(start)));
+            tokens.extend(self.tokenize_line(stripped, lineno, start + line_len - stripped.len()));
+            tokens.push(Token::new(TokenType::Newline, TokenValue::Str("\n".into()), lineno, line_len).with_raw("\n").with_offset(start + line_len));
         }
 
         // Close remaining indents
+        let end = line_start.saturating_sub(1);
         while self.indent_stack.len() > 1 {
             self.indent_stack.pop();
-            tokens.push(Token::new(TokenType::Dedent, TokenValue::None, num_lines.saturating_sub(1), 0));
+            tokens.push(Token::new(TokenType::Dedent, TokenValue::None, num_lines.saturating_sub(1), 0).with_offset(end));
         }
 
-        tokens.push(Token::new(TokenType::Eof, TokenValue::None, num_lines.saturating_sub(1), 0));
+        tokens.push(Token::new(TokenType::Eof, TokenValue::None, num_lines.saturating_sub(1), 0).with_offset(end));
         tokens
     }
 
-    fn handle_indent(&mut self, indent: usize, line: usize) -> Vec<Token> {
+    /// Indent width, with tabs advancing to the next tab stop. Reports a line whose
+    /// indent mixes tabs and spaces, or uses a different one than earlier lines.
+    fn measure_indent(&mut self, lead: &str, line: usize) -> usize {
+        let mut width = 0;
+        for c in lead.chars() {
+            let tab = self.options.tab_width;
+            width = if c == '\t' { (width / tab + 1) * tab } else { width + 1 };
+        }
+        let Some(first) = lead.chars().next() else { return width };
+        let is_tab = |c: char| c == '\t';
+        let span = |col: usize| Span::range(line, col, line, lead.chars().count());
+        if let Some(col) = lead.chars().position(|c| is_tab(c) != is_tab(first)) {
+            self.errors.push(ParseError::new("Indentation mixes tabs and spaces", ErrorKind::InvalidIndentation, line, col)
+                .with_span(span(col)).with_suggestion("Indent with either tabs or spaces, not both"));
+        } else if let Some((style, first_line)) = self.indent_style {
+            if is_tab(style) != is_tab(first) {
+                let (this, other) = if is_tab(first) { ("tabs", "spaces") } else { ("spaces", "tabs") };
+                self.errors.push(ParseError::new(
+                    format!("Line indented with {}, but line {} uses {}", this, first_line + 1, other),
+                    ErrorKind::InvalidIndentation, line, 0,
+                ).with_span(span(0)).with_suggestion(format!("Indent with {} throughout the file", other)));
+            }
+        } else {
+            self.indent_style = Some((first, line));
+        }
+        width
+    }
+
+    /// Block level for an indent `width`: the width itself when no unit is configured,
+    /// else whole units, reporting widths that aren't a multiple of the unit
+    fn indent_level(&mut self, width: usize, line: usize) -> usize {
+        let Some(unit) = self.options.indent_unit else { return width };
+        if !width.is_multiple_of(unit) {
+            self.errors.push(ParseError::new(
+                format!("Indent of {} columns is not a multiple of {}", width, unit),
+                ErrorKind::InvalidIndentation, line, 0,
+            ).with_span(Span::range(line, 0, line, width)).with_suggestion(format!("Indent each block level by {} columns", unit)));
+        }
+        width / unit
+    }
+
+    fn handle_indent(&mut self, indent: usize, width: usize, line: usize) -> Vec<Token> {
         let mut tokens = Vec::new();
         let current = *self.indent_stack.last().unwrap_or(&0);
 
+        if self.options.indent_unit.is_some() && indent > current + 1 {
+            self.errors.push(ParseError::new(
+                format!("Line is indented {} levels deeper than the line before", indent - current),
+                ErrorKind::InvalidIndentation, line, 0,
+            ).with_span(Span::range(line, 0, line, width)).with_suggestion("Open one block level at a time"));
+        }
         if indent > current {
             self.indent_stack.push(indent);
             tokens.push(Token::new(TokenType::Indent, TokenValue::None, line, 0));
@@ -301,7 +515,9 @@ impl Lexer {
         tokens
     }
 
-    fn tokenize_line(&self, line: &str, lineno: usize) -> Vec<Token> {
+    /// Tokens of an unindented line starting at byte `base` of the source. A
+    /// string left open runs to the end of the line and is reported.
+    fn tokenize_line(&mut self, line: &str, lineno: usize, base: usize) -> Vec<Token> {
         let mut tokens = Vec::new();
         let mut pos = 0;
 
@@ -320,7 +536,7 @@ impl Lexer {
                     if let Some(ttype) = pattern.ttype {
                         let raw = m.as_str();
                         let value = Self::parse_value(raw, ttype);
-                        tokens.push(Token::new(ttype, value, lineno, pos));
+                        tokens.push(Token::new(ttype, value, lineno, pos).with_raw(raw).with_offset(base + pos));
                     }
                     pos += m.len();
                     matched = true;
@@ -328,7 +544,15 @@ impl Lexer {
                 }
             }
 
-            if !matched {
+            if !matched && remaining.starts_with(['"', '\'']) {
+                let quote = &remaining[..1];
+                self.errors.push(ParseError::new("Unterminated string", ErrorKind::UnterminatedString, lineno, pos)
+                    .with_span(Span::range(lineno, pos, lineno, line.len()))
+                    .with_suggestion(format!("Add {} to close the string", quote)));
+                tokens.push(Token::new(TokenType::String, TokenValue::Str(remaining[1..].trim_end().to_string()), lineno, pos)
+                    .with_raw(remaining).with_offset(base + pos));
+                pos = line.len();
+            } else if !matched {
                 pos += 1; // Skip unknown character
             }
         }
@@ -397,8 +621,14 @@ impl Lexer {
 #[pymethods]
 impl Lexer {
     #[new]
-    fn py_new(source: &str) -> Self {
-        Self::new(source)
+    #[pyo3(signature = (source, tab_width=DEFAULT_TAB_WIDTH, indent_unit=None))]
+    fn py_new(source: &str, tab_width: usize, indent_unit: Option<usize>) -> Self {
+        Self::with_options(source, LexerOptions { tab_width, indent_unit })
+    }
+
+    /// Indentation errors found while tokenizing
+    fn get_errors(&self) -> Vec<ParseError> {
+        self.errors.clone()
     }
 
     /// Tokenize and return list of tokens
@@ -446,6 +676,29 @@ mod tests {
         assert!(tokens.iter().any(|t| t.ttype == TokenType::Color));
     }
 
+    #[test]
+    fn test_lexer_theme_colors() {
+        let tokens = Lexer::new("fill currentColor stroke var(--accent, #000)").tokenize();
+        let colors: Vec<_> = tokens.iter().filter(|t| t.ttype == TokenType::Color).map(|t| &t.value).collect();
+        assert_eq!(colors, [&TokenValue::Str("currentColor".into()), &TokenValue::Str("var(--accent, #000)".into())]);
+    }
+
+    #[test]
+    fn test_lexer_raw_slices() {
+        let tokens = Lexer::new(r#"fill #FFF opacity 1.50 "Hi""#).tokenize();
+        let raws: Vec<&str> = tokens.iter().map(|t| t.raw.as_str()).collect();
+        assert_eq!(raws, ["fill", "#FFF", "opacity", "1.50", r#""Hi""#, "\n", ""]);
+        assert_eq!(tokens[3].value, TokenValue::Num(1.5));
+        assert_eq!(tokens[4].value, TokenValue::Str("Hi".into()));
+    }
+
+    #[test]
+    fn test_token_display() {
+        let tokens = Lexer::new("rect at 1,2\n  fill #f00").tokenize();
+        let shown: Vec<String> = tokens.iter().take(5).map(Token::to_string).collect();
+        assert_eq!(shown, [r#"Token(Ident "rect" @1:1)"#, r#"Token(Ident "at" @1:6)"#, "Token(Pair 1,2 @1:9)", r#"Token(Newline "\n" @1:12)"#, "Token(Indent @2:1)"]);
+    }
+
     #[test]
     fn test_lexer_string() {
         let mut lexer = Lexer::new(r#"text "Hello""#);
@@ -469,6 +722,67 @@ mod tests {
         assert!(tokens.iter().any(|t| t.ttype == TokenType::Dedent));
     }
 
+    #[test]
+    fn test_lexer_mixed_indent() {
+        let mut lexer = Lexer::new("group\n \trect\n  circle");
+        lexer.tokenize();
+        assert_eq!(lexer.errors.len(), 1, "{:?}", lexer.errors);
+        let err = &lexer.errors[0];
+        assert_eq!((err.kind, err.line, err.col), (ErrorKind::InvalidIndentation, 1, 1));
+        assert_eq!(err.message, "Indentation mixes tabs and spaces");
+
+        let mut lexer = Lexer::new("a\n  b\nc\n\td");
+        lexer.tokenize();
+        assert_eq!(lexer.errors[0].message, "Line indented with tabs, but line 2 uses spaces");
+    }
+
+    #[test]
+    fn test_lexer_unterminated_string() {
+        let mut lexer = Lexer::new("rect\n  text \"Hello at 5,5\ncircle at 10,10");
+        let tokens = lexer.tokenize();
+        assert_eq!(lexer.errors.len(), 1, "{:?}", lexer.errors);
+        let err = &lexer.errors[0];
+        assert_eq!((err.kind, err.line, err.col), (ErrorKind::UnterminatedString, 1, 5));
+        let string = tokens.iter().find(|t| t.ttype == TokenType::String).unwrap();
+        assert_eq!(string.col, err.col);
+        assert_eq!(err.message, "Unterminated string");
+        assert!(tokens.iter().any(|t| t.ttype == TokenType::String && matches!(&t.value, TokenValue::Str(s) if s == "Hello at 5,5")));
+        let line3: Vec<(TokenType, &str)> = tokens.iter().filter(|t| t.line == 2 && !t.raw.is_empty()).map(|t| (t.ttype, t.raw.as_str())).collect();
+        assert_eq!(line3, [(TokenType::Ident, "circle"), (TokenType::Ident, "at"), (TokenType::Pair, "10,10"), (TokenType::Newline, "\n")]);
+    }
+
+    #[test]
+    fn test_lexer_tab_indent() {
+        let mut lexer = Lexer::new("group\n\trect\n\t\tfill #fff\n\tcircle").with_tab_width(2);
+        let tokens = lexer.tokenize();
+        assert!(lexer.errors.is_empty(), "{:?}", lexer.errors);
+        let structure: Vec<TokenType> = tokens.iter().map(|t| t.ttype)
+            .filter(|t| matches!(t, TokenType::Indent | TokenType::Dedent)).collect();
+        assert_eq!(structure, [TokenType::Indent, TokenType::Indent, TokenType::Dedent, TokenType::Dedent]);
+        // A tab reaches the same level as `tab_width` spaces
+        assert!(Lexer::new("a\n\tb\n    c").tokenize().iter().filter(|t| t.ttype == TokenType::Indent).count() == 1);
+    }
+
+    #[test]
+    fn test_lexer_indent_unit() {
+        let structure = |src: &str, unit| {
+            let mut lexer = Lexer::with_options(src, LexerOptions { indent_unit: Some(unit), ..LexerOptions::default() });
+            let tokens: Vec<TokenType> = lexer.tokenize().into_iter().map(|t| t.ttype).collect();
+            (tokens, lexer.errors)
+        };
+        let two = structure("group\n  rect\n    fill #fff\n  circle\ntext", 2);
+        let four = structure("group\n    rect\n        fill #fff\n    circle\ntext", 4);
+        assert!(two.1.is_empty() && four.1.is_empty(), "{:?} {:?}", two.1, four.1);
+        assert_eq!(two.0, four.0);
+
+        let (_, errors) = structure("group\n  rect\n      fill #fff", 4);
+        let messages: Vec<&str> = errors.iter().map(|e| e.message.as_str()).collect();
+        assert_eq!(messages, ["Indent of 2 columns is not a multiple of 4", "Indent of 6 columns is not a multiple of 4"]);
+        let deep = &structure("a\n        b", 4).1[0];
+        assert_eq!(deep.message, "Line is indented 2 levels deeper than the line before");
+        assert_eq!(deep.span, Span::range(1, 0, 1, 8));
+    }
+
     #[test]
     fn test_lexer_comment() {
         let mut lexer = Lexer::new("// comment\nrect");
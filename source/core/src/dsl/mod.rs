@@ -1,22 +1,34 @@
 //! DSL lexer and parser modules
 
+mod error;
 mod lexer;
 mod parser;
 
-pub use lexer::{CanvasSize, Lexer, Token, TokenType, TokenValue};
+pub use error::{ErrorKind, ErrorSeverity, ParseError, Span};
+
+pub use lexer::{AspectRatio, CanvasSize, Lexer, LexerOptions, Token, TokenType, TokenValue, DEFAULT_TAB_WIDTH};
 pub use parser::{
-    AstCanvas, AstGraph, AstNode, AstShape, AstStyle, AstTransform,
-    ErrorKind, ErrorSeverity, FullStyle, GradientDef, GraphEdge, GraphNode,
-    ParseError, Parser, PropValue, ShadowDef, Span,
+    AstCanvas, AstDocument, AstGraph, AstNode, AstShape, AstStyle, AstSymbol, AstTransform, AstUse,
+    FORMAT_VERSION, FullStyle, GradientDef, GraphEdge, GraphNode,
+    Parser, PropValue, MAX_NESTING_DEPTH, ShadowDef,
     // Animation primitives
     Animation, AnimationState, AnimatableProperty, Direction, Duration,
     Easing, FillMode, Interpolation, Iteration, Keyframes, KeyframeStep,
     PlayState, StepPosition, Transition,
+    // AST passes
+    fit_text, post_resolve, resolve_viewbox_sizes, sort_by_z, validate,
 };
 
+// Arrowhead names shared with the SVG marker writer
+#[cfg(any(feature = "python", feature = "bench", feature = "wasm"))]
+pub(crate) use parser::ARROW_HEADS;
+
+#[cfg(feature = "python")]
+pub use parser::py_validate;
+
 // Re-export WASM bindings
 #[cfg(feature = "wasm")]
 pub use lexer::tokenize;
 #[cfg(feature = "wasm")]
-pub use parser::{parse, parse_with_errors};
+pub use parser::{load_ast, parse, parse_with_errors, validate_json};
 
@@ -1,7 +1,8 @@
 //! Animation primitives: keyframes, transitions, temporal interpolation
 //!
 //! CSS-based animation system for smooth, hardware-accelerated motion.
-//! Generates inline `<style>` blocks with @keyframes and CSS transitions.
+//! Generates inline `<style>` blocks with @keyframes and CSS transitions,
+//! or SMIL `<animate>` elements for renderers without CSS animation.
 
 use serde::{Deserialize, Serialize};
 use ts_rs::TS;
@@ -45,6 +46,18 @@ impl Easing {
         }
     }
 
+    /// Cubic-bezier control points `(x1, y1, x2, y2)`; `None` for linear and stepped timing
+    pub fn control_points(&self) -> Option<(f64, f64, f64, f64)> {
+        match *self {
+            Self::Ease => Some((0.25, 0.1, 0.25, 1.0)),
+            Self::EaseIn => Some((0.42, 0.0, 1.0, 1.0)),
+            Self::EaseOut => Some((0.0, 0.0, 0.58, 1.0)),
+            Self::EaseInOut => Some((0.42, 0.0, 0.58, 1.0)),
+            Self::CubicBezier(x1, y1, x2, y2) => Some((x1, y1, x2, y2)),
+            Self::Linear | Self::Steps(..) => None,
+        }
+    }
+
     pub fn from_str(s: &str) -> Option<Self> {
         match s {
             "linear" => Some(Self::Linear),
@@ -239,6 +252,8 @@ pub enum AnimatableProperty {
     Fill(String),
     Stroke(String),
     StrokeWidth(f64),
+    /// `stroke-dashoffset`, for draw-on line effects
+    StrokeDashOffset(f64),
     Transform(String),
     Translate(f64, f64),
     Rotate(f64),
@@ -262,6 +277,7 @@ impl AnimatableProperty {
             Self::Fill(c) => format!("fill: {};", c),
             Self::Stroke(c) => format!("stroke: {};", c),
             Self::StrokeWidth(w) => format!("stroke-width: {};", w),
+            Self::StrokeDashOffset(v) => format!("stroke-dashoffset: {};", v),
             Self::Transform(t) => format!("transform: {};", t),
             Self::Translate(x, y) => format!("transform: translate({}px, {}px);", x, y),
             Self::Rotate(deg) => format!("transform: rotate({}deg);", deg),
@@ -281,6 +297,7 @@ impl AnimatableProperty {
             Self::Fill(_) => "fill",
             Self::Stroke(_) => "stroke",
             Self::StrokeWidth(_) => "stroke-width",
+            Self::StrokeDashOffset(_) => "stroke-dashoffset",
             Self::Transform(_) | Self::Translate(_, _) | Self::Rotate(_) | Self::Scale(_, _) => "transform",
             Self::PathD(_) => "d",
             Self::X(_) | Self::Cx(_) => "cx",
@@ -290,6 +307,26 @@ impl AnimatableProperty {
             Self::Height(_) => "height",
         }
     }
+
+    /// Value as written in a SMIL `values` list
+    pub fn smil_value(&self) -> String {
+        match self {
+            Self::Opacity(v) | Self::StrokeWidth(v) | Self::StrokeDashOffset(v) | Self::Rotate(v)
+            | Self::X(v) | Self::Y(v) | Self::Cx(v) | Self::Cy(v) | Self::R(v) | Self::Width(v) | Self::Height(v) => v.to_string(),
+            Self::Fill(s) | Self::Stroke(s) | Self::Transform(s) | Self::PathD(s) => s.clone(),
+            Self::Translate(x, y) | Self::Scale(x, y) => format!("{} {}", x, y),
+        }
+    }
+
+    /// `animateTransform` type for the decomposed transform properties
+    pub fn smil_transform_type(&self) -> Option<&'static str> {
+        match self {
+            Self::Translate(..) => Some("translate"),
+            Self::Rotate(_) => Some("rotate"),
+            Self::Scale(..) => Some("scale"),
+            _ => None,
+        }
+    }
 }
 
 // ─────────────────────────────────────────────────────────────────────────────
@@ -412,8 +449,88 @@ impl Animation {
     pub fn to_style(&self) -> String {
         format!("animation: {};", self.to_css())
     }
+
+    /// Generate a CSS alternative to [`Self::to_smil`]: the `@keyframes` block (named after
+    /// this animation, so the pair always matches) and a `selector { animation: ... }` rule
+    /// carrying every timing field, play state included.
+    pub fn to_css_rule(&self, keyframes: &Keyframes, selector: &str) -> (String, String) {
+        let frames: Vec<String> = keyframes.steps.iter().map(|s| s.to_css()).collect();
+        let block = format!("@keyframes {} {{ {} }}", self.name, frames.join(" "));
+        let rule = format!("{} {{ animation: {} {}; }}", selector, self.to_css(), self.play_state.to_css());
+        (block, rule)
+    }
+
+    /// Generate a SMIL `<animate>` (`<animateTransform>` for translate/rotate/scale) driving
+    /// `target_attr` through the matching properties of `keyframes`; empty if no step sets it.
+    ///
+    /// Missing 0%/100% frames hold the nearest value. SMIL has no alternate direction, so
+    /// alternating runs unroll into a mirrored cycle of twice the duration, and stepped
+    /// easing jumps at each keyframe (`calcMode="discrete"`).
+    pub fn to_smil(&self, keyframes: &Keyframes, target_attr: &str) -> String {
+        let mut matched: Vec<(f64, &AnimatableProperty)> = keyframes.steps.iter()
+            .filter_map(|s| s.properties.iter().find(|p| p.property_name() == target_attr).map(|p| ((s.offset / 100.0).clamp(0.0, 1.0), p)))
+            .collect();
+        if matched.is_empty() { return String::new(); }
+        matched.sort_by(|a, b| a.0.total_cmp(&b.0));
+        let transform_type = matched[0].1.smil_transform_type();
+
+        let mut forward: Vec<(f64, String)> = matched.iter().map(|(t, p)| (*t, p.smil_value())).collect();
+        if forward[0].0 > 0.0 { forward.insert(0, (0.0, forward[0].1.clone())); }
+        if let Some((t, v)) = forward.last().cloned() { if t < 1.0 { forward.push((1.0, v)); } }
+        let backward: Vec<(f64, String)> = forward.iter().rev().map(|(t, v)| (1.0 - t, v.clone())).collect();
+
+        // Playing a segment backwards mirrors its timing curve
+        let spline = self.easing.control_points();
+        let mirrored = spline.map(|(x1, y1, x2, y2)| (1.0 - x2, 1.0 - y2, 1.0 - x1, 1.0 - y1));
+        let reversed = matches!(self.direction, Direction::Reverse | Direction::AlternateReverse);
+        let ((first, first_spline), (second, second_spline)) = if reversed {
+            ((backward, mirrored), (forward, spline))
+        } else {
+            ((forward, spline), (backward, mirrored))
+        };
+        let mut splines = vec![first_spline; first.len() - 1];
+        let (mut frames, mut dur, mut iteration) = (first, self.duration.as_ms(), self.iteration);
+        if matches!(self.direction, Direction::Alternate | Direction::AlternateReverse) {
+            splines.extend(vec![second_spline; second.len() - 1]);
+            frames = frames.iter().map(|(t, v)| (t / 2.0, v.clone()))
+                .chain(second.iter().skip(1).map(|(t, v)| (0.5 + t / 2.0, v.clone())))
+                .collect();
+            dur *= 2.0;
+            if let Iteration::Count(n) = iteration { iteration = Iteration::Count(n / 2.0); }
+        }
+
+        let values: Vec<&str> = frames.iter().map(|(_, v)| v.as_str()).collect();
+        let times: Vec<String> = frames.iter().map(|(t, _)| smil_num(*t)).collect();
+        let mut out = match transform_type {
+            Some(ty) => format!(r#"<animateTransform attributeName="{}" type="{}""#, target_attr, ty),
+            None => format!(r#"<animate attributeName="{}""#, target_attr),
+        };
+        out.push_str(&format!(r#" values="{}" keyTimes="{}" dur="{}""#, values.join(";"), times.join(";"), Duration::ms(dur).to_css()));
+        match (self.play_state, self.delay.as_ms() > 0.0) {
+            (PlayState::Paused, _) => out.push_str(r#" begin="indefinite""#),
+            (PlayState::Running, true) => out.push_str(&format!(r#" begin="{}""#, self.delay.to_css())),
+            (PlayState::Running, false) => {}
+        }
+        match self.easing {
+            Easing::Linear => out.push_str(r#" calcMode="linear""#),
+            Easing::Steps(..) => out.push_str(r#" calcMode="discrete""#),
+            _ => {
+                let splines: Vec<String> = splines.iter().flatten()
+                    .map(|(x1, y1, x2, y2)| [x1, y1, x2, y2].map(|v| smil_num(*v)).join(" "))
+                    .collect();
+                out.push_str(&format!(r#" calcMode="spline" keySplines="{}""#, splines.join(";")));
+            }
+        }
+        let repeat = match iteration { Iteration::Infinite => "indefinite".to_string(), Iteration::Count(n) => smil_num(n) };
+        let fill = if matches!(self.fill_mode, FillMode::Forwards | FillMode::Both) { "freeze" } else { "remove" };
+        out.push_str(&format!(r#" repeatCount="{}" fill="{}"/>"#, repeat, fill));
+        out
+    }
 }
 
+/// Format a SMIL number, rounding away float noise from offset arithmetic
+fn smil_num(v: f64) -> String { format!("{}", (v * 10000.0).round() / 10000.0) }
+
 #[cfg(feature = "python")]
 #[pymethods]
 impl Animation {
@@ -545,13 +662,8 @@ impl Interpolation {
     /// Apply easing function to linear progress [0,1]
     fn ease(&self, t: f64) -> f64 {
         match &self.easing {
-            Easing::Linear => t,
-            Easing::Ease => cubic_bezier(t, 0.25, 0.1, 0.25, 1.0),
-            Easing::EaseIn => cubic_bezier(t, 0.42, 0.0, 1.0, 1.0),
-            Easing::EaseOut => cubic_bezier(t, 0.0, 0.0, 0.58, 1.0),
-            Easing::EaseInOut => cubic_bezier(t, 0.42, 0.0, 0.58, 1.0),
-            Easing::CubicBezier(x1, y1, x2, y2) => cubic_bezier(t, *x1, *y1, *x2, *y2),
             Easing::Steps(n, pos) => step(*n, *pos, t),
+            e => e.control_points().map_or(t, |(x1, y1, x2, y2)| cubic_bezier(t, x1, y1, x2, y2)),
         }
     }
 }
@@ -685,6 +797,17 @@ mod tests {
         assert!(css.contains("opacity"));
     }
 
+    #[test]
+    fn test_dash_offset_keyframes() {
+        let kf = Keyframes::new("draw")
+            .with_step(KeyframeStep::new(0.0).with_property(AnimatableProperty::StrokeDashOffset(120.0)))
+            .with_step(KeyframeStep::new(100.0).with_property(AnimatableProperty::StrokeDashOffset(0.0)));
+        assert_eq!(kf.steps[0].properties[0].property_name(), "stroke-dashoffset");
+        let css = kf.to_css();
+        assert!(css.contains("0% { stroke-dashoffset: 120; }"), "{}", css);
+        assert!(css.contains("100% { stroke-dashoffset: 0; }"), "{}", css);
+    }
+
     #[test]
     fn test_animation_css() {
         let anim = Animation::new("pulse")
@@ -699,6 +822,53 @@ mod tests {
         assert!(css.contains("infinite"));
     }
 
+    fn fade() -> Keyframes {
+        Keyframes::new("fade")
+            .with_step(KeyframeStep::new(0.0).with_property(AnimatableProperty::Opacity(0.0)))
+            .with_step(KeyframeStep::new(100.0).with_property(AnimatableProperty::Opacity(1.0)))
+    }
+
+    #[test]
+    fn test_smil_opacity() {
+        let anim = Animation::new("fade").with_duration(Duration::ms(500.0)).with_fill(FillMode::Forwards);
+        assert_eq!(
+            anim.to_smil(&fade(), "opacity"),
+            r#"<animate attributeName="opacity" values="0;1" keyTimes="0;1" dur="500ms" calcMode="spline" keySplines="0.25 0.1 0.25 1" repeatCount="1" fill="freeze"/>"#
+        );
+        assert_eq!(anim.to_smil(&fade(), "fill"), "");
+    }
+
+    #[test]
+    fn test_css_rule_steps() {
+        let anim = Animation::new("blink").with_duration(Duration::secs(1.0)).with_easing(Easing::Steps(4, StepPosition::Start));
+        let (block, rule) = anim.to_css_rule(&fade(), "#dot");
+        assert_eq!(block, "@keyframes blink { 0% { opacity: 0; } 100% { opacity: 1; } }");
+        assert_eq!(rule, "#dot { animation: blink 1s steps(4,jump-start) 0ms 1 normal none running; }");
+    }
+
+    #[test]
+    fn test_css_rule_alternate() {
+        let mut anim = Animation::new("fade").with_direction(Direction::Alternate).with_fill(FillMode::Both).infinite();
+        anim.play_state = PlayState::Paused;
+        let (_, rule) = anim.to_css_rule(&fade(), ".icon");
+        assert_eq!(rule, ".icon { animation: fade 300ms ease 0ms infinite alternate both paused; }");
+    }
+
+    #[test]
+    fn test_smil_alternate_and_transform() {
+        let anim = Animation::new("fade").with_duration(Duration::secs(1.0)).with_easing(Easing::Linear)
+            .with_direction(Direction::Alternate).with_delay(Duration::ms(200.0)).infinite();
+        let smil = anim.to_smil(&fade(), "opacity");
+        assert!(smil.contains(r#"values="0;1;0" keyTimes="0;0.5;1" dur="2s" begin="200ms" calcMode="linear" repeatCount="indefinite""#), "{}", smil);
+
+        let spin = Keyframes::new("spin")
+            .with_step(KeyframeStep::new(0.0).with_property(AnimatableProperty::Rotate(0.0)))
+            .with_step(KeyframeStep::new(100.0).with_property(AnimatableProperty::Rotate(360.0)));
+        let smil = Animation::new("spin").with_easing(Easing::EaseIn).with_direction(Direction::Reverse).to_smil(&spin, "transform");
+        assert!(smil.starts_with(r#"<animateTransform attributeName="transform" type="rotate" values="360;0""#), "{}", smil);
+        assert!(smil.contains(r#"keySplines="0 0 0.58 1""#), "{}", smil);
+    }
+
     #[test]
     fn test_transition_css() {
         let trans = Transition::new("opacity")
@@ -1,6 +1,7 @@
 //! AST types for the iconoglott DSL
 
-use super::super::lexer::{CanvasSize, TokenValue};
+use super::super::error::Span;
+use super::super::lexer::{AspectRatio, CanvasSize, TokenValue};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use ts_rs::TS;
@@ -26,6 +27,37 @@ pub struct AstStyle {
     pub font_size: f64,
     pub font_weight: String,
     pub text_anchor: String,
+    /// Text `dominant-baseline` (alphabetic, middle, hanging)
+    #[serde(default)]
+    pub baseline: Option<String>,
+    /// Stroke dash pattern (dash, gap, ...); empty for a solid stroke
+    #[serde(default)]
+    pub dash: Vec<f64>,
+    /// Offset into the dash pattern
+    #[serde(default)]
+    pub dash_offset: f64,
+    /// Stroke width as a percent of the viewBox's shorter side; layout
+    /// resolution replaces `stroke_width` with it
+    #[serde(default)]
+    pub stroke_width_pct: Option<f64>,
+    /// Font size as a percent of the viewBox's shorter side, like `stroke_width_pct`
+    #[serde(default)]
+    pub font_size_pct: Option<f64>,
+    /// Extra space between glyphs (`letter-spacing`)
+    #[serde(default)]
+    pub letter_spacing: f64,
+    /// Extra space per space character (`word-spacing`)
+    #[serde(default)]
+    pub word_spacing: f64,
+    /// Text `text-decoration` (underline, line-through, overline)
+    #[serde(default)]
+    pub decoration: Option<String>,
+    /// Text writing direction (ltr, rtl)
+    #[serde(default)]
+    pub direction: Option<String>,
+    /// `fill-rule` (nonzero, evenodd); unset leaves the SVG default, nonzero
+    #[serde(default)]
+    pub fill_rule: Option<String>,
 }
 
 /// Extended style with shadow/gradient (separate for Python compat)
@@ -48,6 +80,34 @@ impl AstStyle {
             ..Default::default()
         }
     }
+
+    /// Tween toward `other`: numeric fields and hex fill/stroke blend, everything
+    /// else (fonts, anchors, dash patterns, non-hex paints) snaps over at `t >= 0.5`.
+    #[cfg(any(feature = "python", feature = "bench", feature = "wasm"))]
+    pub fn lerp(&self, other: &AstStyle, t: f32) -> AstStyle {
+        use crate::color::Color;
+        let snap = t >= 0.5;
+        let num = |a: f64, b: f64| a + (b - a) * t as f64;
+        let paint = |a: &Option<String>, b: &Option<String>| {
+            let mixed = a.as_deref().and_then(Color::try_parse_hex)
+                .zip(b.as_deref().and_then(Color::try_parse_hex))
+                .map(|(a, b)| a.mix(&b, t).to_hex());
+            mixed.or_else(|| if snap { b.clone() } else { a.clone() })
+        };
+        let pick = if snap { other } else { self };
+        AstStyle {
+            fill: paint(&self.fill, &other.fill),
+            stroke: paint(&self.stroke, &other.stroke),
+            stroke_width: num(self.stroke_width, other.stroke_width),
+            opacity: num(self.opacity, other.opacity),
+            corner: num(self.corner, other.corner),
+            font_size: num(self.font_size, other.font_size),
+            dash_offset: num(self.dash_offset, other.dash_offset),
+            letter_spacing: num(self.letter_spacing, other.letter_spacing),
+            word_spacing: num(self.word_spacing, other.word_spacing),
+            ..pick.clone()
+        }
+    }
 }
 
 #[cfg(feature = "python")]
@@ -87,15 +147,18 @@ pub struct GradientDef {
     pub from: String,
     pub to: String,
     pub angle: f64,
+    /// One gradient across every shape using it (`gradient ... shared`)
+    #[serde(default)]
+    pub shared: bool,
 }
 
 #[cfg(feature = "python")]
 #[pymethods]
 impl GradientDef {
     #[new]
-    #[pyo3(signature = (gtype="linear".to_string(), from="#fff".to_string(), to="#000".to_string(), angle=90.0))]
-    fn py_new(gtype: String, from: String, to: String, angle: f64) -> Self {
-        Self { gtype, from, to, angle }
+    #[pyo3(signature = (gtype="linear".to_string(), from="#fff".to_string(), to="#000".to_string(), angle=0.0, shared=false))]
+    fn py_new(gtype: String, from: String, to: String, angle: f64, shared: bool) -> Self {
+        Self { gtype, from, to, angle, shared }
     }
 }
 
@@ -108,6 +171,54 @@ pub struct AstTransform {
     pub rotate: f64,
     pub scale: Option<(f64, f64)>,
     pub origin: Option<(f64, f64)>,
+    /// Origin as percents of the shape's own bounds (`origin 50% 50%` is its
+    /// center); lowering resolves it into `origin` once the bounds are known
+    #[serde(default)]
+    pub origin_pct: Option<(f64, f64)>,
+}
+
+impl AstTransform {
+    /// Copy with `origin_pct` turned into an absolute `origin` within `bounds`
+    /// `(x, y, w, h)`; unchanged when no percent origin is set
+    pub fn resolve_origin(&self, (x, y, w, h): (f64, f64, f64, f64)) -> AstTransform {
+        match self.origin_pct {
+            Some((px, py)) => AstTransform { origin: Some((x + w * px / 100.0, y + h * py / 100.0)), origin_pct: None, ..self.clone() },
+            None => self.clone(),
+        }
+    }
+
+    /// SVG transform list. Rotation and scale pivot on `origin` when set, so
+    /// `scale -1 1` with `origin 50,0` mirrors about x=50.
+    pub fn to_svg(&self) -> Option<String> {
+        let mut parts = Vec::with_capacity(5);
+        if let Some((tx, ty)) = self.translate { parts.push(format!("translate({} {})", tx, ty)); }
+        if self.rotate != 0.0 {
+            parts.push(match self.origin {
+                Some((ox, oy)) => format!("rotate({} {} {})", self.rotate, ox, oy),
+                None => format!("rotate({})", self.rotate),
+            });
+        }
+        if let Some((sx, sy)) = self.scale {
+            match self.origin {
+                // `0.0 - o` rather than `-o` so a zero axis prints as 0, not -0
+                Some((ox, oy)) if ox != 0.0 || oy != 0.0 => parts.push(format!("translate({} {}) scale({} {}) translate({} {})", ox, oy, sx, sy, 0.0 - ox, 0.0 - oy)),
+                _ => parts.push(format!("scale({} {})", sx, sy)),
+            }
+        }
+        if parts.is_empty() { None } else { Some(parts.join(" ")) }
+    }
+
+    /// Map a point through the transform, composed as in `to_svg`
+    pub fn apply(&self, (x, y): (f64, f64)) -> (f64, f64) {
+        let (ox, oy) = self.origin.unwrap_or((0.0, 0.0));
+        let (sx, sy) = self.scale.unwrap_or((1.0, 1.0));
+        let (x, y) = (ox + (x - ox) * sx, oy + (y - oy) * sy);
+        let (sin, cos) = self.rotate.to_radians().sin_cos();
+        let (dx, dy) = (x - ox, y - oy);
+        let (x, y) = (ox + dx * cos - dy * sin, oy + dx * sin + dy * cos);
+        let (tx, ty) = self.translate.unwrap_or((0.0, 0.0));
+        (x + tx, y + ty)
+    }
 }
 
 /// Node definition for graphs/flowcharts
@@ -146,14 +257,20 @@ pub struct GraphEdge {
     pub to: String,
     pub style: String,       // straight, curved, orthogonal
     pub arrow: String,       // none, forward, backward, both
+    pub arrow_head: String,  // triangle, open, diamond, circle
+    pub arrow_size: f64,
     pub label: Option<String>,
+    pub label_bg: Option<String>,
     pub stroke: Option<String>,
     pub stroke_width: f64,
 }
 
 impl Default for GraphEdge {
     fn default() -> Self {
-        Self { from: String::new(), to: String::new(), style: "straight".into(), arrow: "forward".into(), label: None, stroke: Some("#333".into()), stroke_width: 2.0 }
+        Self {
+            from: String::new(), to: String::new(), style: "straight".into(), arrow: "forward".into(),
+            arrow_head: "triangle".into(), arrow_size: 10.0, label: None, label_bg: None, stroke: Some("#333".into()), stroke_width: 2.0,
+        }
     }
 }
 
@@ -172,13 +289,14 @@ pub struct AstGraph {
     pub layout: String,      // hierarchical, force, grid, tree, manual
     pub direction: String,   // vertical, horizontal
     pub spacing: f64,
+    pub anchors: String,     // axis, sides, corners
     pub nodes: Vec<GraphNode>,
     pub edges: Vec<GraphEdge>,
 }
 
 impl Default for AstGraph {
     fn default() -> Self {
-        Self { layout: "manual".into(), direction: "vertical".into(), spacing: 50.0, nodes: Vec::new(), edges: Vec::new() }
+        Self { layout: "manual".into(), direction: "vertical".into(), spacing: 50.0, anchors: "axis".into(), nodes: Vec::new(), edges: Vec::new() }
     }
 }
 
@@ -214,6 +332,11 @@ impl AstTransform {
     fn get_origin(&self) -> Option<(f64, f64)> { self.origin }
     #[setter]
     fn set_origin(&mut self, v: Option<(f64, f64)>) { self.origin = v; }
+
+    #[getter]
+    fn get_origin_pct(&self) -> Option<(f64, f64)> { self.origin_pct }
+    #[setter]
+    fn set_origin_pct(&mut self, v: Option<(f64, f64)>) { self.origin_pct = v; }
 }
 
 /// Canvas definition using standardized sizes
@@ -222,18 +345,26 @@ impl AstTransform {
 #[cfg_attr(feature = "python", pyclass(get_all, set_all))]
 pub struct AstCanvas {
     pub size: CanvasSize,
+    #[serde(default)]
+    pub aspect: AspectRatio,
     pub fill: String,
+    /// Background gradient, painted over `fill`
+    #[serde(default)]
+    pub gradient: Option<GradientDef>,
+    /// Background image href, scaled to cover the canvas
+    #[serde(default)]
+    pub image: Option<String>,
 }
 
 impl AstCanvas {
-    pub fn width(&self) -> u32 { self.size.pixels() }
-    pub fn height(&self) -> u32 { self.size.pixels() }
-    pub fn dimensions(&self) -> (u32, u32) { self.size.dimensions() }
+    pub fn width(&self) -> u32 { self.dimensions().0 }
+    pub fn height(&self) -> u32 { self.dimensions().1 }
+    pub fn dimensions(&self) -> (u32, u32) { self.size.dimensions_with(self.aspect) }
 }
 
 impl Default for AstCanvas {
     fn default() -> Self {
-        Self { size: CanvasSize::Medium, fill: "#fff".into() }
+        Self { size: CanvasSize::Medium, aspect: AspectRatio::SQUARE, fill: "#fff".into(), gradient: None, image: None }
     }
 }
 
@@ -243,7 +374,7 @@ impl AstCanvas {
     #[new]
     #[pyo3(signature = (size=CanvasSize::Medium, fill="#fff".to_string()))]
     fn py_new(size: CanvasSize, fill: String) -> Self {
-        Self { size, fill }
+        Self { size, aspect: AspectRatio::SQUARE, fill, gradient: None, image: None }
     }
     
     #[getter]
@@ -263,6 +394,8 @@ pub enum Dimension {
     Percent(f64),
     /// Auto-size based on content
     Auto,
+    /// `calc(50% + 10)`: a percentage of parent plus a pixel offset
+    Calc { percent: f64, px: f64 },
 }
 
 impl Default for Dimension {
@@ -276,6 +409,7 @@ impl Dimension {
             Self::Px(v) => Some(*v),
             Self::Percent(p) => Some(parent_size * p / 100.0),
             Self::Auto => None, // Needs content measurement
+            Self::Calc { percent, px } => Some(parent_size * percent / 100.0 + px),
         }
     }
     
@@ -378,12 +512,32 @@ pub enum PropValue {
     Layout(Box<LayoutProps>),
     /// Unresolved variable reference (name, line, col)
     VarRef(String, usize, usize),
+    /// Ordered scalar list (`[4 2 1]`, `["a" "b"]`)
+    List(Vec<PropValue>),
 }
 
 impl Default for PropValue {
     fn default() -> Self { Self::None }
 }
 
+impl PropValue {
+    /// Value of a bracketed list: `Points` when every item is an `x,y` pair (as in
+    /// `[0,0 10,0]`), otherwise a scalar `List` with any pairs split into two numbers
+    pub fn from_list(items: &[TokenValue]) -> Self {
+        if items.iter().all(|v| matches!(v, TokenValue::Pair(..))) {
+            return Self::Points(items.iter().filter_map(|v| match v { TokenValue::Pair(a, b) => Some((*a, *b)), _ => None }).collect());
+        }
+        Self::List(items.iter().flat_map(|v| match v {
+            TokenValue::Pair(a, b) => vec![Self::Num(*a), Self::Num(*b)],
+            TokenValue::Num(n) => vec![Self::Num(*n)],
+            TokenValue::Str(s) => vec![Self::Str(s.clone())],
+            TokenValue::PercentPair(a, b) => vec![Self::PercentPair(*a, *b)],
+            TokenValue::List(inner) => vec![Self::from_list(inner)],
+            TokenValue::None => vec![],
+        }).collect())
+    }
+}
+
 /// Shape in the AST
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize, TS)]
 #[ts(export)]
@@ -397,6 +551,12 @@ pub struct AstShape {
     pub transform: AstTransform,
     pub animation: Option<super::anim::AnimationState>,
     pub children: Vec<AstShape>,
+    /// Block-local variables (`$fg = #000` inside a group), shadowing outer ones
+    #[serde(default)]
+    pub vars: Vec<(String, TokenValue, Span)>,
+    /// Non-rendering editor metadata (`meta layer "bg"`)
+    #[serde(default)]
+    pub meta: HashMap<String, String>,
 }
 
 impl AstShape {
@@ -410,19 +570,12 @@ impl AstShape {
             transform: AstTransform::default(),
             animation: None,
             children: Vec::new(),
+            vars: Vec::new(),
+            meta: HashMap::new(),
         }
     }
 }
 
-#[cfg(feature = "python")]
-fn dim_to_py(py: Python<'_>, dim: &Dimension) -> PyObject {
-    match dim {
-        Dimension::Px(v) => v.into_py(py),
-        Dimension::Percent(p) => format!("{}%", p).into_py(py),
-        Dimension::Auto => "auto".into_py(py),
-    }
-}
-
 #[cfg(feature = "python")]
 #[pymethods]
 impl AstShape {
@@ -437,18 +590,7 @@ impl AstShape {
         use pyo3::types::PyDict;
         let dict = PyDict::new(py);
         for (k, v) in &self.props {
-            let val: PyObject = match v {
-                PropValue::None => py.None(),
-                PropValue::Str(s) => s.clone().into_py(py),
-                PropValue::Num(n) => n.into_py(py),
-                PropValue::Pair(a, b) | PropValue::PercentPair(a, b) => (*a, *b).into_py(py),
-                PropValue::Points(pts) => pts.clone().into_py(py),
-                PropValue::Dim(d) => dim_to_py(py, d),
-                PropValue::DimPair(dp) => (dim_to_py(py, &dp.width), dim_to_py(py, &dp.height)).into_py(py),
-                PropValue::Layout(_) => "<layout>".into_py(py),
-                PropValue::VarRef(name, _, _) => format!("${}", name).into_py(py),
-            };
-            dict.set_item(k, val).ok();
+            dict.set_item(k, super::python::prop_value_to_py(py, v)).ok();
         }
         dict.into()
     }
@@ -480,11 +622,17 @@ pub struct AstSymbol {
     pub id: String,
     pub viewbox: Option<(f64, f64, f64, f64)>, // x, y, width, height
     pub children: Vec<AstShape>,
+    /// Nested symbol instances, drawn after `children`
+    #[serde(default)]
+    pub uses: Vec<AstUse>,
+    /// Location of the id string, for diagnostics
+    #[serde(default)]
+    pub span: Span,
 }
 
 impl Default for AstSymbol {
     fn default() -> Self {
-        Self { id: String::new(), viewbox: None, children: Vec::new() }
+        Self { id: String::new(), viewbox: None, children: Vec::new(), uses: Vec::new(), span: Span::default() }
     }
 }
 
@@ -494,7 +642,7 @@ impl AstSymbol {
     #[new]
     #[pyo3(signature = (id, viewbox=None))]
     fn py_new(id: String, viewbox: Option<(f64, f64, f64, f64)>) -> Self {
-        Self { id, viewbox, children: Vec::new() }
+        Self { id, viewbox, children: Vec::new(), uses: Vec::new(), span: Span::default() }
     }
 }
 
@@ -508,11 +656,14 @@ pub struct AstUse {
     pub size: Option<(f64, f64)>,
     pub transform: AstTransform,
     pub style: AstStyle,
+    /// Location of the href string, for diagnostics
+    #[serde(default)]
+    pub span: Span,
 }
 
 impl Default for AstUse {
     fn default() -> Self {
-        Self { href: String::new(), at: None, size: None, transform: AstTransform::default(), style: AstStyle::new() }
+        Self { href: String::new(), at: None, size: None, transform: AstTransform::default(), style: AstStyle::new(), span: Span::default() }
     }
 }
 
@@ -522,7 +673,7 @@ impl AstUse {
     #[new]
     #[pyo3(signature = (href, at=None, size=None))]
     fn py_new(href: String, at: Option<(f64, f64)>, size: Option<(f64, f64)>) -> Self {
-        Self { href, at, size, transform: AstTransform::default(), style: AstStyle::new() }
+        Self { href, at, size, transform: AstTransform::default(), style: AstStyle::new(), span: Span::default() }
     }
 }
 
@@ -536,117 +687,8 @@ pub enum AstNode {
     Graph(AstGraph),
     Symbol(AstSymbol),
     Use(AstUse),
-    Variable { name: String, value: Option<TokenValue> },
+    Variable { name: String, value: Option<TokenValue>, #[serde(default)] span: Span },
     Keyframes(super::anim::Keyframes),
+    /// `include "path"` left unresolved (no loader was run)
+    Include(String),
 }
-
-/// Error severity levels
-#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize, TS)]
-#[ts(export)]
-#[cfg_attr(feature = "python", pyclass)]
-pub enum ErrorSeverity {
-    Error,
-    Warning,
-    Hint,
-}
-
-/// Error categories for structured diagnostics
-#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize, TS)]
-#[ts(export)]
-#[cfg_attr(feature = "python", pyclass)]
-pub enum ErrorKind {
-    UnexpectedToken,
-    UnknownCommand,
-    InvalidValue,
-    MissingToken,
-    InvalidIndentation,
-    UnterminatedBlock,
-    InvalidProperty,
-    UndefinedVariable,
-    DuplicateVariable,
-}
-
-impl ErrorKind {
-    pub fn code(self) -> &'static str {
-        match self {
-            Self::UnexpectedToken => "E001",
-            Self::UnknownCommand => "E002",
-            Self::InvalidValue => "E003",
-            Self::MissingToken => "E004",
-            Self::InvalidIndentation => "E005",
-            Self::UnterminatedBlock => "E006",
-            Self::InvalidProperty => "E007",
-            Self::UndefinedVariable => "E008",
-            Self::DuplicateVariable => "E009",
-        }
-    }
-}
-
-/// Source span for error locations
-#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, TS)]
-#[ts(export)]
-#[cfg_attr(feature = "python", pyclass(get_all))]
-pub struct Span {
-    pub start_line: usize,
-    pub start_col: usize,
-    pub end_line: usize,
-    pub end_col: usize,
-}
-
-impl Span {
-    pub fn point(line: usize, col: usize) -> Self {
-        Self { start_line: line, start_col: col, end_line: line, end_col: col + 1 }
-    }
-
-    pub fn range(start_line: usize, start_col: usize, end_line: usize, end_col: usize) -> Self {
-        Self { start_line, start_col, end_line, end_col }
-    }
-}
-
-/// Parse error with recovery context
-#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, TS)]
-#[ts(export)]
-#[cfg_attr(feature = "python", pyclass(get_all))]
-pub struct ParseError {
-    pub message: String,
-    pub line: usize,
-    pub col: usize,
-    pub kind: ErrorKind,
-    pub severity: ErrorSeverity,
-    pub span: Span,
-    pub suggestion: Option<String>,
-    pub recovered: bool,
-}
-
-impl ParseError {
-    pub fn new(message: impl Into<String>, kind: ErrorKind, line: usize, col: usize) -> Self {
-        Self {
-            message: message.into(),
-            line, col,
-            kind,
-            severity: ErrorSeverity::Error,
-            span: Span::point(line, col),
-            suggestion: None,
-            recovered: false,
-        }
-    }
-
-    pub fn with_span(mut self, span: Span) -> Self { self.span = span; self }
-    pub fn with_suggestion(mut self, s: impl Into<String>) -> Self { self.suggestion = Some(s.into()); self }
-    pub fn with_severity(mut self, sev: ErrorSeverity) -> Self { self.severity = sev; self }
-    pub fn as_recovered(mut self) -> Self { self.recovered = true; self }
-}
-
-#[cfg(feature = "python")]
-#[pymethods]
-impl ParseError {
-    fn __repr__(&self) -> String {
-        format!("ParseError[{}]({:?}, {}:{}{})", 
-            self.kind.code(), self.message, self.line, self.col,
-            self.suggestion.as_ref().map(|s| format!(", suggestion={:?}", s)).unwrap_or_default())
-    }
-    
-    #[getter]
-    fn code(&self) -> &'static str { self.kind.code() }
-}
-
@@ -4,7 +4,9 @@
 //! Uses synchronization tokens (Newline, Dedent) for error recovery.
 
 use super::ast::*;
-use super::super::lexer::{CanvasSize, Token, TokenType, TokenValue};
+use super::super::error::{ErrorKind, ErrorSeverity, ParseError, Span};
+use super::super::lexer::{AspectRatio, CanvasSize, Lexer, Token, TokenType, TokenValue};
+use std::cell::RefCell;
 use std::collections::{HashMap, HashSet};
 
 #[cfg(feature = "python")]
@@ -26,7 +28,7 @@ lazy_static::lazy_static! {
             .into_iter().collect()
     };
     pub(crate) static ref STYLE_PROPS: HashSet<&'static str> = {
-        ["fill", "stroke", "opacity", "corner", "shadow", "gradient", "blur", "animate", "transition"]
+        ["fill", "fill-rule", "stroke", "opacity", "corner", "dash", "dash-offset", "shadow", "gradient", "blur", "animate", "transition"]
             .into_iter().collect()
     };
     pub(crate) static ref EASING_FUNCS: HashSet<&'static str> = {
@@ -42,7 +44,7 @@ lazy_static::lazy_static! {
             .into_iter().collect()
     };
     pub(crate) static ref TEXT_PROPS: HashSet<&'static str> = {
-        ["font", "bold", "italic", "center", "middle", "end"]
+        ["font", "bold", "italic", "center", "middle", "end", "baseline", "letter-spacing", "word-spacing", "decoration", "direction"]
             .into_iter().collect()
     };
     pub(crate) static ref TRANSFORM_PROPS: HashSet<&'static str> = {
@@ -50,7 +52,12 @@ lazy_static::lazy_static! {
             .into_iter().collect()
     };
     pub(crate) static ref LAYOUT_PROPS: HashSet<&'static str> = {
-        ["gap", "padding", "justify", "align", "wrap", "width", "height", "size", "anchor", "fill-parent", "center-in"]
+        ["gap", "padding", "justify", "align", "wrap", "width", "height", "size", "anchor", "fill-parent", "center-in", "aspect"]
+            .into_iter().chain(SIZE_LIMIT_PROPS.iter().copied()).collect()
+    };
+    /// Bounds a layout child's size is clamped to after flex sizing
+    pub(crate) static ref SIZE_LIMIT_PROPS: HashSet<&'static str> = {
+        ["min-width", "max-width", "min-height", "max-height"]
             .into_iter().collect()
     };
     pub(crate) static ref JUSTIFY_VALUES: HashSet<&'static str> = {
@@ -69,16 +76,134 @@ lazy_static::lazy_static! {
         ["straight", "curved", "orthogonal"]
             .into_iter().collect()
     };
-    pub(crate) static ref ARROW_TYPES: HashSet<&'static str> = {
-        ["none", "forward", "backward", "both"]
-            .into_iter().collect()
-    };
     pub(crate) static ref GRAPH_LAYOUTS: HashSet<&'static str> = {
         ["hierarchical", "force", "grid", "tree", "manual"]
             .into_iter().collect()
     };
 }
 
+// ─────────────────────────────────────────────────────────────────────────────
+// Suggestions
+// ─────────────────────────────────────────────────────────────────────────────
+
+/// Maximum edit distance for a "Did you mean" suggestion
+const SUGGEST_THRESHOLD: usize = 2;
+
+/// Levenshtein edit distance between two strings (char-wise)
+pub(crate) fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut prev: Vec<usize> = (0..=b.len()).collect();
+    let mut row = vec![0; b.len() + 1];
+    for (i, ca) in a.chars().enumerate() {
+        row[0] = i + 1;
+        for (j, &cb) in b.iter().enumerate() {
+            let sub = prev[j] + (ca != cb) as usize;
+            row[j + 1] = sub.min(prev[j + 1] + 1).min(row[j] + 1);
+        }
+        std::mem::swap(&mut prev, &mut row);
+    }
+    prev[b.len()]
+}
+
+/// Closest candidate within `SUGGEST_THRESHOLD` edits (ties broken alphabetically)
+pub(crate) fn closest_match<'a>(input: &str, candidates: impl IntoIterator<Item = &'a str>) -> Option<&'a str> {
+    let input = input.to_lowercase();
+    candidates.into_iter()
+        .map(|c| (edit_distance(&input, c), c))
+        .filter(|&(d, _)| d <= SUGGEST_THRESHOLD)
+        .min()
+        .map(|(_, c)| c)
+}
+
+/// "Unexpected <type> '<text>' <place>", quoting the token exactly as written;
+/// synthetic tokens (indent, newline, EOF) have no text to quote
+fn unexpected(tok: &Token, place: &str) -> String {
+    if tok.raw.trim().is_empty() { return format!("Unexpected {:?} {}", tok.ttype, place); }
+    format!("Unexpected {:?} '{}' {}", tok.ttype, tok.raw, place)
+}
+
+/// Format a "Did you mean" hint for the closest candidate, if any
+fn did_you_mean<'a>(input: &str, candidates: impl IntoIterator<Item = &'a str>) -> Option<String> {
+    closest_match(input, candidates).map(|c| format!("Did you mean '{}'?", c))
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Value Validation
+// ─────────────────────────────────────────────────────────────────────────────
+
+/// Graph edge anchor modes (resolved by `anchor::best_anchors` at render time):
+/// - `axis`: facing side midpoints along the axis the nodes are furthest apart on
+/// - `sides`: the pair of side midpoints giving the shortest edge that leaves both nodes
+/// - `corners`: like `sides`, with the corners as candidates too
+pub(crate) const ANCHOR_MODES: [&str; 3] = ["axis", "sides", "corners"];
+
+/// Arrow directions for edges, lines and paths
+pub(crate) const ARROW_TYPES: &[&str] = &["none", "forward", "backward", "both"];
+
+/// Arrowhead outlines, the first being the default
+pub(crate) const ARROW_HEADS: [&str; 4] = ["triangle", "open", "diamond", "circle"];
+
+/// Media types accepted for embedded `data:` image URIs
+pub(crate) const IMAGE_MEDIA_TYPES: &[&str] = &["image/png", "image/jpeg", "image/svg+xml"];
+
+/// Text baselines (SVG `dominant-baseline`)
+pub(crate) const TEXT_BASELINES: &[&str] = &["alphabetic", "middle", "hanging"];
+
+/// Image fit modes (mapped to `preserveAspectRatio` at render time)
+pub(crate) const IMAGE_FIT_MODES: &[&str] = &["cover", "contain"];
+
+/// Text decorations (SVG `text-decoration`)
+pub(crate) const TEXT_DECORATIONS: &[&str] = &["underline", "line-through", "overline"];
+
+/// Values of `fill-rule`
+pub(crate) const FILL_RULES: &[&str] = &["nonzero", "evenodd"];
+
+/// Text writing directions (SVG `direction`)
+pub(crate) const TEXT_DIRECTIONS: &[&str] = &["ltr", "rtl"];
+
+/// Validate a `data:` URI href (media type and base64 payload); other hrefs pass through
+pub(crate) fn validate_data_uri(href: &str) -> Result<(), String> {
+    let Some(rest) = href.strip_prefix("data:") else { return Ok(()) };
+    let Some((meta, payload)) = rest.split_once(',') else {
+        return Err("Malformed data URI: missing ',' before payload".into());
+    };
+    let mut parts = meta.split(';');
+    let media = parts.next().unwrap_or("").to_lowercase();
+    if !IMAGE_MEDIA_TYPES.contains(&media.as_str()) {
+        return Err(format!("Unsupported image media type '{}'", media));
+    }
+    if parts.any(|p| p.eq_ignore_ascii_case("base64")) {
+        let valid_chars = payload.bytes().all(|b| b.is_ascii_alphanumeric() || matches!(b, b'+' | b'/' | b'='));
+        let padded = payload.trim_end_matches('=');
+        if payload.is_empty() || !valid_chars || payload.len() % 4 != 0 || padded.contains('=') || payload.len() - padded.len() > 2 {
+            return Err("Invalid base64 payload in data URI".into());
+        }
+    }
+    Ok(())
+}
+
+/// Percent and pixel parts of `calc(100% - 20)`: percent and pixel terms joined
+/// by space-separated `+` or `-`, as in CSS
+pub(crate) fn parse_calc(expr: &str) -> Option<(f64, f64)> {
+    let body = expr.strip_prefix("calc(")?.strip_suffix(')')?;
+    let (mut percent, mut px, mut sign) = (0.0, 0.0, Some(1.0));
+    for term in body.split_whitespace() {
+        match (term, sign) {
+            ("+", None) => sign = Some(1.0),
+            ("-", None) => sign = Some(-1.0),
+            (_, Some(s)) => {
+                match term.strip_suffix('%') {
+                    Some(p) => percent += s * p.parse::<f64>().ok()?,
+                    None => px += s * term.parse::<f64>().ok()?,
+                }
+                sign = None;
+            }
+            _ => return None,
+        }
+    }
+    sign.is_none().then_some((percent, px))
+}
+
 // ─────────────────────────────────────────────────────────────────────────────
 // Parser
 // ─────────────────────────────────────────────────────────────────────────────
@@ -89,25 +214,57 @@ pub struct Parser {
     tokens: Vec<Token>,
     pos: usize,
     pub(crate) variables: HashMap<String, TokenValue>,
+    /// Variable definitions in source order (name, span), for unused-variable hints
+    variable_defs: Vec<(String, Span)>,
+    /// Variables referenced anywhere, including forward references
+    used_variables: RefCell<HashSet<String>>,
+    /// Emit hints (unused variables/symbols) after parsing
+    hints: bool,
     pub errors: Vec<ParseError>,
     /// Track indent depth for recovery
     indent_depth: usize,
     /// Panic mode flag - true when recovering from error
     panic_mode: bool,
+    /// Current shape nesting, and the level past which nested statements are skipped
+    depth: usize,
+    max_depth: usize,
+    /// Warn about shapes missing size, radius, end point or outline data
+    geometry_warnings: bool,
 }
 
+/// Default limit on nested blocks. Each level costs several recursive frames, so
+/// this stays well inside a 1MB wasm stack even in debug builds.
+pub const MAX_NESTING_DEPTH: usize = 64;
+
 impl Parser {
     pub fn new(tokens: Vec<Token>) -> Self {
         Self {
             tokens,
             pos: 0,
             variables: HashMap::new(),
+            variable_defs: Vec::new(),
+            used_variables: RefCell::new(HashSet::new()),
+            hints: true,
             errors: Vec::new(),
             indent_depth: 0,
             panic_mode: false,
+            depth: 0,
+            max_depth: MAX_NESTING_DEPTH,
+            geometry_warnings: false,
         }
     }
 
+    /// Disable hint diagnostics (unused variables and symbols)
+    pub fn without_hints(mut self) -> Self { self.hints = false; self }
+
+    /// Limit how deeply shapes may nest; deeper statements are reported and skipped
+    pub fn with_max_depth(mut self, max_depth: usize) -> Self { self.max_depth = max_depth; self }
+
+    /// Warn about shapes missing the geometry that decides their extent. Off for a
+    /// bare parser, since bare shapes with default sizes are common in sketches;
+    /// `compile`, `validate` and the bindings' diagnostics turn it on.
+    pub fn with_geometry_warnings(mut self) -> Self { self.geometry_warnings = true; self }
+
     pub(crate) fn current(&self) -> Option<&Token> {
         self.tokens.get(self.pos)
     }
@@ -116,7 +273,6 @@ impl Parser {
         self.tokens.get(self.pos + 1)
     }
 
-    #[allow(dead_code)]
     pub(crate) fn peek_n(&self, n: usize) -> Option<&Token> {
         self.tokens.get(self.pos + n)
     }
@@ -149,6 +305,7 @@ impl Parser {
     pub(crate) fn resolve(&self, tok: &Token) -> TokenValue {
         if tok.ttype == TokenType::Var {
             if let TokenValue::Str(name) = &tok.value {
+                self.used_variables.borrow_mut().insert(name.clone());
                 // Check local scope first (for backward compatibility in same-block vars)
                 if let Some(val) = self.variables.get(name) {
                     return val.clone();
@@ -161,7 +318,6 @@ impl Parser {
     }
 
     /// Create a VarRef PropValue for deferred resolution
-    #[allow(dead_code)] // Available for future use in property parsing
     pub(crate) fn var_ref(&self, tok: &Token) -> PropValue {
         if let TokenValue::Str(name) = &tok.value {
             let name = name.strip_prefix('$').unwrap_or(name);
@@ -185,12 +341,72 @@ impl Parser {
     fn error_at_current(&mut self, msg: &str, kind: ErrorKind, suggestion: Option<&str>) {
         if self.panic_mode { return; } // Suppress cascade errors
         
-        let (line, col) = self.current().map(|t| (t.line, t.col)).unwrap_or((0, 0));
-        let mut err = ParseError::new(msg, kind, line, col);
+        let mut err = ParseError::spanning(msg, kind, self.current_span());
+        if let Some(s) = suggestion { err = err.with_suggestion(s); }
+        self.errors.push(err);
+    }
+
+    /// Record a non-fatal warning at current token
+    fn warn_at_current(&mut self, msg: &str, kind: ErrorKind, suggestion: Option<&str>) {
+        let mut err = ParseError::spanning(msg, kind, self.current_span()).with_severity(ErrorSeverity::Warning);
         if let Some(s) = suggestion { err = err.with_suggestion(s); }
         self.errors.push(err);
     }
 
+    /// Span of the whole current token
+    fn current_span(&self) -> Span {
+        self.current().map_or_else(|| Span::point(0, 0), Token::span)
+    }
+
+    /// Consume a number token, clamping it into `[min, max]` with a warning if out of range
+    fn parse_ranged_number(&mut self, prop: &str, min: f64, max: f64) -> Option<f64> {
+        let n = match self.current() {
+            Some(Token { ttype: TokenType::Number, value: TokenValue::Num(n), .. }) => *n,
+            _ => return None,
+        };
+        let clamped = n.clamp(min, max);
+        if clamped != n {
+            let range = if max == f64::MAX { format!(">= {}", min) } else { format!("[{}, {}]", min, max) };
+            self.warn_at_current(
+                &format!("{} {} is out of range {}; clamped to {}", prop, n, range, clamped),
+                ErrorKind::InvalidValue,
+                None,
+            );
+        }
+        self.advance();
+        Some(clamped)
+    }
+
+    /// Consume a `N%` token as is
+    fn parse_signed_percent(&mut self) -> Option<f64> {
+        let p = match self.current() {
+            Some(Token { ttype: TokenType::Percent, value: TokenValue::Num(p), .. }) => *p,
+            _ => return None,
+        };
+        self.advance();
+        Some(p)
+    }
+
+    /// Consume a `N%` token, clamped to be non-negative
+    fn parse_percent(&mut self) -> Option<f64> {
+        let p = match self.current() {
+            Some(Token { ttype: TokenType::Percent, value: TokenValue::Num(p), .. }) => p.max(0.0),
+            _ => return None,
+        };
+        self.advance();
+        Some(p)
+    }
+
+    /// Stroke width in pixels, or in percent of the viewBox (resolved during layout)
+    fn parse_stroke_width(&mut self, style: &mut AstStyle) {
+        if let Some(p) = self.parse_percent() {
+            style.stroke_width_pct = Some(p);
+        } else if let Some(n) = self.parse_ranged_number("stroke-width", 0.0, f64::MAX) {
+            style.stroke_width = n;
+            style.stroke_width_pct = None;
+        }
+    }
+
     /// Record error and immediately synchronize to recover
     fn error_and_sync(&mut self, msg: &str, kind: ErrorKind, suggestion: Option<&str>) {
         self.error_at_current(msg, kind, suggestion);
@@ -259,11 +475,100 @@ impl Parser {
         }
     }
 
-    /// Check if errors occurred
-    pub fn has_errors(&self) -> bool { !self.errors.is_empty() }
+    /// Check if errors occurred (warnings and hints don't count)
+    pub fn has_errors(&self) -> bool { self.errors.iter().any(|e| e.severity == ErrorSeverity::Error) }
 
-    /// Get error count
-    pub fn error_count(&self) -> usize { self.errors.len() }
+    /// Get error count (warnings and hints excluded)
+    pub fn error_count(&self) -> usize { self.errors.iter().filter(|e| e.severity == ErrorSeverity::Error).count() }
+
+    /// Inline top-level `include "path"` statements with the tokens of the source
+    /// `loader` returns for that path. Runs recursively; include cycles and paths the
+    /// loader can't supply are reported and the statement dropped.
+    pub fn resolve_includes(&mut self, loader: impl Fn(&str) -> Option<String>) {
+        let tokens = std::mem::take(&mut self.tokens);
+        self.tokens = self.expand_includes(tokens, &loader, &mut Vec::new());
+        self.pos = 0;
+    }
+
+    fn expand_includes(&mut self, tokens: Vec<Token>, loader: &dyn Fn(&str) -> Option<String>, stack: &mut Vec<String>) -> Vec<Token> {
+        let mut out = Vec::with_capacity(tokens.len());
+        let (mut depth, mut line_start) = (0usize, true);
+        let mut iter = tokens.into_iter().peekable();
+        while let Some(tok) = iter.next() {
+            match tok.ttype {
+                TokenType::Indent => depth += 1,
+                TokenType::Dedent => depth = depth.saturating_sub(1),
+                _ => {}
+            }
+            let is_include = line_start && depth == 0 && tok.value == TokenValue::Str("include".into())
+                && iter.peek().is_some_and(|t| t.ttype == TokenType::String);
+            line_start = matches!(tok.ttype, TokenType::Newline | TokenType::Dedent);
+            if !is_include {
+                out.push(tok);
+                continue;
+            }
+            let Some(Token { value: TokenValue::Str(path), col, raw, offset, .. }) = iter.next() else { continue };
+            let span = Span::range(tok.line, tok.col, tok.line, col + raw.chars().count()).with_bytes(tok.offset, offset + raw.len());
+            let err = |msg: String, kind, fix: &str| ParseError::spanning(msg, kind, span.clone()).with_suggestion(fix);
+            if stack.contains(&path) {
+                let chain = stack.iter().chain([&path]).map(String::as_str).collect::<Vec<_>>().join(" -> ");
+                self.errors.push(err(format!("Cyclic include: {}", chain), ErrorKind::CyclicInclude, "Move the shared definitions into a file neither includes"));
+                continue;
+            }
+            match loader(&path) {
+                Some(source) => {
+                    let mut lexer = Lexer::new(&source);
+                    let mut included = lexer.tokenize();
+                    included.pop(); // Eof
+                    self.errors.append(&mut lexer.errors);
+                    stack.push(path);
+                    out.extend(self.expand_includes(included, loader, stack));
+                    stack.pop();
+                }
+                None => self.errors.push(err(format!("Cannot load include '{}'", path), ErrorKind::MissingInclude, "Check the path is relative to the including file")),
+            }
+        }
+        out
+    }
+
+    /// Parse one standalone value (`#ff0`, `50x30`, `25%`, `$x`, `[0,0 10,0]`), e.g. for a
+    /// property inspector. Variables come back as unresolved `VarRef`s; anything after
+    /// the value is an error.
+    pub fn parse_value(source: &str) -> Result<PropValue, Vec<ParseError>> {
+        let mut lexer = Lexer::new(source.trim());
+        let mut parser = Self::new(lexer.tokenize());
+        let value = parser.parse_value_token();
+        parser.skip_newlines();
+        if let Some(tok) = parser.current().filter(|t| t.ttype != TokenType::Eof) {
+            let msg = unexpected(tok, "after value");
+            parser.error_at_current(&msg, ErrorKind::UnexpectedToken, Some("Give exactly one value"));
+        }
+        lexer.errors.append(&mut parser.errors);
+        if lexer.errors.is_empty() { Ok(value) } else { Err(lexer.errors) }
+    }
+
+    fn parse_value_token(&mut self) -> PropValue {
+        let Some(tok) = self.current().cloned() else { return PropValue::None };
+        let value = match (tok.ttype, &tok.value) {
+            (TokenType::LBracket, _) => return PropValue::from_list(&self.parse_list_items()),
+            (TokenType::Var, _) => self.var_ref(&tok),
+            (TokenType::Percent, TokenValue::Num(n)) => PropValue::Dim(Dimension::Percent(*n)),
+            (TokenType::Pair, TokenValue::Pair(a, b)) => PropValue::Pair(*a, *b),
+            (TokenType::PercentPair, TokenValue::PercentPair(a, b)) => PropValue::PercentPair(*a, *b),
+            (_, TokenValue::Num(n)) => PropValue::Num(*n),
+            (TokenType::Color | TokenType::String | TokenType::Ident | TokenType::Size, TokenValue::Str(s)) => PropValue::Str(s.clone()),
+            (TokenType::Eof | TokenType::Newline, _) => {
+                self.error_at_current("Expected a value", ErrorKind::MissingToken, Some("e.g. #ff0, 50x30, 25% or $accent"));
+                return PropValue::None;
+            }
+            _ => {
+                self.error_at_current(&format!("Expected a value, found {:?}", tok.ttype), ErrorKind::UnexpectedToken, None);
+                return PropValue::None;
+            }
+        };
+        self.advance();
+        value
+    }
 
     /// Parse the token stream into an AST
     pub fn parse(&mut self) -> AstNode {
@@ -274,15 +579,87 @@ impl Parser {
             if tok.ttype == TokenType::Eof {
                 break;
             }
+            // A block left over from a rejected statement closes here; recovery stops short of it
+            if tok.ttype == TokenType::Dedent {
+                self.advance();
+                continue;
+            }
             if let Some(node) = self.parse_statement() {
                 children.push(node);
             }
             self.skip_newlines();
         }
 
+        if self.hints { self.hint_unused(&children); }
         AstNode::Scene(children)
     }
 
+    /// Hint at variables never referenced and symbols never instantiated.
+    /// Names starting with `_` (`$_scratch`, `symbol "_draft"`) are exempt.
+    fn hint_unused(&mut self, nodes: &[AstNode]) {
+        let hint = |msg: String, kind, span: &Span, fix: &str| ParseError::new(msg, kind, span.start_line, span.start_col)
+            .with_span(span.clone()).with_severity(ErrorSeverity::Hint).with_suggestion(fix);
+        let used = self.used_variables.borrow();
+        let mut seen = HashSet::new();
+        let mut hints: Vec<ParseError> = self.variable_defs.iter()
+            .filter(|(name, _)| !name.starts_with("$_") && !used.contains(name) && seen.insert(name.as_str()))
+            .map(|(name, span)| hint(format!("Variable '{}' is never used", name), ErrorKind::UnusedVariable, span,
+                &format!("Remove it, or rename to '$_{}' to silence this hint", name.trim_start_matches('$'))))
+            .collect();
+        let used_symbols: HashSet<&str> = nodes.iter().flat_map(|n| match n {
+            AstNode::Use(u) => vec![u.href.as_str()],
+            AstNode::Symbol(s) => s.uses.iter().map(|u| u.href.as_str()).collect(),
+            _ => Vec::new(),
+        }).collect();
+        hints.extend(nodes.iter().filter_map(|n| match n {
+            AstNode::Symbol(s) if !s.id.starts_with('_') && !used_symbols.contains(s.id.as_str()) => Some(
+                hint(format!("Symbol '{}' is never used", s.id), ErrorKind::UnusedSymbol, &s.span,
+                    &format!("Instantiate it with: use \"{}\"", s.id))),
+            _ => None,
+        }));
+        self.errors.extend(hints);
+    }
+
+    /// Parse a statement inside a block, one level deeper. Past `max_depth` the
+    /// statement and everything under it is skipped with an error instead.
+    fn parse_nested(&mut self) -> Option<AstNode> {
+        if self.depth >= self.max_depth {
+            self.error_at_current(
+                &format!("Nesting exceeds the limit of {} levels", self.max_depth),
+                ErrorKind::NestingTooDeep,
+                Some("Flatten the structure or split it into symbols"),
+            );
+            self.skip_statement();
+            return None;
+        }
+        self.depth += 1;
+        let node = self.parse_statement();
+        self.depth -= 1;
+        node
+    }
+
+    /// Skip the current line and any block indented under it, without recursing
+    fn skip_statement(&mut self) {
+        let mut level = 0usize;
+        while let Some(tok) = self.current() {
+            match tok.ttype {
+                TokenType::Eof => break,
+                TokenType::Indent => level += 1,
+                TokenType::Dedent if level == 0 => break,
+                TokenType::Dedent => {
+                    level -= 1;
+                    if level == 0 { self.advance(); break; }
+                }
+                TokenType::Newline if level == 0 && self.peek_next().map(|t| t.ttype) != Some(TokenType::Indent) => {
+                    self.advance();
+                    break;
+                }
+                _ => {}
+            }
+            self.advance();
+        }
+    }
+
     pub(crate) fn parse_statement(&mut self) -> Option<AstNode> {
         let tok = self.current()?;
 
@@ -324,6 +701,7 @@ impl Parser {
             "edge" => Some(AstNode::Shape(self.parse_edge_as_shape())),
             "symbol" => Some(self.parse_symbol()),
             "use" => Some(self.parse_use()),
+            "include" => Some(self.parse_include()),
             _ if SHAPES.contains(cmd.as_str()) => Some(self.parse_shape(&cmd)),
             _ => {
                 // Unknown command - suggest similar valid commands
@@ -339,29 +717,12 @@ impl Parser {
         }
     }
 
-    /// Suggest similar valid commands for typos
+    /// Suggest the closest valid command for typos
     fn suggest_command(cmd: &str) -> Option<String> {
         let all_cmds = ["canvas", "group", "stack", "row", "graph", "node", "edge",
-                        "symbol", "use", "rect", "circle", "ellipse", "line", "path", 
+                        "symbol", "use", "include", "rect", "circle", "ellipse", "line", "path", 
                         "polygon", "text", "image", "arc", "curve", "diamond"];
-        
-        // Simple Levenshtein-style matching for common typos
-        let cmd_lower = cmd.to_lowercase();
-        for valid in all_cmds {
-            if cmd_lower.starts_with(&valid[..1.min(valid.len())]) && 
-               (cmd_lower.len() as i32 - valid.len() as i32).abs() <= 2 {
-                return Some(format!("Did you mean '{}'?", valid));
-            }
-        }
-        
-        // Check for prefix matches
-        for valid in all_cmds {
-            if valid.starts_with(&cmd_lower) || cmd_lower.starts_with(valid) {
-                return Some(format!("Did you mean '{}'?", valid));
-            }
-        }
-        
-        Some(format!("Valid commands: {}", all_cmds[..8].join(", ")))
+        did_you_mean(cmd, all_cmds)
     }
 
     fn parse_variable(&mut self) -> Option<AstNode> {
@@ -370,10 +731,15 @@ impl Parser {
             TokenValue::Str(s) => s.clone(),
             _ => return None,
         };
+        let span = name_tok.span();
+        self.variable_defs.push((name.clone(), span.clone()));
 
         if self.matches(&[TokenType::Equals]) {
             self.advance();
-            if let Some(val_tok) = self.current() {
+            if self.matches(&[TokenType::LBracket]) {
+                let items = self.parse_list_items();
+                self.variables.insert(name.clone(), TokenValue::List(items));
+            } else if let Some(val_tok) = self.current() {
                 if !self.matches(&[TokenType::Newline, TokenType::Eof]) {
                     self.variables.insert(name.clone(), val_tok.value.clone());
                     self.advance();
@@ -384,6 +750,7 @@ impl Parser {
         Some(AstNode::Variable {
             name: name.clone(),
             value: self.variables.get(&name).cloned(),
+            span,
         })
     }
 
@@ -397,11 +764,9 @@ impl Parser {
                     if let Some(size) = CanvasSize::from_str(&name) {
                         canvas.size = size;
                     } else {
-                        self.error_at_current(
-                            &format!("Invalid canvas size '{}'", name),
-                            ErrorKind::InvalidValue,
-                            Some(&format!("Valid sizes: {}", CanvasSize::all_names().join(", ")))
-                        );
+                        let hint = did_you_mean(&name, CanvasSize::all_names().iter().copied())
+                            .unwrap_or_else(|| format!("Valid sizes: {}", CanvasSize::all_names().join(", ")));
+                        self.error_at_current(&format!("Invalid canvas size '{}'", name), ErrorKind::InvalidValue, Some(&hint));
                     }
                 }
             }
@@ -421,6 +786,10 @@ impl Parser {
             );
         }
 
+        if let Some(aspect) = self.parse_aspect_ratio() {
+            canvas.aspect = aspect;
+        }
+
         // Parse canvas properties with recovery
         while self.matches(&[TokenType::Ident, TokenType::Size]) {
             let prop = self.current().and_then(|t| match &t.value {
@@ -446,12 +815,31 @@ impl Parser {
                         );
                     }
                 }
+                Some("gradient") => canvas.gradient = Some(self.parse_gradient()),
+                Some("image") => {
+                    match self.current().map(|t| t.value.clone()) {
+                        Some(TokenValue::Str(href)) if self.matches(&[TokenType::String]) => {
+                            canvas.image = Some(href);
+                            self.advance();
+                        }
+                        _ => self.error_at_current(
+                            "Expected image href after 'image'",
+                            ErrorKind::InvalidValue,
+                            Some("Quote the href, e.g. image \"bg.png\"")
+                        ),
+                    }
+                }
                 Some(p) => {
-                    self.error_at_current(
-                        &format!("Unknown canvas property '{}'", p),
-                        ErrorKind::InvalidProperty,
-                        Some("Valid canvas properties: fill")
-                    );
+                    if let Some(hint) = did_you_mean(p, CanvasSize::all_names().iter().copied()) {
+                        self.error_at_current(&format!("Invalid canvas size '{}'", p), ErrorKind::InvalidValue, Some(&hint));
+                    } else {
+                        let hint = did_you_mean(p, ["fill", "gradient", "image"]);
+                        self.error_at_current(
+                            &format!("Unknown canvas property '{}'", p),
+                            ErrorKind::InvalidProperty,
+                            Some(hint.as_deref().unwrap_or("Valid canvas properties: fill, gradient, image"))
+                        );
+                    }
                     self.sync_to_line_end();
                 }
                 None => {}
@@ -461,6 +849,40 @@ impl Parser {
         AstNode::Canvas(canvas)
     }
 
+    /// Parse optional canvas aspect ratio: `W:H` or a named ratio (`wide`, `tall`, `square`)
+    fn parse_aspect_ratio(&mut self) -> Option<AspectRatio> {
+        let is = |t: Option<&Token>, ty: TokenType| t.map(|t| t.ttype == ty).unwrap_or(false);
+        if is(self.current(), TokenType::Number) && is(self.peek_next(), TokenType::Colon) && is(self.peek_n(2), TokenType::Number) {
+            let num = |t: Option<&Token>| match t.map(|t| &t.value) { Some(TokenValue::Num(n)) => *n, _ => 0.0 };
+            let (w, h) = (num(self.current()), num(self.peek_n(2)));
+            let ratio = (w.fract() == 0.0 && h.fract() == 0.0).then(|| AspectRatio::new(w as u32, h as u32)).flatten();
+            if ratio.is_none() {
+                self.error_at_current(
+                    &format!("Invalid aspect ratio '{}:{}'", w, h),
+                    ErrorKind::InvalidValue,
+                    Some("Use positive whole numbers like 2:1 or 16:9"),
+                );
+            }
+            for _ in 0..3 { self.advance(); }
+            return ratio;
+        }
+        let name = self.ident_value()?;
+        let ratio = AspectRatio::from_name(&name)?;
+        self.advance();
+        Some(ratio)
+    }
+
+    /// `aspect W:H` on a layout child, stored as a pair for the solver
+    fn parse_child_aspect(&mut self, shape: &mut AstShape) {
+        let start = self.pos;
+        match self.parse_aspect_ratio() {
+            Some(r) => { shape.props.insert("aspect".into(), PropValue::Pair(r.w as f64, r.h as f64)); }
+            // A malformed `W:H` has already been reported
+            None if self.pos == start => self.error_at_current("Expected an aspect ratio", ErrorKind::InvalidValue, Some("e.g. aspect 16:9")),
+            None => {}
+        }
+    }
+
     fn parse_group(&mut self) -> AstNode {
         let mut shape = AstShape::new("group");
 
@@ -475,7 +897,10 @@ impl Parser {
         self.skip_newlines();
         if self.matches(&[TokenType::Indent]) {
             self.advance();
+            // Variables defined in the block go out of scope with it
+            let outer = self.variables.clone();
             self.parse_block(&mut shape);
+            self.variables = outer;
         }
 
         AstNode::Shape(shape)
@@ -553,6 +978,11 @@ impl Parser {
                             let dim = self.parse_dimension_value();
                             shape.props.insert("height".into(), PropValue::Dim(dim));
                         }
+                        p if SIZE_LIMIT_PROPS.contains(p) => {
+                            let dim = self.parse_dimension_value();
+                            shape.props.insert(p.into(), PropValue::Dim(dim));
+                        }
+                        "aspect" => self.parse_child_aspect(&mut shape),
                         "padding" => {
                             layout.padding = Some(self.parse_padding());
                         }
@@ -622,6 +1052,15 @@ impl Parser {
                             self.advance();
                             return Dimension::Auto;
                         }
+                        if s.starts_with("calc(") {
+                            let calc = parse_calc(s);
+                            if calc.is_none() {
+                                let msg = format!("Invalid calc expression '{}'", s);
+                                self.error_at_current(&msg, ErrorKind::InvalidValue, Some("Expected e.g. calc(100% - 20)"));
+                            }
+                            self.advance();
+                            return calc.map_or(Dimension::Auto, |(percent, px)| Dimension::Calc { percent, px });
+                        }
                     }
                 }
                 _ => {}
@@ -662,43 +1101,51 @@ impl Parser {
     fn parse_justify_content(&mut self) -> JustifyContent {
         use super::ast::JustifyContent;
         
-        if self.matches(&[TokenType::Ident]) {
-            if let Some(tok) = self.advance() {
-                if let TokenValue::Str(s) = &tok.value {
-                    return match s.as_str() {
-                        "start" => JustifyContent::Start,
-                        "end" => JustifyContent::End,
-                        "center" => JustifyContent::Center,
-                        "space-between" => JustifyContent::SpaceBetween,
-                        "space-around" => JustifyContent::SpaceAround,
-                        "space-evenly" => JustifyContent::SpaceEvenly,
-                        _ => JustifyContent::Start,
-                    };
-                }
+        let Some(s) = self.ident_value() else { return JustifyContent::Start };
+        let val = match s.as_str() {
+            "start" => JustifyContent::Start,
+            "end" => JustifyContent::End,
+            "center" => JustifyContent::Center,
+            "space-between" => JustifyContent::SpaceBetween,
+            "space-around" => JustifyContent::SpaceAround,
+            "space-evenly" => JustifyContent::SpaceEvenly,
+            _ => {
+                let hint = did_you_mean(&s, JUSTIFY_VALUES.iter().copied());
+                self.error_at_current(&format!("Invalid justify value '{}'", s), ErrorKind::InvalidValue, hint.as_deref());
+                JustifyContent::Start
             }
-        }
-        JustifyContent::Start
+        };
+        self.advance();
+        val
     }
     
     /// Parse align-items value
     fn parse_align_items(&mut self) -> AlignItems {
         use super::ast::AlignItems;
         
-        if self.matches(&[TokenType::Ident]) {
-            if let Some(tok) = self.advance() {
-                if let TokenValue::Str(s) = &tok.value {
-                    return match s.as_str() {
-                        "start" => AlignItems::Start,
-                        "end" => AlignItems::End,
-                        "center" => AlignItems::Center,
-                        "stretch" => AlignItems::Stretch,
-                        "baseline" => AlignItems::Baseline,
-                        _ => AlignItems::Start,
-                    };
-                }
+        let Some(s) = self.ident_value() else { return AlignItems::Start };
+        let val = match s.as_str() {
+            "start" => AlignItems::Start,
+            "end" => AlignItems::End,
+            "center" => AlignItems::Center,
+            "stretch" => AlignItems::Stretch,
+            "baseline" => AlignItems::Baseline,
+            _ => {
+                let hint = did_you_mean(&s, ALIGN_VALUES.iter().copied());
+                self.error_at_current(&format!("Invalid align value '{}'", s), ErrorKind::InvalidValue, hint.as_deref());
+                AlignItems::Start
             }
+        };
+        self.advance();
+        val
+    }
+
+    /// String value of the current token if it is an identifier (not consumed)
+    fn ident_value(&self) -> Option<String> {
+        match self.current() {
+            Some(Token { ttype: TokenType::Ident, value: TokenValue::Str(s), .. }) => Some(s.clone()),
+            _ => None,
         }
-        AlignItems::Start
     }
     
     /// Parse padding values (1, 2, or 4 values)
@@ -750,7 +1197,7 @@ impl Parser {
 
                     // Check for nested shapes
                     if SHAPES.contains(prop.as_str()) || prop == "stack" || prop == "row" {
-                        match self.parse_statement() {
+                        match self.parse_nested() {
                             Some(AstNode::Shape(mut child)) => {
                                 // Check for child layout constraints
                                 self.apply_child_layout_props(&mut child);
@@ -819,6 +1266,11 @@ impl Parser {
                 let dim = self.parse_dimension_value();
                 shape.props.insert("height".into(), PropValue::Dim(dim));
             }
+            p if SIZE_LIMIT_PROPS.contains(p) => {
+                let dim = self.parse_dimension_value();
+                shape.props.insert(p.into(), PropValue::Dim(dim));
+            }
+            "aspect" => self.parse_child_aspect(shape),
             "size" => {
                 let dim_pair = self.parse_dimension_pair();
                 shape.props.insert("size".into(), PropValue::DimPair(dim_pair));
@@ -937,11 +1389,12 @@ impl Parser {
                                     if GRAPH_LAYOUTS.contains(s.as_str()) {
                                         graph.layout = s;
                                     } else {
-                                        self.error_at_current(
-                                            &format!("Invalid layout '{}'", s),
-                                            ErrorKind::InvalidValue,
-                                            Some(&format!("Valid layouts: {}", GRAPH_LAYOUTS.iter().copied().collect::<Vec<_>>().join(", ")))
-                                        );
+                                        let hint = did_you_mean(&s, GRAPH_LAYOUTS.iter().copied()).unwrap_or_else(|| {
+                                            let mut names: Vec<_> = GRAPH_LAYOUTS.iter().copied().collect();
+                                            names.sort_unstable();
+                                            format!("Valid layouts: {}", names.join(", "))
+                                        });
+                                        self.error_at_current(&format!("Invalid layout '{}'", s), ErrorKind::InvalidValue, Some(&hint));
                                     }
                                 }
                             } else {
@@ -977,18 +1430,30 @@ impl Parser {
                                 self.error_at_current("Expected number after 'spacing'", ErrorKind::MissingToken, None);
                             }
                         }
+                        "anchors" => match self.ident_value() {
+                            Some(mode) if ANCHOR_MODES.contains(&mode.as_str()) => {
+                                graph.anchors = mode;
+                                self.advance();
+                            }
+                            found => {
+                                let hint = found.as_deref().and_then(|m| did_you_mean(m, ANCHOR_MODES))
+                                    .unwrap_or_else(|| format!("Valid anchor modes: {}", ANCHOR_MODES.join(", ")));
+                                self.error_at_current("Expected anchor mode", ErrorKind::InvalidValue, Some(&hint));
+                                if found.is_some() { self.advance(); }
+                            }
+                        },
                         _ => {
                             self.error_at_current(
                                 &format!("Unknown graph property '{}'", cmd),
                                 ErrorKind::InvalidProperty,
-                                Some("Valid graph properties: node, edge, layout, direction, spacing")
+                                Some("Valid graph properties: node, edge, layout, direction, spacing, anchors")
                             );
                             self.sync_to_line_end();
                         }
                     }
                 } else {
                     self.error_at_current(
-                        &format!("Unexpected {:?} in graph block", tok.ttype),
+                        &unexpected(tok, "in graph block"),
                         ErrorKind::UnexpectedToken,
                         None
                     );
@@ -1162,14 +1627,16 @@ impl Parser {
                         "arrow" if self.matches(&[TokenType::Ident]) => {
                             if let Some(t) = self.advance() {
                                 if let TokenValue::Str(s) = &t.value {
-                                    if ARROW_TYPES.contains(s.as_str()) { edge.arrow = s.clone(); }
+                                    if ARROW_TYPES.contains(&s.as_str()) { edge.arrow = s.clone(); }
                                 }
                             }
+                            self.parse_arrow_head(&mut edge);
                         }
                         "label" if self.matches(&[TokenType::String]) => {
                             if let Some(t) = self.advance() {
                                 if let TokenValue::Str(s) = &t.value { edge.label = Some(s.clone()); }
                             }
+                            self.parse_label_bg(&mut edge);
                         }
                         "stroke" if self.matches(&[TokenType::Color, TokenType::Var]) => {
                             if let Some(t) = self.current() {
@@ -1178,7 +1645,7 @@ impl Parser {
                             }
                         }
                         k if EDGE_STYLES.contains(k) => edge.style = k.to_string(),
-                        k if ARROW_TYPES.contains(k) => edge.arrow = k.to_string(),
+                        k if ARROW_TYPES.contains(&k) => edge.arrow = k.to_string(),
                         _ => {}
                     }
                 }
@@ -1219,14 +1686,16 @@ impl Parser {
                         "arrow" if self.matches(&[TokenType::Ident]) => {
                             if let Some(t) = self.advance() {
                                 if let TokenValue::Str(s) = &t.value {
-                                    if ARROW_TYPES.contains(s.as_str()) { edge.arrow = s.clone(); }
+                                    if ARROW_TYPES.contains(&s.as_str()) { edge.arrow = s.clone(); }
                                 }
                             }
+                            self.parse_arrow_head(edge);
                         }
                         "label" if self.matches(&[TokenType::String]) => {
                             if let Some(t) = self.advance() {
                                 if let TokenValue::Str(s) = &t.value { edge.label = Some(s.clone()); }
                             }
+                            self.parse_label_bg(edge);
                         }
                         "stroke" if self.matches(&[TokenType::Color, TokenType::Var]) => {
                             if let Some(t) = self.current() {
@@ -1235,7 +1704,7 @@ impl Parser {
                             }
                         }
                         k if EDGE_STYLES.contains(k) => edge.style = k.to_string(),
-                        k if ARROW_TYPES.contains(k) => edge.arrow = k.to_string(),
+                        k if ARROW_TYPES.contains(&k) => edge.arrow = k.to_string(),
                         _ => {}
                     }
                 } else {
@@ -1245,6 +1714,31 @@ impl Parser {
         }
     }
 
+    /// Optional `style <head>` and `size N` after an edge's arrow direction
+    fn parse_arrow_head(&mut self, edge: &mut GraphEdge) {
+        loop {
+            let next = self.peek_next().map(|t| (t.ttype, t.value.clone()));
+            match (self.ident_value().as_deref(), next) {
+                (Some("style"), Some((TokenType::Ident, TokenValue::Str(head)))) if ARROW_HEADS.contains(&head.as_str()) => {
+                    edge.arrow_head = head;
+                }
+                (Some("size"), Some((TokenType::Number, TokenValue::Num(n)))) => edge.arrow_size = n,
+                _ => break,
+            }
+            self.advance();
+            self.advance();
+        }
+    }
+
+    /// Optional `bg <color>` after an edge label
+    fn parse_label_bg(&mut self, edge: &mut GraphEdge) {
+        if self.ident_value().as_deref() != Some("bg") { return; }
+        let Some(tok) = self.peek_next().filter(|t| matches!(t.ttype, TokenType::Color | TokenType::Var)) else { return };
+        if let TokenValue::Str(s) = self.resolve(tok) { edge.label_bg = Some(s); }
+        self.advance();
+        self.advance();
+    }
+
     /// Parse standalone node (outside graph) - returns as shape with special kind
     fn parse_node_as_shape(&mut self) -> AstShape {
         let node = self.parse_graph_node();
@@ -1266,7 +1760,10 @@ impl Parser {
         shape.props.insert("to".into(), PropValue::Str(edge.to));
         shape.props.insert("style".into(), PropValue::Str(edge.style));
         shape.props.insert("arrow".into(), PropValue::Str(edge.arrow));
+        shape.props.insert("arrow-head".into(), PropValue::Str(edge.arrow_head));
+        shape.props.insert("arrow-size".into(), PropValue::Num(edge.arrow_size));
         if let Some(label) = edge.label { shape.props.insert("label".into(), PropValue::Str(label)); }
+        if let Some(bg) = edge.label_bg { shape.props.insert("label-bg".into(), PropValue::Str(bg)); }
         if let Some(stroke) = edge.stroke { shape.style.stroke = Some(stroke); }
         shape.style.stroke_width = edge.stroke_width;
         shape
@@ -1280,7 +1777,10 @@ impl Parser {
         // Parse symbol ID (required)
         if self.matches(&[TokenType::String]) {
             if let Some(tok) = self.advance() {
-                if let TokenValue::Str(s) = &tok.value { symbol.id = s.clone(); }
+                if let TokenValue::Str(s) = &tok.value {
+                    symbol.id = s.clone();
+                    symbol.span = tok.span();
+                }
             }
         } else {
             self.error_at_current("Expected symbol ID (string)", ErrorKind::MissingToken, Some("symbol \"my-icon\""));
@@ -1346,16 +1846,17 @@ impl Parser {
                         _ => { self.advance(); continue; }
                     };
 
-                    if SHAPES.contains(cmd.as_str()) || cmd == "group" {
-                        match self.parse_statement() {
+                    if SHAPES.contains(cmd.as_str()) || cmd == "group" || cmd == "use" {
+                        match self.parse_nested() {
                             Some(AstNode::Shape(child)) => symbol.children.push(child),
+                            Some(AstNode::Use(use_ref)) => symbol.uses.push(use_ref),
                             _ => {}
                         }
                     } else {
                         self.error_at_current(
                             &format!("Only shapes allowed in symbol block, found '{}'", cmd),
                             ErrorKind::InvalidProperty,
-                            Some("Use rect, circle, path, etc. inside symbol blocks")
+                            Some("Use rect, circle, path, use, etc. inside symbol blocks")
                         );
                         self.advance();
                         self.sync_to_line_end();
@@ -1367,6 +1868,20 @@ impl Parser {
         }
     }
 
+    /// Parse `include "path"` into an unresolved `Include` node; the file is
+    /// loaded and spliced in later by `resolve_includes`
+    fn parse_include(&mut self) -> AstNode {
+        let path = match self.current() {
+            Some(Token { ttype: TokenType::String, value: TokenValue::Str(s), .. }) => s.clone(),
+            _ => {
+                self.error_at_current("Expected include path (string)", ErrorKind::MissingToken, Some("include \"palette.icon\""));
+                String::new()
+            }
+        };
+        if !path.is_empty() { self.advance(); }
+        AstNode::Include(path)
+    }
+
     /// Parse use reference to instantiate a symbol (SVG <use>)
     fn parse_use(&mut self) -> AstNode {
         use super::ast::AstUse;
@@ -1375,7 +1890,10 @@ impl Parser {
         // Parse symbol reference (required)
         if self.matches(&[TokenType::String]) {
             if let Some(tok) = self.advance() {
-                if let TokenValue::Str(s) = &tok.value { use_ref.href = s.clone(); }
+                if let TokenValue::Str(s) = &tok.value {
+                    use_ref.href = s.clone();
+                    use_ref.span = tok.span();
+                }
             }
         } else {
             self.error_at_current("Expected symbol reference (string)", ErrorKind::MissingToken, Some("use \"my-icon\""));
@@ -1472,6 +1990,7 @@ impl Parser {
 
     pub(crate) fn parse_shape(&mut self, kind: &str) -> AstNode {
         let mut shape = AstShape::new(kind);
+        let start = self.pos.checked_sub(1).and_then(|i| self.tokens.get(i)).map(|t| (t.line, t.col)).unwrap_or((0, 0));
 
         while let Some(tok) = self.current() {
             if self.matches(&[TokenType::Newline, TokenType::Eof]) {
@@ -1510,9 +2029,18 @@ impl Parser {
                         TokenValue::Str(s) => s.clone(),
                         _ => { self.advance(); continue; }
                     };
+                    // Inline style (`stroke #000 width 2 opacity 0.8`) reuses the block handlers;
+                    // keywords claim their numbers, so bare numbers stay positional
+                    if STYLE_PROPS.contains(key.as_str()) {
+                        self.parse_style_prop(&mut shape);
+                        continue;
+                    }
                     self.advance();
 
                     match key.as_str() {
+                        "width" if self.matches(&[TokenType::Number, TokenType::Percent]) => {
+                            self.parse_stroke_width(&mut shape.style);
+                        }
                         "at" if self.matches(&[TokenType::Pair]) => {
                             if let Some(t) = self.advance() {
                                 if let TokenValue::Pair(a, b) = t.value {
@@ -1541,6 +2069,14 @@ impl Parser {
                                 }
                             }
                         }
+                        "arrow" if matches!(kind, "line" | "path") => {
+                            if let Some(a) = self.parse_arrow() { shape.props.insert("arrow".into(), PropValue::Str(a)); }
+                        }
+                        "rx" | "ry" if matches!(kind, "circle" | "ellipse") && self.matches(&[TokenType::Number]) => {
+                            if let Some(TokenValue::Num(n)) = self.advance().map(|t| t.value.clone()) {
+                                shape.props.insert(key, PropValue::Num(n));
+                            }
+                        }
                         "from" if self.matches(&[TokenType::Pair]) => {
                             if let Some(t) = self.advance() {
                                 if let TokenValue::Pair(a, b) = t.value {
@@ -1566,9 +2102,61 @@ impl Parser {
                             shape.props.insert("points".into(), PropValue::Points(self.parse_points()));
                         }
                         "href" if self.matches(&[TokenType::String]) => {
+                            if let Some(TokenValue::Str(s)) = self.current().map(|t| t.value.clone()) {
+                                if let Err(msg) = validate_data_uri(&s) {
+                                    self.warn_at_current(&msg, ErrorKind::InvalidValue, Some(&format!("Supported types: {}", IMAGE_MEDIA_TYPES.join(", "))));
+                                }
+                                shape.props.insert("href".into(), PropValue::Str(s));
+                            }
+                            self.advance();
+                        }
+                        "fit" if kind == "image" => {
+                            match self.ident_value() {
+                                Some(mode) if IMAGE_FIT_MODES.contains(&mode.as_str()) => {
+                                    shape.props.insert("fit".into(), PropValue::Str(mode));
+                                    self.advance();
+                                }
+                                _ => {
+                                    let found = self.ident_value();
+                                    let hint = found.as_deref().and_then(|m| did_you_mean(m, IMAGE_FIT_MODES.iter().copied()))
+                                        .unwrap_or_else(|| format!("Valid fit modes: {}", IMAGE_FIT_MODES.join(", ")));
+                                    self.error_at_current("Expected image fit mode", ErrorKind::InvalidValue, Some(&hint));
+                                    if found.is_some() { self.advance(); }
+                                }
+                            }
+                        }
+                        "baseline" if kind == "text" => {
+                            if let Some(b) = self.parse_baseline() { shape.style.baseline = Some(b); }
+                        }
+                        "letter-spacing" if kind == "text" => {
+                            if let Some(n) = self.parse_ranged_number("letter-spacing", f64::MIN, f64::MAX) { shape.style.letter_spacing = n; }
+                        }
+                        "word-spacing" if kind == "text" => {
+                            if let Some(n) = self.parse_ranged_number("word-spacing", f64::MIN, f64::MAX) { shape.style.word_spacing = n; }
+                        }
+                        "decoration" if kind == "text" => {
+                            if let Some(d) = self.parse_decoration() { shape.style.decoration = Some(d); }
+                        }
+                        "direction" if kind == "text" => {
+                            if let Some(d) = self.parse_text_direction() { shape.style.direction = Some(d); }
+                        }
+                        "fit" if kind == "text" && self.matches(&[TokenType::Pair]) => {
                             if let Some(t) = self.advance() {
-                                if let TokenValue::Str(s) = &t.value {
-                                    shape.props.insert("href".into(), PropValue::Str(s.clone()));
+                                if let TokenValue::Pair(w, h) = t.value {
+                                    shape.props.insert("fit".into(), PropValue::Pair(w, h));
+                                }
+                            }
+                        }
+                        "truncate" if kind == "text" => {
+                            match self.parse_ranged_number("truncate", 0.0, f64::MAX) {
+                                Some(w) => { shape.props.insert("truncate".into(), PropValue::Num(w)); }
+                                None => self.error_at_current("Expected a truncate width", ErrorKind::InvalidValue, Some("e.g. truncate 120")),
+                            }
+                        }
+                        "z" if self.matches(&[TokenType::Number]) => {
+                            if let Some(t) = self.advance() {
+                                if let TokenValue::Num(n) = t.value {
+                                    shape.props.insert("z".into(), PropValue::Num(n.trunc()));
                                 }
                             }
                         }
@@ -1615,15 +2203,73 @@ impl Parser {
             }
         }
 
+        if matches!(kind, "circle" | "ellipse") { self.check_radii(&mut shape, start); }
+
         self.skip_newlines();
         if self.matches(&[TokenType::Indent]) {
             self.advance();
             self.parse_block(&mut shape);
         }
+        if self.geometry_warnings { self.check_geometry(&shape, start); }
 
         AstNode::Shape(shape)
     }
 
+    /// Warn when a shape lacks the geometry that decides its visible extent. Sized
+    /// shapes fall back to a default, so those are named; a path or polygon without
+    /// data draws nothing.
+    fn check_geometry(&mut self, shape: &AstShape, (line, col): (usize, usize)) {
+        let has = |key: &str| shape.props.contains_key(key);
+        let missing = match shape.kind.as_str() {
+            "rect" if !has("size") => Some(("size", "using the default 100x100", "Add 'size WxH'")),
+            "circle" if !has("radius") => Some(("radius", "using the default 50", "Add 'radius N'")),
+            "ellipse" if !has("radius") && !has("size") => Some(("radius", "using the default 50,30", "Add 'radius rx,ry'")),
+            "line" if !has("to") => Some(("end point", "drawing to 100,100", "Add 'to X,Y'")),
+            "path" if !has("d") && !has("content") => Some(("path data", "nothing is drawn", "Add the outline: path \"M0 0 L10 10\"")),
+            "polygon" if !matches!(shape.props.get("points"), Some(PropValue::Points(p)) if p.len() >= 3) => {
+                Some(("points", "nothing is drawn", "Give at least three points: 'points [0,0 10,0 5,8]'"))
+            }
+            _ => None,
+        };
+        if let Some((what, fallback, suggestion)) = missing {
+            self.errors.push(ParseError::new(
+                format!("{} at {}:{} has no {}; {}", shape.kind, line + 1, col + 1, what, fallback),
+                ErrorKind::MissingGeometry, line, col,
+            ).with_severity(ErrorSeverity::Warning).with_suggestion(suggestion));
+        }
+    }
+
+    /// Fold `rx`/`ry` into `radius` and reconcile it with the shape kind: a circle
+    /// with two different radii is promoted to an ellipse, and an ellipse with a
+    /// single radius is drawn as a circle. Both warn.
+    fn check_radii(&mut self, shape: &mut AstShape, (line, col): (usize, usize)) {
+        let (rx, ry) = (shape.props.remove("rx"), shape.props.remove("ry"));
+        match (rx, ry) {
+            (Some(PropValue::Num(a)), Some(PropValue::Num(b))) => { shape.props.insert("radius".into(), PropValue::Pair(a, b)); }
+            (Some(PropValue::Num(r)), None) | (None, Some(PropValue::Num(r))) => { shape.props.insert("radius".into(), PropValue::Num(r)); }
+            _ => {}
+        }
+        let warning = match (shape.kind.as_str(), shape.props.get("radius")) {
+            ("circle", Some(&PropValue::Pair(a, b))) if a == b => {
+                shape.props.insert("radius".into(), PropValue::Num(a));
+                None
+            }
+            ("circle", Some(&PropValue::Pair(a, b))) => {
+                shape.kind = "ellipse".into();
+                Some((format!("Circle has two radii ({}, {}); drawing an ellipse", a, b), "Use 'ellipse' for two radii"))
+            }
+            ("ellipse", Some(&PropValue::Num(r))) => {
+                shape.props.insert("radius".into(), PropValue::Pair(r, r));
+                Some((format!("Ellipse has a single radius ({}); drawing a circle", r), "Give both radii: 'radius rx,ry' or 'rx N ry N'"))
+            }
+            _ => None,
+        };
+        if let Some((msg, suggestion)) = warning {
+            self.errors.push(ParseError::new(msg, ErrorKind::InvalidValue, line, col)
+                .with_severity(ErrorSeverity::Warning).with_suggestion(suggestion));
+        }
+    }
+
     pub(crate) fn parse_block(&mut self, shape: &mut AstShape) {
         while let Some(tok) = self.current() {
             if tok.ttype == TokenType::Dedent {
@@ -1653,7 +2299,7 @@ impl Parser {
                     };
 
                     if SHAPES.contains(prop.as_str()) {
-                        match self.parse_statement() {
+                        match self.parse_nested() {
                             Some(AstNode::Shape(child)) => shape.children.push(child),
                             _ => {} // Error already recorded, continue with next
                         }
@@ -1663,13 +2309,9 @@ impl Parser {
                         self.parse_text_prop(&mut shape.style);
                     } else if TRANSFORM_PROPS.contains(prop.as_str()) {
                         self.parse_transform_prop(&mut shape.transform);
-                    } else if prop == "width" && self.peek_next().map(|t| t.ttype == TokenType::Number).unwrap_or(false) {
+                    } else if prop == "width" && self.peek_next().is_some_and(|t| matches!(t.ttype, TokenType::Number | TokenType::Percent)) {
                         self.advance();
-                        if let Some(t) = self.advance() {
-                            if let TokenValue::Num(n) = t.value {
-                                shape.style.stroke_width = n;
-                            }
-                        }
+                        self.parse_stroke_width(&mut shape.style);
                     } else if prop == "d" && self.peek_next().map(|t| t.ttype == TokenType::String).unwrap_or(false) {
                         self.advance();
                         if let Some(t) = self.advance() {
@@ -1677,24 +2319,47 @@ impl Parser {
                                 shape.props.insert("d".into(), PropValue::Str(s.clone()));
                             }
                         }
+                    } else if prop == "z" && self.peek_next().map(|t| t.ttype == TokenType::Number).unwrap_or(false) {
+                        self.advance();
+                        if let Some(TokenValue::Num(n)) = self.advance().map(|t| t.value.clone()) {
+                            shape.props.insert("z".into(), PropValue::Num(n.trunc()));
+                        }
+                    } else if prop == "arrow" && matches!(shape.kind.as_str(), "line" | "path") {
+                        self.advance();
+                        if let Some(a) = self.parse_arrow() { shape.props.insert("arrow".into(), PropValue::Str(a)); }
                     } else if prop == "points" && self.peek_next().map(|t| t.ttype == TokenType::LBracket).unwrap_or(false) {
                         self.advance();
                         shape.props.insert("points".into(), PropValue::Points(self.parse_points()));
+                    } else if prop == "aspect" {
+                        self.advance();
+                        self.parse_child_aspect(shape);
+                    } else if SIZE_LIMIT_PROPS.contains(prop.as_str()) {
+                        self.advance();
+                        let dim = self.parse_dimension_value();
+                        shape.props.insert(prop, PropValue::Dim(dim));
+                    } else if prop == "meta" {
+                        self.advance();
+                        self.parse_meta(shape);
+                    } else if prop == "layer" {
+                        self.advance();
+                        self.parse_layer(shape);
                     } else {
                         // Unknown property in block - report and skip line
                         self.error_at_current(
                             &format!("Unknown property '{}' in {} block", prop, shape.kind),
                             ErrorKind::InvalidProperty,
-                            Self::suggest_property(&prop, &shape.kind).as_deref()
+                            Self::suggest_property(&prop).as_deref()
                         );
                         self.advance();
                         self.sync_to_line_end();
                     }
+                } else if tok.ttype == TokenType::Var && shape.kind == "group" {
+                    if let Some(AstNode::Variable { name, value: Some(value), span }) = self.parse_variable() {
+                        shape.vars.push((name, value, span));
+                    }
                 } else {
-                    // Unexpected token in block
-                    let ttype = tok.ttype;
                     self.error_at_current(
-                        &format!("Unexpected {:?} in block", ttype),
+                        &unexpected(tok, "in block"),
                         ErrorKind::UnexpectedToken,
                         Some("Expected property name or nested shape")
                     );
@@ -1704,22 +2369,52 @@ impl Parser {
         }
     }
 
-    /// Suggest similar property names
-    fn suggest_property(prop: &str, kind: &str) -> Option<String> {
-        let all_props: Vec<&str> = STYLE_PROPS.iter()
+    /// Suggest the closest valid block property name
+    fn suggest_property(prop: &str) -> Option<String> {
+        let block_props = ["width", "d", "points", "z", "meta", "layer", "aspect"];
+        did_you_mean(prop, STYLE_PROPS.iter()
             .chain(TEXT_PROPS.iter())
+            .chain(SIZE_LIMIT_PROPS.iter())
             .chain(TRANSFORM_PROPS.iter())
-            .copied()
-            .collect();
-        
-        let prop_lower = prop.to_lowercase();
-        for valid in &all_props {
-            if valid.starts_with(&prop_lower) || prop_lower.starts_with(*valid) {
-                return Some(format!("Did you mean '{}'?", valid));
+            .chain(block_props.iter())
+            .copied())
+    }
+
+    /// `meta <key> "<value>"`, after the `meta` keyword
+    fn parse_meta(&mut self, shape: &mut AstShape) {
+        let key = match self.current().map(|t| (t.ttype, &t.value)) {
+            Some((TokenType::Ident, TokenValue::Str(k))) => k.clone(),
+            _ => {
+                self.error_at_current("Expected a key after 'meta'", ErrorKind::InvalidValue, Some("e.g. meta layer \"bg\""));
+                self.sync_to_line_end();
+                return;
+            }
+        };
+        self.advance();
+        match self.current().map(|t| (t.ttype, &t.value)) {
+            Some((TokenType::String, TokenValue::Str(v))) => {
+                shape.meta.insert(key, v.clone());
+                self.advance();
+            }
+            _ => {
+                self.error_at_current(&format!("Expected a quoted value for meta '{}'", key), ErrorKind::InvalidValue, None);
+                self.sync_to_line_end();
+            }
+        }
+    }
+
+    /// `layer "<name>"`, after the `layer` keyword
+    fn parse_layer(&mut self, shape: &mut AstShape) {
+        match self.current().map(|t| (t.ttype, &t.value)) {
+            Some((TokenType::String, TokenValue::Str(name))) => {
+                shape.props.insert("layer".into(), PropValue::Str(name.clone()));
+                self.advance();
+            }
+            _ => {
+                self.error_at_current("Expected a quoted layer name", ErrorKind::InvalidValue, Some("e.g. layer \"background\""));
+                self.sync_to_line_end();
             }
         }
-        
-        Some(format!("Valid {} properties: fill, stroke, opacity, transform, etc.", kind))
     }
 
     fn parse_style_prop(&mut self, shape: &mut AstShape) {
@@ -1751,44 +2446,49 @@ impl Parser {
                         self.advance();
                     }
                 }
-                if self.matches(&[TokenType::Number]) {
-                    if let Some(t) = self.advance() {
-                        if let TokenValue::Num(n) = t.value {
-                            shape.style.stroke_width = n;
-                        }
-                    }
-                }
+                self.parse_stroke_width(&mut shape.style);
                 if self.matches(&[TokenType::Ident]) {
                     if let Some(tok) = self.current() {
                         if matches!(&tok.value, TokenValue::Str(s) if s == "width") {
                             self.advance();
-                            if self.matches(&[TokenType::Number]) {
-                                if let Some(t) = self.advance() {
-                                    if let TokenValue::Num(n) = t.value {
-                                        shape.style.stroke_width = n;
-                                    }
-                                }
-                            }
+                            self.parse_stroke_width(&mut shape.style);
                         }
                     }
                 }
             }
+            "fill-rule" => {
+                if let Some(rule) = self.parse_fill_rule() { shape.style.fill_rule = Some(rule); }
+            }
             "opacity" => {
-                if self.matches(&[TokenType::Number]) {
-                    if let Some(t) = self.advance() {
-                        if let TokenValue::Num(n) = t.value {
-                            shape.style.opacity = n;
-                        }
-                    }
+                if let Some(n) = self.parse_ranged_number("opacity", 0.0, 1.0) {
+                    shape.style.opacity = n;
                 }
             }
             "corner" => {
-                if self.matches(&[TokenType::Number]) {
-                    if let Some(t) = self.advance() {
-                        if let TokenValue::Num(n) = t.value {
-                            shape.style.corner = n;
-                        }
-                    }
+                if let Some(n) = self.parse_ranged_number("corner", 0.0, f64::MAX) {
+                    shape.style.corner = n;
+                }
+            }
+            "dash" => {
+                shape.style.dash.clear();
+                if self.matches(&[TokenType::LBracket]) {
+                    let lengths = match PropValue::from_list(&self.parse_list_items()) {
+                        PropValue::Points(pts) => pts.into_iter().flat_map(|(a, b)| [a, b]).collect(),
+                        PropValue::List(items) => items.into_iter().filter_map(|v| match v { PropValue::Num(n) => Some(n), _ => None }).collect(),
+                        _ => Vec::new(),
+                    };
+                    shape.style.dash = lengths.into_iter().map(|n: f64| n.max(0.0)).collect();
+                }
+                while let Some(n) = self.parse_ranged_number("dash", 0.0, f64::MAX) {
+                    shape.style.dash.push(n);
+                }
+                if shape.style.dash.is_empty() {
+                    self.error_at_current("Expected dash lengths after 'dash'", ErrorKind::InvalidValue, Some("Give dash and gap lengths, e.g. dash 5 3"));
+                }
+            }
+            "dash-offset" => {
+                if let Some(n) = self.parse_ranged_number("dash-offset", f64::MIN, f64::MAX) {
+                    shape.style.dash_offset = n;
                 }
             }
             "shadow" => {
@@ -1829,10 +2529,13 @@ impl Parser {
                         }
                     }
                 }
-                if self.matches(&[TokenType::Number]) {
+                if let Some(p) = self.parse_percent() {
+                    style.font_size_pct = Some(p);
+                } else if self.matches(&[TokenType::Number]) {
                     if let Some(t) = self.advance() {
                         if let TokenValue::Num(n) = t.value {
                             style.font_size = n;
+                            style.font_size_pct = None;
                         }
                     }
                 }
@@ -1841,10 +2544,44 @@ impl Parser {
             "italic" => style.font_weight = "italic".into(),
             "center" => style.text_anchor = "middle".into(),
             "end" => style.text_anchor = "end".into(),
+            "baseline" => if let Some(b) = self.parse_baseline() { style.baseline = Some(b); },
+            "letter-spacing" => if let Some(n) = self.parse_ranged_number("letter-spacing", f64::MIN, f64::MAX) { style.letter_spacing = n; },
+            "word-spacing" => if let Some(n) = self.parse_ranged_number("word-spacing", f64::MIN, f64::MAX) { style.word_spacing = n; },
+            "decoration" => if let Some(d) = self.parse_decoration() { style.decoration = Some(d); },
+            "direction" => if let Some(d) = self.parse_text_direction() { style.direction = Some(d); },
             _ => {}
         }
     }
 
+    /// Parse a `baseline` value, reporting unknown names with a suggestion
+    fn parse_baseline(&mut self) -> Option<String> { self.parse_keyword("text baseline", TEXT_BASELINES) }
+
+    /// Parse a `decoration` value, reporting unknown names with a suggestion
+    fn parse_decoration(&mut self) -> Option<String> { self.parse_keyword("text decoration", TEXT_DECORATIONS) }
+
+    /// Parse a `fill-rule` value, reporting unknown names with a suggestion
+    fn parse_fill_rule(&mut self) -> Option<String> { self.parse_keyword("fill rule", FILL_RULES) }
+
+    /// Parse a text `direction` value, reporting unknown names with a suggestion
+    fn parse_text_direction(&mut self) -> Option<String> { self.parse_keyword("text direction", TEXT_DIRECTIONS) }
+
+    /// Arrowhead mode for a standalone line or path
+    fn parse_arrow(&mut self) -> Option<String> { self.parse_keyword("arrow direction", ARROW_TYPES) }
+
+    /// Consume one of `valid`; otherwise report "Expected <what>" with a suggestion or the valid list
+    fn parse_keyword(&mut self, what: &str, valid: &[&str]) -> Option<String> {
+        match self.ident_value() {
+            Some(k) if valid.contains(&k.as_str()) => { self.advance(); Some(k) }
+            found => {
+                let hint = found.as_deref().and_then(|k| did_you_mean(k, valid.iter().copied()))
+                    .unwrap_or_else(|| format!("Valid {}s: {}", what, valid.join(", ")));
+                self.error_at_current(&format!("Expected {}", what), ErrorKind::InvalidValue, Some(&hint));
+                if found.is_some() { self.advance(); }
+                None
+            }
+        }
+    }
+
     fn parse_transform_prop(&mut self, transform: &mut AstTransform) {
         let prop = match self.advance().and_then(|t| match &t.value {
             TokenValue::Str(s) => Some(s.clone()),
@@ -1881,10 +2618,13 @@ impl Parser {
                         }
                     }
                 } else if self.matches(&[TokenType::Number]) {
-                    if let Some(t) = self.advance() {
-                        if let TokenValue::Num(n) = t.value {
-                            transform.scale = Some((n, n));
-                        }
+                    if let Some(TokenValue::Num(sx)) = self.advance().map(|t| t.value.clone()) {
+                        // `scale -1 1` sets each axis; a lone number is uniform
+                        let sy = match self.current().map(|t| &t.value) {
+                            Some(TokenValue::Num(sy)) => { let sy = *sy; self.advance(); sy }
+                            _ => sx,
+                        };
+                        transform.scale = Some((sx, sy));
                     }
                 }
             }
@@ -1895,6 +2635,11 @@ impl Parser {
                             transform.origin = Some((a, b));
                         }
                     }
+                } else if let Some(px) = self.parse_signed_percent() {
+                    match self.parse_signed_percent() {
+                        Some(py) => { transform.origin = None; transform.origin_pct = Some((px, py)); }
+                        None => self.error_at_current("Expected a second origin percent", ErrorKind::InvalidValue, Some("e.g. origin 50% 50%")),
+                    }
                 }
             }
             _ => {}
@@ -1937,7 +2682,8 @@ impl Parser {
             gtype: "linear".into(),
             from: "#fff".into(),
             to: "#000".into(),
-            angle: 90.0,
+            angle: 0.0,
+            shared: false,
         };
 
         while self.matches(&[TokenType::Ident, TokenType::Color, TokenType::Number]) {
@@ -1948,10 +2694,13 @@ impl Parser {
                             TokenValue::Str(s) => s.clone(),
                             _ => { self.advance(); continue; }
                         };
+                        // Leave unrelated words (the next property) to the caller
+                        if !matches!(val.as_str(), "linear" | "radial" | "from" | "to" | "shared") { break; }
                         self.advance();
 
                         match val.as_str() {
                             "linear" | "radial" => gradient.gtype = val,
+                            "shared" => gradient.shared = true,
                             "from" if self.matches(&[TokenType::Color]) => {
                                 if let Some(t) = self.advance() {
                                     if let TokenValue::Str(s) = &t.value {
@@ -2150,6 +2899,7 @@ impl Parser {
                     }
                 }
             }
+            "dash-offset" => return self.parse_ranged_number("dash-offset", f64::MIN, f64::MAX).map(AnimatableProperty::StrokeDashOffset),
             "rotate" => {
                 if self.matches(&[TokenType::Number]) {
                     if let Some(t) = self.advance() {
@@ -2367,6 +3117,36 @@ impl Parser {
         trans
     }
 
+    /// Items of a bracketed scalar list (`[4 2 1]`, `[4, 2, 1]`, `["a" #f00]`). Commas
+    /// are separators, so `4,2` arrives as a pair; `PropValue::from_list` splits it.
+    pub(crate) fn parse_list_items(&mut self) -> Vec<TokenValue> {
+        let mut items = Vec::new();
+        self.advance(); // consume [
+        while let Some(tok) = self.current().cloned() {
+            match tok.ttype {
+                TokenType::RBracket => { self.advance(); break; }
+                TokenType::Newline => { self.advance(); }
+                TokenType::Eof => {
+                    self.error_at_current("Unclosed list", ErrorKind::UnterminatedBlock, Some("Add ']' to close the list"));
+                    break;
+                }
+                TokenType::Number | TokenType::Pair | TokenType::String | TokenType::Color | TokenType::Ident => {
+                    items.push(tok.value);
+                    self.advance();
+                }
+                _ => {
+                    self.error_at_current(
+                        &format!("Expected a number or string in list, found {:?}", tok.ttype),
+                        ErrorKind::InvalidValue,
+                        Some("Lists hold numbers or strings, e.g. [4 2 1]")
+                    );
+                    self.advance();
+                }
+            }
+        }
+        items
+    }
+
     pub(crate) fn parse_points(&mut self) -> Vec<(f64, f64)> {
         let mut points = Vec::new();
         
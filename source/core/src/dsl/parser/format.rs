@@ -0,0 +1,58 @@
+//! Versioned JSON format for persisted ASTs
+//!
+//! Serialized documents are wrapped as `{"version": N, "ast": ...}` so tools can
+//! detect blobs written by a newer iconoglott. Unversioned blobs (a bare AST or a
+//! `parse_with_errors` result) predate the envelope and load as version 0.
+
+use super::ast::AstNode;
+use super::super::error::{ErrorKind, ParseError};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// Current serialization format version for AST and scene JSON
+pub const FORMAT_VERSION: u32 = 1;
+
+/// Versioned envelope around a serialized AST
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct AstDocument {
+    pub version: u32,
+    pub ast: AstNode,
+}
+
+impl AstDocument {
+    pub fn new(ast: AstNode) -> Self { Self { version: FORMAT_VERSION, ast } }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).unwrap_or_else(|_| "null".to_string())
+    }
+
+    /// Load a serialized AST, migrating unversioned blobs and rejecting newer formats
+    pub fn from_json(json: &str) -> Result<Self, Box<ParseError>> {
+        let value: Value = serde_json::from_str(json).map_err(|e| invalid_json(&e))?;
+        let version = match value.get("version") {
+            None => 0,
+            Some(v) => v.as_u64().and_then(|v| u32::try_from(v).ok()).ok_or_else(|| Box::new(
+                ParseError::new(format!("Invalid format version {}", v), ErrorKind::UnsupportedVersion, 0, 0)
+                    .with_suggestion("Version must be a non-negative integer")
+            ))?,
+        };
+        if version > FORMAT_VERSION {
+            return Err(Box::new(ParseError::new(
+                format!("AST format version {} is newer than the supported version {}", version, FORMAT_VERSION),
+                ErrorKind::UnsupportedVersion, 0, 0
+            ).with_suggestion("Upgrade iconoglott to load this document")));
+        }
+        // v0 blobs may be a bare AST rather than an envelope
+        let ast = match value {
+            Value::Object(mut obj) if version > 0 || obj.contains_key("ast") => obj.remove("ast").unwrap_or(Value::Null),
+            value => value,
+        };
+        let ast = serde_json::from_value(ast).map_err(|e| invalid_json(&e))?;
+        Ok(Self { version: FORMAT_VERSION, ast })
+    }
+}
+
+fn invalid_json(e: &serde_json::Error) -> Box<ParseError> {
+    let (line, col) = (e.line().saturating_sub(1), e.column().saturating_sub(1));
+    Box::new(ParseError::new(format!("Invalid AST JSON: {}", e), ErrorKind::InvalidValue, line, col))
+}
@@ -2,10 +2,17 @@
 //!
 //! Multi-pass solver with topological ordering and convergence detection.
 //! Resolves percentage-based dimensions, auto-sizing, and constraint-based positioning.
+//!
+//! Containers are solved incrementally: a solver remembers the rect of every
+//! `layout` and `group` subtree it solved, keyed by a fingerprint of the subtree's
+//! layout inputs and its parent bounds. A subtree is dirty when that key is new,
+//! and only dirty subtrees are re-solved on the next pass.
 
 #![allow(dead_code)] // Public API - methods used externally
 
 use super::ast::*;
+use crate::hash::Fnv1a;
+use std::cell::{Cell, RefCell};
 use std::collections::{HashMap, HashSet, VecDeque};
 
 /// Resolved layout rectangle with absolute coordinates
@@ -37,21 +44,42 @@ pub struct LayoutContext {
     pub parent: LayoutRect,
     pub computed: HashMap<String, LayoutRect>,
     pub default_size: (f64, f64),
+    /// Coordinate system that viewBox-relative sizes (`stroke 2%`) resolve against
+    pub viewbox: LayoutRect,
 }
 
 impl Default for LayoutContext {
     fn default() -> Self {
-        Self { parent: LayoutRect::new(0.0, 0.0, 100.0, 100.0), computed: HashMap::new(), default_size: (32.0, 32.0) }
+        let bounds = LayoutRect::new(0.0, 0.0, 100.0, 100.0);
+        Self { parent: bounds.clone(), computed: HashMap::new(), default_size: (32.0, 32.0), viewbox: bounds }
     }
 }
 
 impl LayoutContext {
     pub fn new(width: f64, height: f64) -> Self {
-        Self { parent: LayoutRect::new(0.0, 0.0, width, height), ..Default::default() }
+        let bounds = LayoutRect::new(0.0, 0.0, width, height);
+        Self { parent: bounds.clone(), viewbox: bounds, ..Default::default() }
     }
     
     pub fn child(&self, bounds: LayoutRect) -> Self {
-        Self { parent: bounds, computed: self.computed.clone(), default_size: self.default_size }
+        Self { parent: bounds, computed: self.computed.clone(), default_size: self.default_size, viewbox: self.viewbox.clone() }
+    }
+
+    pub fn with_viewbox(mut self, viewbox: LayoutRect) -> Self {
+        self.viewbox = viewbox;
+        self
+    }
+
+    /// Replace percent stroke widths and font sizes with lengths, taking the
+    /// percent of the viewBox's shorter side
+    pub fn resolve_style(&self, style: &mut AstStyle) {
+        let side = self.viewbox.width.min(self.viewbox.height);
+        if let Some(p) = style.stroke_width_pct.take() {
+            style.stroke_width = Dimension::Percent(p).resolve(side).unwrap_or(style.stroke_width);
+        }
+        if let Some(p) = style.font_size_pct.take() {
+            style.font_size = Dimension::Percent(p).resolve(side).unwrap_or(style.font_size);
+        }
     }
 }
 
@@ -59,6 +87,9 @@ impl LayoutContext {
 // Multi-Pass Constraint Solver
 // ─────────────────────────────────────────────────────────────────────────────
 
+const WIDTH_LIMITS: (&str, &str) = ("min-width", "max-width");
+const HEIGHT_LIMITS: (&str, &str) = ("min-height", "max-height");
+
 /// Constraint dependency node
 #[derive(Clone)]
 struct DepNode<'a> {
@@ -67,11 +98,61 @@ struct DepNode<'a> {
     deps: HashSet<String>,
 }
 
-/// Multi-pass layout solver with topological ordering and convergence detection
+/// Multi-pass layout solver with topological ordering and convergence detection.
+///
+/// Keep one solver across edits to reuse the rects of unchanged containers.
 #[derive(Default)]
 pub struct LayoutSolver {
     max_iterations: usize,
     convergence_eps: f64,
+    cache: RefCell<LayoutCache>,
+    solves: Cell<usize>,
+}
+
+/// Container rects by `subtree_key`. Entries not looked up during a solve are
+/// dropped at its end, so the cache only holds the latest tree.
+#[derive(Default)]
+struct LayoutCache {
+    current: HashMap<u64, LayoutRect>,
+    previous: HashMap<u64, LayoutRect>,
+}
+
+impl LayoutCache {
+    fn get(&mut self, key: u64) -> Option<LayoutRect> {
+        if let Some(rect) = self.current.get(&key) { return Some(rect.clone()); }
+        let rect = self.previous.remove(&key)?;
+        self.current.insert(key, rect.clone());
+        Some(rect)
+    }
+
+    fn sweep(&mut self) { self.previous = std::mem::take(&mut self.current); }
+}
+
+/// Fingerprint of everything `LayoutSolver::resolve` reads: the subtree's kinds
+/// and props, the parent bounds, and the default size. Props are hashed in key
+/// order so equal subtrees match regardless of map iteration order.
+fn subtree_key(shape: &AstShape, ctx: &LayoutContext) -> u64 {
+    fn shape_into(h: &mut Fnv1a, shape: &AstShape) {
+        h.write_u64(shape.kind.len() as u64);
+        h.write_str(&shape.kind);
+        let mut props: Vec<_> = shape.props.iter().collect();
+        props.sort_by(|a, b| a.0.cmp(b.0));
+        h.write_u64(props.len() as u64);
+        for (k, v) in props {
+            let v = format!("{:?}", v);
+            h.write_u64(k.len() as u64);
+            h.write_str(k);
+            h.write_u64(v.len() as u64);
+            h.write_str(&v);
+        }
+        h.write_u64(shape.children.len() as u64);
+        shape.children.iter().for_each(|c| shape_into(h, c));
+    }
+    let mut h = Fnv1a::default();
+    let p = &ctx.parent;
+    for v in [p.x, p.y, p.width, p.height, ctx.default_size.0, ctx.default_size.1] { h.write_u64(v.to_bits()); }
+    shape_into(&mut h, shape);
+    h.finish()
 }
 
 impl LayoutSolver {
@@ -172,20 +253,46 @@ impl LayoutSolver {
             prev_results.clone_from(&results);
         }
         
+        self.cache.borrow_mut().sweep();
         results
     }
+
+    /// Number of container subtrees solved so far, excluding cache hits
+    pub fn solve_count(&self) -> usize { self.solves.get() }
+
+    /// Resolve layout for every top-level shape of a scene, keyed `shape_<i>`
+    pub fn resolve_scene(&self, ast: &AstNode, canvas_width: f64, canvas_height: f64) -> HashMap<String, LayoutRect> {
+        let mut ctx = LayoutContext::new(canvas_width, canvas_height);
+        if let AstNode::Scene(children) = ast {
+            let shapes: Vec<_> = children.iter()
+                .filter_map(|n| if let AstNode::Shape(s) = n { Some(s) } else { None })
+                .collect();
+            // Use multi-pass solver with topological ordering
+            let rects = self.solve_multi_pass(&shapes, &mut ctx);
+            for (i, rect) in rects.into_iter().enumerate() {
+                ctx.computed.insert(format!("shape_{}", i), rect);
+            }
+        }
+        ctx.computed
+    }
 }
 
 impl LayoutSolver {
-    pub fn new() -> Self { Self { max_iterations: 8, convergence_eps: 0.01 } }
+    pub fn new() -> Self { Self { max_iterations: 8, convergence_eps: 0.01, ..Default::default() } }
     
-    /// Resolve layout for a shape and its children
+    /// Resolve layout for a shape and its children, reusing the cached rect of a
+    /// container whose subtree and parent bounds haven't changed
     pub fn resolve(&self, shape: &AstShape, ctx: &mut LayoutContext) -> LayoutRect {
-        match shape.kind.as_str() {
+        if !matches!(shape.kind.as_str(), "layout" | "group") { return self.resolve_shape(shape, ctx); }
+        let key = subtree_key(shape, ctx);
+        if let Some(rect) = self.cache.borrow_mut().get(key) { return rect; }
+        self.solves.set(self.solves.get() + 1);
+        let rect = match shape.kind.as_str() {
             "layout" => self.resolve_layout_container(shape, ctx),
-            "group" => self.resolve_group(shape, ctx),
-            _ => self.resolve_shape(shape, ctx),
-        }
+            _ => self.resolve_group(shape, ctx),
+        };
+        self.cache.borrow_mut().current.insert(key, rect.clone());
+        rect
     }
     
     /// Resolve a layout container (stack/row)
@@ -213,12 +320,15 @@ impl LayoutSolver {
         // If auto-sized, update bounds based on children
         if self.is_auto_sized(shape) {
             let (content_w, content_h) = self.compute_content_size(&child_rects, is_horizontal, gap);
-            if self.get_width_dim(shape).is_auto() {
+            let (auto_w, auto_h) = self.auto_axes(shape);
+            if auto_w {
                 bounds.width = content_w + pl.resolve(bounds.width).unwrap_or(0.0) + pr.resolve(bounds.width).unwrap_or(0.0);
             }
-            if self.get_height_dim(shape).is_auto() {
+            if auto_h {
                 bounds.height = content_h + pt.resolve(bounds.height).unwrap_or(0.0) + pb.resolve(bounds.height).unwrap_or(0.0);
             }
+            bounds.width = self.clamp_size(shape, WIDTH_LIMITS, bounds.width, ctx.parent.width);
+            bounds.height = self.clamp_size(shape, HEIGHT_LIMITS, bounds.height, ctx.parent.height);
         }
         
         bounds
@@ -278,7 +388,7 @@ impl LayoutSolver {
         };
         
         // Second pass: position children
-        for (i, rect) in child_rects.iter_mut().enumerate() {
+        for (i, (rect, child)) in child_rects.iter_mut().zip(children).enumerate() {
             let (main, cross) = if is_horizontal { (rect.width, rect.height) } else { (rect.height, rect.width) };
             
             // Cross-axis alignment
@@ -293,11 +403,11 @@ impl LayoutSolver {
             if is_horizontal {
                 rect.x = container.x + pos;
                 rect.y = container.y + cross_pos;
-                if align == AlignItems::Stretch { rect.height = cross_size; }
+                if align == AlignItems::Stretch { rect.height = self.clamp_size(child, HEIGHT_LIMITS, cross_size, container.height); }
             } else {
                 rect.x = container.x + cross_pos;
                 rect.y = container.y + pos;
-                if align == AlignItems::Stretch { rect.width = cross_size; }
+                if align == AlignItems::Stretch { rect.width = self.clamp_size(child, WIDTH_LIMITS, cross_size, container.width); }
             }
             
             pos += main + gap + (if i < children.len() - 1 { extra_gap } else { 0.0 });
@@ -385,12 +495,30 @@ impl LayoutSolver {
         }
     }
     
-    fn resolve_width(&self, shape: &AstShape, ctx: &LayoutContext) -> f64 {
-        self.get_width_dim(shape).resolve(ctx.parent.width).unwrap_or(ctx.default_size.0)
-    }
+    fn resolve_width(&self, shape: &AstShape, ctx: &LayoutContext) -> f64 { self.resolve_size(shape, ctx).0 }
     
-    fn resolve_height(&self, shape: &AstShape, ctx: &LayoutContext) -> f64 {
-        self.get_height_dim(shape).resolve(ctx.parent.height).unwrap_or(ctx.default_size.1)
+    fn resolve_height(&self, shape: &AstShape, ctx: &LayoutContext) -> f64 { self.resolve_size(shape, ctx).1 }
+
+    /// Clamped width and height. With `aspect`, a dimension left `auto` follows
+    /// the other one once that is clamped, and is then clamped itself.
+    fn resolve_size(&self, shape: &AstShape, ctx: &LayoutContext) -> (f64, f64) {
+        let width = self.get_width_dim(shape).resolve(ctx.parent.width);
+        let height = self.get_height_dim(shape).resolve(ctx.parent.height);
+        let clamp_w = |w| self.clamp_size(shape, WIDTH_LIMITS, w, ctx.parent.width);
+        let clamp_h = |h| self.clamp_size(shape, HEIGHT_LIMITS, h, ctx.parent.height);
+        match (self.aspect_ratio(shape), width, height) {
+            (Some(ratio), Some(w), None) => { let w = clamp_w(w); (w, clamp_h(w / ratio)) }
+            (Some(ratio), None, Some(h)) => { let h = clamp_h(h); (clamp_w(h * ratio), h) }
+            _ => (clamp_w(width.unwrap_or(ctx.default_size.0)), clamp_h(height.unwrap_or(ctx.default_size.1))),
+        }
+    }
+
+    /// `size` held within the shape's `(min, max)` limit props, resolved against
+    /// `parent`. The minimum wins when the two conflict, as in CSS.
+    fn clamp_size(&self, shape: &AstShape, (min, max): (&str, &str), size: f64, parent: f64) -> f64 {
+        let limit = |key: &str| match shape.props.get(key) { Some(PropValue::Dim(d)) => d.resolve(parent), _ => None };
+        let size = limit(max).map_or(size, |max| size.min(max));
+        limit(min).map_or(size, |min| size.max(min))
     }
     
     fn get_width_dim(&self, shape: &AstShape) -> Dimension {
@@ -419,8 +547,20 @@ impl LayoutSolver {
         Dimension::Auto
     }
     
+    /// Axes sized by content: those left `auto`, unless `aspect` derives it from the other
+    fn auto_axes(&self, shape: &AstShape) -> (bool, bool) {
+        let (w, h) = (self.get_width_dim(shape).is_auto(), self.get_height_dim(shape).is_auto());
+        if w != h && self.aspect_ratio(shape).is_some() { (false, false) } else { (w, h) }
+    }
+
+    /// Width over height from `aspect W:H`
+    fn aspect_ratio(&self, shape: &AstShape) -> Option<f64> {
+        match shape.props.get("aspect") { Some(PropValue::Pair(w, h)) if *w > 0.0 && *h > 0.0 => Some(w / h), _ => None }
+    }
+
     fn is_auto_sized(&self, shape: &AstShape) -> bool {
-        self.get_width_dim(shape).is_auto() || self.get_height_dim(shape).is_auto()
+        let (w, h) = self.auto_axes(shape);
+        w || h
     }
     
     /// Extract LayoutProps from shape
@@ -492,25 +632,33 @@ impl LayoutSolver {
 
 /// Convenience function to resolve layout for an AST using multi-pass solver
 pub fn resolve_layout(ast: &AstNode, canvas_width: f64, canvas_height: f64) -> HashMap<String, LayoutRect> {
-    let mut ctx = LayoutContext::new(canvas_width, canvas_height);
-    let solver = LayoutSolver::new();
-    
-    if let AstNode::Scene(children) = ast {
-        // Collect shapes for multi-pass solving
-        let shapes: Vec<_> = children.iter()
-            .filter_map(|n| if let AstNode::Shape(s) = n { Some(s) } else { None })
-            .collect();
-        
-        // Use multi-pass solver with topological ordering
-        let rects = solver.solve_multi_pass(&shapes, &mut ctx);
-        
-        // Store results
-        for (i, rect) in rects.into_iter().enumerate() {
-            ctx.computed.insert(format!("shape_{}", i), rect);
+    LayoutSolver::new().resolve_scene(ast, canvas_width, canvas_height)
+}
+
+/// Resolve viewBox-relative sizes throughout an AST. Top-level shapes use the
+/// canvas as their viewBox and symbol children use the symbol's own, falling
+/// back to `ctx` when there is neither.
+pub fn resolve_viewbox_sizes(node: &mut AstNode, ctx: &LayoutContext) {
+    fn shape(s: &mut AstShape, ctx: &LayoutContext) {
+        ctx.resolve_style(&mut s.style);
+        s.children.iter_mut().for_each(|c| shape(c, ctx));
+    }
+    match node {
+        AstNode::Scene(children) => {
+            let canvas = children.iter().find_map(|c| if let AstNode::Canvas(c) = c { Some(c.dimensions()) } else { None });
+            let ctx = canvas.map_or_else(|| ctx.clone(), |(w, h)| LayoutContext::new(w as f64, h as f64));
+            children.iter_mut().for_each(|c| resolve_viewbox_sizes(c, &ctx));
+        }
+        AstNode::Shape(s) => shape(s, ctx),
+        AstNode::Symbol(sym) => {
+            let ctx = match sym.viewbox {
+                Some((x, y, w, h)) => ctx.clone().with_viewbox(LayoutRect::new(x, y, w, h)),
+                None => ctx.clone(),
+            };
+            sym.children.iter_mut().for_each(|c| shape(c, &ctx));
         }
+        _ => {}
     }
-    
-    ctx.computed
 }
 
 #[cfg(test)]
@@ -629,6 +777,63 @@ mod tests {
         assert!((rects[0].width - 50.0).abs() < 0.001);
     }
     
+    #[test]
+    fn test_size_limits_clamp_flex_sizing() {
+        let limited = |kind: &str, width: Dimension, key: &str, limit: f64| {
+            let mut shape = AstShape::new(kind);
+            shape.props.insert("width".into(), PropValue::Dim(width));
+            shape.props.insert("height".into(), PropValue::Dim(Dimension::Px(20.0)));
+            shape.props.insert(key.into(), PropValue::Dim(Dimension::Px(limit)));
+            shape
+        };
+        let children = vec![
+            // Grows with the container, up to its max
+            limited("rect", Dimension::Percent(80.0), "max-width", 100.0),
+            // Shrinks with it, down to its min
+            limited("rect", Dimension::Percent(10.0), "min-width", 30.0),
+            limited("rect", Dimension::Px(40.0), "max-height", 60.0),
+        ];
+        let solver = LayoutSolver::new();
+        let container = LayoutRect::new(0.0, 0.0, 200.0, 100.0);
+        let mut ctx = LayoutContext::new(200.0, 100.0);
+        let rects = solver.layout_children(&children, &container, true, 0.0, JustifyContent::Start, AlignItems::Stretch, &mut ctx);
+        let widths: Vec<_> = rects.iter().map(|r| r.width).collect();
+        assert_eq!(widths, vec![100.0, 30.0, 40.0]);
+        assert_eq!(rects[1].x, 100.0);
+        // Stretching to the 100px cross axis stops at max-height
+        assert_eq!(rects.iter().map(|r| r.height).collect::<Vec<_>>(), vec![100.0, 100.0, 60.0]);
+    }
+
+    #[test]
+    fn test_aspect_derives_auto_dimension() {
+        let mut shape = AstShape::new("rect");
+        shape.props.insert("width".into(), PropValue::Dim(Dimension::Px(160.0)));
+        shape.props.insert("aspect".into(), PropValue::Pair(16.0, 9.0));
+        let rect = LayoutSolver::new().resolve_shape(&shape, &LayoutContext::new(200.0, 100.0));
+        assert!((rect.width - 160.0).abs() < 0.001 && (rect.height - 90.0).abs() < 0.001, "{:?}", rect);
+    }
+
+    #[test]
+    fn test_incremental_resolve_only_dirty_container() {
+        let stack = |w: f64| {
+            let mut layout = make_layout_shape("horizontal", JustifyContent::Start, AlignItems::Start);
+            layout.children = vec![make_child(w, 20.0), make_child(40.0, 20.0)];
+            AstNode::Shape(layout)
+        };
+        let solver = LayoutSolver::new();
+        let first = solver.resolve_scene(&AstNode::Scene(vec![stack(40.0), stack(10.0)]), 200.0, 100.0);
+        assert_eq!(solver.solve_count(), 2);
+        // Solving the same tree again is all cache hits
+        solver.resolve_scene(&AstNode::Scene(vec![stack(40.0), stack(10.0)]), 200.0, 100.0);
+        assert_eq!(solver.solve_count(), 2);
+
+        let second = solver.resolve_scene(&AstNode::Scene(vec![stack(50.0), stack(10.0)]), 200.0, 100.0);
+        assert_eq!(solver.solve_count(), 3);
+        assert!((second["shape_0"].width - 90.0).abs() < 0.001, "{:?}", second["shape_0"]);
+        assert!((first["shape_0"].width - 80.0).abs() < 0.001);
+        assert!(second["shape_1"].approx_eq(&first["shape_1"], 0.001));
+    }
+
     #[test]
     fn test_topo_sort_no_deps() {
         let solver = LayoutSolver::new();
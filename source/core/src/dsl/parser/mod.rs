@@ -8,11 +8,14 @@
 mod anim;
 mod ast;
 mod core;
+mod format;
 mod layout;
 mod symbols;
 
 #[cfg(feature = "python")]
 mod python;
+#[cfg(feature = "python")]
+pub use python::py_validate;
 
 #[cfg(feature = "wasm")]
 mod wasm;
@@ -26,7 +29,7 @@ mod proptest_tests;
 // Re-export AST types
 pub use ast::{
     AstCanvas, AstGraph, AstNode, AstShape, AstStyle, AstTransform, AstSymbol, AstUse,
-    FullStyle, GradientDef, GraphEdge, GraphNode, ParseError, PropValue, ShadowDef,
+    FullStyle, GradientDef, GraphEdge, GraphNode, PropValue, ShadowDef,
 };
 
 // Re-export dimension and layout types (allow unused - used externally)
@@ -36,19 +39,21 @@ pub use ast::{
     Constraint, Edge, Axis, LayoutProps,
 };
 
-// Re-export error types for diagnostics
-pub use ast::{ErrorKind, ErrorSeverity, Span};
-
 // Re-export parser
-pub use self::core::Parser;
+pub use self::core::{Parser, MAX_NESTING_DEPTH};
+#[cfg(any(feature = "python", feature = "bench", feature = "wasm"))]
+pub(crate) use self::core::ARROW_HEADS;
+
+// Re-export versioned JSON format
+pub use format::{AstDocument, FORMAT_VERSION};
 
 // Re-export symbol table and resolution
 #[allow(unused_imports)] // Public API for external use
-pub use symbols::{resolve, Scope, Symbol, SymbolTable, ResolveResult};
+pub use symbols::{fit_text, post_resolve, resolve, sort_by_z, validate, Scope, Symbol, SymbolTable, ResolveResult};
 
 // Re-export layout solver (allow unused - used externally)
 #[allow(unused_imports)]
-pub use layout::{LayoutSolver, LayoutContext, LayoutRect, resolve_layout};
+pub use layout::{LayoutSolver, LayoutContext, LayoutRect, resolve_layout, resolve_viewbox_sizes};
 
 // Re-export animation primitives
 pub use anim::{
@@ -59,5 +64,5 @@ pub use anim::{
 
 // Re-export WASM bindings
 #[cfg(feature = "wasm")]
-pub use wasm::{parse, parse_with_errors};
+pub use wasm::{load_ast, parse, parse_with_errors, validate as validate_json};
 
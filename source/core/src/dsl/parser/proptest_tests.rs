@@ -6,6 +6,7 @@
 
 use proptest::prelude::*;
 use super::ast::*;
+use super::super::error::{ErrorKind, ErrorSeverity, ParseError};
 use super::core::Parser;
 use super::super::lexer::{CanvasSize, Lexer};
 
@@ -158,9 +159,10 @@ proptest! {
         let source = gen_variable_source(&name, &color);
         let (ast, errors) = parse(&source);
         
-        prop_assert!(errors.is_empty(), "Variable should parse without errors: {:?}", errors);
+        // An unused variable still earns a hint
+        prop_assert!(errors.iter().all(|e| e.severity == ErrorSeverity::Hint), "Variable should parse without errors: {:?}", errors);
         if let AstNode::Scene(children) = ast {
-            if let Some(AstNode::Variable { name: var_name, value }) = children.first() {
+            if let Some(AstNode::Variable { name: var_name, value, .. }) = children.first() {
                 // Variable name includes $ prefix from lexer
                 prop_assert_eq!(var_name, &format!("${}", name));
                 prop_assert!(value.is_some(), "Variable should have a value");
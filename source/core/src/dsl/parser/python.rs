@@ -3,6 +3,7 @@
 #![cfg(feature = "python")]
 
 use super::ast::*;
+use super::super::error::ParseError;
 use super::core::Parser;
 use super::super::lexer::TokenValue;
 use pyo3::prelude::*;
@@ -11,13 +12,29 @@ use pyo3::types::{PyDict, PyList};
 #[pymethods]
 impl Parser {
     #[new]
-    fn py_new(tokens: Vec<super::super::lexer::Token>) -> Self {
-        Self::new(tokens)
+    #[pyo3(signature = (tokens, geometry_warnings=false))]
+    fn py_new(tokens: Vec<super::super::lexer::Token>, geometry_warnings: bool) -> Self {
+        let parser = Self::new(tokens);
+        if geometry_warnings { parser.with_geometry_warnings() } else { parser }
     }
 
     /// Parse and return the AST as native Python objects
     fn parse_py(&mut self, py: Python<'_>) -> PyObject {
-        ast_node_to_py(py, &self.parse())
+        let mut ast = self.parse();
+        super::symbols::post_resolve(&mut ast);
+        ast_node_to_py(py, &ast)
+    }
+
+    /// Inline `include "path"` statements; `loader(path)` returns the source or None.
+    /// An exception raised by the loader is re-raised once resolution finishes.
+    #[pyo3(name = "resolve_includes")]
+    fn py_resolve_includes(&mut self, py: Python<'_>, loader: PyObject) -> PyResult<()> {
+        let failure = std::cell::RefCell::new(None);
+        self.resolve_includes(|path| match loader.call1(py, (path,)).and_then(|v| v.extract::<Option<String>>(py)) {
+            Ok(source) => source,
+            Err(e) => { failure.borrow_mut().get_or_insert(e); None }
+        });
+        failure.into_inner().map_or(Ok(()), Err)
     }
 
     /// Get parse errors
@@ -26,6 +43,13 @@ impl Parser {
     }
 }
 
+/// Lex, parse and resolve `source`, returning only its diagnostics (for linting)
+#[pyfunction]
+#[pyo3(name = "validate")]
+pub fn py_validate(source: &str) -> Vec<ParseError> {
+    super::symbols::validate(source)
+}
+
 /// Convert AstNode to Python object directly
 pub fn ast_node_to_py(py: Python<'_>, node: &AstNode) -> PyObject {
     let dict = PyDict::new(py);
@@ -38,9 +62,12 @@ pub fn ast_node_to_py(py: Python<'_>, node: &AstNode) -> PyObject {
         AstNode::Canvas(c) => {
             let canvas = PyDict::new(py);
             canvas.set_item("size", c.size.to_string()).ok();
+            canvas.set_item("aspect", (c.aspect.w, c.aspect.h)).ok();
             canvas.set_item("width", c.width()).ok();
             canvas.set_item("height", c.height()).ok();
             canvas.set_item("fill", &c.fill).ok();
+            canvas.set_item("gradient", c.gradient.as_ref().map(|g| gradient_to_py(py, g))).ok();
+            canvas.set_item("image", &c.image).ok();
             dict.set_item("Canvas", canvas).ok();
         }
         AstNode::Shape(s) => {
@@ -49,7 +76,7 @@ pub fn ast_node_to_py(py: Python<'_>, node: &AstNode) -> PyObject {
         AstNode::Graph(g) => {
             dict.set_item("Graph", ast_graph_to_py(py, g)).ok();
         }
-        AstNode::Variable { name, value } => {
+        AstNode::Variable { name, value, .. } => {
             let var = PyDict::new(py);
             var.set_item("name", name).ok();
             var.set_item("value", token_value_to_py(py, value.as_ref())).ok();
@@ -64,6 +91,9 @@ pub fn ast_node_to_py(py: Python<'_>, node: &AstNode) -> PyObject {
         AstNode::Keyframes(kf) => {
             dict.set_item("Keyframes", ast_keyframes_to_py(py, kf)).ok();
         }
+        AstNode::Include(path) => {
+            dict.set_item("Include", path).ok();
+        }
     }
     dict.into()
 }
@@ -84,6 +114,8 @@ pub fn ast_symbol_to_py(py: Python<'_>, symbol: &AstSymbol) -> PyObject {
     dict.set_item("viewbox", symbol.viewbox).ok();
     let children = PyList::new(py, symbol.children.iter().map(|c| ast_shape_to_py(py, c)));
     dict.set_item("children", children).ok();
+    let uses = PyList::new(py, symbol.uses.iter().map(|u| ast_use_to_py(py, u)));
+    dict.set_item("uses", uses).ok();
     dict.into()
 }
 
@@ -114,6 +146,7 @@ pub fn ast_graph_to_py(py: Python<'_>, graph: &AstGraph) -> PyObject {
     dict.set_item("layout", &graph.layout).ok();
     dict.set_item("direction", &graph.direction).ok();
     dict.set_item("spacing", graph.spacing).ok();
+    dict.set_item("anchors", &graph.anchors).ok();
     let nodes = PyList::new(py, graph.nodes.iter().map(|n| graph_node_to_py(py, n)));
     dict.set_item("nodes", nodes).ok();
     let edges = PyList::new(py, graph.edges.iter().map(|e| graph_edge_to_py(py, e)));
@@ -139,7 +172,10 @@ pub fn graph_edge_to_py(py: Python<'_>, edge: &GraphEdge) -> PyObject {
     dict.set_item("to", &edge.to).ok();
     dict.set_item("style", &edge.style).ok();
     dict.set_item("arrow", &edge.arrow).ok();
+    dict.set_item("arrow_head", &edge.arrow_head).ok();
+    dict.set_item("arrow_size", edge.arrow_size).ok();
     dict.set_item("label", &edge.label).ok();
+    dict.set_item("label_bg", &edge.label_bg).ok();
     dict.set_item("stroke", &edge.stroke).ok();
     dict.set_item("stroke_width", edge.stroke_width).ok();
     dict.into()
@@ -167,9 +203,18 @@ pub fn ast_shape_to_py(py: Python<'_>, shape: &AstShape) -> PyObject {
     style.set_item("corner", shape.style.corner).ok();
     style.set_item("font", shape.style.font.as_deref()).ok();
     style.set_item("font_size", shape.style.font_size).ok();
+    style.set_item("baseline", shape.style.baseline.as_deref()).ok();
+    style.set_item("letter_spacing", shape.style.letter_spacing).ok();
+    style.set_item("word_spacing", shape.style.word_spacing).ok();
+    style.set_item("decoration", shape.style.decoration.as_deref()).ok();
+    style.set_item("direction", shape.style.direction.as_deref()).ok();
+    style.set_item("dash", &shape.style.dash).ok();
+    style.set_item("dash_offset", shape.style.dash_offset).ok();
+    style.set_item("fill_rule", &shape.style.fill_rule).ok();
     style.set_item("font_weight", &shape.style.font_weight).ok();
     style.set_item("text_anchor", &shape.style.text_anchor).ok();
     dict.set_item("style", style).ok();
+    dict.set_item("meta", &shape.meta).ok();
     
     // Convert shadow
     if let Some(shadow) = &shape.shadow {
@@ -183,12 +228,7 @@ pub fn ast_shape_to_py(py: Python<'_>, shape: &AstShape) -> PyObject {
     
     // Convert gradient
     if let Some(grad) = &shape.gradient {
-        let g = PyDict::new(py);
-        g.set_item("gtype", &grad.gtype).ok();
-        g.set_item("from", &grad.from).ok();
-        g.set_item("to", &grad.to).ok();
-        g.set_item("angle", grad.angle).ok();
-        dict.set_item("gradient", g).ok();
+        dict.set_item("gradient", gradient_to_py(py, grad)).ok();
     }
     
     // Convert transform
@@ -197,6 +237,7 @@ pub fn ast_shape_to_py(py: Python<'_>, shape: &AstShape) -> PyObject {
     transform.set_item("rotate", shape.transform.rotate).ok();
     transform.set_item("scale", shape.transform.scale).ok();
     transform.set_item("origin", shape.transform.origin).ok();
+    transform.set_item("origin_pct", shape.transform.origin_pct).ok();
     dict.set_item("transform", transform).ok();
     
     // Convert children recursively
@@ -216,27 +257,95 @@ pub fn prop_value_to_py(py: Python<'_>, val: &PropValue) -> PyObject {
         PropValue::Points(pts) => PyList::new(py, pts.iter().map(|(a, b)| (*a, *b))).into(),
         PropValue::Dim(d) => dimension_to_py(py, d),
         PropValue::DimPair(dp) => (dimension_to_py(py, &dp.width), dimension_to_py(py, &dp.height)).into_py(py),
-        PropValue::Layout(_) => "<layout>".into_py(py),
+        PropValue::Layout(layout) => layout_to_py(py, layout),
+        PropValue::List(items) => PyList::new(py, items.iter().map(|v| prop_value_to_py(py, v))).into(),
         PropValue::VarRef(name, _, _) => format!("${}", name).into_py(py),
     }
 }
 
-/// Convert Dimension to Python object
-fn dimension_to_py(py: Python<'_>, dim: &Dimension) -> PyObject {
-    match dim {
-        Dimension::Px(v) => v.into_py(py),
-        Dimension::Percent(p) => format!("{}%", p).into_py(py),
-        Dimension::Auto => "auto".into_py(py),
+/// Convert LayoutProps to a Python dict, with enum values spelled as in the DSL
+fn layout_to_py(py: Python<'_>, layout: &LayoutProps) -> PyObject {
+    let d = PyDict::new(py);
+    d.set_item("direction", layout.direction.as_deref()).ok();
+    d.set_item("justify", match layout.justify {
+        JustifyContent::Start => "start", JustifyContent::End => "end", JustifyContent::Center => "center",
+        JustifyContent::SpaceBetween => "space-between", JustifyContent::SpaceAround => "space-around",
+        JustifyContent::SpaceEvenly => "space-evenly",
+    }).ok();
+    d.set_item("align", match layout.align {
+        AlignItems::Start => "start", AlignItems::End => "end", AlignItems::Center => "center",
+        AlignItems::Stretch => "stretch", AlignItems::Baseline => "baseline",
+    }).ok();
+    d.set_item("gap", dimension_to_py(py, &layout.gap)).ok();
+    let padding = layout.padding.as_ref().map(|(t, r, b, l)| {
+        PyList::new(py, [t, r, b, l].map(|v| dimension_to_py(py, v))).to_object(py)
+    });
+    d.set_item("padding", padding).ok();
+    d.set_item("wrap", layout.wrap).ok();
+    d.set_item("constraints", PyList::new(py, layout.constraints.iter().map(|c| constraint_to_py(py, c)))).ok();
+    d.into()
+}
+
+/// Convert Constraint to a Python dict tagged by `type`
+fn constraint_to_py(py: Python<'_>, c: &Constraint) -> PyObject {
+    let axis = |a: &Axis| match a { Axis::Horizontal => "horizontal", Axis::Vertical => "vertical" };
+    let d = PyDict::new(py);
+    match c {
+        Constraint::AnchorEdge { edge, offset } => {
+            d.set_item("type", "anchor").ok();
+            d.set_item("edge", match edge { Edge::Top => "top", Edge::Right => "right", Edge::Bottom => "bottom", Edge::Left => "left" }).ok();
+            d.set_item("offset", dimension_to_py(py, offset)).ok();
+        }
+        Constraint::CenterAxis { axis: a, offset } => {
+            d.set_item("type", "center").ok();
+            d.set_item("axis", axis(a)).ok();
+            d.set_item("offset", dimension_to_py(py, offset)).ok();
+        }
+        Constraint::MatchSize { target, axis: a } => {
+            d.set_item("type", "match").ok();
+            d.set_item("target", target).ok();
+            d.set_item("axis", axis(a)).ok();
+        }
+        Constraint::Fill { weight } => {
+            d.set_item("type", "fill").ok();
+            d.set_item("weight", weight).ok();
+        }
     }
+    d.into()
+}
+
+/// Convert GradientDef to Python dict
+fn gradient_to_py(py: Python<'_>, grad: &GradientDef) -> PyObject {
+    let g = PyDict::new(py);
+    g.set_item("gtype", &grad.gtype).ok();
+    g.set_item("from", &grad.from).ok();
+    g.set_item("to", &grad.to).ok();
+    g.set_item("angle", grad.angle).ok();
+    g.set_item("shared", grad.shared).ok();
+    g.into()
+}
+
+/// Convert Dimension to a `{type, value}` dict: `px` or `percent` with a number,
+/// `auto` with `None`, and `calc` with `None` plus its `percent` and `px` parts
+pub(super) fn dimension_to_py(py: Python<'_>, dim: &Dimension) -> PyObject {
+    let d = PyDict::new(py);
+    let (kind, value) = match dim {
+        Dimension::Px(v) => ("px", Some(*v)),
+        Dimension::Percent(p) => ("percent", Some(*p)),
+        Dimension::Auto => ("auto", None),
+        Dimension::Calc { percent, px } => {
+            d.set_item("percent", percent).ok();
+            d.set_item("px", px).ok();
+            ("calc", None)
+        }
+    };
+    d.set_item("type", kind).ok();
+    d.set_item("value", value).ok();
+    d.into()
 }
 
 /// Convert TokenValue to Python object
 pub fn token_value_to_py(py: Python<'_>, val: Option<&TokenValue>) -> PyObject {
-    match val {
-        None | Some(TokenValue::None) => py.None(),
-        Some(TokenValue::Str(s)) => s.into_py(py),
-        Some(TokenValue::Num(n)) => n.into_py(py),
-        Some(TokenValue::Pair(a, b)) | Some(TokenValue::PercentPair(a, b)) => (*a, *b).into_py(py),
-    }
+    val.map_or_else(|| py.None(), |v| v.to_py(py))
 }
 
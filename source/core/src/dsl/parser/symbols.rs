@@ -3,7 +3,9 @@
 //! Provides separate variable resolution with proper scoping and error reporting.
 
 use super::ast::*;
-use super::super::lexer::TokenValue;
+use super::super::error::{ErrorKind, ParseError, Span};
+use super::core::Parser;
+use super::super::lexer::{Lexer, TokenValue};
 use std::collections::HashMap;
 
 #[allow(dead_code)] // Will be used for future scope features
@@ -102,15 +104,165 @@ pub fn resolve(ast: AstNode) -> ResolveResult {
     ResolveResult { ast: resolved, errors: resolver.errors }
 }
 
+/// Check `source` without producing output: lex, parse and resolve, returning every
+/// error, warning and hint in pipeline order. Layout solving reports nothing, so it
+/// is skipped; `include`s are not followed.
+pub fn validate(source: &str) -> Vec<ParseError> {
+    let mut lexer = Lexer::new(source);
+    let mut parser = Parser::new(lexer.tokenize()).with_geometry_warnings();
+    let mut errors = std::mem::take(&mut lexer.errors);
+    let ast = parser.parse();
+    errors.append(&mut parser.errors);
+    errors.extend(resolve(ast).errors);
+    errors
+}
+
+/// Passes that finish a parsed AST for rendering; run once, after `resolve` when used
+pub fn post_resolve(ast: &mut AstNode) {
+    sort_by_z(ast);
+    super::layout::resolve_viewbox_sizes(ast, &Default::default());
+    fit_text(ast);
+}
+
+/// Reorder shapes by their `z` prop (stable, so ties keep source order)
+pub fn sort_by_z(node: &mut AstNode) {
+    fn sort_shape(shape: &mut AstShape) {
+        shape.children.sort_by_key(z_index);
+        shape.children.iter_mut().for_each(sort_shape);
+    }
+    match node {
+        AstNode::Scene(children) => {
+            sort_nodes_by_z(children);
+            children.iter_mut().for_each(sort_by_z);
+        }
+        AstNode::Shape(shape) => sort_shape(shape),
+        AstNode::Symbol(symbol) => symbol.children.iter_mut().for_each(sort_shape),
+        _ => {}
+    }
+}
+
+/// Shrink `font_size` of text shapes with a `fit WxH` box so the content fits
+pub fn fit_text(node: &mut AstNode) {
+    fn fit_shape(shape: &mut AstShape) {
+        if let (Some(PropValue::Pair(w, h)), Some(PropValue::Str(content))) = (shape.props.get("fit"), shape.props.get("content")) {
+            let family = shape.style.font.as_deref().unwrap_or("system-ui");
+            shape.style.font_size = crate::font::fit_font_size(content, family, *w as f32, *h as f32, shape.style.font_size as f32) as f64;
+        }
+        shape.children.iter_mut().for_each(fit_shape);
+    }
+    match node {
+        AstNode::Scene(children) => children.iter_mut().for_each(fit_text),
+        AstNode::Shape(shape) => fit_shape(shape),
+        AstNode::Symbol(symbol) => symbol.children.iter_mut().for_each(fit_shape),
+        _ => {}
+    }
+}
+
+/// Stacking order from a shape's `z` prop (defaults to 0)
+fn z_index(shape: &AstShape) -> i64 {
+    match shape.props.get("z") { Some(PropValue::Num(n)) => *n as i64, _ => 0 }
+}
+
+/// Stable-sort renderable scene nodes by `z`, leaving other nodes (canvas, variables) in place
+fn sort_nodes_by_z(nodes: &mut [AstNode]) {
+    let slots: Vec<usize> = nodes.iter().enumerate()
+        .filter(|(_, n)| matches!(n, AstNode::Shape(_) | AstNode::Graph(_) | AstNode::Use(_)))
+        .map(|(i, _)| i)
+        .collect();
+    let key = |n: &AstNode| if let AstNode::Shape(s) = n { z_index(s) } else { 0 };
+    let mut order = slots.clone();
+    order.sort_by_key(|&i| key(&nodes[i]));
+    if order == slots { return; }
+    let picked: Vec<AstNode> = order.iter().map(|&i| nodes[i].clone()).collect();
+    for (slot, node) in slots.into_iter().zip(picked) { nodes[slot] = node; }
+}
+
 /// Resolver walks AST collecting definitions and resolving references
 struct Resolver {
     symbols: SymbolTable,
+    /// Symbol definitions (`symbol "id"`) by id, for `use` validation
+    symbol_defs: HashMap<String, Span>,
     errors: Vec<ParseError>,
 }
 
 impl Resolver {
     fn new() -> Self {
-        Self { symbols: SymbolTable::new(), errors: Vec::new() }
+        Self { symbols: SymbolTable::new(), symbol_defs: HashMap::new(), errors: Vec::new() }
+    }
+
+    /// Define a variable in the current scope, reporting a redefinition at its own span;
+    /// `block` names the shape whose scope it is, `None` for the scene
+    fn define_variable(&mut self, name: &str, value: &TokenValue, span: &Span, block: Option<&str>) {
+        let Some(prev) = self.symbols.define(name.to_string(), value.clone(), span.start_line, span.start_col) else { return };
+        let err = match block {
+            None => ParseError::spanning(
+                format!("Variable '{}' already defined at line {}", name, prev.line + 1),
+                ErrorKind::DuplicateVariable, span.clone()
+            ).with_suggestion(&format!("Previous definition was at {}:{}", prev.line + 1, prev.col + 1)),
+            Some(kind) => ParseError::spanning(
+                format!("Variable '{}' already defined in this {} at line {}", name, kind, prev.line + 1),
+                ErrorKind::DuplicateVariable, span.clone()
+            ).with_suggestion("Each block may define a variable once; inner blocks may shadow it"),
+        };
+        self.errors.push(err);
+    }
+
+    /// Record a symbol definition, reporting duplicates
+    fn define_symbol(&mut self, sym: &AstSymbol) {
+        if let Some(prev) = self.symbol_defs.get(&sym.id) {
+            let err = ParseError::spanning(
+                format!("Symbol '{}' already defined at line {}", sym.id, prev.start_line + 1),
+                ErrorKind::DuplicateSymbol, sym.span.clone()
+            ).with_suggestion("Rename one of the symbols; ids must be unique");
+            self.errors.push(err);
+        } else {
+            self.symbol_defs.insert(sym.id.clone(), sym.span.clone());
+        }
+    }
+
+    /// Report each cycle in the symbol → nested `use` graph, naming its path
+    fn check_symbol_cycles(&mut self, children: &[AstNode]) {
+        let graph: HashMap<&str, &AstSymbol> = children.iter()
+            .filter_map(|c| if let AstNode::Symbol(s) = c { Some((s.id.as_str(), s)) } else { None })
+            .collect();
+        let mut ids: Vec<&str> = graph.keys().copied().collect();
+        ids.sort_unstable();
+        let (mut done, mut path) = (std::collections::HashSet::new(), Vec::new());
+        for id in ids { self.visit_symbol(id, &graph, &mut path, &mut done); }
+    }
+
+    fn visit_symbol<'a>(&mut self, id: &'a str, graph: &HashMap<&'a str, &'a AstSymbol>,
+                        path: &mut Vec<&'a str>, done: &mut std::collections::HashSet<&'a str>) {
+        if done.contains(id) { return; }
+        let Some(sym) = graph.get(id) else { return };
+        path.push(id);
+        for use_ref in &sym.uses {
+            let href = use_ref.href.as_str();
+            if let Some(start) = path.iter().position(|p| *p == href) {
+                let cycle = path[start..].iter().chain(std::iter::once(&href)).copied().collect::<Vec<_>>().join(" -> ");
+                self.errors.push(ParseError::spanning(
+                    format!("Cyclic symbol reference: {}", cycle),
+                    ErrorKind::CyclicSymbol, use_ref.span.clone()
+                ).with_suggestion("A symbol cannot use itself, directly or through other symbols"));
+            } else {
+                self.visit_symbol(href, graph, path, done);
+            }
+        }
+        path.pop();
+        done.insert(id);
+    }
+
+    /// Report a `use` whose href has no matching symbol
+    fn check_use(&mut self, use_ref: &AstUse) {
+        if self.symbol_defs.contains_key(&use_ref.href) { return; }
+        let mut err = ParseError::spanning(
+            format!("Undefined symbol '{}'", use_ref.href),
+            ErrorKind::UndefinedSymbol, use_ref.span.clone()
+        );
+        if let Some(id) = super::core::closest_match(&use_ref.href, self.symbol_defs.keys().map(String::as_str)) {
+            err = err.with_suggestion(format!("Did you mean '{}'?", id));
+        }
+        self.errors.push(err);
     }
 
     fn resolve_node(&mut self, node: AstNode) -> AstNode {
@@ -118,33 +270,27 @@ impl Resolver {
             AstNode::Scene(children) => {
                 // First pass: collect all variable definitions at scene level
                 for child in &children {
-                    if let AstNode::Variable { name, value } = child {
-                        if let Some(val) = value {
-                            // Check for duplicate in current scope
-                            if let Some(prev) = self.symbols.define(name.clone(), val.clone(), 0, 0) {
-                                self.errors.push(
-                                    ParseError::new(
-                                        format!("Variable '{}' already defined at line {}", name, prev.line),
-                                        ErrorKind::DuplicateVariable, 0, 0
-                                    ).with_suggestion(&format!("Previous definition was at {}:{}", prev.line, prev.col))
-                                );
-                            }
-                        }
+                    if let AstNode::Variable { name, value: Some(val), span } = child {
+                        self.define_variable(name, val, span, None);
                     }
                 }
+                // Symbol definitions (forward `use` references are allowed)
+                for child in &children {
+                    if let AstNode::Symbol(sym) = child { self.define_symbol(sym); }
+                }
+                for child in &children {
+                    match child {
+                        AstNode::Use(use_ref) => self.check_use(use_ref),
+                        AstNode::Symbol(sym) => sym.uses.iter().for_each(|u| self.check_use(u)),
+                        _ => {}
+                    }
+                }
+                self.check_symbol_cycles(&children);
                 // Second pass: resolve all references
                 let resolved: Vec<_> = children.into_iter().map(|c| self.resolve_node(c)).collect();
                 AstNode::Scene(resolved)
             }
-            AstNode::Shape(mut shape) => {
-                // Resolve props that may have VarRefs
-                shape.props = self.resolve_props(shape.props);
-                // Resolve style colors
-                shape.style = self.resolve_style(shape.style);
-                // Recursively resolve children
-                shape.children = shape.children.into_iter().map(|c| self.resolve_shape(c)).collect();
-                AstNode::Shape(shape)
-            }
+            AstNode::Shape(shape) => AstNode::Shape(self.resolve_shape(shape)),
             AstNode::Graph(mut graph) => {
                 // Resolve node styles
                 graph.nodes = graph.nodes.into_iter().map(|n| self.resolve_graph_node(n)).collect();
@@ -152,11 +298,12 @@ impl Resolver {
                 graph.edges = graph.edges.into_iter().map(|e| self.resolve_graph_edge(e)).collect();
                 AstNode::Graph(graph)
             }
-            AstNode::Variable { name, value } => AstNode::Variable { name, value },
+            AstNode::Variable { name, value, span } => AstNode::Variable { name, value, span },
             AstNode::Canvas(c) => AstNode::Canvas(self.resolve_canvas(c)),
             AstNode::Symbol(mut symbol) => {
                 // Resolve children in symbol
                 symbol.children = symbol.children.into_iter().map(|c| self.resolve_shape(c)).collect();
+                symbol.uses = symbol.uses.into_iter().map(|mut u| { u.style = self.resolve_style(u.style); u }).collect();
                 AstNode::Symbol(symbol)
             }
             AstNode::Use(mut use_ref) => {
@@ -165,13 +312,22 @@ impl Resolver {
                 AstNode::Use(use_ref)
             }
             AstNode::Keyframes(k) => AstNode::Keyframes(k),
+            AstNode::Include(path) => AstNode::Include(path),
         }
     }
 
     fn resolve_shape(&mut self, mut shape: AstShape) -> AstShape {
+        let scoped = !shape.vars.is_empty();
+        if scoped {
+            self.symbols.push_scope();
+            for (name, value, span) in &shape.vars {
+                self.define_variable(name, value, span, Some(&shape.kind));
+            }
+        }
         shape.props = self.resolve_props(shape.props);
         shape.style = self.resolve_style(shape.style);
         shape.children = shape.children.into_iter().map(|c| self.resolve_shape(c)).collect();
+        if scoped { self.symbols.pop_scope(); }
         shape
     }
 
@@ -188,6 +344,7 @@ impl Resolver {
                         TokenValue::Num(n) => PropValue::Num(*n),
                         TokenValue::Pair(a, b) | TokenValue::PercentPair(a, b) => PropValue::Pair(*a, *b),
                         TokenValue::None => PropValue::None,
+                        TokenValue::List(items) => PropValue::from_list(items),
                     }
                 } else {
                     self.errors.push(
@@ -207,6 +364,7 @@ impl Resolver {
                         TokenValue::Num(n) => PropValue::Num(*n),
                         TokenValue::Pair(a, b) | TokenValue::PercentPair(a, b) => PropValue::Pair(*a, *b),
                         TokenValue::None => PropValue::None,
+                        TokenValue::List(items) => PropValue::from_list(items),
                     }
                 } else {
                     self.errors.push(
@@ -288,12 +446,12 @@ impl Resolver {
     }
 
     fn resolve_graph_edge(&mut self, mut edge: GraphEdge) -> GraphEdge {
-        // Resolve stroke if it's a variable reference
-        if let Some(ref stroke) = edge.stroke {
-            if let Some(name) = stroke.strip_prefix("$VAR:") {
+        // Resolve stroke and label background if they're variable references
+        for color in [&mut edge.stroke, &mut edge.label_bg] {
+            if let Some(name) = color.as_deref().and_then(|c| c.strip_prefix("$VAR:")) {
                 if let Some(symbol) = self.symbols.lookup(name) {
                     if let TokenValue::Str(s) = &symbol.value {
-                        edge.stroke = Some(s.clone());
+                        *color = Some(s.clone());
                     }
                 } else {
                     self.errors.push(
@@ -302,7 +460,7 @@ impl Resolver {
                             ErrorKind::UndefinedVariable, 0, 0
                         ).with_suggestion(&format!("Variable '{}' was used but never defined", name))
                     );
-                    edge.stroke = None;
+                    *color = None;
                 }
             }
         }
@@ -374,7 +532,8 @@ mod tests {
         let ast = AstNode::Scene(vec![
             AstNode::Variable { 
                 name: "accent".into(), 
-                value: Some(TokenValue::Str("#ff0".into())) 
+                value: Some(TokenValue::Str("#ff0".into())),
+                span: Span::default(),
             },
             AstNode::Shape(AstShape {
                 kind: "rect".into(),
@@ -3,10 +3,12 @@
 #![cfg(test)]
 
 use super::ast::*;
+use super::super::error::{ErrorKind, ErrorSeverity, ParseError};
 use super::core::Parser;
-use super::symbols::resolve;
-use super::layout::{LayoutSolver, LayoutContext};
-use super::super::lexer::{CanvasSize, Lexer};
+use super::format::{AstDocument, FORMAT_VERSION};
+use super::symbols::{post_resolve, resolve};
+use super::layout::{resolve_viewbox_sizes, LayoutSolver, LayoutContext};
+use super::super::lexer::{CanvasSize, Lexer, TokenValue};
 
 fn parse_source(source: &str) -> AstNode {
     let mut lexer = Lexer::new(source);
@@ -23,6 +25,19 @@ fn parse_with_errors(source: &str) -> (AstNode, Vec<ParseError>) {
     (ast, parser.errors)
 }
 
+#[test]
+#[cfg(any(feature = "python", feature = "bench", feature = "wasm"))]
+fn test_style_lerp() {
+    let a = AstStyle { fill: Some("#000".into()), opacity: 0.0, font: Some("Arial".into()), ..AstStyle::new() };
+    let b = AstStyle { fill: Some("#fff".into()), opacity: 1.0, font: Some("Inter".into()), stroke: Some("red".into()), ..AstStyle::new() };
+    let mid = a.lerp(&b, 0.5);
+    assert_eq!(mid.opacity, 0.5);
+    assert_eq!(mid.fill.as_deref(), Some("#808080"));
+    assert_eq!(mid.font.as_deref(), Some("Inter"));
+    assert_eq!(a.lerp(&b, 0.25).stroke, None);
+    assert_eq!(a.lerp(&b, 1.0).fill.as_deref(), Some("#ffffff"));
+}
+
 #[test]
 fn test_empty_source() {
     let ast = parse_source("");
@@ -47,6 +62,19 @@ fn test_canvas() {
     }
 }
 
+#[test]
+fn test_canvas_background_paint() {
+    let (ast, errors) = parse_with_errors("canvas large gradient linear #000 #333 image \"bg.png\"");
+    assert!(errors.is_empty(), "{:?}", errors);
+    let AstNode::Scene(children) = ast else { panic!("Expected Scene") };
+    let AstNode::Canvas(c) = &children[0] else { panic!("Expected Canvas") };
+    let g = c.gradient.as_ref().expect("gradient");
+    assert_eq!((g.gtype.as_str(), g.from.as_str(), g.to.as_str()), ("linear", "#000", "#333"));
+    assert_eq!(c.image.as_deref(), Some("bg.png"));
+    let (_, errors) = parse_with_errors("canvas large image");
+    assert_eq!(errors.len(), 1);
+}
+
 #[test]
 fn test_canvas_sizes() {
     for (name, expected_px) in [("nano", 16), ("micro", 24), ("tiny", 32), ("small", 48), 
@@ -63,6 +91,34 @@ fn test_canvas_sizes() {
     }
 }
 
+#[test]
+fn test_canvas_size_from_pixels() {
+    assert_eq!(CanvasSize::from_pixels(64), Some(CanvasSize::Medium));
+    assert_eq!(CanvasSize::from_pixels(70), None);
+    assert_eq!(CanvasSize::nearest(70), CanvasSize::Medium);
+    assert_eq!(CanvasSize::nearest(80), CanvasSize::Medium); // tie with 96 goes down
+    assert_eq!((CanvasSize::nearest(0), CanvasSize::nearest(4096)), (CanvasSize::Nano, CanvasSize::Giant));
+    assert!(CanvasSize::ALL.iter().all(|&s| CanvasSize::from_pixels(s.pixels()) == Some(s)));
+}
+
+#[test]
+fn test_canvas_aspect_ratio() {
+    let canvas = |src: &str| match parse_source(src) {
+        AstNode::Scene(children) => match &children[0] { AstNode::Canvas(c) => c.clone(), _ => panic!("Expected Canvas") },
+        _ => panic!("Expected Scene"),
+    };
+    assert_eq!(canvas("canvas large 2:1").dimensions(), (192, 96));
+    assert_eq!(canvas("canvas large tall fill #000").dimensions(), (96, 192));
+    assert_eq!(canvas("canvas large wide").dimensions(), (192, 96));
+    assert_eq!(canvas("canvas medium 16:9").dimensions(), (114, 64));
+    assert_eq!(canvas("canvas medium").dimensions(), (64, 64));
+    assert_eq!(canvas("canvas giant 9999999:1").dimensions(), (u32::MAX, 512));
+    assert_eq!(canvas("canvas nano 1:4000000000").dimensions(), (16, u32::MAX));
+
+    let (_, errors) = parse_with_errors("canvas large 0:1");
+    assert_eq!(errors[0].kind, ErrorKind::InvalidValue);
+}
+
 #[test]
 fn test_rect() {
     let ast = parse_source("rect at 100,200 size 50x30 #ff0");
@@ -85,6 +141,60 @@ fn test_circle() {
     }
 }
 
+#[test]
+fn test_circle_two_radii_promoted_to_ellipse() {
+    let (ast, errors) = parse_with_errors("circle at 10,10 radius 8,4\ncircle radius 6,6\ncircle rx 3 ry 2");
+    let AstNode::Scene(children) = ast else { panic!("Expected Scene") };
+    let shapes: Vec<_> = children.iter().map(|n| match n { AstNode::Shape(s) => (s.kind.as_str(), s.props.get("radius").cloned()), _ => panic!("Expected Shape") }).collect();
+    assert_eq!(shapes, vec![
+        ("ellipse", Some(PropValue::Pair(8.0, 4.0))),
+        ("circle", Some(PropValue::Num(6.0))),
+        ("ellipse", Some(PropValue::Pair(3.0, 2.0))),
+    ]);
+    let warnings: Vec<_> = errors.iter().map(|e| (e.severity, e.line, e.message.as_str())).collect();
+    assert_eq!(warnings, vec![
+        (ErrorSeverity::Warning, 0, "Circle has two radii (8, 4); drawing an ellipse"),
+        (ErrorSeverity::Warning, 2, "Circle has two radii (3, 2); drawing an ellipse"),
+    ]);
+}
+
+#[test]
+fn test_ellipse_single_radius_warns() {
+    let (ast, errors) = parse_with_errors("ellipse radius 5\nellipse rx 7\nellipse rx 4 ry 2");
+    let AstNode::Scene(children) = ast else { panic!("Expected Scene") };
+    let radii: Vec<_> = children.iter().map(|n| match n { AstNode::Shape(s) => s.props.get("radius").cloned(), _ => panic!("Expected Shape") }).collect();
+    assert_eq!(radii, vec![Some(PropValue::Pair(5.0, 5.0)), Some(PropValue::Pair(7.0, 7.0)), Some(PropValue::Pair(4.0, 2.0))]);
+    assert_eq!(errors.len(), 2, "{:?}", errors);
+    assert_eq!(errors[0].message, "Ellipse has a single radius (5); drawing a circle");
+    assert_eq!((errors[1].severity, errors[1].line), (ErrorSeverity::Warning, 1));
+}
+
+#[test]
+fn test_line_arrow() {
+    let (ast, errors) = parse_with_errors("line from 0,0 to 100,0 arrow forward\npath d \"M0 0 L10 10\"\n  arrow both\nline arrow sideways");
+    let AstNode::Scene(children) = ast else { panic!("Expected Scene") };
+    let arrows: Vec<_> = children.iter().map(|n| match n { AstNode::Shape(s) => s.props.get("arrow").cloned(), _ => panic!("Expected Shape") }).collect();
+    assert_eq!(arrows, vec![Some(PropValue::Str("forward".into())), Some(PropValue::Str("both".into())), None]);
+    assert_eq!(errors.len(), 1, "{:?}", errors);
+    assert_eq!((errors[0].message.as_str(), errors[0].line), ("Expected arrow direction", 3));
+}
+
+#[test]
+fn test_edge_arrow_head() {
+    let src = "graph\n  edge \"a\" -> \"b\" arrow forward style open size 12 curved\n  edge \"b\" -> \"c\" arrow both style curved\n  edge \"c\" -> \"a\"\n    arrow backward style diamond";
+    let (ast, errors) = parse_with_errors(src);
+    assert!(errors.is_empty(), "{:?}", errors);
+    let AstNode::Scene(children) = ast else { panic!("Expected Scene") };
+    let AstNode::Graph(g) = &children[0] else { panic!("Expected Graph") };
+    let heads: Vec<_> = g.edges.iter().map(|e| (e.arrow.as_str(), e.arrow_head.as_str(), e.arrow_size, e.style.as_str())).collect();
+    assert_eq!(heads, vec![
+        ("forward", "open", 12.0, "curved"),
+        // `style` after the arrow still sets the edge style when it names one
+        ("both", "triangle", 10.0, "curved"),
+        ("backward", "diamond", 10.0, "straight"),
+    ]);
+}
+
 #[test]
 fn test_nested_style() {
     let ast = parse_source("rect\n  fill #ff0\n  stroke #000 2");
@@ -171,6 +281,22 @@ fn test_error_recovery_unknown_command() {
     }
 }
 
+#[test]
+fn test_error_recovery_unknown_block() {
+    let (ast, errors) = parse_with_errors("layout vertical\n  rect size 20x20\n    fill #000\ncircle at 5,5");
+    assert_eq!(errors[0].kind, ErrorKind::UnknownCommand);
+    // The orphaned block is skipped and parsing resumes after it
+    assert!(matches!(ast, AstNode::Scene(ref c) if matches!(c.last(), Some(AstNode::Shape(s)) if s.kind == "circle")));
+}
+
+#[test]
+fn test_error_quotes_token_source() {
+    let (_, errors) = parse_with_errors("rect\n  #FFF");
+    assert_eq!(errors[0].message, "Unexpected Color '#FFF' in block");
+    let (_, errors) = parse_with_errors("graph\n  1.50");
+    assert_eq!(errors[0].message, "Unexpected Number '1.50' in graph block");
+}
+
 #[test]
 fn test_error_recovery_multiple_errors() {
     let (ast, errors) = parse_with_errors("badcmd\nrect at 100,100\nanotherbad\ncircle 50");
@@ -259,6 +385,110 @@ fn test_error_codes() {
     assert_eq!(errors[0].kind.code(), "E002"); // UnknownCommand
 }
 
+#[test]
+fn test_error_json_diagnostic() {
+    let (_, errors) = parse_with_errors("rekt at 100,100");
+    let err = &errors[0];
+    assert_eq!(err.code, "E002");
+
+    let json: serde_json::Value = serde_json::from_str(&err.to_json()).unwrap();
+    assert_eq!(json["code"], "E002");
+    assert_eq!(json["kind"], "UnknownCommand");
+    assert_eq!(json["span"]["start_line"], 0);
+    assert_eq!(json["suggestion"].as_str(), err.suggestion.as_deref());
+    assert!(json["suggestion"].as_str().unwrap().starts_with("Did you mean"));
+}
+
+#[test]
+fn test_edit_distance() {
+    use super::core::edit_distance;
+    assert_eq!(edit_distance("rect", "rect"), 0);
+    assert_eq!(edit_distance("recf", "rect"), 1);
+    assert_eq!(edit_distance("centre", "center"), 2);
+    assert_eq!(edit_distance("", "abc"), 3);
+}
+
+#[test]
+fn test_suggest_closest_command() {
+    let (_, errors) = parse_with_errors("recf at 10,10");
+    assert_eq!(errors[0].suggestion.as_deref(), Some("Did you mean 'rect'?"));
+}
+
+#[test]
+fn test_suggest_nothing_beyond_threshold() {
+    let (_, errors) = parse_with_errors("rect at 10,10\n  colur #f00");
+    let err = errors.iter().find(|e| e.kind == ErrorKind::InvalidProperty).unwrap();
+    // "fill"/"stroke" are too far away to be offered for "colur"
+    let hint = err.suggestion.as_deref().unwrap_or("");
+    assert!(!hint.contains("fill") && !hint.contains("stroke"));
+
+    let (_, errors) = parse_with_errors("zzzzzz");
+    assert_eq!(errors[0].suggestion, None);
+}
+
+#[test]
+fn test_suggest_layout_values() {
+    let (_, errors) = parse_with_errors("stack\n  justify centre");
+    assert_eq!(errors[0].kind, ErrorKind::InvalidValue);
+    assert_eq!(errors[0].suggestion.as_deref(), Some("Did you mean 'center'?"));
+
+    let (_, errors) = parse_with_errors("canvas larg");
+    assert_eq!(errors[0].suggestion.as_deref(), Some("Did you mean 'large'?"));
+
+    let (_, errors) = parse_with_errors("graph\n  layout gird");
+    assert_eq!(errors[0].suggestion.as_deref(), Some("Did you mean 'grid'?"));
+}
+
+#[test]
+fn test_opacity_out_of_range_warns_and_clamps() {
+    let (ast, errors) = parse_with_errors("rect at 0,0\n  opacity 5");
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].severity, ErrorSeverity::Warning);
+    assert_eq!(errors[0].kind, ErrorKind::InvalidValue);
+    assert!(errors[0].message.contains("opacity 5"));
+    let AstNode::Scene(children) = ast else { panic!("Expected Scene") };
+    let AstNode::Shape(s) = &children[0] else { panic!("Expected Shape") };
+    assert!((s.style.opacity - 1.0).abs() < 0.001);
+}
+
+#[test]
+fn test_negative_widths_clamped() {
+    let (ast, errors) = parse_with_errors("rect at 0,0\n  corner -4\n  stroke #000 -2");
+    assert_eq!(errors.len(), 2);
+    assert!(errors.iter().all(|e| e.severity == ErrorSeverity::Warning));
+    let AstNode::Scene(children) = ast else { panic!("Expected Scene") };
+    let AstNode::Shape(s) = &children[0] else { panic!("Expected Shape") };
+    assert_eq!(s.style.corner, 0.0);
+    assert_eq!(s.style.stroke_width, 0.0);
+}
+
+#[test]
+fn test_image_data_uri_valid() {
+    let (ast, errors) = parse_with_errors(r#"image at 0,0 size 16x16 href "data:image/png;base64,iVBORw0KGgo=" fit contain"#);
+    assert!(errors.is_empty(), "{:?}", errors);
+    if let AstNode::Scene(children) = ast {
+        if let AstNode::Shape(s) = &children[0] {
+            assert!(matches!(s.props.get("href"), Some(PropValue::Str(h)) if h.starts_with("data:image/png")));
+            assert!(matches!(s.props.get("fit"), Some(PropValue::Str(f)) if f == "contain"));
+        }
+    }
+}
+
+#[test]
+fn test_image_data_uri_bad_media_type_warns() {
+    let (ast, errors) = parse_with_errors(r#"image at 0,0 href "data:image/tiff;base64,AAAA""#);
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].severity, ErrorSeverity::Warning);
+    assert!(errors[0].message.contains("image/tiff"));
+    // href is kept so the shape still renders
+    if let AstNode::Scene(children) = ast {
+        assert!(matches!(&children[0], AstNode::Shape(s) if s.props.contains_key("href")));
+    }
+
+    let (_, errors) = parse_with_errors(r#"image href "data:image/png;base64,not*base64""#);
+    assert!(errors[0].message.contains("base64"));
+}
+
 #[test]
 fn test_unclosed_points_recovery() {
     let (ast, errors) = parse_with_errors("polygon points [100,100 200,200\nrect at 50,50");
@@ -278,7 +508,8 @@ fn test_unclosed_points_recovery() {
 
 fn parse_and_resolve(source: &str) -> (AstNode, Vec<ParseError>) {
     let (ast, mut parse_errors) = parse_with_errors(source);
-    let result = resolve(ast);
+    let mut result = resolve(ast);
+    post_resolve(&mut result.ast);
     parse_errors.extend(result.errors);
     (result.ast, parse_errors)
 }
@@ -292,6 +523,49 @@ fn test_undefined_variable_error() {
     assert!(errors.iter().any(|e| e.message.contains("undefined")));
 }
 
+fn group_fills(ast: &AstNode) -> Vec<Option<String>> {
+    fn walk(s: &AstShape, out: &mut Vec<Option<String>>) {
+        if s.kind != "group" { out.push(s.style.fill.clone()); }
+        s.children.iter().for_each(|c| walk(c, out));
+    }
+    let mut out = Vec::new();
+    if let AstNode::Scene(children) = ast {
+        children.iter().for_each(|c| if let AstNode::Shape(s) = c { walk(s, &mut out) });
+    }
+    out
+}
+
+#[test]
+fn test_group_variable_shadows_outer() {
+    let src = "$accent = #f00\ngroup\n  $accent = #0f0\n  rect\n    fill $accent\n  circle\n    fill $late\n  $late = #00f\ncircle\n  fill $accent";
+    let (ast, errors) = parse_and_resolve(src);
+    assert!(errors.iter().all(|e| e.severity != ErrorSeverity::Error), "{:?}", errors);
+    let fills = group_fills(&ast);
+    assert_eq!(fills, [Some("#0f0".into()), Some("#00f".into()), Some("#f00".into())]);
+}
+
+#[test]
+fn test_group_variable_scope_fallback_and_leak() {
+    let src = "$accent = #f00\ngroup\n  $fg = #000\n  rect\n    fill $accent\ncircle\n  fill $fg";
+    let (ast, errors) = parse_and_resolve(src);
+    assert_eq!(group_fills(&ast), [Some("#f00".into()), None]);
+    let undefined: Vec<_> = errors.iter().filter(|e| e.kind == ErrorKind::UndefinedVariable).collect();
+    assert_eq!(undefined.len(), 1, "{:?}", errors);
+    assert!(undefined[0].message.contains("fg"));
+}
+
+#[test]
+fn test_duplicate_variable_reported_at_redefinition() {
+    let src = "$accent = #f00\n$accent = #0f0\ngroup\n  $fg = #000\n  rect\n    fill $fg\n  $fg = #111";
+    let (_, errors) = parse_and_resolve(src);
+    let dups: Vec<_> = errors.iter().filter(|e| e.kind == ErrorKind::DuplicateVariable)
+        .map(|e| (e.line, e.col, e.message.as_str())).collect();
+    assert_eq!(dups, [
+        (1, 0, "Variable '$accent' already defined at line 1"),
+        (6, 0, "Variable '$fg' already defined in this group at line 4"),
+    ]);
+}
+
 #[test]
 fn test_defined_variable_resolved() {
     let (ast, errors) = parse_and_resolve("$accent = #ff0\ncircle $accent");
@@ -353,6 +627,407 @@ fn test_variable_in_nested_block() {
     }
 }
 
+#[test]
+fn test_z_order_sorts_shapes() {
+    let (ast, errors) = parse_and_resolve("canvas small\nrect at 0,0 z 1 #f00\nrect at 5,5\n  z 0\n  fill #00f\ncircle 3");
+    assert!(errors.is_empty(), "{:?}", errors);
+    let AstNode::Scene(children) = ast else { panic!("Expected Scene") };
+    // Canvas stays put; z 0 shapes keep source order, z 1 moves to the top
+    assert!(matches!(&children[0], AstNode::Canvas(_)));
+    let kinds: Vec<_> = children[1..].iter().map(|n| match n {
+        AstNode::Shape(s) => (s.kind.clone(), s.style.fill.clone().or(s.props.get("fill").map(|_| "inline".into()))),
+        _ => panic!("Expected Shape"),
+    }).collect();
+    assert_eq!(kinds[0], ("rect".into(), Some("#00f".into())));
+    assert_eq!(kinds[1].0, "circle");
+    assert_eq!(kinds[2].0, "rect");
+}
+
+#[test]
+fn test_inline_style_one_line() {
+    let (ast, errors) = parse_with_errors("rect at 10,20 100x50 stroke #000 width 2 opacity 0.8 corner 4");
+    assert!(errors.is_empty(), "{:?}", errors);
+    let AstNode::Scene(children) = ast else { panic!("Expected Scene") };
+    let AstNode::Shape(s) = &children[0] else { panic!("Expected Shape") };
+    assert_eq!(s.props.get("at"), Some(&PropValue::Pair(10.0, 20.0)));
+    assert_eq!(s.props.get("size"), Some(&PropValue::Pair(100.0, 50.0)));
+    assert_eq!(s.style.stroke.as_deref(), Some("#000"));
+    assert_eq!((s.style.stroke_width, s.style.opacity, s.style.corner), (2.0, 0.8, 4.0));
+    // The stroke color is not mistaken for the positional fill
+    assert!(!s.props.contains_key("fill"));
+}
+
+#[test]
+fn test_inline_style_keeps_positional_numbers() {
+    let AstNode::Scene(children) = parse_source("circle at 50,50 20 #f00 width 3 fill #0f0") else { panic!("Expected Scene") };
+    let AstNode::Shape(s) = &children[0] else { panic!("Expected Shape") };
+    assert_eq!(s.props.get("radius"), Some(&PropValue::Num(20.0)));
+    assert_eq!(s.props.get("fill"), Some(&PropValue::Str("#f00".into())));
+    assert_eq!(s.style.stroke_width, 3.0);
+    assert_eq!(s.style.fill.as_deref(), Some("#0f0"));
+}
+
+#[test]
+fn test_dash_offset_draw_on() {
+    use super::anim::AnimatableProperty;
+    let src = "@keyframes draw\n  0% dash-offset 120\n  100% dash-offset 0\npath d \"M0 0 L100 0\" stroke #000\n  dash 120 40\n  dash-offset 120";
+    let (ast, errors) = parse_with_errors(src);
+    assert!(errors.is_empty(), "{:?}", errors);
+    let AstNode::Scene(children) = ast else { panic!("Expected Scene") };
+    let AstNode::Keyframes(kf) = &children[0] else { panic!("Expected Keyframes") };
+    assert_eq!(kf.steps[0].properties, vec![AnimatableProperty::StrokeDashOffset(120.0)]);
+    assert!(kf.to_css().contains("stroke-dashoffset: 0;"));
+    let AstNode::Shape(s) = &children[1] else { panic!("Expected Shape") };
+    assert_eq!((s.style.dash.as_slice(), s.style.dash_offset), (&[120.0, 40.0][..], 120.0));
+}
+
+#[test]
+fn test_unused_variable_hint() {
+    let (_, errors) = parse_with_errors("$accent = #f00\nrect at 0,0");
+    assert_eq!(errors.len(), 1, "{:?}", errors);
+    assert_eq!((errors[0].kind, errors[0].severity), (ErrorKind::UnusedVariable, ErrorSeverity::Hint));
+    assert!(errors[0].to_json().contains(r#""severity":"Hint""#));
+    assert_eq!((errors[0].span.start_col, errors[0].span.end_col), (0, 7));
+    let (_, errors) = parse_with_errors("$accent = #f00\nrect at 0,0 $accent");
+    assert!(errors.is_empty(), "{:?}", errors);
+}
+
+#[test]
+fn test_unused_hints_suppressible() {
+    // Forward references count as uses
+    let (_, errors) = parse_with_errors("rect $later\n$later = #00f");
+    assert!(errors.is_empty(), "{:?}", errors);
+    let (_, errors) = parse_with_errors("$_scratch = 4\nsymbol \"_draft\"\n  rect");
+    assert!(errors.is_empty(), "{:?}", errors);
+    let tokens = Lexer::new("$x = 1\nsymbol \"icon\"\n  rect").tokenize();
+    let mut parser = Parser::new(tokens.clone());
+    parser.parse();
+    assert_eq!(parser.errors.iter().map(|e| e.kind).collect::<Vec<_>>(), vec![ErrorKind::UnusedVariable, ErrorKind::UnusedSymbol]);
+    let mut quiet = Parser::new(tokens).without_hints();
+    quiet.parse();
+    assert!(quiet.errors.is_empty());
+}
+
+#[test]
+fn test_missing_geometry_warnings() {
+    let check = |src: &str| {
+        let mut parser = Parser::new(Lexer::new(src).tokenize()).with_geometry_warnings();
+        parser.parse();
+        parser.errors
+    };
+    let errors = check("rect at 10,20");
+    assert_eq!(errors.len(), 1, "{:?}", errors);
+    assert_eq!((errors[0].kind, errors[0].severity, errors[0].line), (ErrorKind::MissingGeometry, ErrorSeverity::Warning, 0));
+    assert_eq!(errors[0].message, "rect at 1:1 has no size; using the default 100x100");
+    let errors = check("circle at 5,5\n  fill #f00");
+    assert_eq!(errors.len(), 1, "{:?}", errors);
+    assert!(errors[0].message.starts_with("circle at 1:1 has no radius"));
+    // Any spelling of the geometry is enough, and the check is opt-in
+    let ok = check("rect size 10x10\n  fill #f00\ncircle 4\nline from 0,0 to 5,5");
+    assert!(ok.is_empty(), "{:?}", ok);
+    assert!(parse_with_errors("rect at 10,20").1.is_empty());
+}
+
+/// `levels` stacks, each nested one indent under the previous, with a rect at the bottom
+fn nested_stacks(levels: usize) -> String {
+    let mut src: String = (0..levels).map(|i| format!("{}stack\n", "  ".repeat(i))).collect();
+    src.push_str(&format!("{}rect", "  ".repeat(levels)));
+    src
+}
+
+#[test]
+fn test_nesting_depth_limit() {
+    // Well past the default limit, parsing reports once and returns
+    let (ast, errors) = parse_with_errors(&format!("{}\ncircle", nested_stacks(1000)));
+    let deep: Vec<_> = errors.iter().filter(|e| e.kind == ErrorKind::NestingTooDeep).collect();
+    assert_eq!(deep.len(), 1, "{:?}", errors);
+    assert!(deep[0].message.contains("64"));
+    let AstNode::Scene(children) = ast else { panic!("Expected Scene") };
+    // The sibling after the skipped block still parses
+    assert!(matches!(&children[1], AstNode::Shape(s) if s.kind == "circle"));
+
+    let tokens = Lexer::new(&format!("{}\ncircle", nested_stacks(3))).tokenize();
+    let mut parser = Parser::new(tokens).with_max_depth(2);
+    let AstNode::Scene(children) = parser.parse() else { panic!("Expected Scene") };
+    assert_eq!(parser.errors.iter().map(|e| e.kind).collect::<Vec<_>>(), vec![ErrorKind::NestingTooDeep]);
+    let AstNode::Shape(outer) = &children[0] else { panic!("Expected Shape") };
+    assert!(outer.children[0].children[0].children.is_empty());
+    assert_eq!(children.len(), 2);
+}
+
+#[test]
+fn test_mirror_scale_about_origin() {
+    let ast = parse_source("rect at 10,0 size 20x10\n  scale -1 1\n  origin 50,0");
+    let AstNode::Scene(children) = ast else { panic!("Expected Scene") };
+    let AstNode::Shape(rect) = &children[0] else { panic!("Expected Shape") };
+    let tf = &rect.transform;
+    assert_eq!(tf.scale, Some((-1.0, 1.0)));
+    assert_eq!(tf.to_svg().as_deref(), Some("translate(50 0) scale(-1 1) translate(-50 0)"));
+    // Left and right edges reflect across x=50
+    assert_eq!(tf.apply((10.0, 0.0)), (90.0, 0.0));
+    assert_eq!(tf.apply((30.0, 5.0)), (70.0, 5.0));
+}
+
+#[test]
+fn test_text_baseline() {
+    let ast = parse_source("text \"A\" at 0,10 baseline middle\ntext \"B\"\n  baseline hanging");
+    let AstNode::Scene(children) = ast else { panic!("Expected Scene") };
+    let baselines: Vec<_> = children.iter().map(|n| match n { AstNode::Shape(s) => s.style.baseline.clone(), _ => None }).collect();
+    assert_eq!(baselines, vec![Some("middle".into()), Some("hanging".into())]);
+    let (_, errors) = parse_with_errors("text \"C\" baseline midle");
+    assert_eq!(errors[0].suggestion.as_deref(), Some("Did you mean 'middle'?"));
+}
+
+#[test]
+fn test_text_spacing() {
+    let ast = parse_source("text \"A b\" letter-spacing 2\ntext \"C d\"\n  word-spacing -1.5");
+    let AstNode::Scene(children) = ast else { panic!("Expected Scene") };
+    let spacing: Vec<_> = children.iter().filter_map(|n| match n { AstNode::Shape(s) => Some((s.style.letter_spacing, s.style.word_spacing)), _ => None }).collect();
+    assert_eq!(spacing, vec![(2.0, 0.0), (0.0, -1.5)]);
+}
+
+#[test]
+fn test_shape_layer() {
+    let ast = parse_source("rect at 0,0 size 10x10\n  layer \"bg\"");
+    let AstNode::Scene(children) = &ast else { panic!("Expected Scene") };
+    let AstNode::Shape(rect) = &children[0] else { panic!("Expected Shape") };
+    assert!(matches!(rect.props.get("layer"), Some(PropValue::Str(l)) if l == "bg"));
+
+    let (_, errors) = parse_with_errors("rect\n  layer bg");
+    assert_eq!(errors[0].message, "Expected a quoted layer name");
+}
+
+#[test]
+fn test_fill_rule() {
+    let ast = parse_source("path \"M0 0 H20 V20 H0 Z M5 5 H15 V15 H5 Z\"\n  fill-rule evenodd");
+    let AstNode::Scene(children) = &ast else { panic!("Expected Scene") };
+    let AstNode::Shape(path) = &children[0] else { panic!("Expected Shape") };
+    assert_eq!(path.style.fill_rule.as_deref(), Some("evenodd"));
+    let (_, errors) = parse_with_errors("path \"M0 0\"\n  fill-rule evenod");
+    assert_eq!(errors[0].suggestion.as_deref(), Some("Did you mean 'evenodd'?"));
+}
+
+#[test]
+fn test_percent_origin() {
+    let ast = parse_source("rect at 0,0 size 10x10\n  rotate 30\n  origin 50% 25%");
+    let AstNode::Scene(children) = &ast else { panic!("Expected Scene") };
+    let AstNode::Shape(rect) = &children[0] else { panic!("Expected Shape") };
+    assert_eq!(rect.transform.origin_pct, Some((50.0, 25.0)));
+    assert_eq!(rect.transform.resolve_origin((10.0, 20.0, 40.0, 8.0)).origin, Some((30.0, 22.0)));
+    let (_, errors) = parse_with_errors("rect\n  origin 50%");
+    assert_eq!(errors[0].message, "Expected a second origin percent");
+}
+
+#[test]
+fn test_shape_meta() {
+    let ast = parse_source("rect at 0,0 size 10x10\n  meta layer \"bg\"\n  meta locked \"true\"");
+    let AstNode::Scene(children) = &ast else { panic!("Expected Scene") };
+    let AstNode::Shape(rect) = &children[0] else { panic!("Expected Shape") };
+    assert_eq!(rect.meta.get("layer").map(String::as_str), Some("bg"));
+    assert_eq!(rect.meta.len(), 2);
+    let json = serde_json::to_string(&ast).unwrap();
+    assert_eq!(serde_json::from_str::<AstNode>(&json).unwrap(), ast);
+    let (_, errors) = parse_with_errors("rect\n  meta layer bg");
+    assert_eq!(errors[0].message, "Expected a quoted value for meta 'layer'");
+}
+
+#[test]
+fn test_text_decoration() {
+    let ast = parse_source("text \"A\" decoration underline\ntext \"B\"\n  decoration line-through");
+    let AstNode::Scene(children) = ast else { panic!("Expected Scene") };
+    let decorations: Vec<_> = children.iter().map(|n| match n { AstNode::Shape(s) => s.style.decoration.clone(), _ => None }).collect();
+    assert_eq!(decorations, vec![Some("underline".into()), Some("line-through".into())]);
+    let (_, errors) = parse_with_errors("text \"C\" decoration underlin");
+    assert_eq!(errors[0].suggestion.as_deref(), Some("Did you mean 'underline'?"));
+}
+
+#[test]
+fn test_text_direction() {
+    let ast = parse_source("text \"A\" direction rtl\ntext \"B\"\n  direction ltr");
+    let AstNode::Scene(children) = ast else { panic!("Expected Scene") };
+    let directions: Vec<_> = children.iter().map(|n| match n { AstNode::Shape(s) => s.style.direction.clone(), _ => None }).collect();
+    assert_eq!(directions, vec![Some("rtl".into()), Some("ltr".into())]);
+    let (_, errors) = parse_with_errors("text \"C\" direction rtk");
+    assert_eq!(errors[0].suggestion.as_deref(), Some("Did you mean 'rtl'?"));
+}
+
+#[test]
+fn test_text_fit_shrinks_long_content() {
+    let (ast, errors) = parse_and_resolve("text \"OK\" at 0,20 fit 80x24\ntext \"Quarterly revenue summary\" at 0,50 fit 80x24");
+    assert!(errors.is_empty(), "{:?}", errors);
+    let AstNode::Scene(children) = ast else { panic!("Expected Scene") };
+    let sizes: Vec<f64> = children.iter().map(|n| match n { AstNode::Shape(s) => s.style.font_size, _ => panic!("Expected Shape") }).collect();
+    assert_eq!(sizes[0], 16.0);
+    assert!(sizes[1] < sizes[0], "{:?}", sizes);
+}
+
+#[test]
+fn test_error_spans_whole_token() {
+    let src = "rect at 0,0\n  fil #f00";
+    let (_, errors) = parse_with_errors(src);
+    let err = &errors[0];
+    assert_eq!(err.message, "Unknown property 'fil' in rect block");
+    assert_eq!((err.span.start_line, err.span.end_col - err.span.start_col), (1, 3));
+    let (start, end) = err.span.bytes.expect("token errors carry byte offsets");
+    assert_eq!(&src[start..end], "fil");
+    assert!(err.to_json().contains(r#""bytes":[14,17]"#), "{}", err.to_json());
+}
+
+#[test]
+fn test_dangling_use_reference() {
+    let (_, errors) = parse_and_resolve("symbol \"star-icon\"\n  circle 4\nuse \"star-icn\" at 10,10");
+    let err = errors.iter().find(|e| e.kind == ErrorKind::UndefinedSymbol).expect("dangling use should error");
+    assert_eq!(err.code, "E011");
+    assert_eq!(err.suggestion.as_deref(), Some("Did you mean 'star-icon'?"));
+    assert_eq!((err.span.start_line, err.span.end_col - err.span.start_col), (2, 10));
+}
+
+#[test]
+fn test_validate_reports_only_diagnostics() {
+    let errors = super::symbols::validate("$accent = #f00\nuse \"star\" at 10,10\nrect at 0,0");
+    let found: Vec<_> = errors.iter().map(|e| (e.kind, e.severity, e.line, e.message.as_str())).collect();
+    assert_eq!(found, vec![
+        (ErrorKind::MissingGeometry, ErrorSeverity::Warning, 2, "rect at 3:1 has no size; using the default 100x100"),
+        (ErrorKind::UnusedVariable, ErrorSeverity::Hint, 0, "Variable '$accent' is never used"),
+        (ErrorKind::UndefinedSymbol, ErrorSeverity::Error, 1, "Undefined symbol 'star'"),
+    ]);
+    assert!(super::symbols::validate("$accent = #f00\nrect at 0,0 size 5x5\n  fill $accent").is_empty());
+}
+
+#[test]
+fn test_duplicate_symbol_definition() {
+    let (_, errors) = parse_and_resolve("symbol \"a\"\n  rect\nsymbol \"a\"\n  circle 2\nuse \"a\"");
+    let dups: Vec<_> = errors.iter().filter(|e| e.kind == ErrorKind::DuplicateSymbol).collect();
+    assert_eq!(dups.len(), 1);
+    assert_eq!(dups[0].line, 2);
+    assert!(!errors.iter().any(|e| e.kind == ErrorKind::UndefinedSymbol));
+}
+
+#[test]
+fn test_symbol_self_reference_cycle() {
+    let (_, errors) = parse_and_resolve("symbol \"loop\"\n  rect\n  use \"loop\"\nuse \"loop\"");
+    let cycles: Vec<_> = errors.iter().filter(|e| e.kind == ErrorKind::CyclicSymbol).collect();
+    assert_eq!(cycles.len(), 1, "{:?}", errors);
+    assert_eq!(cycles[0].code, "E012");
+    assert_eq!(cycles[0].message, "Cyclic symbol reference: loop -> loop");
+    assert_eq!(cycles[0].line, 2);
+}
+
+#[test]
+fn test_symbol_two_way_cycle() {
+    let (ast, errors) = parse_and_resolve("symbol \"a\"\n  use \"b\"\nsymbol \"b\"\n  circle 2\n  use \"a\"");
+    let cycles: Vec<_> = errors.iter().filter(|e| e.kind == ErrorKind::CyclicSymbol).collect();
+    assert_eq!(cycles.len(), 1, "{:?}", errors);
+    assert_eq!(cycles[0].message, "Cyclic symbol reference: a -> b -> a");
+    assert!(!errors.iter().any(|e| e.kind == ErrorKind::UndefinedSymbol));
+    let AstNode::Scene(children) = ast else { panic!("Expected Scene") };
+    let AstNode::Symbol(b) = &children[1] else { panic!("Expected Symbol") };
+    assert_eq!((b.children.len(), b.uses.len()), (1, 1));
+}
+
+fn parse_with_includes(source: &str, files: &[(&str, &str)]) -> (AstNode, Vec<ParseError>) {
+    let mut parser = Parser::new(Lexer::new(source).tokenize());
+    parser.resolve_includes(|path| files.iter().find(|(p, _)| *p == path).map(|(_, src)| src.to_string()));
+    let ast = parser.parse();
+    (ast, parser.errors)
+}
+
+#[test]
+fn test_include_inlines_source() {
+    let palette = "$accent = #e94560\nsymbol \"dot\"\n  circle at 5,5 radius 5";
+    let (ast, errors) = parse_with_includes("include \"palette.icon\"\nrect\n  fill $accent\nuse \"dot\"", &[("palette.icon", palette)]);
+    assert!(errors.is_empty(), "{:?}", errors);
+    let result = resolve(ast);
+    assert!(result.errors.is_empty(), "{:?}", result.errors);
+    let AstNode::Scene(children) = &result.ast else { panic!("Expected Scene") };
+    assert!(matches!(&children[1], AstNode::Symbol(s) if s.id == "dot"));
+    let AstNode::Shape(rect) = &children[2] else { panic!("Expected Shape") };
+    assert_eq!(rect.style.fill.as_deref(), Some("#e94560"));
+    // Without a loader the statement is kept as a node
+    let unresolved = parse_source("include \"palette.icon\"");
+    assert!(matches!(&unresolved, AstNode::Scene(c) if c[0] == AstNode::Include("palette.icon".into())));
+}
+
+#[test]
+fn test_include_cycle_and_missing() {
+    let (ast, errors) = parse_with_includes("include \"a\"\ninclude \"nope\"", &[("a", "include \"b\"\nrect"), ("b", "include \"a\"\ncircle")]);
+    let kinds: Vec<_> = errors.iter().map(|e| (e.kind, e.message.as_str())).collect();
+    assert_eq!(kinds, [
+        (ErrorKind::CyclicInclude, "Cyclic include: a -> b -> a"),
+        (ErrorKind::MissingInclude, "Cannot load include 'nope'"),
+    ]);
+    let AstNode::Scene(children) = ast else { panic!("Expected Scene") };
+    assert_eq!(children.len(), 2);
+}
+
+#[test]
+fn test_parse_value() {
+    assert_eq!(Parser::parse_value("#ff0"), Ok(PropValue::Str("#ff0".into())));
+    assert_eq!(Parser::parse_value(" 50x30\n"), Ok(PropValue::Pair(50.0, 30.0)));
+    assert_eq!(Parser::parse_value("25%"), Ok(PropValue::Dim(Dimension::Percent(25.0))));
+    assert_eq!(Parser::parse_value("$accent"), Ok(PropValue::VarRef("accent".into(), 0, 0)));
+    assert_eq!(Parser::parse_value("[0,0 10,0]"), Ok(PropValue::Points(vec![(0.0, 0.0), (10.0, 0.0)])));
+}
+
+#[test]
+fn test_parse_value_errors() {
+    let errors = Parser::parse_value("#ff0 extra").unwrap_err();
+    assert_eq!(errors.len(), 1);
+    assert_eq!((errors[0].kind, errors[0].col), (ErrorKind::UnexpectedToken, 5));
+    assert_eq!(errors[0].message, "Unexpected Ident 'extra' after value");
+    assert_eq!(Parser::parse_value("").unwrap_err()[0].kind, ErrorKind::MissingToken);
+    let errors = Parser::parse_value("\"abc").unwrap_err();
+    assert_eq!(errors[0].kind, ErrorKind::UnterminatedString);
+}
+
+#[test]
+fn test_parse_scalar_lists() {
+    let nums = PropValue::List(vec![PropValue::Num(4.0), PropValue::Num(2.0), PropValue::Num(1.0)]);
+    assert_eq!(Parser::parse_value("[4 2 1]"), Ok(nums.clone()));
+    assert_eq!(Parser::parse_value("[4, 2, 1]"), Ok(nums.clone()));
+    assert_eq!(Parser::parse_value("[4,2,1]"), Ok(nums.clone()));
+    assert_eq!(Parser::parse_value(r#"["a" #f00]"#), Ok(PropValue::List(vec![PropValue::Str("a".into()), PropValue::Str("#f00".into())])));
+    let json = serde_json::to_string(&nums).unwrap();
+    assert_eq!(serde_json::from_str::<PropValue>(&json).unwrap(), nums);
+    assert_eq!(Parser::parse_value("[4 2").unwrap_err()[0].kind, ErrorKind::UnterminatedBlock);
+}
+
+#[test]
+fn test_list_dash_and_variable() {
+    let (ast, errors) = parse_with_errors("$_d = [3 1]\npath d \"M0 0 L10 0\" stroke #000\n  dash [4 2 1]");
+    assert!(errors.is_empty(), "{:?}", errors);
+    let AstNode::Scene(children) = ast else { panic!("Expected Scene") };
+    assert!(matches!(&children[0], AstNode::Variable { value: Some(TokenValue::List(items)), .. } if items.len() == 2));
+    let AstNode::Shape(s) = &children[1] else { panic!("Expected Shape") };
+    assert_eq!(s.style.dash, vec![4.0, 2.0, 1.0]);
+}
+
+#[test]
+fn test_ast_document_roundtrip() {
+    let ast = parse_source("canvas small\nrect at 1,2 #f00");
+    let json = AstDocument::new(ast.clone()).to_json();
+    assert!(json.starts_with(&format!("{{\"version\":{}", FORMAT_VERSION)));
+    assert_eq!(AstDocument::from_json(&json).unwrap().ast, ast);
+}
+
+#[test]
+fn test_ast_document_migrates_unversioned() {
+    let ast = parse_source("circle 4");
+    let bare = serde_json::to_string(&ast).unwrap();
+    assert_eq!(AstDocument::from_json(&bare).unwrap().ast, ast);
+    let legacy = format!(r#"{{"ast":{},"errors":[]}}"#, bare);
+    assert_eq!(AstDocument::from_json(&legacy).unwrap().ast, ast);
+}
+
+#[test]
+fn test_ast_document_rejects_future_version() {
+    let blob = r#"{"version":99,"ast":{"Scene":[{"Hologram":{"depth":3}}]}}"#;
+    let err = AstDocument::from_json(blob).unwrap_err();
+    assert_eq!(err.kind, ErrorKind::UnsupportedVersion);
+    assert_eq!(err.code, "E013");
+    assert!(err.message.contains("version 99 is newer than the supported version 1"), "{}", err.message);
+}
+
 // ─────────────────────────────────────────────────────────────────────────────
 // Layout System Tests
 // ─────────────────────────────────────────────────────────────────────────────
@@ -509,6 +1184,78 @@ fn test_layout_solver_percentages() {
     assert!((rect.height - 40.0).abs() < 0.001, "height = 40, got {}", rect.height);
 }
 
+#[test]
+fn test_layout_size_limits() {
+    let ast = parse_source("row width 50% max-width 120\n  rect size 10x10\n    min-width 20%");
+    let AstNode::Scene(children) = &ast else { panic!("Expected Scene") };
+    let AstNode::Shape(row) = &children[0] else { panic!("Expected Shape") };
+    assert_eq!(row.props.get("max-width"), Some(&PropValue::Dim(Dimension::Px(120.0))));
+    assert_eq!(row.children[0].props.get("min-width"), Some(&PropValue::Dim(Dimension::Percent(20.0))));
+    let rect = LayoutSolver::new().resolve(row, &mut LayoutContext::new(400.0, 100.0));
+    assert!((rect.width - 120.0).abs() < 0.001, "50% of 400 capped at 120, got {}", rect.width);
+}
+
+#[test]
+fn test_layout_child_aspect() {
+    let ast = parse_source("row size 400x200\n  stack width 160 aspect 16:9\n  stack\n    height 50%\n    aspect wide\n    max-width 150");
+    let AstNode::Scene(children) = &ast else { panic!("Expected Scene") };
+    let AstNode::Shape(row) = &children[0] else { panic!("Expected Shape") };
+    assert_eq!(row.children[0].props.get("aspect"), Some(&PropValue::Pair(16.0, 9.0)));
+    let solver = LayoutSolver::new();
+    let mut ctx = LayoutContext::new(400.0, 200.0);
+    let video = solver.resolve(&row.children[0], &mut ctx);
+    assert!((video.width - 160.0).abs() < 0.001 && (video.height - 90.0).abs() < 0.001, "{:?}", video);
+    // The derived width is still held to max-width
+    let banner = solver.resolve(&row.children[1], &mut ctx);
+    assert!((banner.height - 100.0).abs() < 0.001 && (banner.width - 150.0).abs() < 0.001, "{:?}", banner);
+
+    let (_, errors) = parse_with_errors("row\n  aspect 16.5:9");
+    assert_eq!(errors.len(), 1, "{:?}", errors);
+    assert_eq!(errors[0].message, "Invalid aspect ratio '16.5:9'");
+}
+
+#[test]
+fn test_layout_calc_dimension() {
+    let ast = parse_source("row width calc(100% - 20) height calc(50% + 10)");
+    let AstNode::Scene(children) = &ast else { panic!("Expected Scene") };
+    let AstNode::Shape(row) = &children[0] else { panic!("Expected Shape") };
+    assert_eq!(row.props.get("width"), Some(&PropValue::Dim(Dimension::Calc { percent: 100.0, px: -20.0 })));
+
+    let rect = LayoutSolver::new().resolve(row, &mut LayoutContext::new(200.0, 100.0));
+    assert!((rect.width - 180.0).abs() < 0.001, "100% of 200 - 20 = 180, got {}", rect.width);
+    assert!((rect.height - 60.0).abs() < 0.001, "50% of 100 + 10 = 60, got {}", rect.height);
+
+    let calc = Dimension::Calc { percent: 50.0, px: 10.0 };
+    assert_eq!(serde_json::from_str::<Dimension>(&serde_json::to_string(&calc).unwrap()).unwrap(), calc);
+    let (_, errors) = parse_with_errors("row width calc(100% -)");
+    assert_eq!(errors[0].message, "Invalid calc expression 'calc(100% -)'");
+}
+
+#[test]
+fn test_viewbox_percent_sizes() {
+    let style_of = |node: &AstNode| match node {
+        AstNode::Symbol(sym) => sym.children[0].style.clone(),
+        AstNode::Shape(s) => s.style.clone(),
+        other => panic!("unexpected node {:?}", other),
+    };
+    let mut ast = parse_source("symbol \"icon\" viewbox 100,100\n  text \"Hi\"\n    font \"Arial\" 5%\n    stroke #000 2%");
+    if let AstNode::Scene(children) = &ast {
+        assert_eq!(style_of(&children[0]).font_size_pct, Some(5.0));
+    }
+    resolve_viewbox_sizes(&mut ast, &LayoutContext::default());
+    let AstNode::Scene(children) = &ast else { panic!("expected scene") };
+    let style = style_of(&children[0]);
+    assert!((style.font_size - 5.0).abs() < 1e-9, "5% of 100 = 5, got {}", style.font_size);
+    assert!((style.stroke_width - 2.0).abs() < 1e-9, "2% of 100 = 2, got {}", style.stroke_width);
+    assert_eq!((style.font_size_pct, style.stroke_width_pct), (None, None));
+
+    // Top-level shapes resolve against the canvas's shorter side
+    let mut ast = parse_source("canvas medium\nrect\n  stroke #000 width 25%");
+    resolve_viewbox_sizes(&mut ast, &LayoutContext::default());
+    let AstNode::Scene(children) = &ast else { panic!("expected scene") };
+    assert!((style_of(&children[1]).stroke_width - 16.0).abs() < 1e-9);
+}
+
 #[test]
 fn test_layout_solver_center_constraint() {
     let mut shape = AstShape::new("rect");
@@ -2,9 +2,11 @@
 
 #![cfg(feature = "wasm")]
 
-use super::ast::{AstNode, ParseError};
+use super::ast::AstNode;
+use super::super::error::ParseError;
 use super::core::Parser;
-use super::symbols::resolve;
+use super::format::{AstDocument, FORMAT_VERSION};
+use super::symbols::{post_resolve, resolve};
 use serde::Serialize;
 use wasm_bindgen::prelude::*;
 
@@ -16,7 +18,8 @@ pub fn parse(source: &str) -> String {
     let mut parser = Parser::new(tokens);
     let ast = parser.parse();
     // Run resolution pass to resolve variable references
-    let result = resolve(ast);
+    let mut result = resolve(ast);
+    post_resolve(&mut result.ast);
     serde_json::to_string(&result.ast).unwrap_or_else(|_| "null".to_string())
 }
 
@@ -24,22 +27,59 @@ pub fn parse(source: &str) -> String {
 #[wasm_bindgen]
 pub fn parse_with_errors(source: &str) -> String {
     let mut lexer = super::super::lexer::Lexer::new(source);
-    let tokens = lexer.tokenize();
-    let mut parser = Parser::new(tokens);
+    let mut parser = Parser::new(lexer.tokenize()).with_geometry_warnings();
+    parser.errors.append(&mut lexer.errors);
+    parse_and_resolve(parser)
+}
+
+/// `parse_with_errors`, first inlining `include "path"` statements with the
+/// source returned by `loader(path)` (a string, or null if unavailable)
+#[wasm_bindgen]
+pub fn parse_with_includes(source: &str, loader: &js_sys::Function) -> String {
+    let mut lexer = super::super::lexer::Lexer::new(source);
+    let mut parser = Parser::new(lexer.tokenize()).with_geometry_warnings();
+    parser.errors.append(&mut lexer.errors);
+    parser.resolve_includes(|path| loader.call1(&JsValue::NULL, &JsValue::from_str(path)).ok().and_then(|v| v.as_string()));
+    parse_and_resolve(parser)
+}
+
+fn parse_and_resolve(mut parser: Parser) -> String {
     let ast = parser.parse();
     let mut errors = parser.errors;
     
     // Run resolution pass
-    let result = resolve(ast);
+    let mut result = resolve(ast);
+    post_resolve(&mut result.ast);
     errors.extend(result.errors);
     
+    parse_result_json(Some(result.ast), errors)
+}
+
+/// Diagnostics for `source` as a JSON array, without building an AST for the caller
+#[wasm_bindgen(js_name = validate)]
+pub fn validate(source: &str) -> String {
+    serde_json::to_string(&super::symbols::validate(source)).unwrap_or_else(|_| "[]".to_string())
+}
+
+/// Load persisted AST JSON (any supported format version), returning the same
+/// `{version, ast, errors}` shape as `parse_with_errors`
+#[wasm_bindgen]
+pub fn load_ast(json: &str) -> String {
+    match AstDocument::from_json(json) {
+        Ok(doc) => parse_result_json(Some(doc.ast), Vec::new()),
+        Err(e) => parse_result_json(None, vec![*e]),
+    }
+}
+
+fn parse_result_json(ast: Option<AstNode>, errors: Vec<ParseError>) -> String {
     #[derive(Serialize)]
     struct ParseResult {
-        ast: AstNode,
+        version: u32,
+        ast: Option<AstNode>,
         errors: Vec<ParseError>,
     }
     
-    serde_json::to_string(&ParseResult { ast: result.ast, errors })
+    serde_json::to_string(&ParseResult { version: FORMAT_VERSION, ast, errors })
         .unwrap_or_else(|_| r#"{"ast":null,"errors":[]}"#.to_string())
 }
 
@@ -0,0 +1,34 @@
+//! Number formatting for SVG output
+//!
+//! Renderers write numbers through `coord` so the same value prints the same
+//! way everywhere: fixed precision, then trailing zeros and a bare decimal
+//! point trimmed. The output never depends on locale and never prints `-0`.
+
+/// Decimals kept by [`num`], enough for sub-pixel geometry
+pub(crate) const NUM_PRECISION: usize = 3;
+
+/// `f` rounded to `precision` decimals, without trailing zeros
+pub fn coord(f: f64, precision: usize) -> String {
+    let s = format!("{:.*}", precision, f);
+    let s = if s.contains('.') { s.trim_end_matches('0').trim_end_matches('.') } else { &s };
+    if s == "-0" { "0".into() } else { s.into() }
+}
+
+/// `f` at the default [`NUM_PRECISION`]
+pub fn num(f: impl Into<f64>) -> String { coord(f.into(), NUM_PRECISION) }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_coord_trims_zeros() {
+        assert_eq!(coord(10.0, 2), "10");
+        assert_eq!(coord(0.5, 2), "0.5");
+        assert_eq!(coord(1.230000, 4), "1.23");
+        assert_eq!(coord(-0.0001, 2), "0");
+        assert_eq!(coord(100.0, 0), "100");
+        assert_eq!(num(0.1 + 0.2), "0.3");
+        assert_eq!(num(-2.0 / 3.0), "-0.667");
+    }
+}
@@ -48,6 +48,14 @@ impl FontMetrics {
         text.chars().map(|c| self.char_width(c)).sum::<f32>() * size
     }
 
+    /// Width with CSS `letter-spacing` added between glyphs and `word-spacing`
+    /// added per space. Both are lengths, not em fractions.
+    pub fn measure_width_spaced(&self, text: &str, size: f32, letter_spacing: f32, word_spacing: f32) -> f32 {
+        let gaps = text.chars().count().saturating_sub(1) as f32;
+        let spaces = text.chars().filter(|&c| c == ' ').count() as f32;
+        self.measure_width(text, size) + letter_spacing * gaps + word_spacing * spaces
+    }
+
     /// Measure text height at given font size  
     pub fn measure_height(&self, size: f32) -> f32 {
         (self.ascender - self.descender) * size
@@ -68,6 +76,17 @@ impl FontMetrics {
     pub fn line_height(&self, size: f32) -> f32 {
         (self.ascender - self.descender + self.line_gap) * size
     }
+
+    /// Bounds of `\n`-separated lines: widest line by stacked line heights
+    pub fn measure_multiline(&self, text: &str, size: f32) -> TextMetrics {
+        let lines = text.split('\n').count().max(1);
+        TextMetrics {
+            width: text.split('\n').map(|l| self.measure_width(l, size)).fold(0.0, f32::max),
+            height: self.line_height(size) * (lines - 1) as f32 + self.measure_height(size),
+            ascender: self.ascender * size,
+            descender: self.descender * size,
+        }
+    }
 }
 
 /// Text measurement result
@@ -171,6 +190,149 @@ pub fn measure_text(text: &str, font_family: &str, size: f32) -> TextMetrics {
     get_metrics(font_family).measure(text, size)
 }
 
+/// Vertical text alignment, mirroring SVG `dominant-baseline`
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Baseline {
+    #[default]
+    Alphabetic,
+    Middle,
+    Hanging,
+}
+
+impl Baseline {
+    pub fn from_name(s: &str) -> Option<Self> {
+        match s {
+            "alphabetic" => Some(Self::Alphabetic),
+            "middle" => Some(Self::Middle),
+            "hanging" => Some(Self::Hanging),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self { Self::Alphabetic => "alphabetic", Self::Middle => "middle", Self::Hanging => "hanging" }
+    }
+}
+
+/// Distance to move the alphabetic baseline down so text sits on `baseline` at y.
+/// `Middle` centers cap-height glyphs; `Hanging` puts the ascender at y.
+pub fn baseline_offset(metrics: &FontMetrics, size: f32, baseline: Baseline) -> f32 {
+    match baseline {
+        Baseline::Alphabetic => 0.0,
+        Baseline::Middle => metrics.cap_height * size / 2.0,
+        Baseline::Hanging => metrics.ascender * size,
+    }
+}
+
+/// Left edge of a `width`-wide line of text anchored at `x`. Right-to-left text
+/// starts at its right edge, so `start` and `end` swap sides; `middle` is the same
+/// either way.
+pub fn anchored_left(x: f32, width: f32, anchor: &str, rtl: bool) -> f32 {
+    let at_end = match anchor {
+        "middle" => return x - width / 2.0,
+        "end" => !rtl,
+        _ => rtl,
+    };
+    if at_end { x - width } else { x }
+}
+
+/// Text decoration line, mirroring SVG `text-decoration`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Decoration {
+    Underline,
+    LineThrough,
+    Overline,
+}
+
+impl Decoration {
+    pub fn from_name(s: &str) -> Option<Self> {
+        match s {
+            "underline" => Some(Self::Underline),
+            "line-through" => Some(Self::LineThrough),
+            "overline" => Some(Self::Overline),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self { Self::Underline => "underline", Self::LineThrough => "line-through", Self::Overline => "overline" }
+    }
+}
+
+/// Distance below the alphabetic baseline to draw `decoration` (negative is above).
+/// Underlines sit halfway into the descender, strikes at half the x-height and
+/// overlines on the ascender.
+pub fn decoration_offset(metrics: &FontMetrics, size: f32, decoration: Decoration) -> f32 {
+    match decoration {
+        Decoration::Underline => -metrics.descender * size / 2.0,
+        Decoration::LineThrough => -metrics.x_height * size / 2.0,
+        Decoration::Overline => -metrics.ascender * size,
+    }
+}
+
+/// Largest font size (up to `max_size`, to 0.01px) at which `text` fits a `box_w`×`box_h` box
+pub fn fit_font_size(text: &str, font_family: &str, box_w: f32, box_h: f32, max_size: f32) -> f32 {
+    let metrics = get_metrics(font_family);
+    let fits = |size: f32| {
+        let m = metrics.measure_multiline(text, size);
+        m.width <= box_w && m.height <= box_h
+    };
+    if max_size <= 0.0 || fits(max_size) { return max_size.max(0.0); }
+    let (mut lo, mut hi) = (0.0_f32, max_size);
+    while hi - lo > 0.01 {
+        let mid = (lo + hi) / 2.0;
+        if fits(mid) { lo = mid; } else { hi = mid; }
+    }
+    (lo * 100.0).floor() / 100.0
+}
+
+/// Greedy word wrap of `text` into lines no wider than `max_width`. `\n` always
+/// breaks; a word wider than the line keeps a line to itself.
+pub fn wrap_text(text: &str, font_family: &str, size: f32, max_width: f32) -> Vec<String> {
+    let metrics = get_metrics(font_family);
+    let mut lines = Vec::new();
+    for para in text.split('\n') {
+        let mut line = String::new();
+        for word in para.split_whitespace() {
+            if !line.is_empty() && metrics.measure_width(&format!("{} {}", line, word), size) > max_width {
+                lines.push(std::mem::take(&mut line));
+            }
+            if !line.is_empty() { line.push(' '); }
+            line.push_str(word);
+        }
+        lines.push(line);
+    }
+    lines
+}
+
+/// `text` cut short with a trailing `…` so it fits `max_width`; unchanged if it
+/// already fits
+pub fn truncate_text(text: &str, font_family: &str, size: f32, max_width: f32) -> String {
+    truncate_to_width(text, font_family, size, max_width, "…")
+}
+
+/// `text` with characters trimmed from the end and `ellipsis` appended until
+/// it fits `max_width`; unchanged if it already fits. When not even the
+/// ellipsis fits, only the ellipsis is left.
+pub fn truncate_to_width(text: &str, font_family: &str, size: f32, max_width: f32, ellipsis: &str) -> String {
+    let metrics = get_metrics(font_family);
+    if metrics.measure_width(text, size) <= max_width { return text.to_string(); }
+    let budget = max_width - metrics.measure_width(ellipsis, size);
+    let mut width = 0.0;
+    let kept: String = text.chars()
+        .take_while(|&c| { width += metrics.char_width(c) * size; width <= budget })
+        .collect();
+    format!("{}{}", kept.trim_end(), ellipsis)
+}
+
+/// Python binding for `truncate_to_width`
+#[cfg(feature = "python")]
+#[pyo3::pyfunction]
+#[pyo3(name = "truncate_to_width", signature = (text, font_family, size, max_width, ellipsis="…"))]
+pub fn py_truncate_to_width(text: &str, font_family: &str, size: f32, max_width: f32, ellipsis: &str) -> String {
+    truncate_to_width(text, font_family, size, max_width, ellipsis)
+}
+
 // ─────────────────────────────────────────────────────────────────────────────
 // Character Width Tables (normalized to 1em)
 // ─────────────────────────────────────────────────────────────────────────────
@@ -335,6 +497,69 @@ mod tests {
         assert_eq!(m.avg_char_width, DEFAULT_SANS_SERIF.avg_char_width);
     }
 
+    #[test]
+    fn test_measure_width_spaced() {
+        let m = get_metrics("Arial");
+        let plain = m.measure_width("Hello", 16.0);
+        assert_eq!(m.measure_width_spaced("Hello", 16.0, 0.0, 0.0), plain);
+        assert!((m.measure_width_spaced("Hello", 16.0, 2.0, 0.0) - (plain + 4.0 * 2.0)).abs() < 1e-4);
+        // Word spacing applies per space, on top of the space's own gap
+        let two = m.measure_width("a b c", 16.0);
+        assert!((m.measure_width_spaced("a b c", 16.0, 1.0, 3.0) - (two + 4.0 + 6.0)).abs() < 1e-4);
+        assert_eq!(m.measure_width_spaced("", 16.0, 5.0, 5.0), 0.0);
+    }
+
+    #[test]
+    fn test_measure_multiline() {
+        let m = get_metrics("Arial");
+        let two = m.measure_multiline("short\nmuch longer line", 10.0);
+        assert_eq!(two.width, m.measure_width("much longer line", 10.0));
+        assert!((two.height - (m.line_height(10.0) + m.measure_height(10.0))).abs() < 1e-4);
+    }
+
+    #[test]
+    fn test_fit_font_size() {
+        let short = fit_font_size("Hi", "Arial", 60.0, 20.0, 16.0);
+        let long = fit_font_size("A considerably longer label", "Arial", 60.0, 20.0, 16.0);
+        assert_eq!(short, 16.0);
+        assert!(long < short, "long={}", long);
+        assert!(measure_text("A considerably longer label", "Arial", long).width <= 60.0);
+    }
+
+    #[test]
+    fn test_wrap_and_truncate() {
+        let lines = wrap_text("Process incoming payment request", "Arial", 16.0, 100.0);
+        assert!(lines.len() >= 2, "{:?}", lines);
+        assert!(lines.iter().all(|l| measure_text(l, "Arial", 16.0).width <= 100.0 || !l.contains(' ')));
+        assert_eq!(wrap_text("a\nb", "Arial", 16.0, 500.0), vec!["a", "b"]);
+
+        let cut = truncate_text("Process incoming payment request", "Arial", 16.0, 80.0);
+        assert!(cut.ends_with('…') && measure_text(&cut, "Arial", 16.0).width <= 80.0, "{}", cut);
+        assert_eq!(truncate_text("Short", "Arial", 16.0, 80.0), "Short");
+    }
+
+    #[test]
+    fn test_truncate_to_width() {
+        let long = "Quarterly revenue summary for the northern region";
+        let cut = truncate_to_width(long, "Arial", 14.0, 120.0, "…");
+        assert!(cut.ends_with('…') && cut.len() < long.len(), "{}", cut);
+        assert!(measure_text(&cut, "Arial", 14.0).width <= 120.0, "{}", cut);
+        assert!(long.starts_with(cut.trim_end_matches('…')));
+        assert_eq!(truncate_to_width("Summary", "Arial", 14.0, 120.0, "…"), "Summary");
+        assert!(truncate_to_width(long, "Arial", 14.0, 120.0, "...").ends_with("..."));
+    }
+
+    #[test]
+    fn test_baseline_offset_centers_caps() {
+        let m = get_metrics("Arial");
+        let (cy, size) = (50.0, 20.0);
+        let baseline_y = cy + baseline_offset(m, size, Baseline::Middle);
+        let cap_top = baseline_y - m.cap_height * size;
+        assert!(((cap_top + baseline_y) / 2.0 - cy).abs() < 1e-4);
+        assert_eq!(baseline_offset(m, size, Baseline::Alphabetic), 0.0);
+        assert_eq!(Baseline::from_name("hanging").map(Baseline::as_str), Some("hanging"));
+    }
+
     #[test]
     fn test_variable_width() {
         let m = get_metrics("Arial");
@@ -8,7 +8,9 @@ use std::sync::atomic::{AtomicU64, Ordering};
 const FNV_OFFSET: u64 = 0xcbf29ce484222325;
 const FNV_PRIME: u64 = 0x100000001b3;
 
-/// Fast FNV-1a hasher for identity computation
+/// Fast FNV-1a hasher for identity computation. Hashing is streaming: any
+/// split of the same bytes across `update`/`write_*` calls gives the same
+/// `finish`, so large fields never need concatenating first.
 #[derive(Debug, Clone, Copy)]
 pub struct Fnv1a(u64);
 
@@ -17,6 +19,7 @@ impl Default for Fnv1a {
 }
 
 impl Fnv1a {
+    /// Fold `data` into the running hash
     #[inline]
     pub fn update(&mut self, data: &[u8]) {
         for &byte in data {
@@ -40,6 +43,7 @@ impl Fnv1a {
     #[inline]
     pub fn write_str(&mut self, s: &str) { self.update(s.as_bytes()); }
 
+    /// Hash of every byte fed so far
     #[inline]
     pub fn finish(self) -> u64 { self.0 }
 }
@@ -53,21 +57,25 @@ pub struct ElementId(pub u64);
 
 impl ElementId {
     /// Create identity from creation order and kind
-    pub fn new(order: u64, kind: u8) -> Self {
-        let mut h = Fnv1a::default();
-        h.write_u64(order);
-        h.write_u8(kind);
-        Self(h.finish())
-    }
+    pub fn new(order: u64, kind: u8) -> Self { Self::from_hasher(Self::hasher(order, kind)) }
 
     /// Create identity with additional key bytes
     pub fn with_key(order: u64, kind: u8, key: &[u8]) -> Self {
+        let mut h = Self::hasher(order, kind);
+        h.update(key);
+        Self::from_hasher(h)
+    }
+
+    /// Hasher seeded like [`Self::new`], for writing key properties field by
+    /// field; close it with [`Self::from_hasher`]
+    pub fn hasher(order: u64, kind: u8) -> Fnv1a {
         let mut h = Fnv1a::default();
         h.write_u64(order);
         h.write_u8(kind);
-        h.update(key);
-        Self(h.finish())
+        h
     }
+
+    pub fn from_hasher(h: Fnv1a) -> Self { Self(h.finish()) }
 }
 
 /// Content hash for detecting element changes (full property comparison)
@@ -82,6 +90,12 @@ impl ContentHash {
     }
 
     pub fn from_svg(svg: &str) -> Self { Self::from_bytes(svg.as_bytes()) }
+
+    /// Hasher for content fed in pieces; `from_hasher` of it equals
+    /// `from_bytes` of the pieces concatenated
+    pub fn hasher() -> Fnv1a { Fnv1a::default() }
+
+    pub fn from_hasher(h: Fnv1a) -> Self { Self(h.finish()) }
 }
 
 /// Monotonic ID generator for stable element ordering
@@ -126,9 +140,22 @@ pub enum ElementKind {
 }
 
 impl ElementKind {
+    /// Every kind, in discriminant order
+    pub const ALL: [ElementKind; 17] = [
+        Self::Rect, Self::Circle, Self::Ellipse, Self::Line, Self::Path, Self::Polygon, Self::Text, Self::Image,
+        Self::Group, Self::Gradient, Self::Filter, Self::Diamond, Self::Node, Self::Edge, Self::Graph, Self::Use, Self::Symbol,
+    ];
+
     pub fn as_u8(self) -> u8 { self as u8 }
-    
-    pub fn name(self) -> &'static str {
+
+    /// Kind for a DSL/SVG kind name, the inverse of [`Self::as_str`]
+    pub fn from_name(s: &str) -> Option<Self> { Self::ALL.into_iter().find(|k| k.as_str() == s) }
+
+    /// Same as [`Self::as_str`]
+    pub fn name(self) -> &'static str { self.as_str() }
+
+    /// Lowercase kind name, as the DSL spells it
+    pub fn as_str(self) -> &'static str {
         match self {
             Self::Rect => "rect",
             Self::Circle => "circle",
@@ -151,6 +178,18 @@ impl ElementKind {
     }
 }
 
+impl std::str::FromStr for ElementKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::from_name(s).ok_or_else(|| format!("unknown element kind '{}'", s))
+    }
+}
+
+impl std::fmt::Display for ElementKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result { f.write_str(self.as_str()) }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -227,6 +266,25 @@ mod tests {
         assert_ne!(h.finish(), FNV_OFFSET);
     }
 
+    #[test]
+    fn test_fnv1a_streaming_matches_whole() {
+        let d = "M0 0 C10 20 30 40 50 60 L70 80 Z".repeat(50);
+        let mut h = ContentHash::hasher();
+        for chunk in d.as_bytes().chunks(7) { h.update(chunk); }
+        assert_eq!(ContentHash::from_hasher(h), ContentHash::from_bytes(d.as_bytes()));
+
+        let mut h = ContentHash::hasher();
+        h.write_str("<path d=\"");
+        h.write_str(&d);
+        h.write_str("\"/>");
+        assert_eq!(ContentHash::from_hasher(h), ContentHash::from_svg(&format!("<path d=\"{}\"/>", d)));
+
+        let mut h = ElementId::hasher(3, ElementKind::Path.as_u8());
+        h.write_str("key");
+        h.write_str("1");
+        assert_eq!(ElementId::from_hasher(h), ElementId::with_key(3, ElementKind::Path.as_u8(), b"key1"));
+    }
+
     #[test]
     fn test_fnv1a_order_matters() {
         let mut h1 = Fnv1a::default();
@@ -403,6 +461,20 @@ mod tests {
         assert_eq!(ElementKind::Filter.name(), "filter");
     }
 
+    #[test]
+    fn test_element_kind_str_round_trip() {
+        for (i, kind) in ElementKind::ALL.into_iter().enumerate() {
+            assert_eq!(kind.as_u8() as usize, i);
+            assert_eq!(ElementKind::from_name(kind.as_str()), Some(kind));
+            assert_eq!(kind.to_string().parse::<ElementKind>(), Ok(kind));
+        }
+        assert_eq!(ElementKind::from_name("use"), Some(ElementKind::Use));
+        assert_eq!(ElementKind::from_name("Rect"), None);
+        assert_eq!(ElementKind::from_name("hexagon"), None);
+        assert_eq!(ElementKind::from_name(""), None);
+        assert_eq!("hexagon".parse::<ElementKind>(), Err("unknown element kind 'hexagon'".to_string()));
+    }
+
     #[test]
     fn test_element_kind_copy() {
         let k1 = ElementKind::Rect;
@@ -14,6 +14,7 @@
 // Core modules (always compiled)
 mod hash;
 mod dsl;
+pub mod fmt;
 pub mod font;
 pub mod path;
 
@@ -23,6 +24,16 @@ pub mod scene;
 #[cfg(any(feature = "python", feature = "bench"))]
 pub mod render;
 
+// Colors (shared by scene rendering and style interpolation)
+#[cfg(any(feature = "python", feature = "bench", feature = "wasm"))]
+pub mod color;
+
+// Edge anchor selection and SVG serialization (shared by scene rendering and the WASM bindings)
+#[cfg(any(feature = "python", feature = "bench", feature = "wasm"))]
+mod anchor;
+#[cfg(any(feature = "python", feature = "bench", feature = "wasm"))]
+mod svg;
+
 // TypeScript type export (test only)
 #[cfg(all(test, any(feature = "python", feature = "bench")))]
 mod ts_export;
@@ -44,6 +55,7 @@ use pyo3::prelude::*;
 fn iconoglott_core(_py: Python<'_>, m: &PyModule) -> PyResult<()> {
     // Canvas sizing
     m.add_class::<dsl::CanvasSize>()?;
+    m.add_class::<dsl::AspectRatio>()?;
     // Lexer & Parser (core DSL processing)
     m.add_class::<dsl::TokenType>()?;
     m.add_class::<dsl::Token>()?;
@@ -56,6 +68,7 @@ fn iconoglott_core(_py: Python<'_>, m: &PyModule) -> PyResult<()> {
     m.add_class::<dsl::ShadowDef>()?;
     m.add_class::<dsl::GradientDef>()?;
     m.add_class::<dsl::ParseError>()?;
+    m.add_class::<dsl::ErrorSeverity>()?;
     // Scene & definitions
     m.add_class::<scene::Scene>()?;
     m.add_class::<scene::Gradient>()?;
@@ -69,6 +82,8 @@ fn iconoglott_core(_py: Python<'_>, m: &PyModule) -> PyResult<()> {
     m.add_class::<scene::Polygon>()?;
     m.add_class::<scene::Text>()?;
     m.add_class::<scene::Image>()?;
+    m.add_class::<scene::Node>()?;
+    m.add_class::<scene::ElementRef>()?;
     // Utilities
     m.add_class::<scene::Style>()?;
     m.add_class::<scene::Color>()?;
@@ -77,6 +92,12 @@ fn iconoglott_core(_py: Python<'_>, m: &PyModule) -> PyResult<()> {
     m.add_function(wrap_pyfunction!(render::compute_patches, m)?)?;
     m.add_function(wrap_pyfunction!(render::needs_redraw, m)?)?;
     m.add_function(wrap_pyfunction!(render::index_scene, m)?)?;
+    // Text layout
+    m.add_function(wrap_pyfunction!(font::py_truncate_to_width, m)?)?;
+    // Full pipeline
+    m.add_function(wrap_pyfunction!(dsl::py_validate, m)?)?;
+    m.add_function(wrap_pyfunction!(scene::py_compile, m)?)?;
+    m.add_function(wrap_pyfunction!(scene::py_compile_with_diagnostics, m)?)?;
     Ok(())
 }
 
@@ -88,16 +109,16 @@ fn iconoglott_core(_py: Python<'_>, m: &PyModule) -> PyResult<()> {
 pub use hash::{ContentHash, ElementId, ElementKind, Fnv1a, IdGen};
 
 // Font metrics (always available)
-pub use font::{get_metrics, measure_text, FontMetrics, TextMetrics};
+pub use font::{baseline_offset, fit_font_size, get_metrics, Baseline, measure_text, truncate_text, truncate_to_width, wrap_text, FontMetrics, TextMetrics};
 
 // Path utilities and boolean operations (always available)
-pub use path::{parse_path_bounds, BoolOp, BoolResult, Polygon as BoolPolygon, path_boolean, flatten_path};
+pub use path::{bounding_circle, exact_bounds, label_anchor, parse_path_bounds, BoolOp, BoolResult, Polygon as BoolPolygon, path_boolean, flatten_contours, flatten_path, morph as path_morph, normalize as path_normalize, reverse as path_reverse};
 
 // Lexer & Parser (always available) - re-export from dsl module
 pub use dsl::{
-    AstCanvas, AstGraph, AstNode, AstShape, AstStyle, AstTransform, CanvasSize,
+    AspectRatio, AstCanvas, AstDocument, AstGraph, AstNode, AstShape, AstStyle, AstTransform, CanvasSize,
     ErrorKind, ErrorSeverity, FullStyle, GradientDef, GraphEdge, GraphNode,
-    Lexer, ParseError, Parser, PropValue, ShadowDef, Span,
+    validate, Lexer, LexerOptions, ParseError, Parser, PropValue, ShadowDef, Span, FORMAT_VERSION,
     Token, TokenType, TokenValue,
     // Animation primitives
     Animation, AnimationState, AnimatableProperty, Direction, Duration,
@@ -111,14 +132,17 @@ pub mod parser { pub use crate::dsl::*; }
 pub mod id { pub use crate::hash::*; }
 
 #[cfg(any(feature = "python", feature = "bench"))]
-pub use render::{CommandHistory, DiffOp, DiffResult, IndexedScene, SceneCommand};
+pub use render::{collect_defs, render_dsl_to_svg, with_id_prefix, CommandHistory, DiffOp, DiffResult, IndexedScene, SceneCommand};
 
 #[cfg(any(feature = "python", feature = "bench"))]
 pub use scene::{
-    ArrowType, Circle, Color, Diamond, Edge, EdgeStyle, Element, Ellipse,
+    ArrowType, Circle, Color, Diagnostic, Diamond, Edge, EdgeStyle, Element, Ellipse,
     Filter, Gradient, GraphContainer, Image, Line, Node, Path, Polygon,
-    Rect, Scene, SceneKeyframes, Style, Symbol, Text, Use,
+    Rect, RenderOptions, Scene, SceneKeyframes, Style, Symbol, Text, Use,
+    build_scene, compile, shape_boolean,
 };
+#[cfg(all(feature = "timings", any(feature = "python", feature = "bench")))]
+pub use scene::{compile_timed, PipelineTimings};
 
 // Shape module alias for compatibility
 #[cfg(any(feature = "python", feature = "bench"))]
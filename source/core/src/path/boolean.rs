@@ -5,6 +5,7 @@
 
 use std::cmp::Ordering;
 use std::collections::BinaryHeap;
+use crate::fmt::coord;
 
 /// Floating point comparison tolerance
 const EPS: f64 = 1e-10;
@@ -165,6 +166,35 @@ impl Polygon {
         area * 0.5
     }
     
+    /// Area-weighted centroid; degenerate (zero-area) outlines fall back to
+    /// the mean of their vertices
+    pub fn centroid(&self) -> Point {
+        let n = self.vertices.len();
+        let area = self.signed_area();
+        if area.abs() < EPS {
+            if n == 0 { return Point::default(); }
+            return self.vertices.iter().fold(Point::default(), |acc, &v| acc.add(v)).scale(1.0 / n as f64);
+        }
+        let mut c = Point::default();
+        for i in 0..n {
+            let (a, b) = (self.vertices[i], self.vertices[(i + 1) % n]);
+            c = c.add(a.add(b).scale(a.cross(b)));
+        }
+        c.scale(1.0 / (6.0 * area))
+    }
+
+    /// Where to center a label: the centroid, or the bounds center when a
+    /// non-convex outline puts the centroid outside the shape
+    pub fn label_anchor(&self) -> Point {
+        let c = self.centroid();
+        if self.vertices.len() < 3 || self.contains(c) { return c; }
+        let (min, max) = self.vertices.iter().fold(
+            (Point::new(f64::MAX, f64::MAX), Point::new(f64::MIN, f64::MIN)),
+            |(lo, hi), v| (Point::new(lo.x.min(v.x), lo.y.min(v.y)), Point::new(hi.x.max(v.x), hi.y.max(v.y))),
+        );
+        min.add(max).scale(0.5)
+    }
+
     /// Check if polygon is counter-clockwise
     pub fn is_ccw(&self) -> bool { self.signed_area() > 0.0 }
     
@@ -339,15 +369,21 @@ pub struct BoolResult {
 }
 
 impl BoolResult {
-    /// Convert to SVG path data
+    /// Whether any contour cuts a hole in another
+    pub fn has_holes(&self) -> bool { self.contours.iter().any(|c| c.is_hole) }
+
+    /// Convert to SVG path data. Outer contours and holes are wound in opposite
+    /// directions, so holes stay empty under either `fill-rule`.
     pub fn to_path_d(&self) -> String {
         self.contours.iter()
             .filter(|c| c.vertices.len() >= 3)
             .map(|c| {
+                let mut c = c.clone();
+                c.normalize();
                 let mut d = String::new();
                 for (i, p) in c.vertices.iter().enumerate() {
-                    if i == 0 { d.push_str(&format!("M{:.4} {:.4}", p.x, p.y)); }
-                    else { d.push_str(&format!(" L{:.4} {:.4}", p.x, p.y)); }
+                    let cmd = if i == 0 { "M" } else { " L" };
+                    d.push_str(&format!("{}{} {}", cmd, coord(p.x, 4), coord(p.y, 4)));
                 }
                 d.push_str(" Z");
                 d
@@ -366,7 +402,7 @@ pub struct PolygonClipper {
 
 impl PolygonClipper {
     pub fn new(subject: Polygon, clip: Polygon) -> Self {
-        Self { subject, clip }
+        Self { subject: without_repeats(subject), clip: without_repeats(clip) }
     }
     
     /// Perform boolean operation
@@ -375,96 +411,37 @@ impl PolygonClipper {
             return BoolResult::default();
         }
         
-        // Use Sutherland-Hodgman for simple convex clipping cases
-        // For general polygons, use Weiler-Atherton or sweep-line based approach
         match op {
-            BoolOp::Intersection => self.intersection(),
-            BoolOp::Union => self.union(),
-            BoolOp::Difference => self.difference(),
             BoolOp::Xor => self.xor(),
+            _ => self.greiner_hormann(op),
         }
     }
-    
-    fn intersection(&self) -> BoolResult {
-        // Sutherland-Hodgman for convex clip polygon
-        let mut output = self.subject.vertices.clone();
-        
-        let n = self.clip.vertices.len();
-        for i in 0..n {
-            if output.is_empty() { break; }
-            
-            let edge_start = self.clip.vertices[i];
-            let edge_end = self.clip.vertices[(i + 1) % n];
-            
-            let input = std::mem::take(&mut output);
-            let m = input.len();
-            
-            for j in 0..m {
-                let current = input[j];
-                let next = input[(j + 1) % m];
-                
-                let curr_inside = is_left(edge_start, edge_end, current);
-                let next_inside = is_left(edge_start, edge_end, next);
-                
-                if curr_inside {
-                    output.push(current);
-                    if !next_inside {
-                        if let Some(pt) = line_intersection(edge_start, edge_end, current, next) {
-                            output.push(pt);
-                        }
-                    }
-                } else if next_inside {
-                    if let Some(pt) = line_intersection(edge_start, edge_end, current, next) {
-                        output.push(pt);
-                    }
-                }
-            }
-        }
-        
-        let mut result = BoolResult::default();
-        if output.len() >= 3 {
-            result.contours.push(Polygon::new(output));
-        }
-        result
-    }
-    
-    fn union(&self) -> BoolResult {
-        // For union, we need to trace the outer boundary
-        // Use Weiler-Atherton approach
-        self.weiler_atherton(BoolOp::Union)
-    }
-    
-    fn difference(&self) -> BoolResult {
-        self.weiler_atherton(BoolOp::Difference)
-    }
-    
+
     fn xor(&self) -> BoolResult {
         // XOR = (A - B) ∪ (B - A)
-        let a_minus_b = self.weiler_atherton(BoolOp::Difference);
+        let a_minus_b = self.greiner_hormann(BoolOp::Difference);
         let clipper_rev = PolygonClipper::new(self.clip.clone(), self.subject.clone());
-        let b_minus_a = clipper_rev.weiler_atherton(BoolOp::Difference);
-        
+        let b_minus_a = clipper_rev.greiner_hormann(BoolOp::Difference);
+
         BoolResult {
             contours: a_minus_b.contours.into_iter()
                 .chain(b_minus_a.contours)
                 .collect(),
         }
     }
-    
-    /// Weiler-Atherton polygon clipping
-    fn weiler_atherton(&self, op: BoolOp) -> BoolResult {
-        // Find all intersection points
+
+    /// Greiner-Hormann clipping for intersection, union and difference
+    fn greiner_hormann(&self, op: BoolOp) -> BoolResult {
         let intersections = self.find_edge_intersections();
-        
+
         if intersections.is_empty() {
             // No intersections - check containment
             return self.handle_no_intersections(op);
         }
-        
-        // Build intersection graph and trace contours
+
         self.trace_contours(&intersections, op)
     }
-    
+
     fn find_edge_intersections(&self) -> Vec<IntersectionPoint> {
         let mut intersections = Vec::new();
         
@@ -481,14 +458,12 @@ impl PolygonClipper {
                 
                 if let Some((pt, t_s, t_c)) = line_intersection_params(s0, s1, c0, c1) {
                     if t_s > EPS && t_s < 1.0 - EPS && t_c > EPS && t_c < 1.0 - EPS {
-                        let entering = is_entering(s0, s1, c0, c1);
                         intersections.push(IntersectionPoint {
                             point: pt,
                             subj_edge: i,
                             clip_edge: j,
                             subj_t: t_s,
                             clip_t: t_c,
-                            entering,
                         });
                     }
                 }
@@ -565,31 +540,48 @@ impl PolygonClipper {
         
         // Link intersection vertices between lists
         self.link_intersections(&mut subj_verts, &mut clip_verts, intersections);
-        
-        // Trace contours based on operation
+
+        // Entry flags alternate along each outline, starting from whether its first
+        // (original) vertex lies inside the other polygon. Union walks the outside of
+        // both; difference walks the outside of the subject and the inside of the clip.
+        let (flip_subj, flip_clip) = match op {
+            BoolOp::Union => (true, true),
+            BoolOp::Difference => (true, false),
+            _ => (false, false),
+        };
+        mark_entries(&mut subj_verts, &self.clip, flip_subj);
+        mark_entries(&mut clip_verts, &self.subject, flip_clip);
+
         let mut result = BoolResult::default();
-        let mut visited = vec![false; subj_verts.len()];
-        
-        for start_idx in 0..subj_verts.len() {
-            if visited[start_idx] || !subj_verts[start_idx].is_intersection { continue; }
-            
-            let should_start = match op {
-                BoolOp::Union => !subj_verts[start_idx].entering,
-                BoolOp::Intersection | BoolOp::Difference => subj_verts[start_idx].entering,
-                BoolOp::Xor => true,
-            };
-            
-            if !should_start { continue; }
-            
-            if let Some(contour) = self.trace_single_contour(
-                &subj_verts, &clip_verts, start_idx, op, &mut visited
-            ) {
-                if contour.vertices.len() >= 3 {
-                    result.contours.push(contour);
+        let mut visited = [vec![false; subj_verts.len()], vec![false; clip_verts.len()]];
+        let lists = [&subj_verts, &clip_verts];
+
+        for start in 0..subj_verts.len() {
+            if visited[0][start] || !subj_verts[start].is_intersection { continue; }
+
+            let mut contour = vec![subj_verts[start].point];
+            let (mut list, mut idx) = (0, start);
+            // Each intersection is left once per contour, so this bounds the walk
+            for _ in 0..=intersections.len() {
+                let verts = lists[list];
+                visited[list][idx] = true;
+                let other = verts[idx].other_idx.unwrap_or(idx);
+                visited[1 - list][other] = true;
+                let forward = verts[idx].entering;
+                loop {
+                    idx = if forward { verts[idx].next } else { verts[idx].prev }.unwrap_or(idx);
+                    contour.push(verts[idx].point);
+                    if verts[idx].is_intersection { break; }
                 }
+                idx = verts[idx].other_idx.unwrap_or(idx);
+                list = 1 - list;
+                if visited[list][idx] { break; }
             }
+            // The walk ends back on the start point
+            if contour.len() > 1 && contour[0] == contour[contour.len() - 1] { contour.pop(); }
+            if contour.len() >= 3 { result.contours.push(Polygon::new(contour)); }
         }
-        
+
         result
     }
     
@@ -626,7 +618,7 @@ impl PolygonClipper {
                 verts.push(Vertex {
                     point: ip.point,
                     is_intersection: true,
-                    entering: if is_subject { ip.entering } else { !ip.entering },
+                    entering: false,
                     other_idx: Some(int_idx),
                     next: None,
                     prev: None,
@@ -645,90 +637,40 @@ impl PolygonClipper {
     }
     
     fn link_intersections(&self, subj: &mut [Vertex], clip: &mut [Vertex], intersections: &[IntersectionPoint]) {
-        for (int_idx, _) in intersections.iter().enumerate() {
-            let subj_idx = subj.iter().position(|v| v.other_idx == Some(int_idx));
-            let clip_idx = clip.iter().position(|v| v.other_idx == Some(int_idx));
-            
-            if let (Some(si), Some(ci)) = (subj_idx, clip_idx) {
+        // Both lists tag intersections by their index in `intersections`; find every
+        // position before rewriting any tag to a list index
+        let positions = |verts: &[Vertex]| {
+            let mut at = vec![None; intersections.len()];
+            for (i, v) in verts.iter().enumerate() {
+                if let (true, Some(k)) = (v.is_intersection, v.other_idx) { at[k] = Some(i); }
+            }
+            at
+        };
+        let (subj_at, clip_at) = (positions(subj), positions(clip));
+        for (si, ci) in subj_at.into_iter().zip(clip_at) {
+            if let (Some(si), Some(ci)) = (si, ci) {
                 subj[si].other_idx = Some(ci);
                 clip[ci].other_idx = Some(si);
             }
         }
     }
-    
-    fn trace_single_contour(
-        &self,
-        subj: &[Vertex],
-        clip: &[Vertex],
-        start: usize,
-        op: BoolOp,
-        visited: &mut [bool],
-    ) -> Option<Polygon> {
-        let mut contour = Vec::new();
-        let mut on_subject = true;
-        let mut idx = start;
-        let mut iterations = 0;
-        let max_iterations = subj.len() + clip.len() + 100;
-        
-        loop {
-            if iterations > max_iterations { break; }
-            iterations += 1;
-            
-            let verts = if on_subject { subj } else { clip };
-            if idx >= verts.len() { break; }
-            
-            let v = &verts[idx];
-            contour.push(v.point);
-            
-            if on_subject && idx < visited.len() {
-                visited[idx] = true;
-            }
-            
-            if v.is_intersection {
-                // Switch between subject and clip
-                let switch = match op {
-                    BoolOp::Intersection => v.entering == on_subject,
-                    BoolOp::Union => v.entering != on_subject,
-                    BoolOp::Difference => on_subject == v.entering,
-                    BoolOp::Xor => true,
-                };
-                
-                if switch {
-                    if let Some(other) = v.other_idx {
-                        on_subject = !on_subject;
-                        idx = other;
-                        let next_verts = if on_subject { subj } else { clip };
-                        if idx < next_verts.len() {
-                            if let Some(n) = next_verts[idx].next {
-                                idx = n;
-                            }
-                        }
-                        
-                        if on_subject && idx == start {
-                            break;
-                        }
-                        continue;
-                    }
-                }
-            }
-            
-            // Move to next vertex
-            if let Some(n) = v.next {
-                idx = n;
-            } else {
-                break;
-            }
-            
-            if on_subject && idx == start {
-                break;
-            }
-        }
-        
-        if contour.len() >= 3 {
-            Some(Polygon::new(contour))
-        } else {
-            None
-        }
+}
+
+/// Drop repeated consecutive vertices, including a closing copy of the first,
+/// so every edge has length
+fn without_repeats(mut poly: Polygon) -> Polygon {
+    poly.vertices.dedup();
+    while poly.vertices.len() > 1 && poly.vertices[0] == poly.vertices[poly.vertices.len() - 1] { poly.vertices.pop(); }
+    poly
+}
+
+/// Set each intersection's entry flag by walking the outline: crossing into
+/// `other` enters it. `flip` inverts the flags to trace the outside instead.
+fn mark_entries(verts: &mut [Vertex], other: &Polygon, flip: bool) {
+    let mut inside = verts.first().is_some_and(|v| other.contains(v.point));
+    for v in verts.iter_mut().filter(|v| v.is_intersection) {
+        v.entering = inside == flip;
+        inside = !inside;
     }
 }
 
@@ -737,6 +679,7 @@ impl PolygonClipper {
 struct Vertex {
     point: Point,
     is_intersection: bool,
+    /// Whether the outline enters the other polygon here (so tracing runs forward)
     entering: bool,
     other_idx: Option<usize>, // Index in other polygon's vertex list
     next: Option<usize>,
@@ -751,20 +694,6 @@ struct IntersectionPoint {
     clip_edge: usize,
     subj_t: f64,
     clip_t: f64,
-    entering: bool,
-}
-
-/// Check if point is on left side of edge (CCW)
-fn is_left(edge_start: Point, edge_end: Point, p: Point) -> bool {
-    let edge = edge_end.sub(edge_start);
-    let to_p = p.sub(edge_start);
-    edge.cross(to_p) >= 0.0
-}
-
-/// Compute line intersection point
-fn line_intersection(a0: Point, a1: Point, b0: Point, b1: Point) -> Option<Point> {
-    let (pt, t, _) = line_intersection_params(a0, a1, b0, b1)?;
-    if t >= 0.0 && t <= 1.0 { Some(pt) } else { None }
 }
 
 /// Compute line intersection with parameters
@@ -782,232 +711,181 @@ fn line_intersection_params(a0: Point, a1: Point, b0: Point, b1: Point) -> Optio
     Some((a0.add(da.scale(t)), t, u))
 }
 
-/// Check if subject edge is entering clip polygon at intersection
-fn is_entering(s0: Point, s1: Point, c0: Point, c1: Point) -> bool {
-    let clip_edge = c1.sub(c0);
-    let subj_dir = s1.sub(s0);
-    // Subject enters clip if subject direction points left of clip edge
-    clip_edge.cross(subj_dir) > 0.0
+/// Flatten SVG path data to line segments.
+///
+/// Each command consumes its own arguments in fixed-size sets, so a command
+/// with too few numbers is dropped without shifting the ones that follow, and
+/// extra sets after one letter repeat the command as SVG specifies (extra sets
+/// after a moveto are linetos).
+///
+/// Every subpath lands in the one vertex list; see [`flatten_contours`] to keep
+/// them apart.
+pub fn flatten_path(d: &str, tolerance: f64) -> Polygon {
+    let mut vertices: Vec<Point> = Vec::new();
+    for contour in flatten_contours(d, tolerance) {
+        // A contour opened after `Z` starts where the closed one ended
+        let skip = usize::from(vertices.last() == contour.vertices.first());
+        vertices.extend(contour.vertices.into_iter().skip(skip));
+    }
+    Polygon::new(vertices)
 }
 
-/// Flatten SVG path data to line segments
-pub fn flatten_path(d: &str, tolerance: f64) -> Polygon {
+/// Flatten SVG path data to one polygon per subpath.
+///
+/// A subpath begins at each moveto, and a drawing command after `Z` opens a
+/// fresh one at the closed subpath's start point, as SVG specifies.
+pub fn flatten_contours(d: &str, tolerance: f64) -> Vec<Polygon> {
+    let mut contours = Vec::new();
     let mut vertices = Vec::new();
-    let (mut cur_x, mut cur_y) = (0.0, 0.0);
-    let (mut start_x, mut start_y) = (0.0, 0.0);
-    let (mut last_ctrl_x, mut last_ctrl_y) = (0.0, 0.0);
+    let mut cur = Point::new(0.0, 0.0);
+    let mut start = cur;
+    let mut last_ctrl = cur;
     let mut last_cmd = ' ';
-    
-    let nums = extract_numbers_f64(d);
-    let cmds: Vec<char> = d.chars()
-        .filter(|c| matches!(c, 'M'|'m'|'L'|'l'|'H'|'h'|'V'|'v'|'C'|'c'|'S'|'s'|'Q'|'q'|'T'|'t'|'A'|'a'|'Z'|'z'))
-        .collect();
-    let mut idx = 0;
-    
-    for cmd in cmds {
-        match cmd {
-            'M' if idx + 1 < nums.len() => {
-                cur_x = nums[idx]; cur_y = nums[idx + 1];
-                start_x = cur_x; start_y = cur_y;
-                vertices.push(Point::new(cur_x, cur_y));
-                idx += 2;
-                last_ctrl_x = cur_x; last_ctrl_y = cur_y;
-            }
-            'm' if idx + 1 < nums.len() => {
-                cur_x += nums[idx]; cur_y += nums[idx + 1];
-                start_x = cur_x; start_y = cur_y;
-                vertices.push(Point::new(cur_x, cur_y));
-                idx += 2;
-                last_ctrl_x = cur_x; last_ctrl_y = cur_y;
-            }
-            'L' if idx + 1 < nums.len() => {
-                cur_x = nums[idx]; cur_y = nums[idx + 1];
-                vertices.push(Point::new(cur_x, cur_y));
-                idx += 2;
-                last_ctrl_x = cur_x; last_ctrl_y = cur_y;
-            }
-            'l' if idx + 1 < nums.len() => {
-                cur_x += nums[idx]; cur_y += nums[idx + 1];
-                vertices.push(Point::new(cur_x, cur_y));
-                idx += 2;
-                last_ctrl_x = cur_x; last_ctrl_y = cur_y;
-            }
-            'H' if idx < nums.len() => {
-                cur_x = nums[idx];
-                vertices.push(Point::new(cur_x, cur_y));
-                idx += 1;
-                last_ctrl_x = cur_x; last_ctrl_y = cur_y;
-            }
-            'h' if idx < nums.len() => {
-                cur_x += nums[idx];
-                vertices.push(Point::new(cur_x, cur_y));
-                idx += 1;
-                last_ctrl_x = cur_x; last_ctrl_y = cur_y;
-            }
-            'V' if idx < nums.len() => {
-                cur_y = nums[idx];
-                vertices.push(Point::new(cur_x, cur_y));
-                idx += 1;
-                last_ctrl_x = cur_x; last_ctrl_y = cur_y;
-            }
-            'v' if idx < nums.len() => {
-                cur_y += nums[idx];
-                vertices.push(Point::new(cur_x, cur_y));
-                idx += 1;
-                last_ctrl_x = cur_x; last_ctrl_y = cur_y;
-            }
-            'C' if idx + 5 < nums.len() => {
-                let pts = flatten_cubic(
-                    Point::new(cur_x, cur_y),
-                    Point::new(nums[idx], nums[idx+1]),
-                    Point::new(nums[idx+2], nums[idx+3]),
-                    Point::new(nums[idx+4], nums[idx+5]),
-                    tolerance,
-                );
-                vertices.extend(pts.into_iter().skip(1));
-                last_ctrl_x = nums[idx+2]; last_ctrl_y = nums[idx+3];
-                cur_x = nums[idx+4]; cur_y = nums[idx+5];
-                idx += 6;
-            }
-            'c' if idx + 5 < nums.len() => {
-                let pts = flatten_cubic(
-                    Point::new(cur_x, cur_y),
-                    Point::new(cur_x + nums[idx], cur_y + nums[idx+1]),
-                    Point::new(cur_x + nums[idx+2], cur_y + nums[idx+3]),
-                    Point::new(cur_x + nums[idx+4], cur_y + nums[idx+5]),
-                    tolerance,
-                );
-                vertices.extend(pts.into_iter().skip(1));
-                last_ctrl_x = cur_x + nums[idx+2]; last_ctrl_y = cur_y + nums[idx+3];
-                cur_x += nums[idx+4]; cur_y += nums[idx+5];
-                idx += 6;
-            }
-            'S' if idx + 3 < nums.len() => {
-                let (x1, y1) = if matches!(last_cmd, 'C'|'c'|'S'|'s') {
-                    (2.0 * cur_x - last_ctrl_x, 2.0 * cur_y - last_ctrl_y)
-                } else { (cur_x, cur_y) };
-                let pts = flatten_cubic(
-                    Point::new(cur_x, cur_y),
-                    Point::new(x1, y1),
-                    Point::new(nums[idx], nums[idx+1]),
-                    Point::new(nums[idx+2], nums[idx+3]),
-                    tolerance,
-                );
-                vertices.extend(pts.into_iter().skip(1));
-                last_ctrl_x = nums[idx]; last_ctrl_y = nums[idx+1];
-                cur_x = nums[idx+2]; cur_y = nums[idx+3];
-                idx += 4;
-            }
-            's' if idx + 3 < nums.len() => {
-                let (x1, y1) = if matches!(last_cmd, 'C'|'c'|'S'|'s') {
-                    (2.0 * cur_x - last_ctrl_x, 2.0 * cur_y - last_ctrl_y)
-                } else { (cur_x, cur_y) };
-                let pts = flatten_cubic(
-                    Point::new(cur_x, cur_y),
-                    Point::new(x1, y1),
-                    Point::new(cur_x + nums[idx], cur_y + nums[idx+1]),
-                    Point::new(cur_x + nums[idx+2], cur_y + nums[idx+3]),
-                    tolerance,
-                );
-                vertices.extend(pts.into_iter().skip(1));
-                last_ctrl_x = cur_x + nums[idx]; last_ctrl_y = cur_y + nums[idx+1];
-                cur_x += nums[idx+2]; cur_y += nums[idx+3];
-                idx += 4;
-            }
-            'Q' if idx + 3 < nums.len() => {
-                let pts = flatten_quadratic(
-                    Point::new(cur_x, cur_y),
-                    Point::new(nums[idx], nums[idx+1]),
-                    Point::new(nums[idx+2], nums[idx+3]),
-                    tolerance,
-                );
-                vertices.extend(pts.into_iter().skip(1));
-                last_ctrl_x = nums[idx]; last_ctrl_y = nums[idx+1];
-                cur_x = nums[idx+2]; cur_y = nums[idx+3];
-                idx += 4;
-            }
-            'q' if idx + 3 < nums.len() => {
-                let pts = flatten_quadratic(
-                    Point::new(cur_x, cur_y),
-                    Point::new(cur_x + nums[idx], cur_y + nums[idx+1]),
-                    Point::new(cur_x + nums[idx+2], cur_y + nums[idx+3]),
-                    tolerance,
-                );
-                vertices.extend(pts.into_iter().skip(1));
-                last_ctrl_x = cur_x + nums[idx]; last_ctrl_y = cur_y + nums[idx+1];
-                cur_x += nums[idx+2]; cur_y += nums[idx+3];
-                idx += 4;
-            }
-            'T' if idx + 1 < nums.len() => {
-                let (x1, y1) = if matches!(last_cmd, 'Q'|'q'|'T'|'t') {
-                    (2.0 * cur_x - last_ctrl_x, 2.0 * cur_y - last_ctrl_y)
-                } else { (cur_x, cur_y) };
-                let pts = flatten_quadratic(
-                    Point::new(cur_x, cur_y),
-                    Point::new(x1, y1),
-                    Point::new(nums[idx], nums[idx+1]),
-                    tolerance,
-                );
-                vertices.extend(pts.into_iter().skip(1));
-                last_ctrl_x = x1; last_ctrl_y = y1;
-                cur_x = nums[idx]; cur_y = nums[idx+1];
-                idx += 2;
-            }
-            't' if idx + 1 < nums.len() => {
-                let (x1, y1) = if matches!(last_cmd, 'Q'|'q'|'T'|'t') {
-                    (2.0 * cur_x - last_ctrl_x, 2.0 * cur_y - last_ctrl_y)
-                } else { (cur_x, cur_y) };
-                let pts = flatten_quadratic(
-                    Point::new(cur_x, cur_y),
-                    Point::new(x1, y1),
-                    Point::new(cur_x + nums[idx], cur_y + nums[idx+1]),
-                    tolerance,
-                );
-                vertices.extend(pts.into_iter().skip(1));
-                last_ctrl_x = x1; last_ctrl_y = y1;
-                cur_x += nums[idx]; cur_y += nums[idx+1];
-                idx += 2;
-            }
-            'A' if idx + 6 < nums.len() => {
-                let pts = flatten_arc(
-                    Point::new(cur_x, cur_y),
-                    nums[idx].abs(), nums[idx+1].abs(), nums[idx+2],
-                    nums[idx+3] != 0.0, nums[idx+4] != 0.0,
-                    Point::new(nums[idx+5], nums[idx+6]),
-                    tolerance,
-                );
-                vertices.extend(pts.into_iter().skip(1));
-                cur_x = nums[idx+5]; cur_y = nums[idx+6];
-                idx += 7;
-                last_ctrl_x = cur_x; last_ctrl_y = cur_y;
-            }
-            'a' if idx + 6 < nums.len() => {
-                let end = Point::new(cur_x + nums[idx+5], cur_y + nums[idx+6]);
-                let pts = flatten_arc(
-                    Point::new(cur_x, cur_y),
-                    nums[idx].abs(), nums[idx+1].abs(), nums[idx+2],
-                    nums[idx+3] != 0.0, nums[idx+4] != 0.0,
-                    end,
-                    tolerance,
-                );
-                vertices.extend(pts.into_iter().skip(1));
-                cur_x = end.x; cur_y = end.y;
-                idx += 7;
-                last_ctrl_x = cur_x; last_ctrl_y = cur_y;
+
+    for (cmd, args) in path_commands(d) {
+        let upper = cmd.to_ascii_uppercase();
+        if upper == 'Z' {
+            if (cur.x - start.x).abs() > EPS || (cur.y - start.y).abs() > EPS {
+                vertices.push(start);
             }
-            'Z' | 'z' => {
-                if (cur_x - start_x).abs() > EPS || (cur_y - start_y).abs() > EPS {
-                    vertices.push(Point::new(start_x, start_y));
+            if !vertices.is_empty() { contours.push(Polygon::new(std::mem::take(&mut vertices))); }
+            cur = start;
+            last_ctrl = cur;
+            last_cmd = cmd;
+            continue;
+        }
+        for (k, a) in args.chunks_exact(arity(upper)).enumerate() {
+            // Sets after the first of a moveto are implicit linetos of the same case
+            let (cmd, upper) = match (cmd, k) {
+                ('M', 1..) => ('L', 'L'),
+                ('m', 1..) => ('l', 'L'),
+                _ => (cmd, upper),
+            };
+            // Relative commands offset every coordinate by the current point
+            let o = if cmd.is_ascii_lowercase() { cur } else { Point::new(0.0, 0.0) };
+            let at = |i: usize| Point::new(o.x + a[i], o.y + a[i + 1]);
+            if upper != 'M' && vertices.is_empty() && !contours.is_empty() { vertices.push(cur); }
+            match upper {
+                'M' => {
+                    if !vertices.is_empty() { contours.push(Polygon::new(std::mem::take(&mut vertices))); }
+                    cur = at(0);
+                    start = cur;
+                    vertices.push(cur);
+                    last_ctrl = cur;
+                }
+                'L' => {
+                    cur = at(0);
+                    vertices.push(cur);
+                    last_ctrl = cur;
+                }
+                'H' => {
+                    cur.x = o.x + a[0];
+                    vertices.push(cur);
+                    last_ctrl = cur;
                 }
-                cur_x = start_x; cur_y = start_y;
-                last_ctrl_x = cur_x; last_ctrl_y = cur_y;
+                'V' => {
+                    cur.y = o.y + a[0];
+                    vertices.push(cur);
+                    last_ctrl = cur;
+                }
+                'C' | 'S' => {
+                    let (c1, c2, end) = if upper == 'C' {
+                        (at(0), at(2), at(4))
+                    } else if matches!(last_cmd, 'C'|'c'|'S'|'s') {
+                        (cur.scale(2.0).sub(last_ctrl), at(0), at(2))
+                    } else {
+                        (cur, at(0), at(2))
+                    };
+                    vertices.extend(flatten_cubic(cur, c1, c2, end, tolerance).into_iter().skip(1));
+                    last_ctrl = c2;
+                    cur = end;
+                }
+                'Q' | 'T' => {
+                    let (ctrl, end) = if upper == 'Q' {
+                        (at(0), at(2))
+                    } else if matches!(last_cmd, 'Q'|'q'|'T'|'t') {
+                        (cur.scale(2.0).sub(last_ctrl), at(0))
+                    } else {
+                        (cur, at(0))
+                    };
+                    vertices.extend(flatten_quadratic(cur, ctrl, end, tolerance).into_iter().skip(1));
+                    last_ctrl = ctrl;
+                    cur = end;
+                }
+                'A' => {
+                    let end = at(5);
+                    let pts = flatten_arc(cur, a[0].abs(), a[1].abs(), a[2], a[3] != 0.0, a[4] != 0.0, end, tolerance);
+                    vertices.extend(pts.into_iter().skip(1));
+                    cur = end;
+                    last_ctrl = cur;
+                }
+                _ => {}
             }
-            _ => {}
+            last_cmd = cmd;
         }
-        last_cmd = cmd;
     }
-    
-    Polygon::new(vertices)
+
+    if !vertices.is_empty() { contours.push(Polygon::new(vertices)); }
+    contours
+}
+
+/// Number of arguments in one set for an (uppercase) path command
+pub(crate) fn arity(cmd: char) -> usize {
+    match cmd {
+        'M' | 'L' | 'T' => 2,
+        'H' | 'V' => 1,
+        'S' | 'Q' => 4,
+        'C' => 6,
+        'A' => 7,
+        _ => 0,
+    }
+}
+
+/// Split path data into commands, each with the numbers written after it up
+/// to the next command letter. Numbers before the first command and unknown
+/// letters are dropped.
+pub(crate) fn path_commands(d: &str) -> Vec<(char, Vec<f64>)> {
+    let mut out: Vec<(char, Vec<f64>)> = Vec::new();
+    let bytes = d.as_bytes();
+    let mut i = 0;
+    while i < bytes.len() {
+        let c = bytes[i] as char;
+        if matches!(c, 'M'|'m'|'L'|'l'|'H'|'h'|'V'|'v'|'C'|'c'|'S'|'s'|'Q'|'q'|'T'|'t'|'A'|'a'|'Z'|'z') {
+            out.push((c, Vec::new()));
+            i += 1;
+        } else if c.is_ascii_digit() || matches!(c, '-' | '+' | '.') {
+            let len = number_len(&bytes[i..]);
+            if len == 0 { i += 1; continue; }
+            if let (Some((_, args)), Ok(n)) = (out.last_mut(), d[i..i + len].parse::<f64>()) {
+                args.push(n);
+            }
+            i += len;
+        } else {
+            i += 1;
+        }
+    }
+    out
+}
+
+/// Length of the number at the start of `b`: sign, digits with at most one
+/// dot, then an optional exponent. `1.5.5` is two numbers, as in SVG.
+fn number_len(b: &[u8]) -> usize {
+    let digits = |from: usize| b[from..].iter().take_while(|c| c.is_ascii_digit()).count();
+    let mut i = usize::from(matches!(b.first(), Some(b'-' | b'+')));
+    let int = digits(i);
+    i += int;
+    let mut frac = 0;
+    if b.get(i) == Some(&b'.') {
+        frac = digits(i + 1);
+        i += 1 + frac;
+    }
+    if int + frac == 0 { return 0; }
+    if matches!(b.get(i), Some(b'e' | b'E')) {
+        let sign = usize::from(matches!(b.get(i + 1), Some(b'-' | b'+')));
+        let exp = digits(i + 1 + sign);
+        if exp > 0 { i += 1 + sign + exp; }
+    }
+    i
 }
 
 /// Flatten cubic bezier to line segments using de Casteljau subdivision
@@ -1120,29 +998,6 @@ fn point_line_dist2(p: Point, a: Point, b: Point) -> f64 {
     p.sub(proj).len2()
 }
 
-fn extract_numbers_f64(d: &str) -> Vec<f64> {
-    let mut nums = Vec::new();
-    let mut buf = String::new();
-    
-    for c in d.chars() {
-        if c.is_ascii_digit() || c == '.' || (c == '-' && buf.is_empty()) || (c == '-' && buf.ends_with('e')) {
-            buf.push(c);
-        } else if c == 'e' || c == 'E' {
-            buf.push('e');
-        } else {
-            if !buf.is_empty() {
-                if let Ok(n) = buf.parse::<f64>() { nums.push(n); }
-                buf.clear();
-            }
-            if c == '-' { buf.push(c); }
-        }
-    }
-    if !buf.is_empty() {
-        if let Ok(n) = buf.parse::<f64>() { nums.push(n); }
-    }
-    nums
-}
-
 /// Perform boolean operation on two SVG paths
 pub fn path_boolean(path_a: &str, path_b: &str, op: BoolOp, tolerance: f64) -> String {
     let poly_a = flatten_path(path_a, tolerance);
@@ -1201,6 +1056,29 @@ mod tests {
         assert!(square.is_ccw());
     }
     
+    #[test]
+    fn test_polygon_centroid() {
+        let pts = |v: &[(f64, f64)]| Polygon::new(v.iter().map(|&(x, y)| Point::new(x, y)).collect());
+        // Winding direction doesn't move the centroid
+        let square = pts(&[(0.0, 0.0), (4.0, 0.0), (4.0, 4.0), (0.0, 4.0)]);
+        assert_eq!(square.centroid(), Point::new(2.0, 2.0));
+        let mut cw = square.clone();
+        cw.reverse();
+        assert_eq!(cw.centroid(), Point::new(2.0, 2.0));
+
+        // 2x1 bar (centroid 1,0.5) plus a 1x1 block above it (0.5,1.5), weighted 2:1
+        let l = pts(&[(0.0, 0.0), (2.0, 0.0), (2.0, 1.0), (1.0, 1.0), (1.0, 2.0), (0.0, 2.0)]);
+        let c = l.centroid();
+        assert!((c.x - 5.0 / 6.0).abs() < 1e-9 && (c.y - 5.0 / 6.0).abs() < 1e-9, "{:?}", c);
+        assert_eq!(l.label_anchor(), c);
+
+        // A U's centroid lands in the notch, so labels use the bounds center
+        let u = pts(&[(0.0, 0.0), (3.0, 0.0), (3.0, 3.0), (2.0, 3.0), (2.0, 1.0), (1.0, 1.0), (1.0, 3.0), (0.0, 3.0)]);
+        assert!(!u.contains(u.centroid()));
+        assert_eq!(u.label_anchor(), Point::new(1.5, 1.5));
+        assert_eq!(pts(&[(0.0, 0.0), (2.0, 2.0)]).centroid(), Point::new(1.0, 1.0));
+    }
+
     #[test]
     fn test_polygon_contains() {
         let square = Polygon::new(vec![
@@ -1264,7 +1142,70 @@ mod tests {
         let poly = flatten_path("M0 0 L10 0 L10 10 L0 10 Z", 1.0);
         assert_eq!(poly.vertices.len(), 5); // 4 vertices + close
     }
+
+    #[test]
+    fn test_flatten_path_repeated_lineto() {
+        let pts = |d: &str| flatten_path(d, 1.0).vertices.iter().map(|p| (p.x, p.y)).collect::<Vec<_>>();
+        assert_eq!(pts("M0 0 L1 1 2 2"), vec![(0.0, 0.0), (1.0, 1.0), (2.0, 2.0)]);
+        assert_eq!(pts("M0 0 l1 1 2 2"), vec![(0.0, 0.0), (1.0, 1.0), (3.0, 3.0)]);
+        assert_eq!(pts("M0,0H5-5.5.5"), vec![(0.0, 0.0), (5.0, 0.0), (-5.5, 0.0), (0.5, 0.0)]);
+    }
+
+    #[test]
+    fn test_flatten_path_implicit_lineto_after_moveto() {
+        let pts = |d: &str| flatten_path(d, 1.0).vertices.iter().map(|p| (p.x, p.y)).collect::<Vec<_>>();
+        assert_eq!(pts("M0 0 1 1 2 2"), vec![(0.0, 0.0), (1.0, 1.0), (2.0, 2.0)]);
+        // Relative moveto continues as relative lineto, and the subpath starts at the moveto
+        assert_eq!(pts("m5 5 10 0 0 10z"), vec![(5.0, 5.0), (15.0, 5.0), (15.0, 15.0), (5.0, 5.0)]);
+    }
+
+    #[test]
+    fn test_flatten_contours_after_close() {
+        let contours: Vec<Vec<_>> = flatten_contours("M0 0 L10 0 Z L20 20", 1.0).iter()
+            .map(|c| c.vertices.iter().map(|p| (p.x, p.y)).collect())
+            .collect();
+        assert_eq!(contours, vec![vec![(0.0, 0.0), (10.0, 0.0), (0.0, 0.0)], vec![(0.0, 0.0), (20.0, 20.0)]]);
+        // Relative commands after the close resolve against the subpath start
+        let moved = flatten_contours("M5 5 l10 0 z m1 1 l0 1", 1.0);
+        assert_eq!(moved.len(), 2);
+        assert_eq!(moved[1].vertices.first().map(|p| (p.x, p.y)), Some((6.0, 6.0)));
+        // The joined outline has no repeated point at the seam
+        assert_eq!(flatten_path("M0 0 L10 0 Z L20 20", 1.0).vertices.len(), 4);
+    }
+
+    #[test]
+    fn test_flatten_path_repeated_cubic() {
+        let once = flatten_path("M0 0 C0 10 10 10 10 0 C10 -10 20 -10 20 0", 0.1);
+        let repeated = flatten_path("M0 0 C0 10 10 10 10 0 10 -10 20 -10 20 0", 0.1);
+        assert_eq!(once.vertices, repeated.vertices);
+        assert_eq!(repeated.vertices.last().map(|p| (p.x, p.y)), Some((20.0, 0.0)));
+    }
+
+    #[test]
+    fn test_flatten_path_truncated_command() {
+        // A cubic missing its end point is dropped; the following lineto keeps its own numbers
+        let poly = flatten_path("M0 0 C10 0 10 10 L20 20", 1.0);
+        let pts: Vec<_> = poly.vertices.iter().map(|p| (p.x, p.y)).collect();
+        assert_eq!(pts, vec![(0.0, 0.0), (20.0, 20.0)]);
+        // Leftover numbers short of a full set are ignored
+        assert_eq!(flatten_path("M0 0 L10 0 5", 1.0).vertices.len(), 2);
+    }
     
+    #[test]
+    fn test_boolean_areas_either_winding() {
+        // Two 10x10 squares overlapping in a 5x5 corner, wound both ways
+        for (a, b) in [
+            ("M0 0 L10 0 L10 10 L0 10 Z", "M5 5 L15 5 L15 15 L5 15 Z"),
+            ("M0 0 L0 10 L10 10 L10 0 Z", "M5 5 L5 15 L15 15 L15 5 Z"),
+        ] {
+            for (op, area) in [(BoolOp::Union, 175.0), (BoolOp::Intersection, 25.0), (BoolOp::Difference, 75.0)] {
+                let result = PolygonClipper::new(flatten_path(a, 0.5), flatten_path(b, 0.5)).compute(op);
+                assert_eq!(result.contours.len(), 1, "{:?} {}", op, result.to_path_d());
+                assert!((result.contours[0].signed_area().abs() - area).abs() < 1e-6, "{:?} {}", op, result.to_path_d());
+            }
+        }
+    }
+
     #[test]
     fn test_path_boolean_union() {
         let a = "M0 0 L10 0 L10 10 L0 10 Z";
@@ -1291,4 +1232,3 @@ mod tests {
         assert!((intersections[0].2.y - 1.0).abs() < EPS);
     }
 }
-
@@ -0,0 +1,104 @@
+//! Minimal enclosing circles
+//!
+//! Welzl's algorithm in its iterative form: points are visited in a shuffled
+//! order and the circle is rebuilt on each point that falls outside it, using
+//! that point with one or two earlier ones as boundary points. The shuffle is
+//! seeded, so the same points always give the same circle.
+
+use super::boolean::{flatten_path, Point};
+
+/// Slack on containment checks, so boundary points don't rebuild the circle
+const EPS: f64 = 1e-9;
+
+/// Smallest circle containing every point, as center and radius. An empty
+/// slice gives a zero circle at the origin.
+pub fn min_enclosing_circle(points: &[Point]) -> (Point, f64) {
+    let mut pts = points.to_vec();
+    shuffle(&mut pts);
+    let Some(&first) = pts.first() else { return (Point::new(0.0, 0.0), 0.0) };
+
+    let mut c = (first, 0.0);
+    for i in 1..pts.len() {
+        if contains(c, pts[i]) { continue; }
+        c = (pts[i], 0.0);
+        for j in 0..i {
+            if contains(c, pts[j]) { continue; }
+            c = diameter_circle(pts[i], pts[j]);
+            for k in 0..j {
+                if !contains(c, pts[k]) { c = circumcircle(pts[i], pts[j], pts[k]); }
+            }
+        }
+    }
+    c
+}
+
+/// Smallest circle enclosing path `d` after flattening, as `(cx, cy, r)`
+pub fn bounding_circle(d: &str, tolerance: f64) -> (f64, f64, f64) {
+    let (center, r) = min_enclosing_circle(&flatten_path(d, tolerance).vertices);
+    (center.x, center.y, r)
+}
+
+fn contains((center, r): (Point, f64), p: Point) -> bool {
+    p.sub(center).len() <= r + EPS * r.max(1.0)
+}
+
+fn diameter_circle(a: Point, b: Point) -> (Point, f64) {
+    (a.add(b).scale(0.5), b.sub(a).len() / 2.0)
+}
+
+/// Circle through all three points; for collinear points, the circle on the
+/// two furthest apart
+fn circumcircle(a: Point, b: Point, c: Point) -> (Point, f64) {
+    let (ab, ac) = (b.sub(a), c.sub(a));
+    let d = 2.0 * ab.cross(ac);
+    if d.abs() < EPS {
+        return [(a, b), (a, c), (b, c)].into_iter()
+            .map(|(p, q)| diameter_circle(p, q))
+            .fold((a, 0.0), |best, c| if c.1 > best.1 { c } else { best });
+    }
+    let center = Point::new(
+        (ac.y * ab.len2() - ab.y * ac.len2()) / d,
+        (ab.x * ac.len2() - ac.x * ab.len2()) / d,
+    ).add(a);
+    (center, center.sub(a).len())
+}
+
+/// Fisher-Yates with a fixed xorshift seed
+fn shuffle(pts: &mut [Point]) {
+    let mut state: u64 = 0x9E37_79B9_7F4A_7C15;
+    for i in (1..pts.len()).rev() {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        pts.swap(i, (state % (i as u64 + 1)) as usize);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_square_corners_circle() {
+        let corners = [Point::new(0.0, 0.0), Point::new(10.0, 0.0), Point::new(10.0, 10.0), Point::new(0.0, 10.0)];
+        let (center, r) = min_enclosing_circle(&corners);
+        assert!(center.sub(Point::new(5.0, 5.0)).len() < 1e-9, "{:?}", center);
+        assert!((r - 200f64.sqrt() / 2.0).abs() < 1e-9, "{}", r);
+
+        let (cx, cy, r) = bounding_circle("M0 0 H10 V10 H0 Z", 0.5);
+        assert!((cx - 5.0).abs() < 1e-9 && (cy - 5.0).abs() < 1e-9 && (r - 50f64.sqrt()).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_circle_encloses_every_point() {
+        // An obtuse triangle's circle sits on its longest side, not its circumcircle
+        let (center, r) = min_enclosing_circle(&[Point::new(0.0, 0.0), Point::new(10.0, 0.0), Point::new(5.0, 1.0)]);
+        assert!(center.sub(Point::new(5.0, 0.0)).len() < 1e-9 && (r - 5.0).abs() < 1e-9, "{:?} {}", center, r);
+
+        let pts: Vec<Point> = flatten_path("M10 50 C10 0 90 0 90 50 S50 120 30 80 Z", 0.1).vertices;
+        let (center, r) = min_enclosing_circle(&pts);
+        assert!(pts.iter().all(|p| p.sub(center).len() <= r + 1e-6));
+        assert_eq!(min_enclosing_circle(&[]), (Point::new(0.0, 0.0), 0.0));
+        assert_eq!(min_enclosing_circle(&[Point::new(3.0, 4.0)]), (Point::new(3.0, 4.0), 0.0));
+    }
+}
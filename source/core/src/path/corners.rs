@@ -0,0 +1,81 @@
+//! Rounded polygon corners
+//!
+//! Each vertex is replaced by a circular arc tangent to both adjoining edges.
+//! The tangent length is capped at half the shorter edge, so neighbouring
+//! fillets never overlap; tighter corners get a proportionally smaller radius.
+
+use super::boolean::Point;
+use crate::fmt::num;
+
+/// Closed path `d` for the polygon `points` with every corner rounded by radius `r`.
+///
+/// Straight-through (collinear) vertices stay as plain line joins. With `r <= 0`
+/// or fewer than three points the outline is returned unrounded.
+pub fn round_corners(points: &[(f32, f32)], r: f32) -> String {
+    let pts: Vec<Point> = points.iter().map(|&(x, y)| Point::new(x as f64, y as f64)).collect();
+    let n = pts.len();
+    if n == 0 { return String::new(); }
+    if n < 3 || r <= 0.0 {
+        let mut d = format!("M{} {}", num(pts[0].x), num(pts[0].y));
+        pts[1..].iter().for_each(|p| d.push_str(&format!(" L{} {}", num(p.x), num(p.y))));
+        return d + " Z";
+    }
+
+    let mut d = String::new();
+    for i in 0..n {
+        let (prev, p, next) = (pts[(i + n - 1) % n], pts[i], pts[(i + 1) % n]);
+        let cmd = if i == 0 { 'M' } else { 'L' };
+        match fillet(prev, p, next, r as f64) {
+            Some((start, end, radius, sweep)) => d.push_str(&format!(
+                "{}{} {} A{} {} 0 0 {} {} {} ",
+                cmd, num(start.x), num(start.y), num(radius), num(radius), sweep as u8, num(end.x), num(end.y),
+            )),
+            None => d.push_str(&format!("{}{} {} ", cmd, num(p.x), num(p.y))),
+        }
+    }
+    d + "Z"
+}
+
+/// Tangent points, effective radius and sweep flag for the corner at `p`,
+/// or `None` when the edges are degenerate or don't turn
+fn fillet(prev: Point, p: Point, next: Point, r: f64) -> Option<(Point, Point, f64, bool)> {
+    let (to_prev, to_next) = (prev.sub(p), next.sub(p));
+    let (len_prev, len_next) = (to_prev.len(), to_next.len());
+    if len_prev < 1e-9 || len_next < 1e-9 { return None; }
+    let (u, v) = (to_prev.scale(1.0 / len_prev), to_next.scale(1.0 / len_next));
+    let angle = u.dot(v).clamp(-1.0, 1.0).acos();
+    if angle < 1e-6 || std::f64::consts::PI - angle < 1e-6 { return None; }
+
+    let half_tan = (angle / 2.0).tan();
+    let tangent = (r / half_tan).min(len_prev.min(len_next) / 2.0);
+    // Positive cross product turns clockwise on screen (y down), the SVG positive sweep
+    let sweep = p.sub(prev).cross(next.sub(p)) > 0.0;
+    Some((p.add(u.scale(tangent)), p.add(v.scale(tangent)), tangent * half_tan, sweep))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::path::parse_path_bounds;
+
+    #[test]
+    fn test_round_square_corners() {
+        let d = round_corners(&[(0.0, 0.0), (100.0, 0.0), (100.0, 100.0), (0.0, 100.0)], 5.0);
+        assert_eq!(d, "M0 5 A5 5 0 0 1 5 0 L95 0 A5 5 0 0 1 100 5 L100 95 A5 5 0 0 1 95 100 L5 100 A5 5 0 0 1 0 95 Z");
+        assert_eq!(d.matches('A').count(), 4);
+        // Every straight run ends where an arc begins
+        assert!(d.split(" L").skip(1).all(|seg| seg.contains('A')));
+        assert_eq!(parse_path_bounds(&d), (0.0, 0.0, 100.0, 100.0));
+    }
+
+    #[test]
+    fn test_round_corners_clamps_and_falls_back() {
+        // An oversized radius shrinks to fit the half-edge
+        let d = round_corners(&[(0.0, 0.0), (10.0, 0.0), (10.0, 10.0), (0.0, 10.0)], 50.0);
+        assert!(d.starts_with("M0 5 A5 5 0 0 1 5 0"), "{}", d);
+        assert_eq!(round_corners(&[(0.0, 0.0), (10.0, 0.0), (5.0, 8.0)], 0.0), "M0 0 L10 0 L5 8 Z");
+        // Collinear vertex keeps a plain join
+        let d = round_corners(&[(0.0, 0.0), (5.0, 0.0), (10.0, 0.0), (10.0, 10.0)], 1.0);
+        assert!(d.contains("L5 0 "), "{}", d);
+    }
+}
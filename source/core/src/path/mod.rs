@@ -1,25 +1,62 @@
 //! SVG path parsing and boolean operations
 //!
-//! - Bounding box calculation for all SVG path commands
+//! - Bounding box calculation for all SVG path commands (exact curve and arc extrema)
 //! - Boolean operations (union, intersection, difference, xor) using sweep-line algorithm
 //! - Path flattening for curves to line segments
+//! - Minimal enclosing circles
+//! - Polygon centroids for label placement
+//! - Shape morphing between two outlines
+//! - Rounded polygon corners
+//! - Path direction reversal
+//! - Normalization to absolute commands
+//! - `transform` attribute evaluation for transformed bounds
 
 pub mod boolean;
+mod circle;
+mod corners;
+pub mod morph;
+mod normalize;
+mod reverse;
+// `transform` bounds are only needed by scene rendering and the WASM bindings
+#[cfg(any(feature = "python", feature = "bench", feature = "wasm"))]
+mod transform;
 
 pub use boolean::{
     BoolOp, BoolResult, Point, Polygon, PolygonClipper, Segment, SweepLine,
-    flatten_path, path_boolean, segment_intersection,
+    flatten_contours, flatten_path, path_boolean, segment_intersection,
 };
+pub use circle::{bounding_circle, min_enclosing_circle};
+pub use corners::round_corners;
+pub use morph::morph;
+pub use normalize::normalize;
+pub use reverse::reverse;
+#[cfg(any(feature = "python", feature = "bench", feature = "wasm"))]
+pub(crate) use transform::Affine;
+
+/// Label anchor for a closed outline: its area-weighted centroid, or the
+/// bounds center when the centroid falls outside a non-convex shape
+pub fn label_anchor(points: &[(f32, f32)]) -> (f32, f32) {
+    let p = Polygon::new(points.iter().map(|&(x, y)| Point::new(x as f64, y as f64)).collect()).label_anchor();
+    (p.x as f32, p.y as f32)
+}
 
 /// Parse SVG path d attribute and compute bounding box (x, y, width, height)
 pub fn parse_path_bounds(d: &str) -> (f32, f32, f32, f32) {
-    let (mut min_x, mut min_y, mut max_x, mut max_y) = (f32::MAX, f32::MAX, f32::MIN, f32::MIN);
-    let (mut cur_x, mut cur_y, mut start_x, mut start_y) = (0.0_f32, 0.0_f32, 0.0_f32, 0.0_f32);
-    let (mut last_ctrl_x, mut last_ctrl_y) = (0.0_f32, 0.0_f32);
+    let (x, y, w, h) = exact_bounds(d);
+    (x as f32, y as f32, w as f32, h as f32)
+}
+
+/// Tight bounding box (x, y, width, height) in full precision: bezier extrema
+/// come from the roots of the curve derivative, arc extrema from the rotated
+/// ellipse's extreme parameter angles
+pub fn exact_bounds(d: &str) -> (f64, f64, f64, f64) {
+    let (mut min_x, mut min_y, mut max_x, mut max_y) = (f64::MAX, f64::MAX, f64::MIN, f64::MIN);
+    let (mut cur_x, mut cur_y, mut start_x, mut start_y) = (0.0_f64, 0.0_f64, 0.0_f64, 0.0_f64);
+    let (mut last_ctrl_x, mut last_ctrl_y) = (0.0_f64, 0.0_f64);
     let mut last_cmd = ' ';
 
-    let mut track = |x: f32, y: f32| { min_x = min_x.min(x); min_y = min_y.min(y); max_x = max_x.max(x); max_y = max_y.max(y); };
-    let nums: Vec<f32> = extract_numbers(d);
+    let mut track = |x: f64, y: f64| { min_x = min_x.min(x); min_y = min_y.min(y); max_x = max_x.max(x); max_y = max_y.max(y); };
+    let nums: Vec<f64> = extract_numbers(d);
     let cmds: Vec<char> = d.chars().filter(|c| matches!(c, 'M'|'m'|'L'|'l'|'H'|'h'|'V'|'v'|'C'|'c'|'S'|'s'|'Q'|'q'|'T'|'t'|'A'|'a'|'Z'|'z')).collect();
     let mut idx = 0;
 
@@ -102,11 +139,11 @@ pub fn parse_path_bounds(d: &str) -> (f32, f32, f32, f32) {
         }
         last_cmd = cmd;
     }
-    if min_x == f32::MAX { (0.0, 0.0, 0.0, 0.0) } else { (min_x, min_y, max_x - min_x, max_y - min_y) }
+    if min_x == f64::MAX { (0.0, 0.0, 0.0, 0.0) } else { (min_x, min_y, max_x - min_x, max_y - min_y) }
 }
 
 /// Compute cubic Bezier bounds by finding extrema
-fn cubic_bezier_bounds(x0: f32, y0: f32, x1: f32, y1: f32, x2: f32, y2: f32, x3: f32, y3: f32, track: &mut impl FnMut(f32, f32)) {
+fn cubic_bezier_bounds(x0: f64, y0: f64, x1: f64, y1: f64, x2: f64, y2: f64, x3: f64, y3: f64, track: &mut impl FnMut(f64, f64)) {
     track(x0, y0); track(x3, y3);
     for (p0, p1, p2, p3, is_x) in [(x0, x1, x2, x3, true), (y0, y1, y2, y3, false)] {
         let a = -p0 + 3.0*p1 - 3.0*p2 + p3;
@@ -123,7 +160,7 @@ fn cubic_bezier_bounds(x0: f32, y0: f32, x1: f32, y1: f32, x2: f32, y2: f32, x3:
 }
 
 /// Compute quadratic Bezier bounds by finding extrema
-fn quadratic_bezier_bounds(x0: f32, y0: f32, x1: f32, y1: f32, x2: f32, y2: f32, track: &mut impl FnMut(f32, f32)) {
+fn quadratic_bezier_bounds(x0: f64, y0: f64, x1: f64, y1: f64, x2: f64, y2: f64, track: &mut impl FnMut(f64, f64)) {
     track(x0, y0); track(x2, y2);
     for (p0, p1, p2, is_x) in [(x0, x1, x2, true), (y0, y1, y2, false)] {
         let denom = p0 - 2.0*p1 + p2;
@@ -138,8 +175,10 @@ fn quadratic_bezier_bounds(x0: f32, y0: f32, x1: f32, y1: f32, x2: f32, y2: f32,
     }
 }
 
-/// Compute arc bounds using endpoint parameterization
-fn arc_bounds(x1: f32, y1: f32, mut rx: f32, mut ry: f32, phi_deg: f32, large_arc: bool, sweep: bool, x2: f32, y2: f32, track: &mut impl FnMut(f32, f32)) {
+/// Compute arc bounds from the center parameterization: each axis has two extreme
+/// parameter angles, tracked when they fall inside the swept range
+fn arc_bounds(x1: f64, y1: f64, mut rx: f64, mut ry: f64, phi_deg: f64, large_arc: bool, sweep: bool, x2: f64, y2: f64, track: &mut impl FnMut(f64, f64)) {
+    use std::f64::consts::{PI, TAU};
     track(x1, y1); track(x2, y2);
     if rx < 1e-10 || ry < 1e-10 { return; }
 
@@ -149,6 +188,8 @@ fn arc_bounds(x1: f32, y1: f32, mut rx: f32, mut ry: f32, phi_deg: f32, large_ar
     let dy = (y1 - y2) / 2.0;
     let x1p = cos_phi * dx + sin_phi * dy;
     let y1p = -sin_phi * dx + cos_phi * dy;
+    // Coincident endpoints draw nothing
+    if x1p.abs() < 1e-12 && y1p.abs() < 1e-12 { return; }
 
     let lambda = (x1p / rx).powi(2) + (y1p / ry).powi(2);
     if lambda > 1.0 { let s = lambda.sqrt(); rx *= s; ry *= s; }
@@ -161,30 +202,35 @@ fn arc_bounds(x1: f32, y1: f32, mut rx: f32, mut ry: f32, phi_deg: f32, large_ar
     let cy = sin_phi * cxp + cos_phi * cyp + (y1 + y2) / 2.0;
 
     let theta1 = ((y1p - cyp) / ry).atan2((x1p - cxp) / rx);
-    let mut dtheta = (((-y1p - cyp) / ry).atan2((-x1p - cxp) / rx) - theta1).rem_euclid(std::f32::consts::TAU);
-    if !sweep { dtheta -= std::f32::consts::TAU; }
-
-    for angle in [0.0_f32, std::f32::consts::FRAC_PI_2, std::f32::consts::PI, 3.0 * std::f32::consts::FRAC_PI_2] {
-        let t = (angle - theta1).rem_euclid(std::f32::consts::TAU);
-        if (sweep && t <= dtheta) || (!sweep && t >= dtheta.abs() - std::f32::consts::TAU) || dtheta.abs() >= std::f32::consts::TAU - 1e-6 {
-            let px = cx + rx * angle.cos() * cos_phi - ry * angle.sin() * sin_phi;
-            let py = cy + rx * angle.cos() * sin_phi + ry * angle.sin() * cos_phi;
+    let mut dtheta = (((-y1p - cyp) / ry).atan2((-x1p - cxp) / rx) - theta1).rem_euclid(TAU);
+    if !sweep { dtheta -= TAU; }
+
+    let point = |a: f64| (
+        cx + rx * a.cos() * cos_phi - ry * a.sin() * sin_phi,
+        cy + rx * a.cos() * sin_phi + ry * a.sin() * cos_phi,
+    );
+    let theta_x = (-ry * sin_phi).atan2(rx * cos_phi);
+    let theta_y = (ry * cos_phi).atan2(rx * sin_phi);
+    for angle in [theta_x, theta_x + PI, theta_y, theta_y + PI] {
+        let swept = if dtheta >= 0.0 { (angle - theta1).rem_euclid(TAU) } else { (theta1 - angle).rem_euclid(TAU) };
+        if swept <= dtheta.abs() {
+            let (px, py) = point(angle);
             track(px, py);
         }
     }
 }
 
-#[inline] fn cubic_at(t: f32, p0: f32, p1: f32, p2: f32, p3: f32) -> f32 {
+#[inline] fn cubic_at(t: f64, p0: f64, p1: f64, p2: f64, p3: f64) -> f64 {
     let mt = 1.0 - t;
     mt*mt*mt*p0 + 3.0*mt*mt*t*p1 + 3.0*mt*t*t*p2 + t*t*t*p3
 }
 
-#[inline] fn quadratic_at(t: f32, p0: f32, p1: f32, p2: f32) -> f32 {
+#[inline] fn quadratic_at(t: f64, p0: f64, p1: f64, p2: f64) -> f64 {
     let mt = 1.0 - t;
     mt*mt*p0 + 2.0*mt*t*p1 + t*t*p2
 }
 
-fn solve_quadratic(a: f32, b: f32, c: f32) -> Vec<f32> {
+fn solve_quadratic(a: f64, b: f64, c: f64) -> Vec<f64> {
     if a.abs() < 1e-10 { return if b.abs() < 1e-10 { vec![] } else { vec![-c / b] }; }
     let disc = b*b - 4.0*a*c;
     if disc < 0.0 { vec![] }
@@ -192,15 +238,15 @@ fn solve_quadratic(a: f32, b: f32, c: f32) -> Vec<f32> {
     else { let sq = disc.sqrt(); vec![(-b - sq) / (2.0 * a), (-b + sq) / (2.0 * a)] }
 }
 
-fn extract_numbers(d: &str) -> Vec<f32> {
+fn extract_numbers(d: &str) -> Vec<f64> {
     let mut nums = Vec::new();
     let mut buf = String::new();
     for c in d.chars() {
         if c.is_ascii_digit() || c == '.' || (c == '-' && buf.is_empty()) || (c == '-' && buf.ends_with('e')) { buf.push(c); }
         else if c == 'e' || c == 'E' { buf.push('e'); }
-        else { if !buf.is_empty() { if let Ok(n) = buf.parse::<f32>() { nums.push(n); } buf.clear(); } if c == '-' { buf.push(c); } }
+        else { if !buf.is_empty() { if let Ok(n) = buf.parse::<f64>() { nums.push(n); } buf.clear(); } if c == '-' { buf.push(c); } }
     }
-    if !buf.is_empty() { if let Ok(n) = buf.parse::<f32>() { nums.push(n); } }
+    if !buf.is_empty() { if let Ok(n) = buf.parse::<f64>() { nums.push(n); } }
     nums
 }
 
@@ -236,6 +282,27 @@ mod tests {
         assert!(x >= -0.01 && (x + w) <= 60.01);
     }
 
+    #[test] fn test_exact_bounds_cubic_extremum() {
+        // y'(t) = 0 at t = (11 - sqrt(37)) / 12 for y control values 40, -30, 10, 40
+        let t = (11.0 - 37f64.sqrt()) / 12.0;
+        let mt = 1.0 - t;
+        let min_y = mt.powi(3) * 40.0 - 3.0 * mt * mt * t * 30.0 + 3.0 * mt * t * t * 10.0 + t.powi(3) * 40.0;
+        let (x, y, w, h) = exact_bounds("M0 40 C20 -30, 70 10, 100 40");
+        assert!((y - min_y).abs() < 1e-6, "{} vs {}", y, min_y);
+        assert!((y + h - 40.0).abs() < 1e-9 && x.abs() < 1e-9 && (w - 100.0).abs() < 1e-9);
+    }
+
+    #[test] fn test_exact_bounds_rotated_arc() {
+        // Rotation doesn't change a circle; the apex is still at y = -50
+        let (x, y, w, h) = exact_bounds("M0 0 A50 50 30 0 1 100 0");
+        assert!((y + 50.0).abs() < 1e-6 && (h - 50.0).abs() < 1e-6, "{} {}", y, h);
+        assert!(x.abs() < 1e-6 && (w - 100.0).abs() < 1e-6);
+        // Rotated ellipse: a quarter turn swaps the radii
+        let (_, y, _, h) = exact_bounds("M-20 0 A40 20 90 0 1 20 0");
+        let (_, y2, _, h2) = exact_bounds("M-20 0 A20 40 0 0 1 20 0");
+        assert!((y - y2).abs() < 1e-6 && (h - h2).abs() < 1e-6, "{} {} / {} {}", y, h, y2, h2);
+    }
+
     #[test] fn test_path_bounds_smooth_quadratic() {
         let (x, y, w, h) = parse_path_bounds("M0 0 Q25 50 50 0 T100 0");
         assert!(x >= -0.01 && (x + w) <= 100.01);
@@ -0,0 +1,101 @@
+//! Shape morphing between two paths
+//!
+//! Both outlines are flattened, resampled to the same number of evenly spaced
+//! points, wound the same way, and rotated so corresponding points are as close
+//! as possible before interpolating. Each path is treated as one closed contour.
+
+use super::boolean::{flatten_path, BoolResult, Point, Polygon};
+
+/// Minimum resample count, so shapes with few vertices still deform smoothly
+const MIN_SAMPLES: usize = 64;
+
+/// Interpolate between path `a_d` (`t = 0`) and `b_d` (`t = 1`).
+///
+/// Paths that flatten to fewer than two points cannot be matched up and snap
+/// from A to B at `t = 0.5`.
+pub fn morph(a_d: &str, b_d: &str, t: f64, tolerance: f64) -> String {
+    let (a, mut b) = (outline(a_d, tolerance), outline(b_d, tolerance));
+    if a.vertices.len() < 2 || b.vertices.len() < 2 {
+        return if t < 0.5 { a_d.to_string() } else { b_d.to_string() };
+    }
+    if a.is_ccw() != b.is_ccw() { b.reverse(); }
+
+    let n = a.vertices.len().max(b.vertices.len()).max(MIN_SAMPLES);
+    let (a, b) = (resample(&a.vertices, n), resample(&b.vertices, n));
+    let shift = best_shift(&a, &b);
+    let vertices = (0..n).map(|i| {
+        let (p, q) = (a[i], b[(i + shift) % n]);
+        p.add(q.sub(p).scale(t))
+    }).collect();
+    BoolResult { contours: vec![Polygon::new(vertices)] }.to_path_d()
+}
+
+/// Flattened contour without the closing duplicate `Z` appends
+fn outline(d: &str, tolerance: f64) -> Polygon {
+    let mut poly = flatten_path(d, tolerance);
+    while poly.vertices.len() > 1 && poly.vertices[poly.vertices.len() - 1].sub(poly.vertices[0]).len2() < 1e-12 {
+        poly.vertices.pop();
+    }
+    poly
+}
+
+/// `n` points spaced evenly by arc length around the closed contour, starting at its first vertex
+fn resample(pts: &[Point], n: usize) -> Vec<Point> {
+    let edges: Vec<(Point, Point)> = (0..pts.len()).map(|i| (pts[i], pts[(i + 1) % pts.len()])).collect();
+    let perimeter: f64 = edges.iter().map(|(p, q)| q.sub(*p).len()).sum();
+    if perimeter < 1e-12 { return vec![pts[0]; n]; }
+
+    let step = perimeter / n as f64;
+    let (mut out, mut edge, mut walked) = (Vec::with_capacity(n), 0, 0.0);
+    for k in 0..n {
+        let target = k as f64 * step;
+        while edge + 1 < edges.len() && walked + edges[edge].1.sub(edges[edge].0).len() < target {
+            walked += edges[edge].1.sub(edges[edge].0).len();
+            edge += 1;
+        }
+        let (p, q) = edges[edge];
+        let len = q.sub(p).len();
+        let f = if len < 1e-12 { 0.0 } else { ((target - walked) / len).clamp(0.0, 1.0) };
+        out.push(p.add(q.sub(p).scale(f)));
+    }
+    out
+}
+
+/// Rotation of `b` minimizing the summed squared distance to `a`
+fn best_shift(a: &[Point], b: &[Point]) -> usize {
+    let n = a.len();
+    let cost = |s: usize| (0..n).map(|i| b[(i + s) % n].sub(a[i]).len2()).sum::<f64>();
+    (0..n).min_by(|&x, &y| cost(x).total_cmp(&cost(y))).unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::path::parse_path_bounds;
+
+    const SQUARE: &str = "M0 0 L100 0 L100 100 L0 100 Z";
+    const TRIANGLE: &str = "M50 0 L100 100 L0 100 Z";
+
+    /// On the triangle's outline or inside it, within float slack
+    fn in_triangle(p: Point) -> bool { p.y <= 100.001 && (p.x - 50.0).abs() <= p.y / 2.0 + 0.001 }
+
+    #[test]
+    fn test_morph_endpoints() {
+        let start = parse_path_bounds(&morph(SQUARE, TRIANGLE, 0.0, 0.5));
+        let end = flatten_path(&morph(SQUARE, TRIANGLE, 1.0, 0.5), 0.5);
+        assert!((start.2 - 100.0).abs() < 0.01 && (start.3 - 100.0).abs() < 0.01);
+        assert!(end.vertices.iter().all(|p| in_triangle(*p)));
+    }
+
+    #[test]
+    fn test_morph_square_to_triangle() {
+        let mid = morph(SQUARE, TRIANGLE, 0.5, 0.5);
+        let poly = flatten_path(&mid, 0.5);
+        assert!(poly.vertices.len() >= 4);
+        let (x, y, w, h) = parse_path_bounds(&mid);
+        assert!(x >= -0.01 && y >= -0.01 && x + w <= 100.01 && y + h <= 100.01);
+        // Halfway between the square's and the triangle's area
+        let area = outline(&mid, 0.5).signed_area().abs();
+        assert!(area > 5000.0 && area < 10000.0, "{}", area);
+    }
+}
@@ -0,0 +1,69 @@
+//! Canonical absolute path data
+//!
+//! Every command is rewritten in absolute uppercase form with one letter per
+//! argument set, horizontal and vertical lines become `L`, and numbers are
+//! rounded to 3 decimals, so equivalent paths compare equal as strings.
+
+use super::boolean::{arity, path_commands, Point};
+use crate::fmt::num;
+
+/// Path `d` rewritten with absolute `M L C S Q T A Z` commands only
+pub fn normalize(d: &str) -> String {
+    let mut out: Vec<String> = Vec::new();
+    let mut cur = Point::new(0.0, 0.0);
+    let mut start = cur;
+    let pt = |p: Point| format!("{} {}", num(p.x), num(p.y));
+
+    for (cmd, args) in path_commands(d) {
+        let upper = cmd.to_ascii_uppercase();
+        if upper == 'Z' {
+            out.push("Z".into());
+            cur = start;
+            continue;
+        }
+        for (k, a) in args.chunks_exact(arity(upper)).enumerate() {
+            let upper = if upper == 'M' && k > 0 { 'L' } else { upper };
+            let o = if cmd.is_ascii_lowercase() { cur } else { Point::new(0.0, 0.0) };
+            let at = |i: usize| Point::new(o.x + a[i], o.y + a[i + 1]);
+            let (seg, end) = match upper {
+                'M' => (format!("M{}", pt(at(0))), at(0)),
+                'L' => (format!("L{}", pt(at(0))), at(0)),
+                'H' => { let p = Point::new(o.x + a[0], cur.y); (format!("L{}", pt(p)), p) }
+                'V' => { let p = Point::new(cur.x, o.y + a[0]); (format!("L{}", pt(p)), p) }
+                'C' => (format!("C{} {} {}", pt(at(0)), pt(at(2)), pt(at(4))), at(4)),
+                'S' | 'Q' => (format!("{}{} {}", upper, pt(at(0)), pt(at(2))), at(2)),
+                'T' => (format!("T{}", pt(at(0))), at(0)),
+                'A' => (format!(
+                    "A{} {} {} {} {} {}", num(a[0]), num(a[1]), num(a[2]), (a[3] != 0.0) as u8, (a[4] != 0.0) as u8, pt(at(5)),
+                ), at(5)),
+                _ => continue,
+            };
+            if upper == 'M' { start = end; }
+            cur = end;
+            out.push(seg);
+        }
+    }
+    out.join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::path::flatten_path;
+
+    #[test]
+    fn test_normalize_relative_square() {
+        let rel = normalize("m10 10 h20 v20 h-20 z");
+        assert_eq!(rel, "M10 10 L30 10 L30 30 L10 30 Z");
+        assert_eq!(rel, normalize("M10,10 L30,10 30,30 10,30 Z"));
+        assert_eq!(flatten_path(&rel, 0.5).vertices, flatten_path("m10 10 h20 v20 h-20 z", 0.5).vertices);
+    }
+
+    #[test]
+    fn test_normalize_curves_and_subpaths() {
+        assert_eq!(normalize("M0 0 c1 1 2 2 3 3 s1 1 2 2"), "M0 0 C1 1 2 2 3 3 S4 4 5 5");
+        assert_eq!(normalize("M5 5 q1 0 2 2 t2 2 a1 1 0 1 0 2 0"), "M5 5 Q6 5 7 7 T9 9 A1 1 0 1 0 11 9");
+        // Relative commands after a close resolve against the subpath start
+        assert_eq!(normalize("m5 5 l10 0 z m1 1 l0 1"), "M5 5 L15 5 Z M6 6 L6 7");
+    }
+}
@@ -0,0 +1,144 @@
+//! Path direction reversal
+//!
+//! The path is resolved into absolute segments per subpath, and each subpath is
+//! walked backwards: lines run to their old start, bezier curves swap their
+//! control points and arcs flip their sweep flag. Smooth and axis-aligned
+//! shorthands come back in their full `C`, `Q` and `L` forms.
+
+use super::boolean::{arity, path_commands, Point};
+use crate::fmt::num;
+
+/// One drawing command in absolute coordinates, ending at its last point
+enum Seg {
+    Line(Point),
+    Cubic(Point, Point, Point),
+    Quad(Point, Point),
+    /// Radii, x-axis rotation, large-arc and sweep flags, then the end point
+    Arc([f64; 5], Point),
+}
+
+impl Seg {
+    fn end(&self) -> Point {
+        match *self { Seg::Line(p) | Seg::Cubic(_, _, p) | Seg::Quad(_, p) | Seg::Arc(_, p) => p }
+    }
+}
+
+struct Subpath {
+    start: Point,
+    segs: Vec<Seg>,
+    closed: bool,
+}
+
+/// Path `d` drawing the same outline in the opposite direction.
+///
+/// Subpaths keep their order and are each reversed in place; a closed subpath
+/// still starts at its original start point and stays closed.
+pub fn reverse(d: &str) -> String {
+    subpaths(d).iter().map(reverse_subpath).collect::<Vec<_>>().join(" ")
+}
+
+fn subpaths(d: &str) -> Vec<Subpath> {
+    let mut out: Vec<Subpath> = Vec::new();
+    let mut cur = Point::new(0.0, 0.0);
+    let mut last_ctrl = cur;
+    let mut last_cmd = ' ';
+
+    for (cmd, args) in path_commands(d) {
+        let upper = cmd.to_ascii_uppercase();
+        if upper == 'Z' {
+            if let Some(sp) = out.last_mut() {
+                sp.closed = true;
+                cur = sp.start;
+            }
+            last_ctrl = cur;
+            last_cmd = cmd;
+            continue;
+        }
+        for (k, a) in args.chunks_exact(arity(upper)).enumerate() {
+            let (cmd, upper) = match (cmd, k) {
+                ('M', 1..) => ('L', 'L'),
+                ('m', 1..) => ('l', 'L'),
+                _ => (cmd, upper),
+            };
+            let o = if cmd.is_ascii_lowercase() { cur } else { Point::new(0.0, 0.0) };
+            let at = |i: usize| Point::new(o.x + a[i], o.y + a[i + 1]);
+            let reflect = |kinds: &[char]| if kinds.contains(&last_cmd) { cur.scale(2.0).sub(last_ctrl) } else { cur };
+            let seg = match upper {
+                'M' => {
+                    cur = at(0);
+                    last_ctrl = cur;
+                    last_cmd = cmd;
+                    out.push(Subpath { start: cur, segs: Vec::new(), closed: false });
+                    continue;
+                }
+                'L' => Seg::Line(at(0)),
+                'H' => Seg::Line(Point::new(o.x + a[0], cur.y)),
+                'V' => Seg::Line(Point::new(cur.x, o.y + a[0])),
+                'C' => Seg::Cubic(at(0), at(2), at(4)),
+                'S' => Seg::Cubic(reflect(&['C', 'c', 'S', 's']), at(0), at(2)),
+                'Q' => Seg::Quad(at(0), at(2)),
+                'T' => Seg::Quad(reflect(&['Q', 'q', 'T', 't']), at(0)),
+                'A' => Seg::Arc([a[0], a[1], a[2], a[3], a[4]], at(5)),
+                _ => continue,
+            };
+            // Drawing with no open subpath starts one at the current point
+            if out.last().is_none_or(|sp| sp.closed) {
+                out.push(Subpath { start: cur, segs: Vec::new(), closed: false });
+            }
+            last_ctrl = match seg { Seg::Cubic(_, c2, _) => c2, Seg::Quad(c, _) => c, _ => seg.end() };
+            cur = seg.end();
+            last_cmd = cmd;
+            if let Some(sp) = out.last_mut() { sp.segs.push(seg); }
+        }
+    }
+    out
+}
+
+fn reverse_subpath(sp: &Subpath) -> String {
+    let mut segs: Vec<&Seg> = sp.segs.iter().collect();
+    // An implicit closing line becomes the first segment walked backwards
+    let closing = Seg::Line(sp.start);
+    if sp.closed && segs.last().is_some_and(|s| s.end() != sp.start) { segs.push(&closing); }
+
+    let pt = |p: Point| format!("{} {}", num(p.x), num(p.y));
+    let mut d = format!("M{}", pt(segs.last().map_or(sp.start, |s| s.end())));
+    for i in (0..segs.len()).rev() {
+        let to = if i == 0 { sp.start } else { segs[i - 1].end() };
+        d.push_str(&match *segs[i] {
+            Seg::Line(_) => format!(" L{}", pt(to)),
+            Seg::Cubic(c1, c2, _) => format!(" C{} {} {}", pt(c2), pt(c1), pt(to)),
+            Seg::Quad(c, _) => format!(" Q{} {}", pt(c), pt(to)),
+            Seg::Arc([rx, ry, rot, large, sweep], _) => format!(
+                " A{} {} {} {} {} {}", num(rx), num(ry), num(rot), (large != 0.0) as u8, (sweep == 0.0) as u8, pt(to),
+            ),
+        });
+    }
+    if sp.closed { d.push_str(" Z"); }
+    d
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::path::flatten_path;
+
+    #[test]
+    fn test_reverse_swaps_curve_handles() {
+        assert_eq!(reverse("M0 0 C1 2 3 4 5 6"), "M5 6 C3 4 1 2 0 0");
+        assert_eq!(reverse("M0 0 h10 v10"), "M10 10 L10 0 L0 0");
+        assert_eq!(reverse("M0 0 A5 5 0 0 1 10 0"), "M10 0 A5 5 0 0 0 0 0");
+        // Subpaths reverse independently, closed ones from their own start
+        assert_eq!(reverse("M0 0 L10 0 L10 10 Z M20 20 L30 30"), "M0 0 L10 10 L10 0 L0 0 Z M30 30 L20 20");
+    }
+
+    #[test]
+    fn test_reverse_twice_round_trips() {
+        let d = "M10 10 C20 0 40 0 50 10 S80 20 90 10 Q100 30 80 40 T40 50 A20 20 0 0 1 10 10 Z";
+        let twice = reverse(&reverse(d));
+        let (a, b) = (flatten_path(d, 0.1), flatten_path(&twice, 0.1));
+        assert_eq!(a.vertices.len(), b.vertices.len());
+        assert!(a.vertices.iter().zip(&b.vertices).all(|(p, q)| p.sub(*q).len() < 0.01), "{}", twice);
+        // A single reversal flips the winding
+        assert!(flatten_path(&reverse(d), 0.1).signed_area() * a.signed_area() < 0.0);
+    }
+}
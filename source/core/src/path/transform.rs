@@ -0,0 +1,79 @@
+//! SVG `transform` attribute evaluation for geometry queries
+
+/// 2D affine matrix `[a b c d e f]`, mapping `(x, y)` to `(ax + cy + e, bx + dy + f)`
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) struct Affine([f32; 6]);
+
+impl Affine {
+    pub const IDENTITY: Self = Self([1.0, 0.0, 0.0, 1.0, 0.0, 0.0]);
+
+    /// Evaluate a transform list; unknown or malformed functions are skipped
+    pub fn parse(s: &str) -> Self {
+        let mut m = Self::IDENTITY;
+        for item in s.split_inclusive(')') {
+            let Some((name, args)) = item.split_once('(') else { continue };
+            let args: Vec<f32> = args.trim_end_matches(')')
+                .split(|c: char| c == ',' || c.is_whitespace())
+                .filter_map(|n| n.parse().ok())
+                .collect();
+            let next = match (name.trim().trim_start_matches(',').trim(), args.as_slice()) {
+                ("matrix", &[a, b, c, d, e, f]) => Self([a, b, c, d, e, f]),
+                ("translate", &[x]) => Self([1.0, 0.0, 0.0, 1.0, x, 0.0]),
+                ("translate", &[x, y]) => Self([1.0, 0.0, 0.0, 1.0, x, y]),
+                ("scale", &[s]) => Self([s, 0.0, 0.0, s, 0.0, 0.0]),
+                ("scale", &[sx, sy]) => Self([sx, 0.0, 0.0, sy, 0.0, 0.0]),
+                ("rotate", &[a]) => Self::rotation(a),
+                ("rotate", &[a, cx, cy]) => Self([1.0, 0.0, 0.0, 1.0, cx, cy])
+                    .then(Self::rotation(a)).then(Self([1.0, 0.0, 0.0, 1.0, -cx, -cy])),
+                ("skewX", &[a]) => Self([1.0, 0.0, a.to_radians().tan(), 1.0, 0.0, 0.0]),
+                ("skewY", &[a]) => Self([1.0, a.to_radians().tan(), 0.0, 1.0, 0.0, 0.0]),
+                _ => continue,
+            };
+            m = m.then(next);
+        }
+        m
+    }
+
+    fn rotation(deg: f32) -> Self {
+        let (sin, cos) = deg.to_radians().sin_cos();
+        Self([cos, sin, -sin, cos, 0.0, 0.0])
+    }
+
+    /// `self` applied after `inner` (SVG lists compose left to right like this)
+    pub fn then(self, inner: Self) -> Self {
+        let ([a, b, c, d, e, f], [a2, b2, c2, d2, e2, f2]) = (self.0, inner.0);
+        Self([
+            a * a2 + c * b2, b * a2 + d * b2,
+            a * c2 + c * d2, b * c2 + d * d2,
+            a * e2 + c * f2 + e, b * e2 + d * f2 + f,
+        ])
+    }
+
+    pub fn apply(&self, x: f32, y: f32) -> (f32, f32) {
+        let [a, b, c, d, e, f] = self.0;
+        (a * x + c * y + e, b * x + d * y + f)
+    }
+
+    /// Axis-aligned box enclosing the transformed `(x, y, w, h)` rectangle
+    pub fn map_bounds(&self, (x, y, w, h): (f32, f32, f32, f32)) -> (f32, f32, f32, f32) {
+        if *self == Self::IDENTITY { return (x, y, w, h); }
+        let corners = [self.apply(x, y), self.apply(x + w, y), self.apply(x, y + h), self.apply(x + w, y + h)];
+        let (min_x, min_y, max_x, max_y) = corners.iter().fold((f32::MAX, f32::MAX, f32::MIN, f32::MIN), |(a, b, c, d), &(px, py)| {
+            (a.min(px), b.min(py), c.max(px), d.max(py))
+        });
+        (min_x, min_y, max_x - min_x, max_y - min_y)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_transform_list() {
+        let (x, y) = Affine::parse("translate(10 0) rotate(90 5 5)").apply(10.0, 5.0);
+        assert!((x - 15.0).abs() < 1e-4 && (y - 10.0).abs() < 1e-4);
+        assert_eq!(Affine::parse("scale(2,3)").map_bounds((1.0, 1.0, 2.0, 2.0)), (2.0, 3.0, 4.0, 6.0));
+        assert_eq!(Affine::parse("bogus(1) translate(4)").apply(0.0, 0.0), (4.0, 0.0));
+    }
+}
@@ -0,0 +1,162 @@
+//! Def collection and deduplication
+//!
+//! Gradients, filters and symbols that differ only by id collapse into one def
+//! under a canonical id from `IdGen`, and every `url(#..)`, `filter` and `href`
+//! reference is rewritten to match. Markers and the background pattern are
+//! already shared under fixed ids and pass through unchanged.
+
+use std::collections::HashMap;
+
+use crate::hash::IdGen;
+use crate::scene::{Element, Filter, Gradient, RenderOptions, Scene, Symbol};
+
+/// Deduplicate the scene's defs in place and return its `<defs>` block.
+///
+/// Canonical ids are assigned in first-seen order (`gradient-0`, `filter-1`, ...),
+/// so the same scene always yields the same ids. References to ids the scene
+/// doesn't define are left alone.
+pub fn collect_defs(scene: &mut Scene) -> String {
+    let ids = IdGen::default();
+    let (gradients, filters, symbols, elements) = scene.defs_mut();
+
+    let (unique, paint) = dedupe(gradients, |g| &g.id, |g, id| Gradient { id, ..g.clone() }, "gradient", &ids);
+    *gradients = unique;
+    let (unique, filter) = dedupe(filters, |f| &f.id, |f, id| Filter { id, ..f.clone() }, "filter", &ids);
+    *filters = unique;
+
+    let refs = Refs { paint, filter, href: HashMap::new(), animation: HashMap::new() };
+    refs.rewrite(elements);
+    symbols.iter_mut().for_each(|s| refs.rewrite(&mut s.children));
+
+    // Symbols compare by content after their children point at canonical paint
+    let (unique, href) = dedupe(symbols, |s| &s.id, |s, id| Symbol { id, ..s.clone() }, "symbol", &ids);
+    *symbols = unique;
+    let refs = Refs { paint: HashMap::new(), filter: HashMap::new(), href, animation: HashMap::new() };
+    refs.rewrite(elements);
+    symbols.iter_mut().for_each(|s| refs.rewrite(&mut s.children));
+
+    scene.defs_svg(RenderOptions::default())
+}
+
+/// Point `elements` and `symbols` at renamed defs: `url(#..)` paints through
+/// `paint`, filters through `filter`, `use` hrefs through `href` and animation
+/// classes through `animation`
+pub(crate) fn rename_refs(
+    elements: &mut [Element], symbols: &mut [Symbol],
+    paint: HashMap<String, String>, filter: HashMap<String, String>, href: HashMap<String, String>, animation: HashMap<String, String>,
+) {
+    let refs = Refs { paint, filter, href, animation };
+    refs.rewrite(elements);
+    symbols.iter_mut().for_each(|s| refs.rewrite(&mut s.children));
+}
+
+/// Keep the first of each group of defs that are equal once their ids are
+/// blanked by `rename`, under a fresh id. Returns the survivors and the
+/// old → canonical id map.
+fn dedupe<T: PartialEq>(
+    defs: &[T], id: impl Fn(&T) -> &str, rename: impl Fn(&T, String) -> T, prefix: &str, ids: &IdGen,
+) -> (Vec<T>, HashMap<String, String>) {
+    let (mut unique, mut keys, mut map) = (Vec::<T>::new(), Vec::<T>::new(), HashMap::new());
+    for def in defs {
+        let key = rename(def, String::new());
+        let canonical = match keys.iter().position(|seen| *seen == key) {
+            Some(i) => id(&unique[i]).to_string(),
+            None => {
+                let canonical = format!("{}-{}", prefix, ids.next());
+                unique.push(rename(def, canonical.clone()));
+                keys.push(key);
+                canonical
+            }
+        };
+        map.entry(id(def).to_string()).or_insert(canonical);
+    }
+    (unique, map)
+}
+
+/// Old → canonical id maps for each kind of reference
+struct Refs {
+    paint: HashMap<String, String>,
+    filter: HashMap<String, String>,
+    href: HashMap<String, String>,
+    animation: HashMap<String, String>,
+}
+
+impl Refs {
+    fn rewrite(&self, els: &mut [Element]) {
+        for el in els {
+            match el {
+                Element::Group(children, _, _) => self.rewrite(children),
+                Element::Graph(g) => {
+                    g.nodes.iter_mut().for_each(|n| self.rewrite_style(&mut n.style));
+                    g.edges.iter_mut().for_each(|e| self.rewrite_style(&mut e.style));
+                }
+                Element::Use(u) => {
+                    if let Some(id) = self.href.get(&u.href) { u.href = id.clone(); }
+                    self.rewrite_style(&mut u.style);
+                }
+                el => if let Some(style) = el.style_mut() { self.rewrite_style(style) },
+            }
+        }
+    }
+
+    fn rewrite_style(&self, style: &mut crate::scene::Style) {
+        for paint in [&mut style.fill, &mut style.stroke].into_iter().flatten() {
+            let id = paint.strip_prefix("url(#").and_then(|rest| rest.strip_suffix(')'));
+            if let Some(canonical) = id.and_then(|id| self.paint.get(id)) { *paint = format!("url(#{})", canonical); }
+        }
+        if let Some(canonical) = style.filter.as_ref().and_then(|f| self.filter.get(f)) { style.filter = Some(canonical.clone()); }
+        if let Some(renamed) = style.animation_class.as_ref().and_then(|c| self.animation.get(c)) { style.animation_class = Some(renamed.clone()); }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scene::{Rect, Style};
+    use crate::CanvasSize;
+
+    fn gradient(id: &str) -> Gradient {
+        Gradient { id: id.into(), kind: "linear".into(), from_color: "#f00".into(), to_color: "#00f".into(), angle: 90.0, shared: false }
+    }
+
+    fn rect(x: f32, fill: &str) -> Element {
+        Element::Rect(Rect { x, y: 0.0, w: 10.0, h: 10.0, rx: 0.0, style: Style { fill: Some(fill.into()), ..Default::default() }, transform: None })
+    }
+
+    #[test]
+    fn test_identical_gradients_share_one_def() {
+        let mut scene = Scene::new(CanvasSize::Medium, "#fff".into());
+        scene.push_gradient(gradient("a"));
+        scene.push_gradient(gradient("b"));
+        scene.push(rect(0.0, "url(#a)"));
+        scene.push(rect(20.0, "url(#b)"));
+
+        let defs = collect_defs(&mut scene);
+        assert_eq!(defs.matches("<linearGradient").count(), 1);
+        assert_eq!(scene.gradients().len(), 1);
+        let fills: Vec<_> = scene.elements().iter().map(|e| e.style().and_then(|s| s.fill.clone())).collect();
+        assert_eq!(fills, vec![Some("url(#gradient-0)".to_string()); 2]);
+        assert!(defs.contains(r#"id="gradient-0""#));
+    }
+
+    #[test]
+    fn test_distinct_defs_and_unknown_refs() {
+        let mut scene = Scene::new(CanvasSize::Medium, "#fff".into());
+        scene.push_gradient(gradient("a"));
+        scene.push_gradient(Gradient { to_color: "#0f0".into(), ..gradient("b") });
+        scene.push_filter(Filter { id: "s1".into(), kind: "blur".into(), dx: 0.0, dy: 0.0, blur: 2.0, color: "#000".into() });
+        scene.push_filter(Filter { id: "s2".into(), kind: "blur".into(), dx: 0.0, dy: 0.0, blur: 2.0, color: "#000".into() });
+        let mut blurred = rect(0.0, "url(#b)");
+        blurred.style_mut().unwrap().filter = Some("s2".into());
+        scene.push(Element::Group(vec![blurred, rect(20.0, "url(#missing)")], None, 1.0));
+
+        collect_defs(&mut scene);
+        assert_eq!(scene.gradients().len(), 2);
+        assert_eq!(scene.filters().len(), 1);
+        let Element::Group(children, _, _) = &scene.elements()[0] else { panic!("expected group") };
+        let style = children[0].style().unwrap();
+        assert_eq!(style.fill.as_deref(), Some("url(#gradient-1)"));
+        assert_eq!(style.filter.as_deref(), Some("filter-2"));
+        assert_eq!(children[1].style().unwrap().fill.as_deref(), Some("url(#missing)"));
+    }
+}
@@ -3,8 +3,9 @@
 //! Uses content-addressed hashing + ID-based reconciliation for O(n) diffing
 //! with minimal SVG regeneration. Inspired by VDOM reconciliation algorithms.
 
+use std::cell::RefCell;
 use std::collections::HashMap;
-use crate::hash::{ContentHash, ElementId, ElementKind, Fnv1a, IdGen};
+use crate::hash::{ContentHash, ElementId, ElementKind, IdGen};
 use crate::scene::{Element, Scene, Style};
 
 /// Indexed element with stable identity and content hash
@@ -26,8 +27,21 @@ impl IndexedElement {
 }
 
 /// Compute stable ID from element's key properties
+///
+/// Graph nodes and edges carry user ids, so their identity is keyed by those alone
+/// (node id, ordered `(from, to)` pair) and survives reordering and layout moves.
+/// Keys can repeat; `IndexedScene::from_scene` tells the repeats apart.
 fn compute_id(el: &Element, order: u64, kind: ElementKind) -> ElementId {
-    let mut h = Fnv1a::default();
+    match el {
+        Element::Node(n) => return ElementId::with_key(0, kind.as_u8(), n.id.as_bytes()),
+        Element::Edge(e) => {
+            let key = [e.from_id.as_bytes(), b"\0", e.to_id.as_bytes()].concat();
+            return ElementId::with_key(0, kind.as_u8(), &key);
+        }
+        _ => {}
+    }
+
+    let mut h = ElementId::hasher(order, kind.as_u8());
     
     match el {
         Element::Rect(r) => { h.write_f32(r.x); h.write_f32(r.y); }
@@ -39,14 +53,13 @@ fn compute_id(el: &Element, order: u64, kind: ElementKind) -> ElementId {
         Element::Text(t) => { h.write_f32(t.x); h.write_f32(t.y); h.write_str(&t.content); }
         Element::Image(i) => h.write_str(&i.href),
         Element::Diamond(d) => { h.write_f32(d.cx); h.write_f32(d.cy); }
-        Element::Node(n) => { h.write_str(&n.id); h.write_f32(n.cx); h.write_f32(n.cy); }
-        Element::Edge(e) => { h.write_str(&e.from_id); h.write_str(&e.to_id); }
-        Element::Group(_, tf) => if let Some(t) = tf { h.write_str(t); },
+        Element::Node(_) | Element::Edge(_) => {}
+        Element::Group(_, tf, _) => if let Some(t) = tf { h.write_str(t); },
         Element::Graph(g) => { h.write_str(&g.layout); h.write_str(&g.direction); }
         Element::Use(u) => { h.write_str(&u.href); h.write_f32(u.x); h.write_f32(u.y); }
     }
     
-    ElementId::with_key(order, kind.as_u8(), &h.finish().to_le_bytes())
+    ElementId::from_hasher(h)
 }
 
 /// Get element kind discriminant
@@ -64,7 +77,7 @@ pub fn element_kind(el: &Element) -> ElementKind {
         Element::Diamond(_) => ElementKind::Diamond,
         Element::Node(_) => ElementKind::Node,
         Element::Edge(_) => ElementKind::Edge,
-        Element::Group(_, _) => ElementKind::Group,
+        Element::Group(..) => ElementKind::Group,
         Element::Graph(_) => ElementKind::Graph,
         Element::Use(_) => ElementKind::Use,
     }
@@ -92,14 +105,21 @@ pub struct IndexedScene {
 impl IndexedScene {
     pub fn from_scene(scene: &Scene) -> Self {
         let gen = IdGen::default();
+        let mut seen: HashMap<ElementId, u64> = HashMap::new();
         let elements: Vec<_> = scene.elements()
             .iter()
             .enumerate()
-            .map(|(idx, el)| IndexedElement::new(el, gen.next(), idx))
+            .map(|(idx, el)| {
+                let mut ie = IndexedElement::new(el, gen.next(), idx);
+                // Repeated node ids and parallel edges share a key; the nth repeat is keyed by n too
+                let repeat = seen.entry(ie.id).or_insert(0);
+                if *repeat > 0 { ie.id = ElementId::with_key(*repeat, ie.kind.as_u8(), &ie.id.0.to_le_bytes()); }
+                *repeat += 1;
+                ie
+            })
             .collect();
         
-        let id_map = elements.iter().map(|e| (e.id, e.index)).collect();
-        Self { elements, id_map }
+        Self::from_elements(elements)
     }
 
     #[inline]
@@ -112,6 +132,72 @@ impl IndexedScene {
 
     #[inline]
     pub fn is_empty(&self) -> bool { self.elements.is_empty() }
+
+    fn from_elements(elements: Vec<IndexedElement>) -> Self {
+        let id_map = elements.iter().map(|e| (e.id, e.index)).collect();
+        Self { elements, id_map }
+    }
+}
+
+/// Indexes of the most recently diffed scenes, keyed by `Scene::revision`.
+///
+/// A revision changes whenever a scene's elements may have, so a hit costs one
+/// lookup and repeated diffs (each frame's new scene being the next one's old)
+/// skip rehashing every element's markup.
+#[derive(Debug, Default)]
+pub struct IndexCache {
+    /// Least recently used first
+    slots: Vec<(u64, IndexedScene)>,
+    builds: usize,
+}
+
+impl IndexCache {
+    /// Scenes kept: enough for both sides of a diff
+    const SLOTS: usize = 2;
+
+    pub fn new() -> Self { Self::default() }
+
+    /// Index of `scene`, rebuilt only when no cached index matches its revision
+    pub fn index(&mut self, scene: &Scene) -> &IndexedScene {
+        let slot = self.slot(scene);
+        &self.slots[slot].1
+    }
+
+    /// `diff(old, new)`, reusing cached indexes for either side
+    pub fn diff(&mut self, old: &Scene, new: &Scene) -> DiffResult {
+        if let Some(result) = redraw_check(old, new) { return result; }
+        self.slot(old);
+        self.slot(new);
+        let get = |s: &Scene| self.slots.iter().find(|(rev, _)| *rev == s.revision()).map(|(_, ix)| ix);
+        match (get(old), get(new)) {
+            (Some(old_ix), Some(new_ix)) => diff_indexed(old, new, old_ix, new_ix),
+            _ => diff_indexed(old, new, &IndexedScene::from_scene(old), &IndexedScene::from_scene(new)),
+        }
+    }
+
+    fn slot(&mut self, scene: &Scene) -> usize {
+        let rev = scene.revision();
+        if let Some(i) = self.slots.iter().position(|(r, _)| *r == rev) {
+            let hit = self.slots.remove(i);
+            self.slots.push(hit);
+        } else {
+            self.builds += 1;
+            if self.slots.len() == Self::SLOTS { self.slots.remove(0); }
+            self.slots.push((rev, IndexedScene::from_scene(scene)));
+        }
+        self.slots.len() - 1
+    }
+
+    /// Drop the cached indexes
+    pub fn clear(&mut self) { self.slots.clear(); }
+
+    /// Number of indexes built rather than reused
+    pub fn build_count(&self) -> usize { self.builds }
+}
+
+thread_local! {
+    /// Backs `diff`, which hosts call on every update, usually with one side unchanged
+    static INDEX_CACHE: RefCell<IndexCache> = RefCell::new(IndexCache::new());
 }
 
 /// Diff result with operations
@@ -139,34 +225,37 @@ impl DiffResult {
 
 /// Diff two scenes using indexed reconciliation
 pub fn diff(old: &Scene, new: &Scene) -> DiffResult {
-    if old.size != new.size || old.background != new.background {
-        return DiffResult::full_redraw();
+    INDEX_CACHE.with(|cache| cache.borrow_mut().diff(old, new))
+}
+
+/// `Some` when the scenes can't be patched by element index and must redraw
+fn redraw_check(old: &Scene, new: &Scene) -> Option<DiffResult> {
+    if old.size != new.size || old.aspect != new.aspect || old.background != new.background
+        || old.background_gradient != new.background_gradient || old.background_image != new.background_image {
+        return Some(DiffResult::full_redraw());
+    }
+    // Ops address top-level SVG children by element index, which layers and z reordering break
+    if !old.paints_in_order() || !new.paints_in_order() {
+        return Some(DiffResult::full_redraw());
     }
+    (old.elements().is_empty() && new.elements().is_empty()).then(DiffResult::empty)
+}
 
+fn diff_indexed(old: &Scene, new: &Scene, old_indexed: &IndexedScene, new_indexed: &IndexedScene) -> DiffResult {
     let old_els = old.elements();
     let new_els = new.elements();
-
-    if old_els.is_empty() && new_els.is_empty() {
-        return DiffResult::empty();
-    }
-
-    let old_indexed = IndexedScene::from_scene(old);
-    let gen = IdGen::default();
-    
     let mut ops = Vec::new();
     let mut matched: Vec<bool> = vec![false; old_els.len()];
 
-    for (new_idx, new_el) in new_els.iter().enumerate() {
-        let new_kind = element_kind(new_el);
-        let new_id = compute_id(new_el, gen.next(), new_kind);
-        let new_hash = ContentHash::from_svg(&new_el.to_svg());
-
+    for (new_ie, new_el) in new_indexed.elements.iter().zip(new_els) {
+        let (new_id, new_idx) = (new_ie.id, new_ie.index);
         if let Some(old_ie) = old_indexed.get(&new_id) {
             matched[old_ie.index] = true;
             
-            if old_ie.hash != new_hash {
+            if old_ie.hash != new_ie.hash {
                 let attrs = diff_attrs(&old_els[old_ie.index], new_el);
-                let svg = if attrs.len() > 3 { Some(new_el.to_svg()) } else { None };
+                // Composite elements (nodes, edges) have no attribute diff; resend their markup
+                let svg = if attrs.is_empty() || attrs.len() > 3 { Some(new_el.to_svg()) } else { None };
                 ops.push(DiffOp::Update { id: new_id.0, idx: new_idx, attrs, svg });
             }
             
@@ -178,12 +267,9 @@ pub fn diff(old: &Scene, new: &Scene) -> DiffResult {
         }
     }
 
-    for (old_idx, &was_matched) in matched.iter().enumerate().rev() {
+    for (old_ie, &was_matched) in old_indexed.elements.iter().zip(&matched).rev() {
         if !was_matched {
-            let old_el = &old_els[old_idx];
-            let old_kind = element_kind(old_el);
-            let old_id = compute_id(old_el, old_idx as u64, old_kind);
-            ops.push(DiffOp::Remove { id: old_id.0, idx: old_idx });
+            ops.push(DiffOp::Remove { id: old_ie.id.0, idx: old_ie.index });
         }
     }
 
@@ -197,7 +283,7 @@ pub fn diff(old: &Scene, new: &Scene) -> DiffResult {
 }
 
 fn build_defs_svg(scene: &Scene) -> String {
-    let mut svg = String::new();
+    let mut svg = scene.background_defs();
     for g in scene.gradients() { svg.push_str(&g.to_svg()); }
     for f in scene.filters() { svg.push_str(&f.to_svg()); }
     for s in scene.symbols() { svg.push_str(&s.to_svg_def()); }
@@ -253,6 +339,7 @@ fn diff_attrs(old: &Element, new: &Element) -> Vec<(String, String)> {
             if o.size != n.size { changes.push(("font-size".into(), n.size.to_string())); }
             if o.weight != n.weight { changes.push(("font-weight".into(), n.weight.clone())); }
             if o.anchor != n.anchor { changes.push(("text-anchor".into(), n.anchor.clone())); }
+            if o.baseline != n.baseline { changes.push(("dominant-baseline".into(), n.baseline.clone().unwrap_or_else(|| "auto".into()))); }
             diff_style(&o.style, &n.style, &mut changes);
             diff_transform(&o.transform, &n.transform, &mut changes);
         }
@@ -262,6 +349,8 @@ fn diff_attrs(old: &Element, new: &Element) -> Vec<(String, String)> {
             if o.w != n.w { changes.push(("width".into(), n.w.to_string())); }
             if o.h != n.h { changes.push(("height".into(), n.h.to_string())); }
             if o.href != n.href { changes.push(("href".into(), n.href.clone())); }
+            if o.hidden != n.hidden { changes.push(display_change(n.hidden)); }
+            if o.fit != n.fit { changes.push(("preserveAspectRatio".into(), n.fit.as_deref().and_then(super::super::scene::preserve_aspect_ratio).unwrap_or("xMidYMid meet").into())); }
             diff_transform(&o.transform, &n.transform, &mut changes);
         }
         (Element::Polygon(o), Element::Polygon(n)) => {
@@ -291,6 +380,12 @@ fn diff_style(old: &Style, new: &Style, out: &mut Vec<(String, String)>) {
     if old.stroke_width != new.stroke_width {
         out.push(("stroke-width".into(), new.stroke_width.to_string()));
     }
+    if old.dash != new.dash {
+        out.push(("stroke-dasharray".into(), new.dash.clone().unwrap_or_default()));
+    }
+    if old.dash_offset != new.dash_offset {
+        out.push(("stroke-dashoffset".into(), new.dash_offset.to_string()));
+    }
     if old.opacity != new.opacity {
         out.push(("opacity".into(), new.opacity.to_string()));
     }
@@ -298,6 +393,16 @@ fn diff_style(old: &Style, new: &Style, out: &mut Vec<(String, String)>) {
         let val = new.filter.as_ref().map(|f| format!("url(#{})", f)).unwrap_or_default();
         out.push(("filter".into(), val));
     }
+    if old.hidden != new.hidden { out.push(display_change(new.hidden)); }
+    // Dropped keys clear their attribute like an unset fill does
+    let changed = new.meta.iter().filter(|(k, v)| old.meta.get(*k) != Some(v)).map(|(k, v)| (k, v.as_str()));
+    let dropped = old.meta.keys().filter(|k| !new.meta.contains_key(*k)).map(|k| (k, ""));
+    for (key, value) in changed.chain(dropped) { out.push((format!("data-{}", key), value.into())); }
+}
+
+#[inline]
+fn display_change(hidden: bool) -> (String, String) {
+    ("display".into(), if hidden { "none" } else { "inline" }.into())
 }
 
 #[inline]
@@ -312,8 +417,8 @@ pub type Patch = DiffOp;
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::scene::{Circle, Rect};
-    use crate::CanvasSize;
+    use crate::scene::{Circle, Edge, Node, Rect};
+    use crate::{AspectRatio, CanvasSize};
 
     fn make_scene(size: CanvasSize, bg: &str) -> Scene {
         Scene::new(size, bg.to_string())
@@ -345,6 +450,7 @@ mod tests {
         let s1 = make_scene(CanvasSize::Large, "#fff");
         let s2 = make_scene(CanvasSize::Giant, "#fff");
         assert!(diff(&s1, &s2).needs_full_redraw());
+        assert!(diff(&s1, &s1.clone().with_aspect(AspectRatio { w: 2, h: 1 })).needs_full_redraw());
     }
 
     #[test]
@@ -354,6 +460,32 @@ mod tests {
         assert!(indexed.is_empty());
     }
 
+    #[test]
+    fn test_index_cache_reuses_unchanged_scene() {
+        let mut scene = Scene::new(CanvasSize::Medium, "#fff".into());
+        scene.push(node("a", 10.0));
+        scene.push(edge("a", "b"));
+        let mut cache = IndexCache::new();
+        let first: Vec<_> = cache.index(&scene).elements.iter().map(|e| (e.id, e.hash)).collect();
+        assert_eq!(cache.index(&scene.clone()).elements.iter().map(|e| (e.id, e.hash)).collect::<Vec<_>>(), first);
+        assert_eq!(cache.build_count(), 1);
+
+        // Editing a clone busts the cache for the clone only
+        let mut renamed = scene.clone();
+        if let Element::Edge(e) = &mut renamed.elements_mut()[1] { e.to_id = "c".into(); }
+        assert_eq!(cache.index(&renamed).elements[1].id, ids(&renamed)[1]);
+        assert_eq!(cache.build_count(), 2);
+
+        // Both sides of a diff stay cached, and the result matches an uncached diff
+        let ops = cache.diff(&scene, &renamed).ops;
+        assert_eq!(cache.build_count(), 2);
+        assert_eq!(ops, diff_indexed(&scene, &renamed, &IndexedScene::from_scene(&scene), &IndexedScene::from_scene(&renamed)).ops);
+
+        scene.push(node("b", 120.0));
+        assert_eq!(cache.index(&scene).len(), 3);
+        assert_eq!(cache.build_count(), 3);
+    }
+
     #[test]
     fn test_element_kind_rect() {
         let el = Element::Rect(Rect { x: 0.0, y: 0.0, w: 100.0, h: 50.0, rx: 0.0, style: Style::default(), transform: None });
@@ -365,4 +497,89 @@ mod tests {
         let el = Element::Circle(Circle { cx: 50.0, cy: 50.0, r: 25.0, style: Style::default(), transform: None });
         assert_eq!(element_kind(&el), ElementKind::Circle);
     }
+
+    fn node(id: &str, cx: f32) -> Element {
+        Element::Node(Node {
+            id: id.into(), shape: "rect".into(), cx, cy: 50.0, w: 80.0, h: 40.0, label: None,
+            style: Style::default(), label_style: Style::default(), transform: None,
+        })
+    }
+
+    fn edge(from: &str, to: &str) -> Element {
+        Element::Edge(Edge {
+            from_id: from.into(), to_id: to.into(), from_pt: (0.0, 0.0), to_pt: (10.0, 10.0),
+            edge_style: "straight".into(), arrow: "forward".into(), arrow_head: "triangle".into(), arrow_size: 10.0,
+            label: None, label_bg: None, style: Style::default(),
+        })
+    }
+
+    fn ids(scene: &Scene) -> Vec<ElementId> {
+        IndexedScene::from_scene(scene).elements.iter().map(|e| e.id).collect()
+    }
+
+    #[test]
+    fn test_graph_ids_keyed_by_user_id() {
+        let mut s1 = make_scene(CanvasSize::Large, "#fff");
+        s1.push(node("a", 50.0));
+        s1.push(node("b", 150.0));
+        s1.push(edge("a", "b"));
+        let mut s2 = make_scene(CanvasSize::Large, "#fff");
+        s2.push(node("renamed", 50.0));
+        s2.push(node("b", 300.0));
+        s2.push(edge("a", "b"));
+        let (before, after) = (ids(&s1), ids(&s2));
+        assert_ne!(before[0], after[0]);
+        assert_eq!(before[1..], after[1..]);
+        assert_ne!(ids(&s1)[2], compute_id(&edge("b", "a"), 2, ElementKind::Edge));
+    }
+
+    #[test]
+    fn test_parallel_edges_keep_distinct_ids() {
+        let labelled = |label: &str| match edge("a", "b") {
+            Element::Edge(e) => Element::Edge(Edge { label: Some(label.into()), ..e }),
+            _ => unreachable!(),
+        };
+        let mut s1 = make_scene(CanvasSize::Large, "#fff");
+        [node("a", 50.0), node("a", 150.0), labelled("x"), labelled("y")].into_iter().for_each(|el| s1.push(el));
+        let before = ids(&s1);
+        assert_eq!(IndexedScene::from_scene(&s1).id_map.len(), 4);
+        assert!(before[0] != before[1] && before[2] != before[3]);
+
+        let mut s2 = s1.clone();
+        s2.elements_mut()[3] = labelled("z");
+        assert_eq!(ids(&s2), before);
+        let ops = diff(&s1, &s2).ops;
+        assert!(matches!(ops[..], [DiffOp::Update { idx: 3, svg: Some(ref svg), .. }] if svg.contains(">z<")), "{:?}", ops);
+    }
+
+    #[test]
+    fn test_moved_node_diffs_as_update() {
+        let mut s1 = make_scene(CanvasSize::Large, "#fff");
+        s1.push(node("a", 50.0));
+        s1.push(node("b", 150.0));
+        let mut s2 = make_scene(CanvasSize::Large, "#fff");
+        s2.push(node("b", 300.0));
+        s2.push(node("a", 50.0));
+        let ops = diff(&s1, &s2).ops;
+        assert!(ops.iter().all(|op| matches!(op, DiffOp::Update { svg: Some(_), .. } | DiffOp::Move { .. })), "{:?}", ops);
+        assert_eq!(ops.iter().filter(|op| matches!(op, DiffOp::Move { .. })).count(), 2);
+    }
+
+    #[test]
+    fn test_visibility_toggle_diffs_as_update() {
+        let rect = |x: f32| Element::Rect(Rect { x, y: 0.0, w: 10.0, h: 10.0, rx: 0.0, style: Style::with_fill("#f00"), transform: None });
+        let mut s1 = make_scene(CanvasSize::Large, "#fff");
+        (0..3).for_each(|i| s1.push(rect(i as f32 * 20.0)));
+        let mut s2 = make_scene(CanvasSize::Large, "#fff");
+        (0..3).for_each(|i| s2.push(rect(i as f32 * 20.0)));
+        s2.elements_mut()[1].set_visible(false);
+
+        assert_eq!(ids(&s1), ids(&s2));
+        assert!(!s2.elements()[1].visible());
+        assert!(s2.elements()[1].to_svg().starts_with(r#"<rect display="none" x="20""#));
+        let ops = diff(&s1, &s2).ops;
+        assert_eq!(ops, vec![DiffOp::Update { id: ids(&s2)[1].0, idx: 1, attrs: vec![("display".into(), "none".into())], svg: None }]);
+        s2.elements_mut()[1].set_visible(true);
+        assert!(diff(&s1, &s2).is_empty());
+    }
 }
@@ -2,10 +2,15 @@
 
 mod cache;
 mod command;
+mod defs;
 mod diff;
+mod patch;
 mod render;
 
 pub use cache::{CacheStats, CachedRenderer, RenderCache};
 pub use command::{CommandHistory, SceneCommand};
-pub use diff::{DiffOp, DiffResult, IndexedElement, IndexedScene, Patch, diff, element_kind};
-pub use render::{RenderPatch, compute_patches, diff_scenes, index_scene, needs_redraw};
+pub use defs::collect_defs;
+pub(crate) use defs::rename_refs;
+pub use diff::{DiffOp, DiffResult, IndexCache, IndexedElement, IndexedScene, Patch, diff, element_kind};
+pub use patch::apply_patches;
+pub use render::{RenderPatch, compute_patches, diff_scenes, index_scene, needs_redraw, render_dsl_to_svg, with_id_prefix};
@@ -0,0 +1,226 @@
+//! Apply diff ops to previously rendered SVG markup
+//!
+//! Splits a cached document into its preamble (style, background, defs) and its
+//! top-level element fragments, rebuilds the fragment list from the ops, and joins
+//! it back together. Unchanged elements are copied verbatim, never re-rendered.
+
+use std::collections::HashMap;
+use super::diff::DiffOp;
+use crate::scene::html_escape;
+
+/// Apply `ops` (from diffing the scene that rendered `prev_svg`) to the markup.
+///
+/// `FullRedraw` carries no markup, so the input is returned untouched and the
+/// caller must fall back to `Scene::render_svg`.
+pub fn apply_patches(prev_svg: &str, ops: &[DiffOp]) -> String {
+    if ops.iter().any(|op| matches!(op, DiffOp::FullRedraw)) { return prev_svg.to_string(); }
+    let Some(doc) = SvgDoc::parse(prev_svg) else { return prev_svg.to_string() };
+
+    let old = &doc.children[doc.first_element..];
+    let (adds, removes) = ops.iter().fold((0, 0), |(a, r), op| match op {
+        DiffOp::Add { .. } => (a + 1, r),
+        DiffOp::Remove { .. } => (a, r + 1),
+        _ => (a, r),
+    });
+    let len = (old.len() + adds).saturating_sub(removes);
+
+    // Untouched slots keep their index; moves and adds override the source
+    let mut slots: Vec<Option<String>> = (0..len).map(|i| old.get(i).map(|s| s.to_string())).collect();
+    let moved: HashMap<u64, usize> = ops.iter().filter_map(|op| match op {
+        DiffOp::Move { id, from, .. } => Some((*id, *from)),
+        _ => None,
+    }).collect();
+    for op in ops {
+        match op {
+            DiffOp::Move { from, to, .. } if *to < len => slots[*to] = old.get(*from).map(|s| s.to_string()),
+            DiffOp::Add { idx, svg, .. } if *idx < len => slots[*idx] = Some(svg.clone()),
+            _ => {}
+        }
+    }
+    for op in ops {
+        let DiffOp::Update { id, idx, attrs, svg } = op else { continue };
+        if *idx >= len { continue; }
+        slots[*idx] = match svg {
+            Some(svg) => Some(svg.clone()),
+            None => old.get(moved.get(id).copied().unwrap_or(*idx))
+                .map(|el| attrs.iter().fold(el.to_string(), |el, (name, value)| set_attr(&el, name, value))),
+        };
+    }
+
+    let mut preamble: Vec<String> = doc.children[..doc.first_element].iter().map(|s| s.to_string()).collect();
+    if let Some(DiffOp::UpdateDefs { svg }) = ops.iter().rev().find(|op| matches!(op, DiffOp::UpdateDefs { .. })) {
+        // Arrow markers are emitted per render, not tracked by the diff: keep them
+        let markers: String = doc.defs.and_then(|i| inner_children(doc.children[i]))
+            .map(|c| c.into_iter().filter(|c| c.starts_with("<marker")).collect())
+            .unwrap_or_default();
+        let defs = (!svg.is_empty() || !markers.is_empty()).then(|| format!("<defs>{}{}</defs>", svg, markers));
+        match (doc.defs, defs) {
+            (Some(i), Some(defs)) => preamble[i] = defs,
+            (Some(i), None) => { preamble.remove(i); }
+            (None, Some(defs)) => preamble.push(defs),
+            (None, None) => {}
+        }
+    }
+
+    let mut out = String::with_capacity(prev_svg.len());
+    out.push_str(doc.open);
+    for s in preamble.iter().chain(slots.iter().flatten()) { out.push_str(s); }
+    out.push_str(doc.close);
+    out
+}
+
+/// Rendered document split at top-level element boundaries
+struct SvgDoc<'a> {
+    open: &'a str,
+    children: Vec<&'a str>,
+    close: &'a str,
+    defs: Option<usize>,
+    first_element: usize,
+}
+
+impl<'a> SvgDoc<'a> {
+    fn parse(svg: &'a str) -> Option<Self> {
+        let open_end = tag_end(svg)?;
+        let close_start = svg.rfind("</svg>")?;
+        let children = split_fragments(svg.get(open_end..close_start)?)?;
+        // Preamble layout matches Scene::render_svg: [<style>] [background] [<defs>]
+        let mut first_element = usize::from(children.first().is_some_and(|c| c.starts_with("<style")));
+        if children.get(first_element).is_some_and(|c| c.starts_with(crate::svg::BACKGROUND_RECT_OPEN)) { first_element += 1; }
+        let defs = children.get(first_element).filter(|c| c.starts_with("<defs")).map(|_| first_element);
+        if defs.is_some() { first_element += 1; }
+        (first_element <= children.len()).then_some(Self {
+            open: &svg[..open_end], children, close: &svg[close_start..], defs, first_element,
+        })
+    }
+}
+
+/// Byte offset just past the `>` closing the tag at the start of `s`
+fn tag_end(s: &str) -> Option<usize> {
+    let mut quote = None;
+    for (i, c) in s.char_indices() {
+        match (quote, c) {
+            (None, '"' | '\'') => quote = Some(c),
+            (Some(q), c) if c == q => quote = None,
+            (None, '>') => return Some(i + 1),
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Split markup into its sibling elements (text and CSS never contain a raw `<`)
+fn split_fragments(s: &str) -> Option<Vec<&str>> {
+    let (mut out, mut start, mut depth, mut i) = (Vec::new(), 0, 0usize, 0);
+    while let Some(off) = s[i..].find('<') {
+        let tag_start = i + off;
+        let end = tag_start + tag_end(&s[tag_start..])?;
+        let tag = &s[tag_start..end];
+        if depth == 0 { start = tag_start; }
+        if tag.starts_with("</") { depth = depth.checked_sub(1)?; } else if !tag.ends_with("/>") { depth += 1; }
+        if depth == 0 { out.push(&s[start..end]); }
+        i = end;
+    }
+    (depth == 0).then_some(out)
+}
+
+fn inner_children(el: &str) -> Option<Vec<&str>> {
+    let open = tag_end(el)?;
+    split_fragments(el.get(open..el.rfind("</")?)?)
+}
+
+/// Set, replace, or (for an empty value) remove an attribute on the root tag.
+/// `textContent` replaces the element's text instead.
+fn set_attr(el: &str, name: &str, value: &str) -> String {
+    let Some(open) = tag_end(el) else { return el.to_string() };
+    if name == "textContent" {
+        let close = el.rfind("</").filter(|&c| c >= open).unwrap_or(open);
+        return format!("{}{}{}", &el[..open], html_escape(value), &el[close..]);
+    }
+    let needle = format!(r#" {}=""#, name);
+    match el[..open].find(&needle) {
+        Some(at) => {
+            let val_start = at + needle.len();
+            let val_end = el[val_start..open].find('"').map_or(open, |e| val_start + e);
+            if value.is_empty() {
+                format!("{}{}", &el[..at], &el[val_end + 1..])
+            } else {
+                format!("{}{}{}", &el[..val_start], value, &el[val_end..])
+            }
+        }
+        None if value.is_empty() => el.to_string(),
+        None => {
+            let insert = if el[..open].ends_with("/>") { open - 2 } else { open - 1 };
+            format!(r#"{} {}="{}"{}"#, &el[..insert], name, value, &el[insert..])
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scene::{Circle, Element, Gradient, Node, Rect, Scene, Style, Text};
+    use crate::CanvasSize;
+
+    fn rect(x: f32, fill: &str) -> Element {
+        Element::Rect(Rect { x, y: 0.0, w: 10.0, h: 10.0, rx: 0.0, style: Style::with_fill(fill), transform: None })
+    }
+
+    fn node(id: &str) -> Element {
+        Element::Node(Node {
+            id: id.into(), shape: "rect".into(), cx: 0.0, cy: 0.0, w: 80.0, h: 40.0, label: Some(id.into()),
+            style: Style::default(), label_style: Style::default(), transform: None,
+        })
+    }
+
+    fn assert_patches(a: &Scene, b: &Scene) {
+        let patched = apply_patches(&a.render_svg(), &a.diff(b).ops);
+        assert_eq!(patched, b.render_svg());
+    }
+
+    #[test]
+    fn test_apply_patches_reproduces_target() {
+        let mut a = Scene::new(CanvasSize::Medium, "#fff".into());
+        for el in [rect(0.0, "#f00"), node("a"), node("b"), rect(5.0, "#000")] { a.push(el); }
+        let mut b = Scene::new(CanvasSize::Medium, "#fff".into());
+        let circle = Element::Circle(Circle { cx: 5.0, cy: 5.0, r: 2.0, style: Style::default(), transform: None });
+        for el in [rect(0.0, "#00f"), node("b"), node("a"), circle] { b.push(el); }
+        assert_patches(&a, &b);
+        assert_patches(&b, &a);
+        assert_patches(&a, &Scene::new(CanvasSize::Medium, "#fff".into()));
+    }
+
+    #[test]
+    fn test_apply_patches_text_and_defs() {
+        let text = |content: &str| Element::Text(Text {
+            x: 0.0, y: 10.0, content: content.into(), font: "system-ui".into(), size: 16.0, weight: "normal".into(),
+            anchor: "start".into(), baseline: None, style: Style::default(), transform: None,
+            letter_spacing: 0.0, word_spacing: 0.0, decoration: None, direction: None,
+        });
+        let mut a = Scene::new(CanvasSize::Medium, "#fff".into());
+        a.push(text("a < b"));
+        let mut b = Scene::new(CanvasSize::Medium, "#fff".into());
+        b.push(text("b > a"));
+        b.push_gradient(Gradient { id: "g".into(), kind: "linear".into(), from_color: "#000".into(), to_color: "#fff".into(), angle: 90.0, shared: false });
+        assert_patches(&a, &b);
+        assert_patches(&b, &a);
+    }
+
+    #[test]
+    fn test_apply_patches_transparent_background() {
+        let scene = |fill: &str| {
+            let mut s = Scene::new(CanvasSize::Medium, "none".into());
+            for el in [rect(0.0, "#f00"), rect(20.0, fill)] { s.push(el); }
+            s
+        };
+        assert!(!scene("#000").render_svg().contains("100%"));
+        assert_patches(&scene("#000"), &scene("#0f0"));
+        assert_patches(&scene("#000"), &Scene::new(CanvasSize::Medium, "none".into()));
+    }
+
+    #[test]
+    fn test_set_attr() {
+        assert_eq!(set_attr(r##"<rect x="0" fill="#f00"/>"##, "fill", "#00f"), r##"<rect x="0" fill="#00f"/>"##);
+        assert_eq!(set_attr(r##"<rect x="0" fill="#f00"/>"##, "fill", ""), r#"<rect x="0"/>"#);
+        assert_eq!(set_attr(r#"<rect x="0"/>"#, "transform", "scale(2)"), r#"<rect x="0" transform="scale(2)"/>"#);
+    }
+}
@@ -6,8 +6,40 @@ use pyo3::prelude::*;
 use pyo3::types::PyDict;
 
 use super::diff::{self, DiffOp, IndexedScene};
+use crate::dsl::ParseError;
 use crate::scene::Scene;
 
+/// Render DSL source to SVG through the full pipeline (lex, parse, lower, render),
+/// returning every diagnostic alongside. The entry point for golden tests: its
+/// output for a given source only changes when rendering itself does.
+pub fn render_dsl_to_svg(source: &str) -> (String, Vec<ParseError>) {
+    crate::scene::compile(source, None)
+}
+
+/// Render `scene` with `prefix` on every emitted `id` and on each `url(#..)` and
+/// `href="#.."` reference to one, so several scenes can be inlined in one page
+/// without their gradients, filters, symbols and markers colliding.
+///
+/// Only markup inside tags is rewritten; text content and external hrefs are left alone.
+pub fn with_id_prefix(scene: &Scene, prefix: &str) -> String { prefix_ids(&scene.render_svg(), prefix) }
+
+fn prefix_ids(svg: &str, prefix: &str) -> String {
+    let mut out = String::with_capacity(svg.len());
+    let mut rest = svg;
+    while let Some(open) = rest.find('<') {
+        out.push_str(&rest[..open]);
+        let close = rest[open..].find('>').map_or(rest.len(), |i| open + i + 1);
+        let tag = &rest[open..close];
+        let tag = tag.replace(" id=\"", &format!(" id=\"{}", prefix))
+            .replace("url(#", &format!("url(#{}", prefix))
+            .replace("href=\"#", &format!("href=\"#{}", prefix));
+        out.push_str(&tag);
+        rest = &rest[close..];
+    }
+    out.push_str(rest);
+    out
+}
+
 /// Compute diff between two scenes for minimal updates
 pub fn diff_scenes(old: &Scene, new: &Scene) -> Vec<DiffOp> {
     let result = diff::diff(old, new);
@@ -53,3 +85,33 @@ pub fn needs_redraw(old: &Scene, new: &Scene) -> bool { !diff::diff(old, new).is
 
 #[cfg_attr(feature = "python", pyfunction)]
 pub fn index_scene(scene: &Scene) -> usize { IndexedScene::from_scene(scene).len() }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dsl::{Lexer, Parser};
+    use crate::scene::build_scene;
+
+    fn attr_values<'a>(svg: &'a str, key: &str, end: char) -> Vec<&'a str> {
+        svg.match_indices(key).map(|(i, _)| {
+            let rest = &svg[i + key.len()..];
+            &rest[..rest.find(end).unwrap()]
+        }).collect()
+    }
+
+    #[test]
+    fn test_with_id_prefix_keeps_references_resolving() {
+        let src = "symbol \"dot\"\n  circle 4\nuse \"dot\" at 10,10\nrect at 0,0 size 5x5\n  gradient linear #000 #fff\n  shadow 0,2 4 #0004\nline from 0,0 to 100,0 arrow both\ntext \"url(#d1)\" at 0,30";
+        let scene = build_scene(&Parser::new(Lexer::new(src).tokenize()).parse());
+        let svg = with_id_prefix(&scene, "a-");
+
+        let ids = attr_values(&svg, " id=\"", '"');
+        let refs: Vec<_> = attr_values(&svg, "\"url(#", ')').into_iter().chain(attr_values(&svg, "href=\"#", '"')).collect();
+        assert!(ids.len() >= 5 && refs.len() >= 5, "{}", svg);
+        assert!(ids.iter().chain(&refs).all(|id| id.starts_with("a-")), "{}", svg);
+        assert!(refs.iter().all(|r| ids.contains(r)), "{}", svg);
+        // Text content that looks like a reference is left as written
+        assert!(svg.contains(">url(#d1)</text>"), "{}", svg);
+        assert_eq!(with_id_prefix(&scene, ""), scene.render_svg());
+    }
+}
@@ -0,0 +1,56 @@
+//! Compact binary scenes for caches and network transfer
+//!
+//! The encoding is bincode of `(version, scene)`, with the same
+//! [`FORMAT_VERSION`] as the JSON formats, so a blob from a newer iconoglott is
+//! rejected instead of misread.
+
+use crate::dsl::{ErrorKind, ParseError, FORMAT_VERSION};
+use super::scene::Scene;
+
+impl Scene {
+    /// Bincode encoding, prefixed with the format version
+    pub fn to_bincode(&self) -> Vec<u8> {
+        bincode::serialize(&(FORMAT_VERSION, self)).expect("scenes always serialize")
+    }
+
+    /// Load a scene written by [`Self::to_bincode`], rejecting newer formats
+    pub fn from_bincode(bytes: &[u8]) -> Result<Scene, Box<ParseError>> {
+        let invalid = |e: bincode::Error| Box::new(ParseError::new(format!("Invalid binary scene: {}", e), ErrorKind::InvalidValue, 0, 0));
+        let version: u32 = bincode::deserialize(bytes).map_err(invalid)?;
+        if version > FORMAT_VERSION {
+            return Err(Box::new(ParseError::new(
+                format!("Scene format version {} is newer than the supported version {}", version, FORMAT_VERSION),
+                ErrorKind::UnsupportedVersion, 0, 0
+            ).with_suggestion("Upgrade iconoglott to load this scene")));
+        }
+        bincode::deserialize::<(u32, Scene)>(bytes).map(|(_, scene)| scene).map_err(invalid)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scene::{build_scene, Element};
+    use crate::dsl::{Lexer, Parser};
+
+    fn scene(src: &str) -> Scene { build_scene(&Parser::new(Lexer::new(src).tokenize()).parse()) }
+
+    #[test]
+    fn test_bincode_round_trip_is_smaller_than_json() {
+        let src = "canvas large fill #222\nrect at 10,10 size 20x20 #f00\n  gradient linear #000 #fff\n  shadow 0,2 4 #0004\ncircle at 40,40 radius 8\n  meta layer \"fg\"\ntext \"Hello\" at 5,80\n  font \"Arial\" 12\ngraph\n  node \"a\" label \"A\"\n  node \"b\" label \"B\"\n  edge \"a\" -> \"b\"";
+        let original = scene(src);
+        assert!(original.elements().iter().any(|e| matches!(e, Element::Graph(_))));
+        let bytes = original.to_bincode();
+        assert_eq!(Scene::from_bincode(&bytes).unwrap(), original);
+        let json = serde_json::to_string(&original).unwrap();
+        assert!(bytes.len() < json.len(), "{} >= {}", bytes.len(), json.len());
+    }
+
+    #[test]
+    fn test_bincode_rejects_newer_and_corrupt_blobs() {
+        let mut bytes = Scene::default().to_bincode();
+        bytes[..4].copy_from_slice(&(FORMAT_VERSION + 1).to_le_bytes());
+        assert_eq!(Scene::from_bincode(&bytes).unwrap_err().kind, ErrorKind::UnsupportedVersion);
+        assert_eq!(Scene::from_bincode(&[1, 0]).unwrap_err().kind, ErrorKind::InvalidValue);
+    }
+}
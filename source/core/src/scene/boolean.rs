@@ -0,0 +1,84 @@
+//! Boolean operations on scene shapes
+//!
+//! Each operand is outlined as path data, flattened, and mapped through its own
+//! `transform`, so the clip runs in scene coordinates and the result needs no
+//! transform of its own.
+
+use crate::path::{flatten_path, Affine, BoolOp, PolygonClipper};
+use super::scene::Element;
+use super::shape::Path;
+
+/// Combine two shapes into one path styled like `a`. Each operand's outline is
+/// its [`Element::to_path_d`], closed for the clip; elements without one count
+/// as empty. A result with holes gets `fill-rule="evenodd"` unless `a` sets a rule.
+pub fn shape_boolean(a: &Element, b: &Element, op: BoolOp, tolerance: f64) -> Path {
+    let result = PolygonClipper::new(polygon(a, tolerance), polygon(b, tolerance)).compute(op);
+    let d = result.to_path_d();
+    let bounds_hint = Some(crate::path::parse_path_bounds(&d));
+    let mut style = a.style().cloned().unwrap_or_default();
+    if result.has_holes() { style.fill_rule.get_or_insert_with(|| "evenodd".into()); }
+    Path { d, style, transform: None, bounds_hint }
+}
+
+/// Flattened outline in scene coordinates
+fn polygon(el: &Element, tolerance: f64) -> crate::path::Polygon {
+    let mut poly = flatten_path(&el.to_path_d(), tolerance);
+    if let Some(t) = el.transform() {
+        let affine = Affine::parse(t);
+        for p in &mut poly.vertices {
+            let (x, y) = affine.apply(p.x as f32, p.y as f32);
+            (p.x, p.y) = (x as f64, y as f64);
+        }
+    }
+    poly
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scene::{Circle, Rect, Style};
+
+    fn rect(x: f32, y: f32, w: f32, h: f32, transform: Option<&str>) -> Element {
+        Element::Rect(Rect { x, y, w, h, rx: 0.0, style: Style::with_fill("#f00"), transform: transform.map(Into::into) })
+    }
+
+    #[test]
+    fn test_union_of_overlapping_rects() {
+        let path = shape_boolean(&rect(0.0, 0.0, 10.0, 10.0, None), &rect(5.0, 5.0, 10.0, 10.0, None), BoolOp::Union, 0.5);
+        assert_eq!(path.d.matches('M').count(), 1, "{}", path.d);
+        assert_eq!(path.bounds(), (0.0, 0.0, 15.0, 15.0));
+        assert_eq!(path.style.fill.as_deref(), Some("#f00"));
+        assert!(path.transform.is_none());
+    }
+
+    #[test]
+    fn test_intersection_uses_transforms_and_curves() {
+        // The second rect is moved onto the same area, so the overlap is the full square
+        let moved = rect(100.0, 100.0, 10.0, 10.0, Some("translate(-100 -100)"));
+        let path = shape_boolean(&rect(0.0, 0.0, 10.0, 10.0, None), &moved, BoolOp::Intersection, 0.5);
+        let (x, y, w, h) = path.bounds();
+        assert!(x.abs() < 0.01 && y.abs() < 0.01 && (w - 10.0).abs() < 0.01 && (h - 10.0).abs() < 0.01, "{}", path.d);
+
+        let circle = Element::Circle(Circle { cx: 0.0, cy: 0.0, r: 10.0, style: Style::default(), transform: None });
+        let quarter = shape_boolean(&circle, &rect(0.5, 0.5, 20.0, 20.0, None), BoolOp::Intersection, 0.1);
+        let (x, y, w, h) = quarter.bounds();
+        assert!((x - 0.5).abs() < 0.01 && (y - 0.5).abs() < 0.01, "{}", quarter.d);
+        assert!((w - 9.49).abs() < 0.1 && (h - 9.49).abs() < 0.1, "{}", quarter.d);
+    }
+
+    #[test]
+    fn test_difference_hole_renders_as_annulus() {
+        let ring = shape_boolean(&rect(0.0, 0.0, 20.0, 20.0, None), &rect(5.0, 5.0, 10.0, 10.0, None), BoolOp::Difference, 0.5);
+        assert!(ring.to_svg().contains(r##"fill="#f00" fill-rule="evenodd""##), "{}", ring.to_svg());
+
+        // The outer square and the hole wind in opposite directions, so nonzero agrees
+        let contours = crate::path::flatten_contours(&ring.d, 0.5);
+        assert_eq!(contours.len(), 2, "{}", ring.d);
+        let (outer, hole) = (contours[0].signed_area(), contours[1].signed_area());
+        assert!((outer.abs() - 400.0).abs() < 1e-6 && (hole.abs() - 100.0).abs() < 1e-6, "{} {}", outer, hole);
+        assert!(outer.signum() != hole.signum(), "{}", ring.d);
+
+        let solid = shape_boolean(&rect(0.0, 0.0, 20.0, 20.0, None), &rect(10.0, 5.0, 20.0, 10.0, None), BoolOp::Difference, 0.5);
+        assert!(solid.style.fill_rule.is_none());
+    }
+}
@@ -0,0 +1,631 @@
+//! Lower a parsed AST into a renderable scene
+//!
+//! Follows the Python interpreter (`lang/eval.py`) shape for shape: the same prop
+//! defaults, layout measurement, and `d1`, `d2`, ... def ids. Graphs use the native
+//! `GraphContainer`, so edges get arrow markers.
+
+#[cfg(feature = "python")]
+use pyo3::{exceptions::PyValueError, prelude::*};
+use std::collections::HashMap;
+use super::scene::{Element, Filter, Gradient, GraphContainer, Scene, SceneKeyframes};
+use super::shape::{Circle, Color, Edge, Ellipse, Image, Line, Node, Path, Polygon, Rect, Style, Symbol, Text, Use};
+use crate::dsl::{post_resolve, AstGraph, AstNode, AstShape, AstStyle, AstSymbol, AstUse, GradientDef, Lexer, ParseError, Parser, PropValue};
+use crate::CanvasSize;
+
+/// Run the full pipeline: lex, parse, lower, render. `size` overrides the canvas tier.
+pub fn compile(source: &str, size: Option<CanvasSize>) -> (String, Vec<ParseError>) {
+    run_pipeline(source, size, |_| {})
+}
+
+/// Wall time spent in each stage of [`compile`]
+#[cfg(feature = "timings")]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct PipelineTimings {
+    pub lex: std::time::Duration,
+    pub parse: std::time::Duration,
+    /// Z-sorting, viewBox-relative sizes and text fitting
+    pub resolve: std::time::Duration,
+    /// Lowering to a scene and writing SVG
+    pub render: std::time::Duration,
+}
+
+#[cfg(feature = "timings")]
+impl PipelineTimings {
+    pub fn total(&self) -> std::time::Duration { self.lex + self.parse + self.resolve + self.render }
+}
+
+/// [`compile`], also timing each stage
+#[cfg(feature = "timings")]
+pub fn compile_timed(source: &str, size: Option<CanvasSize>) -> (String, Vec<ParseError>, PipelineTimings) {
+    let mut timings = PipelineTimings::default();
+    let mut last = std::time::Instant::now();
+    let (svg, errors) = run_pipeline(source, size, |stage| {
+        let now = std::time::Instant::now();
+        let slot = match stage {
+            Stage::Lex => &mut timings.lex,
+            Stage::Parse => &mut timings.parse,
+            Stage::Resolve => &mut timings.resolve,
+            Stage::Render => &mut timings.render,
+        };
+        *slot = now - last;
+        last = now;
+    });
+    (svg, errors, timings)
+}
+
+/// Pipeline stage just finished, reported to `run_pipeline`'s callback
+#[cfg_attr(not(feature = "timings"), allow(dead_code))]
+enum Stage { Lex, Parse, Resolve, Render }
+
+fn run_pipeline(source: &str, size: Option<CanvasSize>, mut done: impl FnMut(Stage)) -> (String, Vec<ParseError>) {
+    let mut lexer = Lexer::new(source);
+    let tokens = lexer.tokenize();
+    done(Stage::Lex);
+    let mut parser = Parser::new(tokens).with_geometry_warnings();
+    parser.errors.append(&mut lexer.errors);
+    let mut ast = parser.parse();
+    done(Stage::Parse);
+    post_resolve(&mut ast);
+    done(Stage::Resolve);
+    let mut scene = build_scene(&ast);
+    if let Some(size) = size { scene.size = size; }
+    let svg = scene.render_svg();
+    done(Stage::Render);
+    (svg, parser.errors)
+}
+
+/// Build a scene from a parsed (and z-sorted) AST
+pub fn build_scene(ast: &AstNode) -> Scene {
+    let mut builder = Builder::default();
+    builder.node(ast);
+    let Builder { mut scene, gradients, filters, .. } = builder;
+    gradients.into_iter().for_each(|g| scene.push_gradient(g));
+    filters.into_iter().for_each(|f| scene.push_filter(f));
+    scene
+}
+
+#[derive(Default)]
+struct Builder {
+    scene: Scene,
+    gradients: Vec<Gradient>,
+    filters: Vec<Filter>,
+    next_def: usize,
+}
+
+impl Builder {
+    fn def_id(&mut self) -> String {
+        self.next_def += 1;
+        format!("d{}", self.next_def)
+    }
+
+    fn node(&mut self, node: &AstNode) {
+        match node {
+            AstNode::Scene(children) => children.iter().for_each(|c| self.node(c)),
+            AstNode::Canvas(c) => {
+                let scene = &mut self.scene;
+                scene.size = c.size;
+                scene.aspect = c.aspect;
+                scene.background = c.fill.clone();
+                scene.background_gradient = c.gradient.as_ref().map(|g| gradient(String::new(), g));
+                scene.background_image = c.image.clone();
+            }
+            AstNode::Shape(s) => {
+                let mut out = Vec::new();
+                self.shape(s, (0.0, 0.0), &mut out);
+                // `sort_by_z` already ordered the shapes; keep `z` so later edits restack the same way
+                let z = num(&s.props, "z").unwrap_or(0.0) as i32;
+                out.iter_mut().for_each(|el| el.set_z(z));
+                self.scene.elements_mut().extend(out);
+            }
+            AstNode::Graph(g) => self.scene.push(Element::Graph(graph(g))),
+            AstNode::Symbol(s) => {
+                let symbol = self.symbol(s);
+                self.scene.push_symbol(symbol);
+            }
+            AstNode::Use(u) => self.scene.push(Element::Use(use_ref(u))),
+            AstNode::Keyframes(kf) => self.scene.push_keyframes(SceneKeyframes::new(kf.name.clone(), kf.to_css())),
+            AstNode::Variable { .. } | AstNode::Include(_) => {}
+        }
+    }
+
+    fn symbol(&mut self, s: &AstSymbol) -> Symbol {
+        let mut children = Vec::new();
+        for c in &s.children { self.shape(c, (0.0, 0.0), &mut children); }
+        children.extend(s.uses.iter().map(|u| Element::Use(use_ref(u))));
+        let viewbox = s.viewbox.map(|(x, y, w, h)| (x as f32, y as f32, w as f32, h as f32));
+        Symbol { id: s.id.clone(), viewbox, children }
+    }
+
+    fn shape(&mut self, s: &AstShape, offset: (f64, f64), out: &mut Vec<Element>) {
+        let props = &s.props;
+        let (x, y) = pair(props, "at").unwrap_or((0.0, 0.0));
+        let (x, y) = ((x + offset.0) as f32, (y + offset.1) as f32);
+        let transform = s.transform.to_svg();
+        let style = self.style(s);
+
+        let mut el = match s.kind.as_str() {
+            "rect" => {
+                let (w, h) = pair(props, "size").unwrap_or((100.0, 100.0));
+                Element::Rect(Rect { x, y, w: w as f32, h: h as f32, rx: s.style.corner as f32, style, transform })
+            }
+            "circle" => Element::Circle(Circle { cx: x, cy: y, r: num(props, "radius").unwrap_or(50.0) as f32, style, transform }),
+            "ellipse" => {
+                let (rx, ry) = num(props, "radius").map(|r| (r, r))
+                    .or_else(|| pair(props, "radius"))
+                    .or_else(|| pair(props, "size"))
+                    .unwrap_or((50.0, 30.0));
+                Element::Ellipse(Ellipse { cx: x, cy: y, rx: rx as f32, ry: ry as f32, style, transform })
+            }
+            "line" => {
+                let (x1, y1) = pair(props, "from").unwrap_or((0.0, 0.0));
+                let (x2, y2) = pair(props, "to").unwrap_or((100.0, 100.0));
+                Element::Line(Line { x1: x1 as f32, y1: y1 as f32, x2: x2 as f32, y2: y2 as f32, style, transform })
+            }
+            "path" => {
+                let d = text(props, "d").or_else(|| text(props, "content")).unwrap_or_default();
+                Element::Path(Path { d, style, transform, bounds_hint: None })
+            }
+            "polygon" => {
+                let points = match props.get("points") {
+                    Some(PropValue::Points(pts)) => pts.iter().map(|&(px, py)| (px as f32, py as f32)).collect(),
+                    _ => Vec::new(),
+                };
+                Element::Polygon(Polygon { points, style, transform })
+            }
+            "text" => {
+                let font = s.style.font.clone().unwrap_or_else(|| "system-ui".into());
+                let mut content = text(props, "content").unwrap_or_default();
+                if let Some(w) = num(props, "truncate") {
+                    content = crate::font::truncate_to_width(&content, &font, s.style.font_size as f32, w as f32, "…");
+                }
+                Element::Text(Text {
+                    x, y, content, font,
+                    size: s.style.font_size as f32, weight: s.style.font_weight.clone(),
+                    anchor: s.style.text_anchor.clone(), baseline: s.style.baseline.clone(), style, transform,
+                    letter_spacing: s.style.letter_spacing as f32, word_spacing: s.style.word_spacing as f32,
+                    decoration: s.style.decoration.clone(), direction: s.style.direction.clone(),
+                })
+            }
+            "image" => {
+                let (w, h) = pair(props, "size").unwrap_or((100.0, 100.0));
+                Element::Image(Image {
+                    x, y, w: w as f32, h: h as f32, href: text(props, "href").unwrap_or_default(),
+                    transform, fit: text(props, "fit"), hidden: false, layer: text(props, "layer"), z: 0,
+                })
+            }
+            // Fade the subtree as one layer so overlapping children don't double-darken
+            "group" | "layout" if s.style.opacity < 1.0 => {
+                let mut inner = Vec::new();
+                let opaque = AstShape { style: AstStyle { opacity: 1.0, ..s.style.clone() }, ..s.clone() };
+                self.shape(&opaque, offset, &mut inner);
+                Element::Group(inner, None, s.style.opacity.clamp(0.0, 1.0) as f32)
+            }
+            "group" => {
+                let start = out.len();
+                for c in &s.children { self.shape(c, (0.0, 0.0), out); }
+                inherit_layer(props, &mut out[start..]);
+                return;
+            }
+            "layout" => {
+                let vertical = text(props, "direction").as_deref() != Some("horizontal");
+                let gap = num(props, "gap").unwrap_or(0.0);
+                let (lx, ly) = pair(props, "at").unwrap_or((0.0, 0.0));
+                let (mut along, start) = (0.0, out.len());
+                for c in &s.children {
+                    if vertical {
+                        self.shape(c, (lx, ly + along), out);
+                        along += measure_height(c) + gap;
+                    } else {
+                        self.shape(c, (lx + along, ly), out);
+                        along += measure_width(c) + gap;
+                    }
+                }
+                inherit_layer(props, &mut out[start..]);
+                return;
+            }
+            _ => return,
+        };
+        if s.transform.origin_pct.is_some() {
+            let (bx, by, bw, bh) = el.bounds();
+            let resolved = s.transform.resolve_origin((bx as f64, by as f64, bw as f64, bh as f64)).to_svg();
+            if let Some(tf) = el.transform_mut() { *tf = resolved; }
+        }
+        out.push(el);
+    }
+
+    /// Scene style for a shape, registering its gradient and shadow defs
+    fn style(&mut self, s: &AstShape) -> Style {
+        let mut fill = s.style.fill.clone().or_else(|| text(&s.props, "fill"));
+        if let Some(g) = &s.gradient {
+            // Shapes naming the same shared gradient reference a single def
+            let existing = self.gradients.iter().find(|d| g.shared && **d == gradient(d.id.clone(), g)).map(|d| d.id.clone());
+            let id = existing.unwrap_or_else(|| {
+                let id = self.def_id();
+                self.gradients.push(gradient(id.clone(), g));
+                id
+            });
+            fill = Some(format!("url(#{})", id));
+        }
+        let filter = s.shadow.as_ref().map(|sh| {
+            let id = self.def_id();
+            self.filters.push(Filter {
+                id: id.clone(), kind: "shadow".into(), dx: sh.x as f32, dy: sh.y as f32, blur: sh.blur as f32, color: sh.color.clone(),
+            });
+            id
+        });
+        let animation_class = s.animation.as_ref().and_then(|a| a.animation.as_ref()).map(|anim| {
+            let class = format!("anim-{}", self.def_id());
+            self.scene.push_keyframes(SceneKeyframes::new(class.clone(), format!(".{} {{ {} }}", class, anim.to_style())));
+            class
+        });
+        Style {
+            fill, filter, animation_class,
+            arrow: text(&s.props, "arrow").filter(|a| a != "none"),
+            meta: s.meta.iter().map(|(k, v)| (k.clone(), v.clone())).collect(),
+            layer: text(&s.props, "layer"),
+            dash: (!s.style.dash.is_empty()).then(|| s.style.dash.iter().map(|d| d.to_string()).collect::<Vec<_>>().join(" ")),
+            ..base_style(&s.style)
+        }
+    }
+}
+
+/// Put a group's children without a layer of their own on the group's layer
+fn inherit_layer(props: &HashMap<String, PropValue>, children: &mut [Element]) {
+    let Some(layer) = text(props, "layer") else { return };
+    for el in children.iter_mut().filter(|el| el.layer().is_none()) { el.set_layer(Some(layer.clone())); }
+}
+
+fn base_style(s: &AstStyle) -> Style {
+    Style {
+        fill: s.fill.clone(), stroke: s.stroke.clone(), stroke_width: s.stroke_width as f32,
+        opacity: s.opacity as f32, corner: s.corner as f32, dash_offset: s.dash_offset as f32,
+        fill_rule: s.fill_rule.clone(),
+        ..Style::default()
+    }
+}
+
+fn gradient(id: String, g: &GradientDef) -> Gradient {
+    Gradient { id, kind: g.gtype.clone(), from_color: g.from.clone(), to_color: g.to.clone(), angle: g.angle as f32, shared: g.shared }
+}
+
+fn use_ref(u: &AstUse) -> Use {
+    let (x, y) = u.at.unwrap_or((0.0, 0.0));
+    Use {
+        href: u.href.clone(), x: x as f32, y: y as f32,
+        width: u.size.map(|s| s.0 as f32), height: u.size.map(|s| s.1 as f32),
+        style: base_style(&u.style), transform: u.transform.to_svg(),
+    }
+}
+
+fn graph(g: &AstGraph) -> GraphContainer {
+    let nodes = g.nodes.iter().map(|n| {
+        let (cx, cy) = n.at.unwrap_or((0.0, 0.0));
+        let (w, h) = n.size.unwrap_or((80.0, 40.0));
+        let style = Style {
+            fill: Some(n.style.fill.clone().unwrap_or_else(|| "#3b82f6".into())), corner: 4.0,
+            ..base_style(&n.style)
+        };
+        Node {
+            id: n.id.clone(), shape: n.shape.clone(), cx: cx as f32, cy: cy as f32, w: w as f32, h: h as f32,
+            label: n.label.clone(), label_style: Style::with_fill(label_fill(style.fill.as_deref())), style, transform: None,
+        }
+    }).collect();
+    let edges = g.edges.iter().map(|e| Edge {
+        from_id: e.from.clone(), to_id: e.to.clone(), from_pt: (0.0, 0.0), to_pt: (0.0, 0.0),
+        edge_style: e.style.clone(), arrow: e.arrow.clone(), arrow_head: e.arrow_head.clone(), arrow_size: e.arrow_size as f32,
+        label: e.label.clone(), label_bg: e.label_bg.clone(),
+        style: Style {
+            stroke: Some(e.stroke.clone().unwrap_or_else(|| "#333".into())), stroke_width: e.stroke_width as f32,
+            opacity: 1.0, ..Style::default()
+        },
+    }).collect();
+    let mut container = GraphContainer {
+        layout: g.layout.clone(), direction: g.direction.clone(), spacing: g.spacing as f32, anchors: g.anchors.clone(), nodes, edges,
+    };
+    container.apply_layout();
+    container.resolve_edges();
+    container
+}
+
+/// White or black node label, whichever reads better on `fill`; white over
+/// gradients and other paints that aren't a plain hex color
+fn label_fill(fill: Option<&str>) -> &'static str {
+    let choices = [Color::parse_hex("#fff"), Color::parse_hex("#000")];
+    match fill.and_then(Color::try_parse_hex) {
+        Some(c) if c.best_text_color(&choices).luminance() < 0.5 => "#000",
+        _ => "#fff",
+    }
+}
+
+fn pair(props: &HashMap<String, PropValue>, key: &str) -> Option<(f64, f64)> {
+    match props.get(key)? { PropValue::Pair(a, b) | PropValue::PercentPair(a, b) => Some((*a, *b)), _ => None }
+}
+
+fn num(props: &HashMap<String, PropValue>, key: &str) -> Option<f64> {
+    match props.get(key)? { PropValue::Num(n) => Some(*n), PropValue::Dim(d) => d.resolve(0.0), _ => None }
+}
+
+fn text(props: &HashMap<String, PropValue>, key: &str) -> Option<String> {
+    match props.get(key)? { PropValue::Str(s) => Some(s.clone()), _ => None }
+}
+
+/// Layout extent along x, matching the Python interpreter's estimate
+fn measure_width(s: &AstShape) -> f64 {
+    let props = &s.props;
+    if let Some((w, _)) = pair(props, "size") { return w; }
+    if let Some(r) = num(props, "radius").or_else(|| pair(props, "radius").map(|r| r.0)) { return r * 2.0; }
+    match s.kind.as_str() {
+        "text" => text(props, "content").map_or(0, |c| c.chars().count()) as f64 * s.style.font_size * 0.6,
+        "layout" => {
+            let gap = num(props, "gap").unwrap_or(0.0);
+            s.children.iter().map(|c| measure_width(c) + gap).sum()
+        }
+        _ => 40.0,
+    }
+}
+
+/// Layout extent along y, matching the Python interpreter's estimate
+fn measure_height(s: &AstShape) -> f64 {
+    let props = &s.props;
+    if let Some((_, h)) = pair(props, "size") { return h; }
+    if let Some(r) = num(props, "radius").or_else(|| pair(props, "radius").map(|r| r.1)) { return r * 2.0; }
+    match s.kind.as_str() {
+        "text" => s.style.font_size * 1.2,
+        "layout" => {
+            let gap = num(props, "gap").unwrap_or(0.0);
+            if text(props, "direction").as_deref() == Some("vertical") {
+                s.children.iter().map(|c| measure_height(c) + gap).sum()
+            } else {
+                s.children.iter().map(measure_height).fold(0.0, f64::max)
+            }
+        }
+        _ => 40.0,
+    }
+}
+
+#[cfg(any(feature = "python", test))]
+fn size_override(name: Option<&str>) -> Result<Option<CanvasSize>, String> {
+    name.map(|n| CanvasSize::from_str(n).ok_or_else(|| format!("Unknown canvas size '{}'", n))).transpose()
+}
+
+/// Compile DSL source straight to SVG, ignoring diagnostics
+#[cfg(feature = "python")]
+#[pyfunction]
+#[pyo3(name = "compile", signature = (source, size_override=None))]
+pub fn py_compile(source: &str, size_override: Option<&str>) -> PyResult<String> {
+    Ok(py_compile_with_diagnostics(source, size_override)?.0)
+}
+
+/// Compile DSL source to `(svg, errors)`; errors include warnings and hints
+#[cfg(feature = "python")]
+#[pyfunction]
+#[pyo3(name = "compile_with_diagnostics", signature = (source, size_override=None))]
+pub fn py_compile_with_diagnostics(source: &str, size_override: Option<&str>) -> PyResult<(String, Vec<ParseError>)> {
+    let size = self::size_override(size_override).map_err(PyValueError::new_err)?;
+    Ok(compile(source, size))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_compile_rect() {
+        let (svg, errors) = compile("canvas medium\nrect at 0,0 size 10x10 #f00", None);
+        assert!(errors.is_empty(), "{:?}", errors);
+        assert!(svg.contains(r##"<rect x="0" y="0" width="10" height="10" fill="#f00""##), "{}", svg);
+        assert!(svg.starts_with(r#"<svg xmlns="http://www.w3.org/2000/svg" width="64" height="64">"#));
+    }
+
+    #[test]
+    fn test_compile_orders_by_z() {
+        let src = "rect at 0,0 size 10x10 z 2 #a00\ncircle at 5,5 radius 2 #b00\nrect at 0,0 size 10x10 #c00\n  z -1\n\
+                   group\n  rect at 0,0 size 5x5 z 1 #d00\n  rect at 0,0 size 5x5 #e00\ncircle at 5,5 radius 2 z 1 #f00";
+        let (svg, errors) = compile(src, None);
+        assert!(errors.is_empty(), "{:?}", errors);
+        let at = |fill: &str| svg.find(&format!(r#"fill="{}""#, fill)).unwrap_or_else(|| panic!("{} missing: {}", fill, svg));
+        let order = ["#c00", "#b00", "#e00", "#d00", "#f00", "#a00"].map(at);
+        assert!(order.windows(2).all(|w| w[0] < w[1]), "{}", svg);
+    }
+
+    #[test]
+    fn test_compile_warns_about_missing_geometry() {
+        use crate::dsl::{ErrorKind, ErrorSeverity};
+        let (svg, errors) = compile("circle at 5,5 #f00", None);
+        assert!(svg.contains("<circle"), "{}", svg);
+        assert!(matches!(&errors[..], [e] if e.kind == ErrorKind::MissingGeometry && e.severity == ErrorSeverity::Warning), "{:?}", errors);
+    }
+
+    #[cfg(feature = "timings")]
+    #[test]
+    fn test_compile_timed() {
+        let src = "canvas large\nstack gap 4 at 10,10\n  rect size 20x20\n  circle radius 5\ntext \"Hi\" at 0,40";
+        let start = std::time::Instant::now();
+        let (svg, errors, timings) = compile_timed(src, None);
+        let wall = start.elapsed();
+        assert_eq!((svg, errors), compile(src, None));
+        assert!(timings.total() <= wall, "{:?} > {:?}", timings, wall);
+        assert!([timings.lex, timings.parse, timings.resolve, timings.render].iter().all(|d| !d.is_zero()), "{:?}", timings);
+    }
+
+    #[test]
+    fn test_compile_layout_and_defs() {
+        let src = "canvas large\nstack gap 4 at 10,10\n  rect size 20x20\n    fill #000\n  circle radius 5\n    fill #fff\nrect at 0,0 size 5x5\n  gradient linear #000 #fff\n  shadow 0,2 4 #0004";
+        let (svg, _) = compile(src, Some(CanvasSize::Small));
+        assert!(svg.contains(r#"width="48" height="48""#), "{}", svg);
+        assert!(svg.contains(r##"<circle cx="10" cy="34" r="5" fill="#fff""##), "{}", svg);
+        assert!(svg.contains(r#"fill="url(#d1)""#) && svg.contains(r#"filter="url(#d2)""#), "{}", svg);
+        assert_eq!(size_override(Some("bogus")).unwrap_err(), "Unknown canvas size 'bogus'");
+    }
+
+    #[test]
+    fn test_compile_shared_gradient() {
+        let src = "rect at 10,10 size 20x20\n  gradient linear #000 #fff 0 shared\nrect at 50,30 size 20x20\n  gradient linear #000 #fff 0 shared\nrect at 0,0 size 5x5\n  gradient linear #000 #fff 0";
+        let (svg, errors) = compile(src, None);
+        assert!(errors.is_empty(), "{:?}", errors);
+        // Union of both rects spans x 10..70, y 10..50
+        assert!(svg.contains(r#"<linearGradient id="d1" gradientUnits="userSpaceOnUse" x1="10" y1="30" x2="70" y2="30">"#), "{}", svg);
+        assert_eq!(svg.matches(r#"fill="url(#d1)""#).count(), 2);
+        assert!(svg.contains(r#"<linearGradient id="d2" x1="0%" y1="50%" x2="100%" y2="50%">"#), "{}", svg);
+    }
+
+    #[test]
+    fn test_compile_gradient_default_angle_runs_left_to_right() {
+        let (svg, _) = compile("rect at 0,0 size 5x5\n  gradient linear #000 #fff", None);
+        assert!(svg.contains(r#"<linearGradient id="d1" x1="0%" y1="50%" x2="100%" y2="50%">"#), "{}", svg);
+    }
+
+    #[test]
+    fn test_compile_meta_data_attrs() {
+        let (svg, errors) = compile("rect at 0,0 size 10x10\n  meta layer \"bg\"\nline from 0,0 to 10,0\n  meta note \"a<b\"", None);
+        assert!(errors.is_empty(), "{:?}", errors);
+        assert!(svg.contains(r#"<rect x="0" y="0" width="10" height="10" data-layer="bg"/>"#), "{}", svg);
+        assert!(svg.contains(r#" data-note="a&lt;b"/>"#), "{}", svg);
+    }
+
+    #[test]
+    fn test_compile_line_arrow() {
+        let (svg, errors) = compile("line from 0,0 to 100,0 arrow forward\nline from 0,10 to 100,10\nline from 0,20 to 100,20 arrow both\n  stroke #e94560", None);
+        assert!(errors.is_empty(), "{:?}", errors);
+        assert!(svg.contains(r#"stroke-width="1" marker-end="url(#line-000-arrow-end)"/>"#), "{}", svg);
+        assert!(svg.contains(r#"marker-start="url(#line-e94560-arrow-start)" marker-end="url(#line-e94560-arrow-end)"/>"#), "{}", svg);
+        assert_eq!(svg.matches("marker-end").count(), 2);
+        assert_eq!(svg.matches(r#"<marker id="line-000-arrow-end""#).count(), 1);
+        let end = &svg[svg.find(r#"<marker id="line-e94560-arrow-end""#).unwrap()..];
+        assert!(end[..end.find("</marker>").unwrap()].contains(r##"fill="#e94560""##), "{}", svg);
+        let (plain, _) = compile("line from 0,0 to 100,0", None);
+        assert!(!plain.contains("<marker"), "{}", plain);
+    }
+
+    #[test]
+    fn test_compile_graph_is_deterministic() {
+        let src = "graph hierarchical\n  node \"c\" label \"C\"\n  node \"a\" label \"A\"\n  node \"b\" label \"B\"\n  edge \"c\" -> \"a\"\n  edge \"a\" -> \"b\"";
+        let (svg, errors) = compile(src, None);
+        assert!(errors.is_empty(), "{:?}", errors);
+        assert_eq!(svg, compile(src, None).0);
+        // Nodes keep declaration order, not id order
+        let at = |label: &str| svg.find(&format!(">{}</text>", label)).unwrap_or_else(|| panic!("{}", svg));
+        assert!(at("C") < at("A") && at("A") < at("B"), "{}", svg);
+    }
+
+    #[test]
+    fn test_graph_edge_arrow_heads() {
+        let src = "graph\n  node \"a\" at 40,40\n  node \"b\" at 200,40\n  node \"c\" at 40,200\n  edge \"a\" -> \"b\" arrow forward style open size 12\n  edge \"a\" -> \"c\" arrow forward style diamond\n  edge \"b\" -> \"c\"";
+        let (svg, errors) = compile(src, None);
+        assert!(errors.is_empty(), "{:?}", errors);
+        assert!(svg.contains(r#"marker-end="url(#graph-arrow-open-12-end)""#), "{}", svg);
+        assert!(svg.contains(r#"marker-end="url(#graph-arrow-diamond-10-end)""#), "{}", svg);
+        assert!(svg.contains(r#"marker-end="url(#graph-arrow-end)""#), "{}", svg);
+        let marker = |id: &str| {
+            let from = svg.find(&format!(r#"<marker id="{}""#, id)).unwrap_or_else(|| panic!("{} missing: {}", id, svg));
+            svg[from..from + svg[from..].find("</marker>").unwrap()].to_string()
+        };
+        assert!(marker("graph-arrow-open-12-end").contains("<path"), "{}", svg);
+        let diamond = marker("graph-arrow-diamond-10-end");
+        let points = &diamond[diamond.find(r#"points=""#).unwrap() + 8..];
+        assert_eq!(points[..points.find('"').unwrap()].split(", ").count(), 4, "{}", diamond);
+    }
+
+    #[test]
+    fn test_graph_edge_label_bg() {
+        let src = "graph\n  node \"a\" at 40,40\n  node \"b\" at 240,40\n  edge \"a\" -> \"b\" label \"calls\" bg #fff\n  edge \"b\" -> \"a\" label \"bare\"";
+        let (svg, errors) = compile(src, None);
+        assert!(errors.is_empty(), "{:?}", errors);
+        let m = crate::font::measure_text("calls", "sans-serif", 12.0);
+        let (w, h) = (m.width + 8.0, m.height + 8.0);
+        let rect = format!(r##"width="{}" height="{}" rx="3" fill="#fff"/>"##, w, h);
+        let (at_rect, at_text) = (svg.find(&rect), svg.find(">calls</text>"));
+        assert!(at_rect.is_some() && at_rect < at_text, "{}", svg);
+        // The rect is centered on the label
+        let text = &svg[..at_text.unwrap()];
+        let tag = &text[text.rfind("<text").unwrap()..];
+        let rect_tag = &svg[svg[..at_rect.unwrap()].rfind("<rect").unwrap()..];
+        let num = |s: &str, attr: &str| -> f32 {
+            let v = &s[s.find(&format!(r#" {}=""#, attr)).unwrap() + attr.len() + 3..];
+            v[..v.find('"').unwrap()].parse().unwrap()
+        };
+        assert!((num(rect_tag, "x") + w / 2.0 - num(tag, "x")).abs() < 1e-3, "{}", svg);
+        assert!((num(rect_tag, "y") + h / 2.0 - num(tag, "y")).abs() < 1e-3, "{}", svg);
+        assert_eq!(svg.matches(r#"rx="3""#).count(), 1, "{}", svg);
+    }
+
+    #[test]
+    fn test_graph_anchor_modes() {
+        let src = |mode: &str| format!("graph\n  {}\n  node \"a\" at 50,50\n  node \"b\" at 170,190 size 60x40\n  edge \"a\" -> \"b\"", mode);
+        let (axis, _) = compile(&src(""), None);
+        assert!(axis.contains(r#"d="M50,70 L170,170""#), "{}", axis);
+        let (sides, errors) = compile(&src("anchors sides"), None);
+        assert!(errors.is_empty(), "{:?}", errors);
+        assert!(sides.contains(r#"d="M90,50 L170,170""#), "{}", sides);
+        let (_, errors) = compile(&src("anchors side"), None);
+        assert_eq!(errors.iter().map(|e| e.message.as_str()).collect::<Vec<_>>(), ["Expected anchor mode"]);
+    }
+
+    #[test]
+    fn test_layers_toggle_without_shifting_ids() {
+        let src = "rect at 0,0 size 10x10 #111\n  layer \"bg\"\ngroup\n  layer \"fg\"\n  circle at 5,5 radius 2 #f00\n  circle at 8,8 radius 2 #0f0\nrect at 20,0 size 10x10 #222\n  layer \"bg\"\nline from 0,0 to 10,10";
+        let ast = Parser::new(Lexer::new(src).tokenize()).parse();
+        let mut scene = build_scene(&ast);
+        assert_eq!(scene.layers(), ["bg", "fg"]);
+        let svg = scene.render_svg();
+        assert!(svg.contains(r##"<g id="layer-bg"><rect x="0" y="0" width="10" height="10" fill="#111"/><rect x="20""##), "{}", svg);
+        assert!(svg.contains(r#"<g id="layer-fg"><circle cx="5""#), "{}", svg);
+
+        let ids = |s: &Scene| crate::render::IndexedScene::from_scene(s).elements.iter().map(|e| e.id).collect::<Vec<_>>();
+        let before = ids(&scene);
+        scene.set_layer_visible("fg", false);
+        let svg = scene.render_svg();
+        assert!(!svg.contains("layer-fg") && !svg.contains("<circle"), "{}", svg);
+        assert!(svg.contains(r#"<g id="layer-bg">"#) && svg.contains("<line"), "{}", svg);
+        assert_eq!(ids(&scene), before);
+        assert!(!scene.layer_visible("fg"));
+        assert!(scene.diff(&build_scene(&ast)).needs_full_redraw());
+        // Element indices no longer address top-level SVG children, even when layers stay put
+        assert!(build_scene(&ast).diff(&build_scene(&ast)).needs_full_redraw());
+    }
+
+    #[test]
+    fn test_text_truncate() {
+        let (svg, errors) = compile("text \"Quarterly revenue summary for the northern region\" at 0,20 truncate 120\ntext \"Summary\" at 0,40 truncate 120", None);
+        assert!(errors.is_empty(), "{:?}", errors);
+        let cut = svg.split("<text").nth(1).unwrap();
+        let cut = &cut[cut.find('>').unwrap() + 1..cut.find("</text>").unwrap()];
+        assert!(cut.ends_with('…') && cut.starts_with("Quarterly"), "{}", cut);
+        assert!(crate::font::measure_text(cut, "system-ui", 16.0).width <= 120.0, "{}", cut);
+        assert!(svg.contains(">Summary</text>"), "{}", svg);
+    }
+
+    #[test]
+    fn test_compile_theme_colors_pass_through() {
+        let (svg, errors) = compile("rect at 0,0 size 10x10\n  fill currentColor\ncircle at 5,5 radius 2 var(--accent)\n  stroke var(--edge, #333) 2", None);
+        assert!(errors.is_empty(), "{:?}", errors);
+        assert!(svg.contains(r#"fill="currentColor""#), "{}", svg);
+        assert!(svg.contains(r#"fill="var(--accent)""#), "{}", svg);
+        assert!(svg.contains(r##"stroke="var(--edge, #333)""##), "{}", svg);
+    }
+
+    #[test]
+    fn test_graph_label_contrasts_with_fill() {
+        let (svg, errors) = compile("graph\n  node \"a\" label \"Dark\" fill #1e293b\n  node \"b\" label \"Light\" fill #fde047", None);
+        assert!(errors.is_empty(), "{:?}", errors);
+        assert!(svg.contains(r##"fill="#fff">Dark</text>"##), "{}", svg);
+        assert!(svg.contains(r##"fill="#000">Light</text>"##), "{}", svg);
+    }
+
+    #[test]
+    fn test_compile_rounded_polygon() {
+        let (svg, errors) = compile("polygon points [0,0 20,0 20,20 0,20] #000\n  corner 5\npolygon points [0,0 10,0 5,8]", None);
+        assert!(errors.is_empty(), "{:?}", errors);
+        assert!(svg.contains(r##"<path d="M0 5 A5 5 0 0 1 5 0 L15 0 A5 5"##), "{}", svg);
+        assert!(svg.contains(r#"<polygon points="0,0 10,0 5,8"/>"#), "{}", svg);
+    }
+
+    #[test]
+    fn test_percent_origin_keeps_center_fixed() {
+        let (svg, errors) = compile("rect at 40,40 size 20x10\n  rotate 45\n  origin 50% 50%", None);
+        assert!(errors.is_empty(), "{:?}", errors);
+        assert!(svg.contains(r#"transform="rotate(45 50 45)""#), "{}", svg);
+        let (cx, cy) = crate::path::Affine::parse("rotate(45 50 45)").apply(50.0, 45.0);
+        assert!((cx - 50.0).abs() < 1e-4 && (cy - 45.0).abs() < 1e-4, "{},{}", cx, cy);
+    }
+}
@@ -1,10 +1,29 @@
 //! Scene graph and shape primitives
 
+#[cfg(feature = "binary")]
+mod binary;
+mod boolean;
+mod build;
 mod scene;
 mod shape;
+mod validate;
 
-pub use scene::{Element, Filter, Gradient, GraphContainer, Scene, SceneKeyframes};
+#[cfg(feature = "python")]
+mod python;
+
+#[cfg(feature = "python")]
+pub use python::ElementRef;
+pub use boolean::shape_boolean;
+pub use build::{build_scene, compile};
+#[cfg(feature = "timings")]
+pub use build::{compile_timed, PipelineTimings};
+#[cfg(feature = "python")]
+pub use build::{py_compile, py_compile_with_diagnostics};
+pub use scene::{Element, Filter, Gradient, GraphContainer, RenderOptions, Scene, SceneKeyframes};
 pub use shape::{
     ArrowType, Circle, Color, Diamond, Edge, EdgeStyle, Ellipse,
     Image, Line, Node, Path, Polygon, Rect, Style, Symbol, Text, Use,
 };
+pub use crate::svg::preserve_aspect_ratio;
+pub use validate::Diagnostic;
+pub(crate) use shape::html_escape;
@@ -0,0 +1,133 @@
+//! Python handles onto elements of a live scene
+
+#![cfg(feature = "python")]
+
+use pyo3::exceptions::{PyIndexError, PyValueError};
+use pyo3::prelude::*;
+use super::scene::{Element, Scene};
+use super::shape::{Node, Style};
+use crate::hash::ElementKind;
+use crate::render::element_kind;
+
+/// Live handle to one element of a Python-owned scene. Reads and writes go
+/// straight to the scene, so edits show up in the next `to_svg()`.
+///
+/// `style` returns a copy: assign it back (`el.style = s`) to apply changes.
+#[pyclass]
+pub struct ElementRef {
+    scene: Py<Scene>,
+    index: usize,
+    /// Node index when the handle points inside a graph
+    node: Option<usize>,
+}
+
+enum Target<'a> { Element(&'a mut Element), Node(&'a mut Node) }
+
+impl ElementRef {
+    pub(super) fn new(scene: Py<Scene>, index: usize, node: Option<usize>) -> Self { Self { scene, index, node } }
+
+    fn with<R>(&self, py: Python<'_>, f: impl FnOnce(Target<'_>) -> PyResult<R>) -> PyResult<R> {
+        let mut scene = self.scene.borrow_mut(py);
+        let target = match (scene.elements_mut().get_mut(self.index), self.node) {
+            (Some(Element::Graph(g)), Some(n)) => g.nodes.get_mut(n).map(Target::Node),
+            (Some(el), None) => Some(Target::Element(el)),
+            _ => None,
+        };
+        f(target.ok_or_else(|| PyIndexError::new_err("element no longer exists in the scene"))?)
+    }
+}
+
+#[pymethods]
+impl ElementRef {
+    #[getter]
+    fn kind(&self, py: Python<'_>) -> PyResult<&'static str> {
+        self.with(py, |t| Ok(match t { Target::Element(el) => element_kind(el), Target::Node(_) => ElementKind::Node }.as_str()))
+    }
+
+    /// Graph node id; `None` for elements without one
+    #[getter]
+    fn get_id(&self, py: Python<'_>) -> PyResult<Option<String>> {
+        self.with(py, |t| Ok(match t {
+            Target::Element(Element::Node(n)) | Target::Node(n) => Some(n.id.clone()),
+            Target::Element(_) => None,
+        }))
+    }
+
+    /// Rename a node; edges in the same graph follow the new id
+    #[setter]
+    fn set_id(&self, py: Python<'_>, id: String) -> PyResult<()> {
+        let mut scene = self.scene.borrow_mut(py);
+        match (scene.elements_mut().get_mut(self.index), self.node) {
+            (Some(Element::Node(n)), None) => { n.id = id; Ok(()) }
+            (Some(Element::Graph(g)), Some(i)) if i < g.nodes.len() => {
+                let old = std::mem::replace(&mut g.nodes[i].id, id.clone());
+                for e in &mut g.edges {
+                    if e.from_id == old { e.from_id = id.clone(); }
+                    if e.to_id == old { e.to_id = id.clone(); }
+                }
+                Ok(())
+            }
+            (Some(_), None) => Err(PyValueError::new_err("only graph nodes carry an id")),
+            _ => Err(PyIndexError::new_err("element no longer exists in the scene")),
+        }
+    }
+
+    /// Copy of the element's style; `None` for images, groups, and graphs
+    #[getter]
+    fn get_style(&self, py: Python<'_>) -> PyResult<Option<Style>> {
+        self.with(py, |t| Ok(match t { Target::Element(el) => el.style().cloned(), Target::Node(n) => Some(n.style.clone()) }))
+    }
+
+    #[setter]
+    fn set_style(&self, py: Python<'_>, style: Style) -> PyResult<()> {
+        self.with(py, |t| {
+            let slot = match t { Target::Element(el) => el.style_mut(), Target::Node(n) => Some(&mut n.style) };
+            *slot.ok_or_else(|| PyValueError::new_err("element has no style"))? = style;
+            Ok(())
+        })
+    }
+
+    /// Hidden elements stay in the scene, rendered with `display="none"`
+    #[getter]
+    fn get_visible(&self, py: Python<'_>) -> PyResult<bool> {
+        self.with(py, |t| Ok(match t { Target::Element(el) => el.visible(), Target::Node(n) => !n.style.hidden }))
+    }
+
+    #[setter]
+    fn set_visible(&self, py: Python<'_>, visible: bool) -> PyResult<()> {
+        self.with(py, |t| {
+            match t { Target::Element(el) => el.set_visible(visible), Target::Node(n) => n.style.hidden = !visible }
+            Ok(())
+        })
+    }
+
+    /// Untransformed bounds as `(x, y, w, h)`
+    #[getter]
+    fn bounds(&self, py: Python<'_>) -> PyResult<(f32, f32, f32, f32)> {
+        self.with(py, |t| Ok(match t { Target::Element(el) => el.bounds(), Target::Node(n) => n.bounds() }))
+    }
+
+    /// SVG transform list; `None` when unset or for edges and graphs
+    #[getter]
+    fn get_transform(&self, py: Python<'_>) -> PyResult<Option<String>> {
+        self.with(py, |t| Ok(match t { Target::Element(el) => el.transform().map(String::from), Target::Node(n) => n.transform.clone() }))
+    }
+
+    #[setter]
+    fn set_transform(&self, py: Python<'_>, transform: Option<String>) -> PyResult<()> {
+        self.with(py, |t| {
+            let slot = match t { Target::Element(el) => el.transform_mut(), Target::Node(n) => Some(&mut n.transform) };
+            *slot.ok_or_else(|| PyValueError::new_err("element has no transform"))? = transform;
+            Ok(())
+        })
+    }
+
+    fn to_svg(&self, py: Python<'_>) -> PyResult<String> {
+        self.with(py, |t| Ok(match t { Target::Element(el) => el.to_svg(), Target::Node(n) => n.to_svg() }))
+    }
+
+    fn __repr__(&self, py: Python<'_>) -> PyResult<String> {
+        let (kind, id) = (self.kind(py)?, self.get_id(py)?);
+        Ok(match id { Some(id) => format!("ElementRef({} {:?})", kind, id), None => format!("ElementRef({} #{})", kind, self.index) })
+    }
+}
@@ -2,10 +2,17 @@
 
 #[cfg(feature = "python")]
 use pyo3::prelude::*;
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::sync::atomic::{AtomicU64, Ordering};
 use serde::{Deserialize, Serialize};
 use ts_rs::TS;
-use super::shape::{Circle, Diamond, Edge, Ellipse, Image, Line, Node, Path, Polygon, Rect, Symbol, Text, Use};
-use crate::CanvasSize;
+use crate::fmt::{coord, num};
+use crate::path::Affine;
+use super::shape::{display_svg, html_escape, Circle, Diamond, Edge, Ellipse, Image, Line, Node, Path, Polygon, Rect, Style, Symbol, Text, Use};
+#[cfg(feature = "python")]
+use super::python::ElementRef;
+use crate::render::DiffResult;
+use crate::{AspectRatio, CanvasSize};
 
 /// A renderable element in the scene
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize, TS)]
@@ -14,7 +21,8 @@ pub enum Element {
     Rect(Rect), Circle(Circle), Ellipse(Ellipse), Line(Line),
     Path(Path), Polygon(Polygon), Text(Text), Image(Image),
     Diamond(Diamond), Node(Node), Edge(Edge),
-    Group(Vec<Element>, Option<String>),
+    /// Children, transform, and group opacity (applied to the subtree as one layer)
+    Group(Vec<Element>, Option<String>, f32),
     Graph(GraphContainer),
     Use(Use),
 }
@@ -26,42 +34,46 @@ pub struct GraphContainer {
     pub layout: String,
     pub direction: String,
     pub spacing: f32,
+    /// Edge anchor selection: `axis`, `sides`, or `corners`
+    pub anchors: String,
+    /// Nodes and edges in declaration order. Layouts place them in this order
+    /// and `to_svg` emits them in it, so the same graph renders byte-identically.
     pub nodes: Vec<Node>,
     pub edges: Vec<Edge>,
 }
 
 impl Default for GraphContainer {
     fn default() -> Self {
-        Self { layout: "manual".into(), direction: "vertical".into(), spacing: 50.0, nodes: Vec::new(), edges: Vec::new() }
+        Self {
+            layout: "manual".into(), direction: "vertical".into(), spacing: 50.0, anchors: "axis".into(),
+            nodes: Vec::new(), edges: Vec::new(),
+        }
     }
 }
 
 impl GraphContainer {
-    /// Compute edge endpoints based on node positions
+    /// Compute edge endpoints based on node positions, in edge order so each
+    /// edge sees the anchors taken by the ones before it
     pub fn resolve_edges(&mut self) {
         use std::collections::HashMap;
         let node_map: HashMap<&str, &Node> = self.nodes.iter().map(|n| (n.id.as_str(), n)).collect();
+        let mut used: HashMap<(&str, &str), usize> = HashMap::new();
         
         for edge in &mut self.edges {
             if let (Some(from_node), Some(to_node)) = (node_map.get(edge.from_id.as_str()), node_map.get(edge.to_id.as_str())) {
-                // Determine best anchor points based on relative positions
-                let (from_side, to_side) = Self::best_anchors(from_node, to_node);
+                let ids = [from_node.id.as_str(), to_node.id.as_str()];
+                let (from_side, to_side) = crate::anchor::best_anchors(
+                    (from_node.cx, from_node.cy, from_node.w, from_node.h), (to_node.cx, to_node.cy, to_node.w, to_node.h),
+                    &self.anchors, |i, a| used.get(&(ids[i], a)).copied().unwrap_or(0),
+                );
+                *used.entry((ids[0], from_side)).or_default() += 1;
+                *used.entry((ids[1], to_side)).or_default() += 1;
                 edge.from_pt = from_node.anchor(from_side);
                 edge.to_pt = to_node.anchor(to_side);
             }
         }
     }
     
-    fn best_anchors(from: &Node, to: &Node) -> (&'static str, &'static str) {
-        let dx = to.cx - from.cx;
-        let dy = to.cy - from.cy;
-        if dy.abs() > dx.abs() {
-            if dy > 0.0 { ("bottom", "top") } else { ("top", "bottom") }
-        } else {
-            if dx > 0.0 { ("right", "left") } else { ("left", "right") }
-        }
-    }
-    
     /// Apply auto-layout to nodes
     pub fn apply_layout(&mut self) {
         match self.layout.as_str() {
@@ -109,12 +121,13 @@ impl GraphContainer {
         
         // Render edges first (behind nodes)
         for edge in &self.edges {
-            svg.push_str(&edge.to_svg((&format!("{}-arrow-start", arrow_prefix), &format!("{}-arrow-end", arrow_prefix))));
+            let (start, end) = edge.marker_ids(arrow_prefix);
+            svg.push_str(&display_svg(edge.to_svg((&start, &end)), edge.style.hidden));
         }
         
         // Render nodes
         for node in &self.nodes {
-            svg.push_str(&node.to_svg());
+            svg.push_str(&display_svg(node.to_svg(), node.style.hidden));
         }
         
         format!("<g class=\"graph\">{}</g>", svg)
@@ -133,21 +146,33 @@ impl GraphContainer {
 
 impl Element {
     pub fn to_svg(&self) -> String {
-        match self {
+        let svg = match self {
             Element::Rect(r) => r.to_svg(), Element::Circle(c) => c.to_svg(),
             Element::Ellipse(e) => e.to_svg(), Element::Line(l) => l.to_svg(),
             Element::Path(p) => p.to_svg(), Element::Polygon(p) => p.to_svg(),
             Element::Text(t) => t.to_svg(), Element::Image(i) => i.to_svg(),
             Element::Diamond(d) => d.to_svg(), Element::Node(n) => n.to_svg(),
-            Element::Edge(e) => e.to_svg(("arrow-start", "arrow-end")),
+            Element::Edge(e) => {
+                let (start, end) = e.marker_ids("arrow");
+                e.to_svg((&start, &end))
+            }
             Element::Use(u) => u.to_svg(),
-            Element::Group(children, tf) => {
-                let inner: String = children.iter().map(|e| e.to_svg()).collect();
-                tf.as_ref().map_or_else(|| format!("<g>{}</g>", inner), |t| format!(r#"<g transform="{}">{}</g>"#, t, inner))
+            Element::Group(children, tf, opacity) => {
+                Self::group_svg(&children.iter().map(|e| e.to_svg()).collect::<String>(), tf.as_deref(), *opacity)
             }
             Element::Graph(g) => g.to_svg("graph"),
+        };
+        match self {
+            Element::Group(..) | Element::Graph(_) => svg,
+            el => display_svg(svg, !el.visible()),
         }
     }
+    fn group_svg(inner: &str, tf: Option<&str>, opacity: f32) -> String {
+        let mut attrs = String::new();
+        if let Some(t) = tf { attrs.push_str(&format!(r#" transform="{}""#, t)); }
+        if opacity < 1.0 { attrs.push_str(&format!(r#" opacity="{}""#, opacity)); }
+        format!("<g{}>{}</g>", attrs, inner)
+    }
     pub fn bounds(&self) -> (f32, f32, f32, f32) {
         match self {
             Element::Rect(r) => r.bounds(), Element::Circle(c) => c.bounds(),
@@ -157,14 +182,251 @@ impl Element {
             Element::Diamond(d) => d.bounds(), Element::Node(n) => n.bounds(),
             Element::Edge(e) => e.bounds(), Element::Graph(g) => g.bounds(),
             Element::Use(u) => u.bounds(),
-            Element::Group(children, _) => {
-                if children.is_empty() { return (0.0, 0.0, 0.0, 0.0); }
-                let (mut min_x, mut min_y, mut max_x, mut max_y) = (f32::MAX, f32::MAX, f32::MIN, f32::MIN);
-                for c in children { let (x, y, w, h) = c.bounds(); min_x = min_x.min(x); min_y = min_y.min(y); max_x = max_x.max(x + w); max_y = max_y.max(y + h); }
-                (min_x, min_y, max_x - min_x, max_y - min_y)
+            Element::Group(children, _, _) => union_bounds(children.iter().map(Element::bounds)),
+        }
+    }
+
+    /// Outline as SVG path data in the element's own coordinates. Rects (with
+    /// rounded corners as arcs), circles, ellipses, diamonds and nodes give a
+    /// closed outline, lines and polygons their points, and paths their own `d`;
+    /// anything else has no geometry and gives an empty string.
+    pub fn to_path_d(&self) -> String {
+        match self {
+            Element::Rect(r) => rect_d(r.x, r.y, r.w, r.h, r.rx),
+            Element::Circle(c) => ellipse_d(c.cx, c.cy, c.r, c.r),
+            Element::Ellipse(e) => ellipse_d(e.cx, e.cy, e.rx, e.ry),
+            Element::Line(l) => format!("M{} {} L{} {}", num(l.x1), num(l.y1), num(l.x2), num(l.y2)),
+            Element::Path(p) => p.d.clone(),
+            Element::Polygon(p) if !p.points.is_empty() => {
+                let pts: Vec<String> = p.points.iter().map(|(x, y)| format!("{} {}", num(*x), num(*y))).collect();
+                format!("M{} Z", pts.join(" L"))
+            }
+            Element::Diamond(d) => diamond_d(d.cx, d.cy, d.w, d.h),
+            Element::Node(n) => match n.shape.as_str() {
+                "circle" => { let r = n.w.min(n.h) / 2.0; ellipse_d(n.cx, n.cy, r, r) }
+                "ellipse" => ellipse_d(n.cx, n.cy, n.w / 2.0, n.h / 2.0),
+                "diamond" => diamond_d(n.cx, n.cy, n.w, n.h),
+                _ => rect_d(n.cx - n.w / 2.0, n.cy - n.h / 2.0, n.w, n.h, 0.0),
+            },
+            _ => String::new(),
+        }
+    }
+
+    /// Paint attributes and transform a shape can share a merged `<path>` under
+    /// (see `RenderOptions::merge_paths`). `None` for elements whose own markup
+    /// carries more than the outline: text, images, nodes, lines and arrowed
+    /// paths, rounded polygons, and hidden or translucent shapes, whose overlaps
+    /// would blend differently once merged. Stroked shapes and shapes with a
+    /// `fill-rule` stay apart too: merged, every fill would paint under every
+    /// stroke, and winding would combine across the shapes.
+    fn merge_key(&self) -> Option<(String, Option<&str>)> {
+        let style = match self {
+            Element::Rect(r) => &r.style, Element::Circle(c) => &c.style, Element::Ellipse(e) => &e.style,
+            Element::Polygon(p) if p.style.corner <= 0.0 && !p.points.is_empty() => &p.style,
+            Element::Diamond(d) if d.style.corner <= 0.0 => &d.style,
+            Element::Path(p) if p.style.arrow.is_none() => &p.style,
+            _ => return None,
+        };
+        if style.hidden || style.opacity < 1.0 || style.stroke.is_some() || style.fill_rule.is_some() { return None; }
+        Some((style.to_svg_attrs(), self.transform()))
+    }
+
+    /// Paint style, for elements that carry one (not images, groups, or graphs)
+    pub fn style(&self) -> Option<&Style> {
+        match self {
+            Element::Rect(r) => Some(&r.style), Element::Circle(c) => Some(&c.style),
+            Element::Ellipse(e) => Some(&e.style), Element::Line(l) => Some(&l.style),
+            Element::Path(p) => Some(&p.style), Element::Polygon(p) => Some(&p.style),
+            Element::Text(t) => Some(&t.style), Element::Diamond(d) => Some(&d.style),
+            Element::Node(n) => Some(&n.style), Element::Edge(e) => Some(&e.style),
+            Element::Use(u) => Some(&u.style),
+            Element::Image(_) | Element::Group(..) | Element::Graph(_) => None,
+        }
+    }
+
+    pub fn style_mut(&mut self) -> Option<&mut Style> {
+        match self {
+            Element::Rect(r) => Some(&mut r.style), Element::Circle(c) => Some(&mut c.style),
+            Element::Ellipse(e) => Some(&mut e.style), Element::Line(l) => Some(&mut l.style),
+            Element::Path(p) => Some(&mut p.style), Element::Polygon(p) => Some(&mut p.style),
+            Element::Text(t) => Some(&mut t.style), Element::Diamond(d) => Some(&mut d.style),
+            Element::Node(n) => Some(&mut n.style), Element::Edge(e) => Some(&mut e.style),
+            Element::Use(u) => Some(&mut u.style),
+            Element::Image(_) | Element::Group(..) | Element::Graph(_) => None,
+        }
+    }
+
+    /// Whether the element renders; groups and graphs are visible while any child is
+    pub fn visible(&self) -> bool {
+        match self {
+            Element::Image(i) => !i.hidden,
+            Element::Group(children, _, _) => children.iter().any(Element::visible),
+            Element::Graph(g) => g.nodes.iter().any(|n| !n.style.hidden) || g.edges.iter().any(|e| !e.style.hidden),
+            el => el.style().is_some_and(|s| !s.hidden),
+        }
+    }
+
+    /// Show or hide the element (every child, for groups and graphs) without
+    /// removing it, so its diff identity is kept
+    pub fn set_visible(&mut self, visible: bool) {
+        match self {
+            Element::Image(i) => i.hidden = !visible,
+            Element::Group(children, _, _) => children.iter_mut().for_each(|c| c.set_visible(visible)),
+            Element::Graph(g) => {
+                g.nodes.iter_mut().for_each(|n| n.style.hidden = !visible);
+                g.edges.iter_mut().for_each(|e| e.style.hidden = !visible);
             }
+            el => if let Some(s) = el.style_mut() { s.hidden = !visible },
+        }
+    }
+
+    /// Stacking order among top-level elements; higher paints later. A group
+    /// takes its first child's; graphs are always 0.
+    pub fn z(&self) -> i32 {
+        match self {
+            Element::Image(i) => i.z,
+            Element::Group(children, _, _) => children.first().map_or(0, Element::z),
+            el => el.style().map_or(0, |s| s.z),
+        }
+    }
+
+    /// Set the stacking order (of every child, for groups)
+    pub fn set_z(&mut self, z: i32) {
+        match self {
+            Element::Image(i) => i.z = z,
+            Element::Group(children, _, _) => children.iter_mut().for_each(|c| c.set_z(z)),
+            el => if let Some(s) = el.style_mut() { s.z = z },
+        }
+    }
+
+    /// Layer the element renders in. A group is on its first child's layer;
+    /// graphs are never on one.
+    pub fn layer(&self) -> Option<&str> {
+        match self {
+            Element::Image(i) => i.layer.as_deref(),
+            Element::Group(children, _, _) => children.first().and_then(Element::layer),
+            el => el.style().and_then(|s| s.layer.as_deref()),
         }
     }
+
+    /// Move the element (every child, for groups) onto `layer`, or off layers with `None`
+    pub fn set_layer(&mut self, layer: Option<String>) {
+        match self {
+            Element::Image(i) => i.layer = layer,
+            Element::Group(children, _, _) => children.iter_mut().for_each(|c| c.set_layer(layer.clone())),
+            el => if let Some(s) = el.style_mut() { s.layer = layer },
+        }
+    }
+
+    /// The element's own `transform` attribute (edges and graphs have none)
+    pub fn transform(&self) -> Option<&str> {
+        match self {
+            Element::Rect(r) => r.transform.as_deref(), Element::Circle(c) => c.transform.as_deref(),
+            Element::Ellipse(e) => e.transform.as_deref(), Element::Line(l) => l.transform.as_deref(),
+            Element::Path(p) => p.transform.as_deref(), Element::Polygon(p) => p.transform.as_deref(),
+            Element::Text(t) => t.transform.as_deref(), Element::Image(i) => i.transform.as_deref(),
+            Element::Diamond(d) => d.transform.as_deref(), Element::Node(n) => n.transform.as_deref(),
+            Element::Use(u) => u.transform.as_deref(), Element::Group(_, tf, _) => tf.as_deref(),
+            Element::Edge(_) | Element::Graph(_) => None,
+        }
+    }
+
+    pub(crate) fn transform_mut(&mut self) -> Option<&mut Option<String>> {
+        match self {
+            Element::Rect(r) => Some(&mut r.transform), Element::Circle(c) => Some(&mut c.transform),
+            Element::Ellipse(e) => Some(&mut e.transform), Element::Line(l) => Some(&mut l.transform),
+            Element::Path(p) => Some(&mut p.transform), Element::Polygon(p) => Some(&mut p.transform),
+            Element::Text(t) => Some(&mut t.transform), Element::Image(i) => Some(&mut i.transform),
+            Element::Diamond(d) => Some(&mut d.transform), Element::Node(n) => Some(&mut n.transform),
+            Element::Use(u) => Some(&mut u.transform), Element::Group(_, tf, _) => Some(tf),
+            Element::Edge(_) | Element::Graph(_) => None,
+        }
+    }
+
+    /// Bounds in the parent's coordinate space, after this element's transform
+    /// (and, for groups, each child's)
+    pub fn transformed_bounds(&self) -> (f32, f32, f32, f32) {
+        let local = match self {
+            Element::Group(children, _, _) => union_bounds(children.iter().map(Element::transformed_bounds)),
+            el => el.bounds(),
+        };
+        self.transform().map_or(local, |tf| Affine::parse(tf).map_bounds(local))
+    }
+
+    /// Shift by `(dx, dy)` in the parent's coordinate space. Transformed elements, paths,
+    /// and groups get a leading `translate`; everything else moves its own coordinates.
+    pub fn translate(&mut self, dx: f32, dy: f32) {
+        let wrap = matches!(self, Element::Path(_) | Element::Group(..)) || self.transform().is_some();
+        if let Some(tf) = self.transform_mut().filter(|_| wrap) {
+            let shift = format!("translate({} {})", num(dx), num(dy));
+            *tf = Some(tf.take().map_or(shift.clone(), |t| format!("{} {}", shift, t)));
+            return;
+        }
+        let mv = |p: &mut (f32, f32)| { p.0 += dx; p.1 += dy; };
+        match self {
+            Element::Rect(r) => { r.x += dx; r.y += dy; }
+            Element::Circle(c) => { c.cx += dx; c.cy += dy; }
+            Element::Ellipse(e) => { e.cx += dx; e.cy += dy; }
+            Element::Line(l) => { l.x1 += dx; l.y1 += dy; l.x2 += dx; l.y2 += dy; }
+            Element::Polygon(p) => p.points.iter_mut().for_each(mv),
+            Element::Text(t) => { t.x += dx; t.y += dy; }
+            Element::Image(i) => { i.x += dx; i.y += dy; }
+            Element::Diamond(d) => { d.cx += dx; d.cy += dy; }
+            Element::Node(n) => { n.cx += dx; n.cy += dy; }
+            Element::Use(u) => { u.x += dx; u.y += dy; }
+            Element::Edge(e) => { mv(&mut e.from_pt); mv(&mut e.to_pt); }
+            Element::Graph(g) => {
+                for n in &mut g.nodes { n.cx += dx; n.cy += dy; }
+                for e in &mut g.edges { mv(&mut e.from_pt); mv(&mut e.to_pt); }
+            }
+            Element::Path(_) | Element::Group(..) => {}
+        }
+    }
+}
+
+/// Distinct arrowhead colors of lines and paths with arrows, in first-use order
+fn arrow_colors<'a>(els: &'a [Element], colors: &mut Vec<&'a str>) {
+    for el in els {
+        match el {
+            Element::Group(children, _, _) => arrow_colors(children, colors),
+            el => if let Some(color) = el.style().filter(|s| s.has_arrow()).map(Style::marker_color) {
+                if !colors.contains(&color) { colors.push(color); }
+            }
+        }
+    }
+}
+
+fn ellipse_d(cx: f32, cy: f32, rx: f32, ry: f32) -> String {
+    let (cy, left, right, rx, ry) = (num(cy), num(cx - rx), num(cx + rx), num(rx), num(ry));
+    format!("M{left} {cy} A{rx} {ry} 0 1 0 {right} {cy} A{rx} {ry} 0 1 0 {left} {cy} Z")
+}
+
+fn diamond_d(cx: f32, cy: f32, w: f32, h: f32) -> String {
+    let (hw, hh) = (w / 2.0, h / 2.0);
+    format!("M{} {} L{} {} L{} {} L{} {} Z", num(cx), num(cy - hh), num(cx + hw), num(cy), num(cx), num(cy + hh), num(cx - hw), num(cy))
+}
+
+/// Rect outline, clamping the corner radius to half the shorter side
+fn rect_d(x0: f32, y0: f32, w: f32, h: f32, rx: f32) -> String {
+    let (x1, y1) = (x0 + w, y0 + h);
+    if rx <= 0.0 {
+        let (x0, y0, x1, y1) = (num(x0), num(y0), num(x1), num(y1));
+        return format!("M{x0} {y0} L{x1} {y0} L{x1} {y1} L{x0} {y1} Z");
+    }
+    let r = rx.min(w / 2.0).min(h / 2.0);
+    let (left, right, top, bottom) = (num(x0 + r), num(x1 - r), num(y0 + r), num(y1 - r));
+    let (x0, y0, x1, y1, rx) = (num(x0), num(y0), num(x1), num(y1), num(r));
+    format!(
+        "M{left} {y0} L{right} {y0} A{rx} {rx} 0 0 1 {x1} {top} L{x1} {bottom} A{rx} {rx} 0 0 1 {right} {y1} L{left} {y1} A{rx} {rx} 0 0 1 {x0} {bottom} L{x0} {top} A{rx} {rx} 0 0 1 {left} {y0} Z"
+    )
+}
+
+/// Smallest box enclosing every `(x, y, w, h)`; zero when there are none
+fn union_bounds(boxes: impl Iterator<Item = (f32, f32, f32, f32)>) -> (f32, f32, f32, f32) {
+    let (min_x, min_y, max_x, max_y) = boxes.fold((f32::MAX, f32::MAX, f32::MIN, f32::MIN), |(a, b, c, d), (x, y, w, h)| {
+        (a.min(x), b.min(y), c.max(x + w), d.max(y + h))
+    });
+    if min_x > max_x { (0.0, 0.0, 0.0, 0.0) } else { (min_x, min_y, max_x - min_x, max_y - min_y) }
 }
 
 /// Gradient definition
@@ -173,26 +435,45 @@ impl Element {
 #[cfg_attr(feature = "python", pyclass(get_all, set_all))]
 pub struct Gradient {
     pub id: String, pub kind: String, pub from_color: String, pub to_color: String, pub angle: f32,
+    /// Span every shape that uses it (`gradientUnits="userSpaceOnUse"`) rather than restarting per shape
+    #[serde(default)]
+    pub shared: bool,
 }
 
 #[cfg(feature = "python")]
 #[pymethods]
 impl Gradient {
     #[new]
-    #[pyo3(signature = (id, kind="linear".to_string(), from_color="#fff".to_string(), to_color="#000".to_string(), angle=90.0))]
-    fn py_new(id: String, kind: String, from_color: String, to_color: String, angle: f32) -> Self { Self { id, kind, from_color, to_color, angle } }
+    #[pyo3(signature = (id, kind="linear".to_string(), from_color="#fff".to_string(), to_color="#000".to_string(), angle=0.0, shared=false))]
+    fn py_new(id: String, kind: String, from_color: String, to_color: String, angle: f32, shared: bool) -> Self { Self { id, kind, from_color, to_color, angle, shared } }
 }
 
 impl Gradient {
+    /// Gradient over each shape's own bounding box
     pub fn to_svg(&self) -> String {
+        let stops = format!(r#"<stop offset="0%" stop-color="{}"/><stop offset="100%" stop-color="{}"/>"#, self.from_color, self.to_color);
+        if self.kind == "radial" {
+            format!(r#"<radialGradient id="{}">{}</radialGradient>"#, self.id, stops)
+        } else {
+            let (x1, y1, x2, y2) = self.vector((0.0, 0.0, 100.0, 100.0));
+            let pct = |v: f32| coord(v as f64, 1);
+            format!(r#"<linearGradient id="{}" x1="{}%" y1="{}%" x2="{}%" y2="{}%">{}</linearGradient>"#, self.id, pct(x1), pct(y1), pct(x2), pct(y2), stops)
+        }
+    }
+
+    /// Gradient in user-space units laid over `(x, y, w, h)`
+    pub fn to_svg_over(&self, (x, y, w, h): (f32, f32, f32, f32)) -> String {
+        let stops = format!(r#"<stop offset="0%" stop-color="{}"/><stop offset="100%" stop-color="{}"/>"#, self.from_color, self.to_color);
         if self.kind == "radial" {
-            format!(r#"<radialGradient id="{}"><stop offset="0%" stop-color="{}"/><stop offset="100%" stop-color="{}"/></radialGradient>"#, self.id, self.from_color, self.to_color)
+            format!(r#"<radialGradient id="{}" gradientUnits="userSpaceOnUse" cx="{}" cy="{}" r="{}">{}</radialGradient>"#,
+                self.id, num(x + w / 2.0), num(y + h / 2.0), num(w.max(h) / 2.0), stops)
         } else {
-            let rad = (self.angle - 90.0).to_radians();
-            format!(r#"<linearGradient id="{}" x1="0%" y1="0%" x2="{:.1}%" y2="{:.1}%"><stop offset="0%" stop-color="{}"/><stop offset="100%" stop-color="{}"/></linearGradient>"#,
-                self.id, 50.0 + 50.0 * rad.cos(), 50.0 + 50.0 * rad.sin(), self.from_color, self.to_color)
+            let (x1, y1, x2, y2) = self.vector((x, y, w, h));
+            format!(r#"<linearGradient id="{}" gradientUnits="userSpaceOnUse" x1="{}" y1="{}" x2="{}" y2="{}">{}</linearGradient>"#, self.id, num(x1), num(y1), num(x2), num(y2), stops)
         }
     }
+
+    fn vector(&self, bounds: (f32, f32, f32, f32)) -> (f32, f32, f32, f32) { crate::svg::gradient_vector(self.angle, bounds) }
 }
 
 /// Filter definition
@@ -235,22 +516,134 @@ impl SceneKeyframes {
     }
 }
 
+/// `elements` drawn with `render`, except that each run of two or more
+/// consecutive mergeable shapes with the same paint attributes and transform
+/// becomes a single `<path>`. Runs never reorder elements, so paint order holds,
+/// and a shape overlapping an earlier one in the run starts a new run, since
+/// opposite windings would cancel where they meet.
+fn merged_svg<'a>(elements: impl Iterator<Item = &'a Element>, render: &mut impl FnMut(&'a Element) -> String) -> String {
+    let overlaps = |(ax, ay, aw, ah): (f32, f32, f32, f32), (bx, by, bw, bh): (f32, f32, f32, f32)| {
+        ax < bx + bw && bx < ax + aw && ay < by + bh && by < ay + ah
+    };
+    let mut out = String::new();
+    let mut elements = elements.peekable();
+    while let Some(el) = elements.next() {
+        let Some(key) = el.merge_key() else { out.push_str(&render(el)); continue };
+        let mut run = vec![el];
+        while let Some(next) = elements.next_if(|next| {
+            next.merge_key().as_ref() == Some(&key) && !run.iter().any(|m| overlaps(m.bounds(), next.bounds()))
+        }) { run.push(next); }
+        if run.len() == 1 { out.push_str(&render(el)); continue; }
+        // Path data goes absolute so a leading relative `m` can't chain off the previous outline
+        let d: Vec<String> = run.iter().map(|e| match e {
+            Element::Path(p) => crate::path::normalize(&p.d),
+            e => e.to_path_d(),
+        }).collect();
+        let tf = key.1.map_or(String::new(), |t| format!(r#" transform="{}""#, t));
+        out.push_str(&format!(r#"<path d="{}"{}{}/>"#, d.join(" "), key.0, tf));
+    }
+    out
+}
+
+/// Rename each of `ids` that `taken` already holds to the first `id-N` (N from 2)
+/// free on both sides. Returns the old → new ids.
+fn disambiguate<'a, 'b>(ids: impl Iterator<Item = &'a mut String>, taken: impl Iterator<Item = &'b str>) -> HashMap<String, String> {
+    let ids: Vec<&mut String> = ids.collect();
+    let taken: HashSet<&str> = taken.collect();
+    let mut used: HashSet<String> = taken.iter().map(|s| s.to_string()).chain(ids.iter().map(|s| s.to_string())).collect();
+    let mut renamed = HashMap::new();
+    for id in ids {
+        if !taken.contains(id.as_str()) { continue; }
+        let fresh = (2..).map(|n| format!("{}-{}", id, n)).find(|c| !used.contains(c)).unwrap_or_default();
+        used.insert(fresh.clone());
+        renamed.entry(std::mem::replace(id, fresh.clone())).or_insert(fresh);
+    }
+    renamed
+}
+
+/// `css` with every identifier (a run of letters, digits, `-` and `_`) that
+/// `renamed` holds swapped for its new name
+fn rename_css_idents(css: &str, renamed: &HashMap<String, String>) -> String {
+    let is_ident = |c: char| c.is_ascii_alphanumeric() || c == '-' || c == '_';
+    let (mut out, mut rest) = (String::with_capacity(css.len()), css);
+    while let Some(start) = rest.find(is_ident) {
+        let len = rest[start..].find(|c| !is_ident(c)).unwrap_or(rest.len() - start);
+        let ident = &rest[start..start + len];
+        out.push_str(&rest[..start]);
+        out.push_str(renamed.get(ident).map_or(ident, String::as_str));
+        rest = &rest[start + len..];
+    }
+    out.push_str(rest);
+    out
+}
+
+/// Nesting limit for inline symbol expansion
+pub const MAX_SYMBOL_DEPTH: usize = 32;
+
+/// Def id reserved for the canvas background gradient
+pub const BACKGROUND_GRADIENT_ID: &str = "canvas-gradient";
+/// Def id reserved for the canvas background image pattern
+pub const BACKGROUND_IMAGE_ID: &str = "canvas-image";
+
+/// Options controlling SVG output
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct RenderOptions {
+    /// Inline symbol children at each `use` instead of emitting `<symbol>`/`<use>`
+    pub expand_symbols: bool,
+    /// Draw each run of consecutive same-styled shapes as one `<path>` with a
+    /// subpath per shape, cutting DOM size for large uniform scenes
+    pub merge_paths: bool,
+}
+
 /// Scene container using standardized sizes
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 #[cfg_attr(feature = "python", pyclass)]
 pub struct Scene {
     pub size: CanvasSize,
+    pub aspect: AspectRatio,
     pub background: String,
+    /// Gradient painted over the solid background
+    pub background_gradient: Option<Gradient>,
+    /// Image covering the canvas, drawn over the gradient or solid background
+    pub background_image: Option<String>,
     elements: Vec<Element>,
     gradients: Vec<Gradient>,
     filters: Vec<Filter>,
     symbols: Vec<Symbol>,
     keyframes: Vec<SceneKeyframes>,
+    /// Layers left out of the rendered output
+    #[serde(default)]
+    hidden_layers: BTreeSet<String>,
+    #[serde(skip)]
+    revision: Revision,
+}
+
+/// Stamp on a scene's element list, drawn fresh from a global counter when the
+/// scene is built and whenever its elements may change. Equal stamps therefore
+/// mean equal elements (a clone shares its original's until either is edited),
+/// which lets `IndexCache` reuse an index without looking at the elements.
+/// Ignored by equality and serialization.
+#[derive(Clone, Copy, Debug)]
+struct Revision(u64);
+
+impl Revision {
+    fn next() -> Self {
+        static NEXT: AtomicU64 = AtomicU64::new(0);
+        Self(NEXT.fetch_add(1, Ordering::Relaxed))
+    }
+}
+
+impl Default for Revision {
+    fn default() -> Self { Self::next() }
+}
+
+impl PartialEq for Revision {
+    fn eq(&self, _: &Self) -> bool { true }
 }
 
 impl Default for Scene {
     fn default() -> Self {
-        Self { size: CanvasSize::Medium, background: "#fff".into(), elements: Vec::new(), gradients: Vec::new(), filters: Vec::new(), symbols: Vec::new(), keyframes: Vec::new() }
+        Self::new(CanvasSize::Medium, "#fff".into())
     }
 }
 
@@ -259,43 +652,98 @@ impl Default for Scene {
 impl Scene {
     #[new]
     #[pyo3(signature = (size=CanvasSize::Medium, background="#fff".to_string()))]
-    fn py_new(size: CanvasSize, background: String) -> Self {
-        Self { size, background, elements: Vec::new(), gradients: Vec::new(), filters: Vec::new(), symbols: Vec::new(), keyframes: Vec::new() }
-    }
+    fn py_new(size: CanvasSize, background: String) -> Self { Self::new(size, background) }
     #[getter] fn get_size(&self) -> CanvasSize { self.size }
     #[setter] fn set_size(&mut self, v: CanvasSize) { self.size = v; }
+    #[getter] fn get_aspect(&self) -> (u32, u32) { (self.aspect.w, self.aspect.h) }
+    #[setter] fn set_aspect(&mut self, v: (u32, u32)) { self.aspect = AspectRatio::new(v.0, v.1).unwrap_or_default(); }
     #[getter] fn get_width(&self) -> u32 { self.width() }
     #[getter] fn get_height(&self) -> u32 { self.height() }
     #[getter] fn get_background(&self) -> String { self.background.clone() }
     #[setter] fn set_background(&mut self, v: String) { self.background = v; }
-    fn add_rect(&mut self, rect: Rect) { self.elements.push(Element::Rect(rect)); }
-    fn add_circle(&mut self, circle: Circle) { self.elements.push(Element::Circle(circle)); }
-    fn add_ellipse(&mut self, ellipse: Ellipse) { self.elements.push(Element::Ellipse(ellipse)); }
-    fn add_line(&mut self, line: Line) { self.elements.push(Element::Line(line)); }
-    fn add_path(&mut self, path: Path) { self.elements.push(Element::Path(path)); }
-    fn add_polygon(&mut self, polygon: Polygon) { self.elements.push(Element::Polygon(polygon)); }
-    fn add_text(&mut self, text: Text) { self.elements.push(Element::Text(text)); }
-    fn add_image(&mut self, image: Image) { self.elements.push(Element::Image(image)); }
+    #[getter] fn get_background_gradient(&self) -> Option<Gradient> { self.background_gradient.clone() }
+    #[setter] fn set_background_gradient(&mut self, v: Option<Gradient>) { self.background_gradient = v; }
+    #[getter] fn get_background_image(&self) -> Option<String> { self.background_image.clone() }
+    #[setter] fn set_background_image(&mut self, v: Option<String>) { self.background_image = v; }
+    fn add_rect(&mut self, rect: Rect) { self.push(Element::Rect(rect)); }
+    fn add_circle(&mut self, circle: Circle) { self.push(Element::Circle(circle)); }
+    fn add_ellipse(&mut self, ellipse: Ellipse) { self.push(Element::Ellipse(ellipse)); }
+    fn add_line(&mut self, line: Line) { self.push(Element::Line(line)); }
+    fn add_path(&mut self, path: Path) { self.push(Element::Path(path)); }
+    fn add_polygon(&mut self, polygon: Polygon) { self.push(Element::Polygon(polygon)); }
+    fn add_text(&mut self, text: Text) { self.push(Element::Text(text)); }
+    fn add_image(&mut self, image: Image) { self.push(Element::Image(image)); }
     fn add_gradient(&mut self, gradient: Gradient) { self.gradients.push(gradient); }
     fn add_filter(&mut self, filter: Filter) { self.filters.push(filter); }
     fn add_symbol(&mut self, symbol: Symbol) { self.symbols.push(symbol); }
-    fn add_use(&mut self, use_el: Use) { self.elements.push(Element::Use(use_el)); }
-    fn clear(&mut self) { self.elements.clear(); self.gradients.clear(); self.filters.clear(); self.symbols.clear(); }
+    fn add_use(&mut self, use_el: Use) { self.push(Element::Use(use_el)); }
+    fn add_node(&mut self, node: Node) { self.push(Element::Node(node)); }
+    /// Live handles to the top-level elements, in the order they render
+    #[pyo3(name = "ordered_elements")]
+    fn py_ordered_elements(slf: PyRef<'_, Self>) -> Vec<ElementRef> {
+        let (py, order) = (slf.py(), slf.paint_order());
+        let scene: Py<Scene> = slf.into();
+        order.into_iter().map(|i| ElementRef::new(scene.clone_ref(py), i, None)).collect()
+    }
+    /// Live handles to the top-level elements, in declaration order
+    #[pyo3(name = "elements")]
+    fn py_elements(slf: PyRef<'_, Self>) -> Vec<ElementRef> {
+        let (py, count) = (slf.py(), slf.elements.len());
+        let scene: Py<Scene> = slf.into();
+        (0..count).map(|i| ElementRef::new(scene.clone_ref(py), i, None)).collect()
+    }
+    /// Handle to the node with `id`, searching top-level nodes and graph contents
+    fn find_by_id(slf: PyRef<'_, Self>, id: &str) -> Option<ElementRef> {
+        let (index, node) = slf.elements.iter().enumerate().find_map(|(i, el)| match el {
+            Element::Node(n) if n.id == id => Some((i, None)),
+            Element::Graph(g) => g.nodes.iter().position(|n| n.id == id).map(|n| (i, Some(n))),
+            _ => None,
+        })?;
+        Some(ElementRef::new(slf.into(), index, node))
+    }
+    /// Wrap another scene's elements in a `<g>`; opacity below 1 fades the group as one layer
+    #[pyo3(signature = (content, transform=None, opacity=1.0))]
+    fn add_group(&mut self, content: PyRef<'_, Scene>, transform: Option<String>, opacity: f32) {
+        self.push(Element::Group(content.elements.clone(), transform, opacity.clamp(0.0, 1.0)));
+    }
+    #[pyo3(name = "bounding_box")]
+    fn py_bounding_box(&self) -> (f64, f64, f64, f64) { self.bounding_box() }
+    #[pyo3(name = "center")]
+    fn py_center(&self) -> (f64, f64) { self.center() }
+    #[pyo3(name = "translate_all")]
+    fn py_translate_all(&mut self, dx: f64, dy: f64) { self.translate_all(dx, dy) }
+    #[pyo3(name = "merge", signature = (other, offset=(0.0, 0.0)))]
+    fn py_merge(&mut self, other: Scene, offset: (f64, f64)) { self.merge(other, offset) }
+    #[pyo3(name = "layers")]
+    fn py_layers(&self) -> Vec<String> { self.layers().into_iter().map(String::from).collect() }
+    #[pyo3(name = "set_layer_visible")]
+    fn py_set_layer_visible(&mut self, name: &str, visible: bool) { self.set_layer_visible(name, visible) }
+    #[pyo3(name = "layer_visible")]
+    fn py_layer_visible(&self, name: &str) -> bool { self.layer_visible(name) }
+    fn clear(&mut self) { self.elements_mut().clear(); self.gradients.clear(); self.filters.clear(); self.symbols.clear(); }
     fn count(&self) -> usize { self.elements.len() }
-    fn to_svg(&self) -> String { self.render_svg() }
+    #[pyo3(signature = (expand_symbols=false, merge_paths=false))]
+    fn to_svg(&self, expand_symbols: bool, merge_paths: bool) -> String { self.render_svg_with(RenderOptions { expand_symbols, merge_paths }) }
     fn to_json(&self) -> String { self.render_json() }
 }
 
 impl Scene {
     pub fn new(size: CanvasSize, background: String) -> Self {
-        Self { size, background, elements: Vec::new(), gradients: Vec::new(), filters: Vec::new(), symbols: Vec::new(), keyframes: Vec::new() }
+        Self {
+            size, aspect: AspectRatio::SQUARE, background, background_gradient: None, background_image: None,
+            elements: Vec::new(), gradients: Vec::new(), filters: Vec::new(), symbols: Vec::new(), keyframes: Vec::new(),
+            hidden_layers: BTreeSet::new(), revision: Revision::next(),
+        }
     }
     
-    #[inline] pub fn width(&self) -> u32 { self.size.pixels() }
-    #[inline] pub fn height(&self) -> u32 { self.size.pixels() }
-    #[inline] pub fn dimensions(&self) -> (u32, u32) { self.size.dimensions() }
+    /// Set a non-square aspect ratio (the size tier stays the shorter side)
+    pub fn with_aspect(mut self, aspect: AspectRatio) -> Self { self.aspect = aspect; self }
+
+    #[inline] pub fn width(&self) -> u32 { self.dimensions().0 }
+    #[inline] pub fn height(&self) -> u32 { self.dimensions().1 }
+    #[inline] pub fn dimensions(&self) -> (u32, u32) { self.size.dimensions_with(self.aspect) }
     
-    pub fn push(&mut self, el: Element) { self.elements.push(el); }
+    pub fn push(&mut self, el: Element) { self.elements_mut().push(el); }
     pub fn push_symbol(&mut self, sym: Symbol) { self.symbols.push(sym); }
     pub fn push_gradient(&mut self, g: Gradient) { self.gradients.push(g); }
     pub fn push_filter(&mut self, f: Filter) { self.filters.push(f); }
@@ -308,13 +756,97 @@ impl Scene {
     pub fn push_keyframes(&mut self, kf: SceneKeyframes) { self.keyframes.push(kf); }
     
     #[inline] pub fn elements(&self) -> &[Element] { &self.elements }
-    #[inline] pub fn elements_mut(&mut self) -> &mut Vec<Element> { &mut self.elements }
+    #[inline] pub fn elements_mut(&mut self) -> &mut Vec<Element> { self.revision = Revision::next(); &mut self.elements }
+    /// Changes whenever the elements may have; see `render::IndexCache`
+    #[inline] pub fn revision(&self) -> u64 { self.revision.0 }
     #[inline] pub fn gradients(&self) -> &[Gradient] { &self.gradients }
     #[inline] pub fn filters(&self) -> &[Filter] { &self.filters }
     #[inline] pub fn symbols(&self) -> &[Symbol] { &self.symbols }
     #[inline] pub fn keyframes(&self) -> &[SceneKeyframes] { &self.keyframes }
 
-    pub fn render_svg(&self) -> String {
+    /// Layer names in the order their first element appears
+    pub fn layers(&self) -> Vec<&str> {
+        let mut names: Vec<&str> = Vec::new();
+        for name in self.elements.iter().filter_map(Element::layer) {
+            if !names.contains(&name) { names.push(name); }
+        }
+        names
+    }
+
+    /// Show or hide every element on layer `name`. Hidden layers stay in the
+    /// scene, so element ids don't shift, but are left out of the SVG.
+    pub fn set_layer_visible(&mut self, name: &str, visible: bool) {
+        if visible { self.hidden_layers.remove(name); } else { self.hidden_layers.insert(name.to_string()); }
+    }
+
+    pub fn layer_visible(&self, name: &str) -> bool { !self.hidden_layers.contains(name) }
+    #[inline] pub fn hidden_layers(&self) -> &BTreeSet<String> { &self.hidden_layers }
+
+    /// Same canvas, defs, keyframes and elements (geometry, style and ids), in the
+    /// same order. Cached hints such as a path's precomputed bounds are ignored, so a
+    /// clone taken as an undo snapshot compares equal until something visible changes.
+    pub fn structurally_eq(&self, other: &Scene) -> bool { self == other }
+
+    /// Union of every element's transformed bounds as `(x, y, w, h)`; zero for an empty scene
+    pub fn bounding_box(&self) -> (f64, f64, f64, f64) {
+        let (x, y, w, h) = union_bounds(self.elements.iter().map(Element::transformed_bounds));
+        (x as f64, y as f64, w as f64, h as f64)
+    }
+
+    /// Center of [`Self::bounding_box`]
+    pub fn center(&self) -> (f64, f64) {
+        let (x, y, w, h) = self.bounding_box();
+        (x + w / 2.0, y + h / 2.0)
+    }
+
+    /// Move all content by `(dx, dy)`, e.g. to recenter it on the canvas
+    pub fn translate_all(&mut self, dx: f64, dy: f64) {
+        for el in self.elements_mut() { el.translate(dx as f32, dy as f32); }
+    }
+
+    /// Append `other`'s elements and defs above this scene's, moved by `offset`.
+    /// Gradients, filters, symbols, keyframes and standalone node ids of `other`
+    /// that are already taken here get a numeric suffix (`glow` becomes `glow-2`),
+    /// and `other`'s paints, filters, `use` hrefs, animation classes and edge
+    /// endpoints follow the rename.
+    pub fn merge(&mut self, mut other: Scene, offset: (f64, f64)) {
+        other.translate_all(offset.0, offset.1);
+        let paint = disambiguate(other.gradients.iter_mut().map(|g| &mut g.id), self.gradients.iter().map(|g| g.id.as_str()));
+        let filter = disambiguate(other.filters.iter_mut().map(|f| &mut f.id), self.filters.iter().map(|f| f.id.as_str()));
+        let href = disambiguate(other.symbols.iter_mut().map(|s| &mut s.id), self.symbols.iter().map(|s| s.id.as_str()));
+        // Keyframes and animation classes share one namespace; a renamed rule is
+        // rewritten wherever its css or an element names it
+        let animation = disambiguate(other.keyframes.iter_mut().map(|k| &mut k.name), self.keyframes.iter().map(|k| k.name.as_str()));
+        for kf in &mut other.keyframes { kf.css = rename_css_idents(&kf.css, &animation); }
+        crate::render::rename_refs(&mut other.elements, &mut other.symbols, paint, filter, href, animation);
+
+        let own_nodes: Vec<String> = self.elements.iter().filter_map(|e| match e { Element::Node(n) => Some(n.id.clone()), _ => None }).collect();
+        let nodes = disambiguate(
+            other.elements.iter_mut().filter_map(|e| match e { Element::Node(n) => Some(&mut n.id), _ => None }),
+            own_nodes.iter().map(String::as_str),
+        );
+        for el in &mut other.elements {
+            if let Element::Edge(e) = el {
+                for end in [&mut e.from_id, &mut e.to_id] {
+                    if let Some(id) = nodes.get(end) { *end = id.clone(); }
+                }
+            }
+        }
+
+        self.elements_mut().append(&mut other.elements);
+        self.gradients.append(&mut other.gradients);
+        self.filters.append(&mut other.filters);
+        self.symbols.append(&mut other.symbols);
+        self.keyframes.append(&mut other.keyframes);
+        self.hidden_layers.append(&mut other.hidden_layers);
+    }
+
+    /// Diff against a newer scene, yielding id-tagged ops that transform `self` into `other`
+    pub fn diff(&self, other: &Scene) -> DiffResult { crate::render::diff(self, other) }
+
+    pub fn render_svg(&self) -> String { self.render_svg_with(RenderOptions::default()) }
+
+    pub fn render_svg_with(&self, opts: RenderOptions) -> String {
         let (w, h) = self.dimensions();
         let mut svg = format!(r#"<svg xmlns="http://www.w3.org/2000/svg" width="{}" height="{}">"#, w, h);
         
@@ -328,40 +860,209 @@ impl Scene {
             svg.push_str("</style>");
         }
         
-        svg.push_str(&format!(r#"<rect width="100%" height="100%" fill="{}"/>"#, self.background));
+        svg.push_str(&crate::svg::background_rect(&self.background_fill()));
         
-        // Check if we need arrow markers (for edges/graphs)
+        svg.push_str(&self.defs_svg(opts));
+        if opts.expand_symbols {
+            let mut stack = Vec::new();
+            svg.push_str(&self.layered_svg(opts, |el| self.render_expanded(el, &mut stack)));
+        } else {
+            svg.push_str(&self.layered_svg(opts, Element::to_svg));
+        }
+        svg.push_str("</svg>");
+        svg
+    }
+
+    /// Top-level elements drawn with `render`, each visible layer wrapped in one
+    /// `<g id="layer-NAME">`; with `merge_paths`, shapes merge within a layer only
+    fn layered_svg<'a>(&'a self, opts: RenderOptions, mut render: impl FnMut(&'a Element) -> String) -> String {
+        self.paint_runs().into_iter().map(|(layer, indices)| {
+            let elements = indices.into_iter().map(|i| &self.elements[i]);
+            let svg: String = if opts.merge_paths { merged_svg(elements, &mut render) } else { elements.map(&mut render).collect() };
+            match layer {
+                Some(name) => format!(r#"<g id="layer-{}">{}</g>"#, html_escape(name), svg),
+                None => svg,
+            }
+        }).collect()
+    }
+
+    /// Element indices in paint order, grouped by layer. Elements are stable-sorted
+    /// by `z`, so equal `z` keeps declaration order; each visible layer's elements
+    /// then gather where its first element falls, and hidden layers are skipped.
+    /// Consecutive unlayered elements share a run.
+    fn paint_runs(&self) -> Vec<(Option<&str>, Vec<usize>)> {
+        let mut order: Vec<usize> = (0..self.elements.len()).collect();
+        order.sort_by_key(|&i| self.elements[i].z());
+        let mut runs: Vec<(Option<&str>, Vec<usize>)> = Vec::new();
+        for i in order {
+            match self.elements[i].layer() {
+                None => match runs.last_mut() {
+                    Some((None, indices)) => indices.push(i),
+                    _ => runs.push((None, vec![i])),
+                },
+                Some(name) if self.hidden_layers.contains(name) => {}
+                Some(name) => match runs.iter_mut().find(|(layer, _)| *layer == Some(name)) {
+                    Some((_, indices)) => indices.push(i),
+                    None => runs.push((Some(name), vec![i])),
+                },
+            }
+        }
+        runs
+    }
+
+    /// Indices of the top-level elements in the order they render (see `paint_runs`)
+    pub fn paint_order(&self) -> Vec<usize> {
+        self.paint_runs().into_iter().flat_map(|(_, indices)| indices).collect()
+    }
+
+    /// Top-level elements in the order they render
+    pub fn ordered_elements(&self) -> Vec<&Element> {
+        self.paint_order().into_iter().map(|i| &self.elements[i]).collect()
+    }
+
+    /// Whether the SVG's top-level children are exactly the elements, in order:
+    /// no layers and no element sorted behind an earlier one by `z`
+    pub fn paints_in_order(&self) -> bool {
+        self.elements.iter().all(|e| e.layer().is_none()) && self.elements.windows(2).all(|w| w[0].z() <= w[1].z())
+    }
+
+    /// The `<defs>` block: background, gradients, filters, symbols (unless expanded)
+    /// and whichever arrow markers the elements need. Empty when nothing is referenced.
+    pub fn defs_svg(&self, opts: RenderOptions) -> String {
         let needs_markers = self.elements.iter().any(|e| matches!(e, Element::Edge(_) | Element::Graph(_)));
-        let needs_defs = !self.gradients.is_empty() || !self.filters.is_empty() || !self.symbols.is_empty() || needs_markers;
-        
-        if needs_defs {
-            svg.push_str("<defs>");
-            for g in &self.gradients { svg.push_str(&g.to_svg()); }
-            for f in &self.filters { svg.push_str(&f.to_svg()); }
-            for s in &self.symbols { svg.push_str(&s.to_svg_def()); }
-            if needs_markers {
-                svg.push_str(&super::shape::arrow_marker_defs("arrow", "#333"));
-                svg.push_str(&super::shape::arrow_marker_defs("graph", "#333"));
+        let mut line_arrow_colors = Vec::new();
+        arrow_colors(&self.elements, &mut line_arrow_colors);
+        self.symbols.iter().for_each(|s| arrow_colors(&s.children, &mut line_arrow_colors));
+        let emit_symbols = !opts.expand_symbols && !self.symbols.is_empty();
+        let background_defs = self.background_defs();
+        if self.gradients.is_empty() && self.filters.is_empty() && !emit_symbols && !needs_markers && line_arrow_colors.is_empty() && background_defs.is_empty() {
+            return String::new();
+        }
+
+        let mut svg = String::from("<defs>");
+        svg.push_str(&background_defs);
+        for g in &self.gradients {
+            svg.push_str(&if g.shared { g.to_svg_over(self.paint_bounds(&g.id)) } else { g.to_svg() });
+        }
+        for f in &self.filters { svg.push_str(&f.to_svg()); }
+        if emit_symbols { for s in &self.symbols { svg.push_str(&s.to_svg_def()); } }
+        if needs_markers {
+            svg.push_str(&super::shape::arrow_marker_defs("arrow", "#333"));
+            svg.push_str(&super::shape::arrow_marker_defs("graph", "#333"));
+            for (prefix, head, size) in self.custom_arrow_heads() {
+                svg.push_str(&super::shape::arrowhead_marker_defs(prefix, "#333", head, size));
             }
-            svg.push_str("</defs>");
         }
-        for el in &self.elements { svg.push_str(&el.to_svg()); }
-        svg.push_str("</svg>");
+        for color in line_arrow_colors {
+            svg.push_str(&super::shape::arrow_marker_defs(&super::shape::line_marker_prefix(color), color));
+        }
+        svg.push_str("</defs>");
         svg
     }
+
+    /// Distinct non-default arrowheads on edges, with the marker prefix each is
+    /// drawn under, in first-use order
+    fn custom_arrow_heads(&self) -> Vec<(&'static str, &str, f32)> {
+        let mut heads: Vec<(&'static str, &str, f32)> = Vec::new();
+        let edges = self.elements.iter().flat_map(|e| match e {
+            Element::Edge(edge) => vec![("arrow", edge)],
+            Element::Graph(g) => g.edges.iter().map(|edge| ("graph", edge)).collect(),
+            _ => Vec::new(),
+        });
+        for (prefix, edge) in edges.filter(|(_, edge)| edge.has_custom_head()) {
+            let head = (prefix, edge.arrow_head.as_str(), edge.arrow_size);
+            if !heads.contains(&head) { heads.push(head); }
+        }
+        heads
+    }
+
+    /// Gradient, filter and symbol lists with the elements referencing them,
+    /// for passes that rewrite defs in place
+    pub(crate) fn defs_mut(&mut self) -> (&mut Vec<Gradient>, &mut Vec<Filter>, &mut Vec<Symbol>, &mut Vec<Element>) {
+        self.revision = Revision::next();
+        (&mut self.gradients, &mut self.filters, &mut self.symbols, &mut self.elements)
+    }
+
+    /// Paint for the background rect: the image pattern, gradient, or solid color
+    fn background_fill(&self) -> String {
+        if self.background_image.is_some() { format!("url(#{})", BACKGROUND_IMAGE_ID) }
+        else if self.background_gradient.is_some() { format!("url(#{})", BACKGROUND_GRADIENT_ID) }
+        else { self.background.clone() }
+    }
+
+    /// Union of the local bounds of every element (inside groups too) painted with
+    /// `url(#id)`, the user space a shared gradient is laid over. Shapes sharing a
+    /// gradient should share a coordinate system, since each is painted in its own.
+    pub fn paint_bounds(&self, id: &str) -> (f32, f32, f32, f32) {
+        fn collect(els: &[Element], url: &str, out: &mut Vec<(f32, f32, f32, f32)>) {
+            for el in els {
+                match el {
+                    Element::Group(children, _, _) => collect(children, url, out),
+                    el if el.style().is_some_and(|s| s.fill.as_deref() == Some(url) || s.stroke.as_deref() == Some(url)) => out.push(el.bounds()),
+                    _ => {}
+                }
+            }
+        }
+        let mut boxes = Vec::new();
+        collect(&self.elements, &format!("url(#{})", id), &mut boxes);
+        union_bounds(boxes.into_iter())
+    }
+
+    /// Defs referenced by the background rect (empty for a solid background)
+    pub fn background_defs(&self) -> String {
+        let mut defs = String::new();
+        if let Some(g) = &self.background_gradient {
+            defs.push_str(&Gradient { id: BACKGROUND_GRADIENT_ID.into(), ..g.clone() }.to_svg());
+        }
+        if let Some(href) = &self.background_image {
+            let (w, h) = self.dimensions();
+            let base = if self.background_gradient.is_some() { format!("url(#{})", BACKGROUND_GRADIENT_ID) } else { self.background.clone() };
+            defs.push_str(&format!(
+                r#"<pattern id="{}" patternUnits="userSpaceOnUse" width="{w}" height="{h}"><rect width="{w}" height="{h}" fill="{}"/><image width="{w}" height="{h}" href="{}" preserveAspectRatio="xMidYMid slice"/></pattern>"#,
+                BACKGROUND_IMAGE_ID, base, html_escape(href)
+            ));
+        }
+        defs
+    }
+
+    /// Render an element with `use` references replaced by the symbol's children.
+    /// `stack` holds the symbols being expanded: cyclic references are dropped and
+    /// nesting stops at `MAX_SYMBOL_DEPTH`.
+    fn render_expanded<'a>(&'a self, el: &'a Element, stack: &mut Vec<&'a str>) -> String {
+        match el {
+            Element::Use(u) => {
+                let Some(sym) = self.symbols.iter().find(|s| s.id == u.href) else { return String::new() };
+                if stack.len() >= MAX_SYMBOL_DEPTH || stack.contains(&sym.id.as_str()) { return String::new(); }
+                stack.push(&sym.id);
+                let inner: String = sym.children.iter().map(|c| self.render_expanded(c, stack)).collect();
+                stack.pop();
+                format!(r#"<g transform="{}"{}>{}</g>"#, u.expansion_transform(sym), u.style.to_svg_attrs(), inner)
+            }
+            Element::Group(children, tf, opacity) => {
+                let inner: String = children.iter().map(|c| self.render_expanded(c, stack)).collect();
+                Element::group_svg(&inner, tf.as_deref(), *opacity)
+            }
+            _ => el.to_svg(),
+        }
+    }
     /// Output the element tree as structured JSON for debugging and tools integration
     pub fn render_json(&self) -> String { 
         let (w, h) = self.dimensions();
         serde_json::json!({
+            "version": crate::FORMAT_VERSION,
             "size": self.size.to_string(),
+            "aspect": self.aspect.to_string(),
             "width": w,
             "height": h,
             "background": self.background,
+            "background_gradient": self.background_gradient,
+            "background_image": self.background_image,
             "elements": self.elements,
             "gradients": self.gradients,
             "filters": self.filters,
             "symbols": self.symbols,
             "keyframes": self.keyframes,
+            "hidden_layers": self.hidden_layers,
         }).to_string()
     }
     
@@ -375,7 +1076,219 @@ impl Scene {
 mod tests {
     use super::*;
     use super::super::shape::Style;
+    use crate::render::DiffOp;
     #[test] fn test_scene_new() { let s = Scene::new(CanvasSize::Large, "#fff".into()); assert_eq!(s.dimensions(), (96, 96)); }
+    #[test] fn test_scene_aspect() { let s = Scene::new(CanvasSize::Large, "#fff".into()).with_aspect(AspectRatio { w: 2, h: 1 }); assert_eq!(s.dimensions(), (192, 96)); assert!(s.render_svg().contains(r#"width="192" height="96""#)); }
+    #[test] fn test_scene_expand_symbols() {
+        let mut s = Scene::new(CanvasSize::Medium, "#fff".into());
+        s.push_symbol(Symbol { id: "dot".into(), viewbox: Some((0.0, 0.0, 10.0, 10.0)), children: vec![Element::Circle(Circle { cx: 5.0, cy: 5.0, r: 5.0, style: Style::with_fill("#f00"), transform: None })] });
+        s.push(Element::Use(Use { href: "dot".into(), x: 8.0, y: 4.0, width: Some(20.0), height: Some(20.0), style: Style { opacity: 1.0, ..Style::default() }, transform: None }));
+        let flat = s.render_svg_with(RenderOptions { expand_symbols: true, ..Default::default() });
+        assert!(!flat.contains("<use") && !flat.contains("<symbol"));
+        assert!(flat.contains(r#"<g transform="translate(8 4) scale(2)"><circle cx="5" cy="5" r="5""#));
+        assert!(s.render_svg().contains("<use href=\"#dot\""));
+    }
+    #[test] fn test_structural_equality() {
+        let mut s = Scene::new(CanvasSize::Medium, "#fff".into());
+        s.push(Element::Rect(Rect { x: 0.0, y: 0.0, w: 10.0, h: 10.0, rx: 0.0, style: Style::with_fill("#f00"), transform: None }));
+        s.push(Element::Path(Path { d: "M0 0 L10 10".into(), style: Style::default(), transform: None, bounds_hint: None }));
+        let mut snapshot = s.clone();
+        assert!(snapshot.structurally_eq(&s));
+        if let Element::Path(p) = &mut snapshot.elements_mut()[1] { p.bounds_hint = Some(p.bounds()); }
+        assert!(snapshot.structurally_eq(&s));
+        if let Element::Rect(r) = &mut snapshot.elements_mut()[0] { r.style.fill = Some("#00f".into()); }
+        assert!(!snapshot.structurally_eq(&s));
+    }
+    #[test] fn test_to_path_d_outlines() {
+        let rect = Element::Rect(Rect { x: 2.0, y: 3.0, w: 10.0, h: 5.0, rx: 0.0, style: Style::default(), transform: None });
+        let corners: Vec<(f64, f64)> = crate::path::flatten_path(&rect.to_path_d(), 0.5).vertices.iter().map(|p| (p.x, p.y)).collect();
+        // The closing vertex returns to the first corner
+        assert_eq!(corners, [(2.0, 3.0), (12.0, 3.0), (12.0, 8.0), (2.0, 8.0), (2.0, 3.0)]);
+
+        let circle = Element::Circle(Circle { cx: 5.0, cy: 5.0, r: 4.0, style: Style::default(), transform: None });
+        let (x, y, w, h) = crate::path::parse_path_bounds(&circle.to_path_d());
+        assert!((x - 1.0).abs() < 0.01 && (y - 1.0).abs() < 0.01 && (w - 8.0).abs() < 0.01 && (h - 8.0).abs() < 0.01);
+        let rounded = Element::Rect(Rect { x: 0.0, y: 0.0, w: 10.0, h: 10.0, rx: 2.0, style: Style::default(), transform: None });
+        assert_eq!(rounded.to_path_d().matches('A').count(), 4);
+        assert!(Element::Group(vec![], None, 1.0).to_path_d().is_empty());
+    }
+    #[test] fn test_bounding_box_and_translate_all() {
+        let mut s = Scene::new(CanvasSize::Medium, "#fff".into());
+        let rect = |x, y, transform| Element::Rect(Rect { x, y, w: 10.0, h: 10.0, rx: 0.0, style: Style::default(), transform });
+        s.push(rect(0.0, 0.0, None));
+        s.push(rect(30.0, 20.0, None));
+        assert_eq!(s.bounding_box(), (0.0, 0.0, 40.0, 30.0));
+        assert_eq!(s.center(), (20.0, 15.0));
+        s.push(rect(0.0, 0.0, Some("translate(50, 0) scale(2)".into())));
+        assert_eq!(s.bounding_box(), (0.0, 0.0, 70.0, 30.0));
+
+        s.translate_all(5.0, -5.0);
+        assert_eq!(s.bounding_box(), (5.0, -5.0, 70.0, 30.0));
+        assert!(matches!(&s.elements()[1], Element::Rect(r) if r.x == 35.0 && r.y == 15.0));
+        assert_eq!(s.elements()[2].transform(), Some("translate(5 -5) translate(50, 0) scale(2)"));
+        assert_eq!(Scene::default().bounding_box(), (0.0, 0.0, 0.0, 0.0));
+    }
+    #[test] fn test_group_opacity() {
+        let mut s = Scene::new(CanvasSize::Medium, "#fff".into());
+        let rect = |x| Element::Rect(Rect { x, y: 0.0, w: 10.0, h: 10.0, rx: 0.0, style: Style::with_fill("#000"), transform: None });
+        s.push(Element::Group(vec![rect(0.0), rect(5.0)], None, 0.5));
+        let svg = s.render_svg();
+        assert_eq!(svg.matches("<g").count(), 1);
+        assert!(svg.contains(r#"<g opacity="0.5"><rect x="0""#));
+        assert_eq!(svg.matches("opacity").count(), 1);
+    }
+    #[test] fn test_scene_expand_cyclic_symbols() {
+        let mut s = Scene::new(CanvasSize::Medium, "#fff".into());
+        let use_of = |id: &str| Element::Use(Use { href: id.into(), x: 0.0, y: 0.0, width: None, height: None, style: Style { opacity: 1.0, ..Style::default() }, transform: None });
+        s.push_symbol(Symbol { id: "a".into(), viewbox: None, children: vec![Element::Rect(Rect { x: 0.0, y: 0.0, w: 1.0, h: 1.0, rx: 0.0, style: Style::with_fill("#f00"), transform: None }), use_of("b")] });
+        s.push_symbol(Symbol { id: "b".into(), viewbox: None, children: vec![use_of("a")] });
+        s.push(use_of("a"));
+        let flat = s.render_svg_with(RenderOptions { expand_symbols: true, ..Default::default() });
+        assert_eq!(flat.matches("<rect x=").count(), 1);
+        assert_eq!(flat.matches("<g ").count(), 2);
+    }
+    #[test] fn test_scene_diff() {
+        let rect = |x, fill| Element::Rect(Rect { x, y: 0.0, w: 10.0, h: 10.0, rx: 0.0, style: Style::with_fill(fill), transform: None });
+        let node = |id: &str| Element::Node(Node { id: id.into(), shape: "rect".into(), cx: 0.0, cy: 0.0, w: 80.0, h: 40.0, label: None, style: Style::default(), label_style: Style::default(), transform: None });
+        let mut old = Scene::new(CanvasSize::Medium, "#fff".into());
+        for el in [rect(0.0, "#f00"), node("a"), node("b"), rect(5.0, "#000")] { old.push(el); }
+        let mut new = Scene::new(CanvasSize::Medium, "#fff".into());
+        for el in [rect(0.0, "#00f"), node("b"), node("a"), Element::Circle(Circle { cx: 5.0, cy: 5.0, r: 2.0, style: Style::default(), transform: None })] { new.push(el); }
+        let result = old.diff(&new);
+        assert!(!result.needs_full_redraw());
+        let ops = &result.ops;
+        assert!(matches!(&ops[0], DiffOp::Update { idx: 0, attrs, .. } if attrs == &[("fill".to_string(), "#00f".to_string())]));
+        assert!(matches!(ops[1], DiffOp::Move { from: 2, to: 1, .. }));
+        assert!(matches!(ops[2], DiffOp::Move { from: 1, to: 2, .. }));
+        assert!(matches!(ops[3], DiffOp::Add { idx: 3, .. }));
+        assert!(matches!(ops[4], DiffOp::Remove { idx: 3, .. }));
+        assert_eq!(ops.len(), 5);
+        assert!(old.diff(&old).is_empty());
+    }
+    #[test] fn test_scene_background_gradient() {
+        let mut s = Scene::new(CanvasSize::Medium, "#fff".into());
+        s.background_gradient = Some(Gradient { id: String::new(), kind: "linear".into(), from_color: "#000".into(), to_color: "#333".into(), angle: 90.0, shared: false });
+        let svg = s.render_svg();
+        assert!(svg.contains(r##"<rect width="100%" height="100%" fill="url(#canvas-gradient)"/><defs><linearGradient id="canvas-gradient""##));
+        assert!(svg.contains(r##"stop-color="#333""##));
+    }
+    #[test] fn test_z_sort_is_stable() {
+        let rect = |x: f32, z: i32| Element::Rect(Rect { x, y: 0.0, w: 5.0, h: 5.0, rx: 0.0, style: Style { z, ..Style::with_fill("#000") }, transform: None });
+        let mut s = Scene::new(CanvasSize::Medium, "#fff".into());
+        [rect(0.0, 1), rect(10.0, 0), rect(20.0, 0)].into_iter().for_each(|el| s.push(el));
+        assert_eq!(s.paint_order(), [1, 2, 0]);
+        assert!(!s.paints_in_order());
+        let svg = s.render_svg();
+        assert!(svg.find(r#"x="20""#) < svg.find(r#"x="0""#), "{}", svg);
+        s.elements_mut()[0].set_z(0);
+        assert_eq!(s.paint_order(), [0, 1, 2]);
+        assert!(s.paints_in_order());
+    }
+    #[test] fn test_merge_paths_breaks_at_style() {
+        let rect = |x: f32, fill: &str| Element::Rect(Rect { x, y: 0.0, w: 5.0, h: 5.0, rx: 0.0, style: Style::with_fill(fill), transform: None });
+        let mut s = Scene::new(CanvasSize::Medium, "#fff".into());
+        [rect(0.0, "#f00"), rect(10.0, "#f00"), rect(20.0, "#f00"), rect(30.0, "#00f")].into_iter().for_each(|el| s.push(el));
+        let merged = s.render_svg_with(RenderOptions { merge_paths: true, ..Default::default() });
+        assert_eq!(merged.matches("<path").count(), 1, "{}", merged);
+        let d = &merged[merged.find(r#"<path d=""#).unwrap()..];
+        assert_eq!(d[..d.find(r##"" fill="#f00""##).unwrap()].matches('M').count(), 3, "{}", merged);
+        assert!(merged.contains(r##"<rect x="30" y="0" width="5" height="5" fill="#00f"/>"##), "{}", merged);
+        assert!(merged.find("<path") < merged.find(r#"<rect x="30""#));
+
+        // A different style between two matching shapes keeps all three apart
+        s.elements_mut()[1] = rect(10.0, "#00f");
+        let merged = s.render_svg_with(RenderOptions { merge_paths: true, ..Default::default() });
+        assert!(!merged.contains("<path"), "{}", merged);
+        assert_eq!(s.render_svg().matches("<rect").count(), 5);
+    }
+    #[test] fn test_merge_paths_keeps_shapes_apart() {
+        let merged = |els: Vec<Element>| {
+            let mut s = Scene::new(CanvasSize::Medium, "#fff".into());
+            els.into_iter().for_each(|el| s.push(el));
+            s.render_svg_with(RenderOptions { merge_paths: true, ..Default::default() })
+        };
+        let path = |d: &str| Element::Path(Path { d: d.into(), style: Style::with_fill("#f00"), transform: None, bounds_hint: None });
+        let rect = |x: f32, style: Style| Element::Rect(Rect { x, y: 0.0, w: 5.0, h: 5.0, rx: 0.0, style, transform: None });
+
+        // A relative second path stays where it was drawn rather than chaining off the first
+        let svg = merged(vec![path("M10 10 h5 v5 h-5 z"), path("m20 20 h5 v5 h-5 z")]);
+        assert!(svg.contains("Z M20 20 L25 20"), "{}", svg);
+
+        // Overlapping outlines would cancel or XOR under a shared winding
+        let svg = merged(vec![rect(0.0, Style::with_fill("#f00")), rect(3.0, Style::with_fill("#f00"))]);
+        assert!(!svg.contains("<path"), "{}", svg);
+
+        let evenodd = Style { fill_rule: Some("evenodd".into()), ..Style::with_fill("#f00") };
+        let svg = merged(vec![rect(0.0, evenodd.clone()), rect(10.0, evenodd)]);
+        assert!(!svg.contains("<path"), "{}", svg);
+
+        let stroked = Style { stroke: Some("#000".into()), stroke_width: 2.0, ..Style::with_fill("#f00") };
+        let svg = merged(vec![rect(0.0, stroked.clone()), rect(10.0, stroked)]);
+        assert!(!svg.contains("<path"), "{}", svg);
+    }
+    #[test] fn test_linear_gradient_angles() {
+        let coords = |angle: f32| {
+            let svg = Gradient { id: "g".into(), kind: "linear".into(), from_color: "#000".into(), to_color: "#fff".into(), angle, shared: false }.to_svg();
+            svg[svg.find("x1").unwrap()..svg.find("><stop").unwrap()].to_string()
+        };
+        assert_eq!(coords(0.0), r#"x1="0%" y1="50%" x2="100%" y2="50%""#);
+        assert_eq!(coords(90.0), r#"x1="50%" y1="0%" x2="50%" y2="100%""#);
+        assert_eq!(coords(180.0), r#"x1="100%" y1="50%" x2="0%" y2="50%""#);
+        assert_eq!(coords(270.0), r#"x1="50%" y1="100%" x2="50%" y2="0%""#);
+    }
+    #[test] fn test_merge_disambiguates_ids() {
+        let part = |fill: &str| {
+            let mut s = Scene::new(CanvasSize::Medium, "#fff".into());
+            s.push_gradient(Gradient { id: "g".into(), kind: "linear".into(), from_color: fill.into(), to_color: "#fff".into(), angle: 0.0, shared: false });
+            s.push(Element::Rect(Rect { x: 0.0, y: 0.0, w: 10.0, h: 10.0, rx: 0.0, style: Style::with_fill("url(#g)"), transform: None }));
+            s
+        };
+        let mut s = part("#f00");
+        s.merge(part("#00f"), (20.0, 5.0));
+
+        assert_eq!(s.elements().len(), 2);
+        assert_eq!(s.elements()[1].bounds(), (20.0, 5.0, 10.0, 10.0));
+        assert_eq!(s.gradients().iter().map(|g| g.id.as_str()).collect::<Vec<_>>(), ["g", "g-2"]);
+        assert_eq!(s.elements()[1].style().and_then(|st| st.fill.as_deref()), Some("url(#g-2)"));
+        let index = crate::render::IndexedScene::from_scene(&s);
+        assert_ne!(index.elements[0].id, index.elements[1].id);
+        let svg = s.render_svg();
+        assert_eq!(svg.matches(r#"id="g""#).count(), 1, "{}", svg);
+        assert_eq!(svg.matches(r#"id="g-2""#).count(), 1, "{}", svg);
+
+        let node = || Element::Node(Node { id: "a".into(), shape: "rect".into(), cx: 0.0, cy: 0.0, w: 10.0, h: 10.0, label: None, style: Style::default(), label_style: Style::default(), transform: None });
+        let mut s = Scene::new(CanvasSize::Medium, "#fff".into());
+        s.push(node());
+        let mut other = Scene::new(CanvasSize::Medium, "#fff".into());
+        other.push(node());
+        s.merge(other, (0.0, 0.0));
+        assert!(matches!(&s.elements()[1], Element::Node(n) if n.id == "a-2"));
+
+        // Each part's animation class and keyframes keep their own rules
+        let animated = |name: &str| {
+            let mut s = Scene::new(CanvasSize::Medium, "#fff".into());
+            s.push_keyframes(SceneKeyframes::new(name, format!("@keyframes {} {{ 0% {{ opacity: 0; }} }}", name)));
+            s.push_keyframes(SceneKeyframes::new("anim-1", format!(".anim-1 {{ animation: {} 1s; }}", name)));
+            s.push(Element::Rect(Rect { x: 0.0, y: 0.0, w: 10.0, h: 10.0, rx: 0.0, style: Style { animation_class: Some("anim-1".into()), ..Default::default() }, transform: None }));
+            s
+        };
+        let mut s = animated("fade");
+        s.merge(animated("fade"), (20.0, 0.0));
+        let css: Vec<&str> = s.keyframes.iter().map(|k| k.css.as_str()).collect();
+        assert_eq!(css[2..], ["@keyframes fade-2 { 0% { opacity: 0; } }", ".anim-1-2 { animation: fade-2 1s; }"]);
+        assert_eq!(s.elements()[1].style().and_then(|st| st.animation_class.as_deref()), Some("anim-1-2"));
+    }
+    #[test] fn test_scene_transparent_background() {
+        assert!(!Scene::new(CanvasSize::Medium, "transparent".into()).render_svg().contains("<rect"));
+        assert!(Scene::new(CanvasSize::Medium, "#fff".into()).render_svg().contains(r##"fill="#fff""##));
+    }
+    #[test] fn test_scene_background_image() {
+        let mut s = Scene::new(CanvasSize::Medium, "#123".into());
+        s.background_image = Some("bg.png".into());
+        let svg = s.render_svg();
+        assert!(svg.contains(r#"fill="url(#canvas-image)""#));
+        assert!(svg.contains(r##"<pattern id="canvas-image" patternUnits="userSpaceOnUse" width="64" height="64"><rect width="64" height="64" fill="#123"/><image width="64" height="64" href="bg.png""##));
+    }
     #[test] fn test_scene_svg() { let s = Scene::new(CanvasSize::Small, "#000".into()); assert!(s.render_svg().contains("</svg>")); assert!(s.render_svg().contains("48")); }
     #[test] fn test_scene_json() {
         let mut s = Scene::new(CanvasSize::Medium, "#f0f0f0".into());
@@ -3,51 +3,11 @@
 #[cfg(feature = "python")]
 use pyo3::prelude::*;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use ts_rs::TS;
-
-/// RGBA color representation
-#[derive(Clone, Debug, Default, Serialize, Deserialize, TS)]
-#[ts(export)]
-#[cfg_attr(feature = "python", pyclass(get_all, set_all))]
-pub struct Color {
-    pub r: u8,
-    pub g: u8,
-    pub b: u8,
-    pub a: f32,
-}
-
-#[cfg(feature = "python")]
-#[pymethods]
-impl Color {
-    #[new]
-    #[pyo3(signature = (r=0, g=0, b=0, a=1.0))]
-    fn py_new(r: u8, g: u8, b: u8, a: f32) -> Self { Self { r, g, b, a } }
-
-    #[staticmethod]
-    fn from_hex(hex: &str) -> PyResult<Self> { Ok(Self::parse_hex(hex)) }
-    fn to_css(&self) -> String { self.css() }
-}
-
-impl Color {
-    pub fn parse_hex(hex: &str) -> Self {
-        let hex = hex.trim_start_matches('#');
-        let (r, g, b) = match hex.len() {
-            3 => (
-                u8::from_str_radix(&hex[0..1].repeat(2), 16).unwrap_or(0),
-                u8::from_str_radix(&hex[1..2].repeat(2), 16).unwrap_or(0),
-                u8::from_str_radix(&hex[2..3].repeat(2), 16).unwrap_or(0),
-            ),
-            6 => (
-                u8::from_str_radix(&hex[0..2], 16).unwrap_or(0),
-                u8::from_str_radix(&hex[2..4], 16).unwrap_or(0),
-                u8::from_str_radix(&hex[4..6], 16).unwrap_or(0),
-            ),
-            _ => (0, 0, 0),
-        };
-        Self { r, g, b, a: 1.0 }
-    }
-    pub fn css(&self) -> String { format!("rgba({},{},{},{})", self.r, self.g, self.b, self.a) }
-}
+use crate::fmt::num;
+pub use crate::color::Color;
+use crate::svg::{arrow_marker_suffix, arrow_markers, preserve_aspect_ratio, Attrs, ARROW_HEADS, ARROW_SIZE, EDGE_LABEL_PADDING, EDGE_LABEL_SIZE};
 
 /// Style properties for shapes
 #[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize, TS)]
@@ -60,8 +20,31 @@ pub struct Style {
     pub opacity: f32,
     pub corner: f32,
     pub filter: Option<String>,
+    /// `fill-rule` (nonzero, evenodd), deciding which overlaps and holes fill
+    #[serde(default)]
+    pub fill_rule: Option<String>,
     /// Animation class name (references CSS animation)
     pub animation_class: Option<String>,
+    /// `stroke-dasharray` value, e.g. "5 3"
+    #[serde(default)]
+    pub dash: Option<String>,
+    #[serde(default)]
+    pub dash_offset: f32,
+    /// Arrowheads on a standalone line or path: `forward`, `backward`, or `both`
+    #[serde(default)]
+    pub arrow: Option<String>,
+    /// Kept in the scene (and the diff) but rendered with `display="none"`
+    #[serde(default)]
+    pub hidden: bool,
+    /// Editor metadata from `meta`, rendered as `data-*` attributes in key order
+    #[serde(default)]
+    pub meta: BTreeMap<String, String>,
+    /// Named layer the element renders in (see `Scene::layers`)
+    #[serde(default)]
+    pub layer: Option<String>,
+    /// Stacking order among top-level elements (see `Element::z`)
+    #[serde(default)]
+    pub z: i32,
 }
 
 #[cfg(feature = "python")]
@@ -70,7 +53,7 @@ impl Style {
     #[new]
     #[pyo3(signature = (fill=None, stroke=None, stroke_width=1.0, opacity=1.0, corner=0.0, filter=None))]
     fn py_new(fill: Option<String>, stroke: Option<String>, stroke_width: f32, opacity: f32, corner: f32, filter: Option<String>) -> Self {
-        Self { fill, stroke, stroke_width, opacity, corner, filter, animation_class: None }
+        Self { fill, stroke, stroke_width, opacity, corner, filter, ..Default::default() }
     }
 }
 
@@ -83,16 +66,55 @@ impl Style {
         Self { animation_class: Some(class.into()), opacity: 1.0, stroke_width: 1.0, ..Default::default() }
     }
     
+    /// Presentation attributes (leading space) in the canonical `svg::ATTR_ORDER`
     pub fn to_svg_attrs(&self) -> String {
-        let mut attrs = Vec::with_capacity(5);
-        if let Some(ref fill) = self.fill { attrs.push(format!(r#"fill="{}""#, fill)); }
-        if let Some(ref stroke) = self.stroke { attrs.push(format!(r#"stroke="{}" stroke-width="{}""#, stroke, self.stroke_width)); }
-        if self.opacity < 1.0 { attrs.push(format!(r#"opacity="{}""#, self.opacity)); }
-        if let Some(ref filter) = self.filter { attrs.push(format!(r#"filter="url(#{})""#, filter)); }
-        if let Some(ref class) = self.animation_class { attrs.push(format!(r#"class="{}""#, class)); }
-        if attrs.is_empty() { String::new() } else { format!(" {}", attrs.join(" ")) }
+        let mut attrs = Attrs::new();
+        if let Some(ref fill) = self.fill { attrs.set("fill", fill); }
+        if let Some(ref rule) = self.fill_rule { attrs.set("fill-rule", rule); }
+        if let Some(ref stroke) = self.stroke { attrs.set("stroke", stroke).set("stroke-width", num(self.stroke_width)); }
+        self.set_dash(&mut attrs);
+        if self.opacity < 1.0 { attrs.set("opacity", num(self.opacity)); }
+        if let Some(ref filter) = self.filter { attrs.set("filter", format!("url(#{})", filter)); }
+        if let Some(ref class) = self.animation_class { attrs.set("class", class); }
+        attrs.render() + &self.meta_attrs()
+    }
+
+    /// `data-*` attributes (leading space) for `meta`, shared by shapes that skip `to_svg_attrs`
+    pub fn meta_attrs(&self) -> String {
+        self.meta.iter().map(|(k, v)| format!(r#" data-{}="{}""#, k, html_escape(v))).collect()
     }
     
+    /// Dash pattern attributes (leading space), shared by strokes that skip `to_svg_attrs`
+    pub fn dash_attrs(&self) -> String {
+        let mut attrs = Attrs::new();
+        self.set_dash(&mut attrs);
+        attrs.render()
+    }
+
+    fn set_dash(&self, attrs: &mut Attrs) {
+        if let Some(ref dash) = self.dash { attrs.set("stroke-dasharray", dash); }
+        if self.dash_offset != 0.0 { attrs.set("stroke-dashoffset", num(self.dash_offset)); }
+    }
+
+    /// `marker-start`/`marker-end` attributes (leading space) for `arrow`, referencing
+    /// the scene's line markers in this style's stroke color
+    pub fn marker_attrs(&self) -> String {
+        let prefix = line_marker_prefix(self.marker_color());
+        let (start, end) = (format!("url(#{}-arrow-start)", prefix), format!("url(#{}-arrow-end)", prefix));
+        match self.arrow.as_deref() {
+            Some("forward") => format!(r#" marker-end="{}""#, end),
+            Some("backward") => format!(r#" marker-start="{}""#, start),
+            Some("both") => format!(r#" marker-start="{}" marker-end="{}""#, start, end),
+            _ => String::new(),
+        }
+    }
+
+    /// Whether `marker_attrs` references any marker
+    pub fn has_arrow(&self) -> bool { matches!(self.arrow.as_deref(), Some("forward" | "backward" | "both")) }
+
+    /// Arrowhead color: the stroke, or the line default when unset
+    pub fn marker_color(&self) -> &str { self.stroke.as_deref().unwrap_or("#000") }
+
     /// Generate style attribute with animation CSS
     pub fn to_style_attr(&self, anim_css: Option<&str>) -> String {
         match anim_css {
@@ -123,9 +145,9 @@ impl Rect {
 
 impl Rect {
     pub fn to_svg(&self) -> String {
-        let rx = if self.rx > 0.0 { format!(r#" rx="{}""#, self.rx) } else { String::new() };
+        let rx = if self.rx > 0.0 { format!(r#" rx="{}""#, num(self.rx)) } else { String::new() };
         format!(r#"<rect x="{}" y="{}" width="{}" height="{}"{}{}{}/>"#,
-            self.x, self.y, self.w, self.h, rx, self.style.to_svg_attrs(), transform_attr(&self.transform))
+            num(self.x), num(self.y), num(self.w), num(self.h), rx, self.style.to_svg_attrs(), transform_attr(&self.transform))
     }
     pub fn bounds(&self) -> (f32, f32, f32, f32) { (self.x, self.y, self.w, self.h) }
 }
@@ -151,7 +173,7 @@ impl Circle {
 
 impl Circle {
     pub fn to_svg(&self) -> String {
-        format!(r#"<circle cx="{}" cy="{}" r="{}"{}{}/>"#, self.cx, self.cy, self.r, self.style.to_svg_attrs(), transform_attr(&self.transform))
+        format!(r#"<circle cx="{}" cy="{}" r="{}"{}{}/>"#, num(self.cx), num(self.cy), num(self.r), self.style.to_svg_attrs(), transform_attr(&self.transform))
     }
     pub fn bounds(&self) -> (f32, f32, f32, f32) { (self.cx - self.r, self.cy - self.r, self.r * 2.0, self.r * 2.0) }
 }
@@ -177,7 +199,7 @@ impl Ellipse {
 
 impl Ellipse {
     pub fn to_svg(&self) -> String {
-        format!(r#"<ellipse cx="{}" cy="{}" rx="{}" ry="{}"{}{}/>"#, self.cx, self.cy, self.rx, self.ry, self.style.to_svg_attrs(), transform_attr(&self.transform))
+        format!(r#"<ellipse cx="{}" cy="{}" rx="{}" ry="{}"{}{}/>"#, num(self.cx), num(self.cy), num(self.rx), num(self.ry), self.style.to_svg_attrs(), transform_attr(&self.transform))
     }
     pub fn bounds(&self) -> (f32, f32, f32, f32) { (self.cx - self.rx, self.cy - self.ry, self.rx * 2.0, self.ry * 2.0) }
 }
@@ -206,8 +228,8 @@ impl Line {
 impl Line {
     pub fn to_svg(&self) -> String {
         let stroke = self.style.stroke.as_deref().unwrap_or("#000");
-        format!(r#"<line x1="{}" y1="{}" x2="{}" y2="{}" stroke="{}" stroke-width="{}"{}/>"#,
-            self.x1, self.y1, self.x2, self.y2, stroke, self.style.stroke_width, transform_attr(&self.transform))
+        format!(r#"<line x1="{}" y1="{}" x2="{}" y2="{}" stroke="{}" stroke-width="{}"{}{}{}{}/>"#,
+            num(self.x1), num(self.y1), num(self.x2), num(self.y2), stroke, num(self.style.stroke_width), self.style.dash_attrs(), self.style.marker_attrs(), self.style.meta_attrs(), transform_attr(&self.transform))
     }
     pub fn bounds(&self) -> (f32, f32, f32, f32) {
         (self.x1.min(self.x2), self.y1.min(self.y2), (self.x1 - self.x2).abs(), (self.y1 - self.y2).abs())
@@ -215,7 +237,7 @@ impl Line {
 }
 
 /// Path primitive
-#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, TS)]
+#[derive(Clone, Debug, Serialize, Deserialize, TS)]
 #[ts(export)]
 #[cfg_attr(feature = "python", pyclass(get_all, set_all))]
 pub struct Path {
@@ -223,6 +245,11 @@ pub struct Path {
     pub bounds_hint: Option<(f32, f32, f32, f32)>,
 }
 
+/// Equal when they draw the same; `bounds_hint` is only a precomputed cache
+impl PartialEq for Path {
+    fn eq(&self, o: &Self) -> bool { self.d == o.d && self.style == o.style && self.transform == o.transform }
+}
+
 #[cfg(feature = "python")]
 #[pymethods]
 impl Path {
@@ -235,7 +262,7 @@ impl Path {
 
 impl Path {
     pub fn to_svg(&self) -> String {
-        format!(r#"<path d="{}"{}{}/>"#, self.d, self.style.to_svg_attrs(), transform_attr(&self.transform))
+        format!(r#"<path d="{}"{}{}{}/>"#, self.d, self.style.to_svg_attrs(), self.style.marker_attrs(), transform_attr(&self.transform))
     }
     pub fn bounds(&self) -> (f32, f32, f32, f32) { self.bounds_hint.unwrap_or_else(|| crate::path::parse_path_bounds(&self.d)) }
 }
@@ -259,8 +286,13 @@ impl Polygon {
 }
 
 impl Polygon {
+    /// `<polygon>`, or a `<path>` with filleted vertices when `style.corner > 0`
     pub fn to_svg(&self) -> String {
-        let pts: String = self.points.iter().map(|(x, y)| format!("{},{}", x, y)).collect::<Vec<_>>().join(" ");
+        if self.style.corner > 0.0 {
+            let d = crate::path::round_corners(&self.points, self.style.corner);
+            return format!(r#"<path d="{}"{}{}/>"#, d, self.style.to_svg_attrs(), transform_attr(&self.transform));
+        }
+        let pts: String = self.points.iter().map(|(x, y)| format!("{},{}", num(*x), num(*y))).collect::<Vec<_>>().join(" ");
         format!(r#"<polygon points="{}"{}{}/>"#, pts, self.style.to_svg_attrs(), transform_attr(&self.transform))
     }
     pub fn bounds(&self) -> (f32, f32, f32, f32) {
@@ -279,39 +311,74 @@ impl Polygon {
 pub struct Text {
     pub x: f32, pub y: f32, pub content: String, pub font: String, pub size: f32,
     pub weight: String, pub anchor: String, pub style: Style, pub transform: Option<String>,
+    /// `dominant-baseline` value (alphabetic, middle, hanging)
+    #[serde(default)]
+    pub baseline: Option<String>,
+    /// Extra space between glyphs (`letter-spacing`)
+    #[serde(default)]
+    pub letter_spacing: f32,
+    /// Extra space per space character (`word-spacing`)
+    #[serde(default)]
+    pub word_spacing: f32,
+    /// `text-decoration` value (underline, line-through, overline)
+    #[serde(default)]
+    pub decoration: Option<String>,
+    /// Writing `direction` (ltr, rtl); `rtl` flips which side `start` anchors
+    #[serde(default)]
+    pub direction: Option<String>,
 }
 
 #[cfg(feature = "python")]
 #[pymethods]
 impl Text {
     #[new]
-    #[pyo3(signature = (x, y, content, font="system-ui".to_string(), size=16.0, weight="normal".to_string(), anchor="start".to_string(), style=None, transform=None))]
-    fn py_new(x: f32, y: f32, content: String, font: String, size: f32, weight: String, anchor: String, style: Option<Style>, transform: Option<String>) -> Self {
-        Self { x, y, content, font, size, weight, anchor, style: style.unwrap_or_default(), transform }
+    #[pyo3(signature = (x, y, content, font="system-ui".to_string(), size=16.0, weight="normal".to_string(), anchor="start".to_string(), style=None, transform=None, baseline=None, letter_spacing=0.0, word_spacing=0.0, decoration=None, direction=None))]
+    fn py_new(x: f32, y: f32, content: String, font: String, size: f32, weight: String, anchor: String, style: Option<Style>, transform: Option<String>, baseline: Option<String>, letter_spacing: f32, word_spacing: f32, decoration: Option<String>, direction: Option<String>) -> Self {
+        Self { x, y, content, font, size, weight, anchor, style: style.unwrap_or_default(), transform, baseline, letter_spacing, word_spacing, decoration, direction }
     }
 }
 
 impl Text {
     pub fn to_svg(&self) -> String {
         let fill = self.style.fill.as_deref().unwrap_or("#000");
-        format!(r#"<text x="{}" y="{}" font-family="{}" font-size="{}" font-weight="{}" text-anchor="{}" fill="{}"{}>{}</text>"#,
-            self.x, self.y, self.font, self.size, self.weight, self.anchor, fill, transform_attr(&self.transform), html_escape(&self.content))
+        let mut extra = self.baseline.as_ref().map_or(String::new(), |b| format!(r#" dominant-baseline="{}""#, b));
+        if self.letter_spacing != 0.0 { extra.push_str(&format!(r#" letter-spacing="{}""#, num(self.letter_spacing))); }
+        if self.word_spacing != 0.0 { extra.push_str(&format!(r#" word-spacing="{}""#, num(self.word_spacing))); }
+        if let Some(d) = &self.decoration { extra.push_str(&format!(r#" text-decoration="{}""#, d)); }
+        if let Some(d) = &self.direction { extra.push_str(&format!(r#" direction="{}""#, d)); }
+        extra.push_str(&self.style.meta_attrs());
+        format!(r#"<text x="{}" y="{}" font-family="{}" font-size="{}" font-weight="{}" text-anchor="{}"{} fill="{}"{}>{}</text>"#,
+            num(self.x), num(self.y), self.font, num(self.size), self.weight, self.anchor, extra, fill, transform_attr(&self.transform), html_escape(&self.content))
     }
     
     /// Compute bounding box using font metrics
     pub fn bounds(&self) -> (f32, f32, f32, f32) {
-        let metrics = crate::font::measure_text(&self.content, &self.font, self.size);
-        let x = match self.anchor.as_str() {
-            "middle" => self.x - metrics.width / 2.0,
-            "end" => self.x - metrics.width,
-            _ => self.x,
-        };
-        (x, self.y - metrics.ascender, metrics.width, metrics.height)
+        let font = crate::font::get_metrics(&self.font);
+        let metrics = self.metrics();
+        let x = crate::font::anchored_left(self.x, metrics.width, &self.anchor, self.direction.as_deref() == Some("rtl"));
+        let baseline = self.baseline.as_deref().and_then(crate::font::Baseline::from_name).unwrap_or_default();
+        let y = self.y + crate::font::baseline_offset(font, self.size, baseline);
+        (x, y - metrics.ascender, metrics.width, metrics.height)
     }
     
-    /// Get detailed text metrics
+    /// Decoration line as `((x1, y), (x2, y))` across the text's width, for
+    /// renderers that draw it themselves instead of honoring `text-decoration`
+    pub fn decoration_line(&self) -> Option<((f32, f32), (f32, f32))> {
+        let decoration = crate::font::Decoration::from_name(self.decoration.as_deref()?)?;
+        let font = crate::font::get_metrics(&self.font);
+        let (x, top, w, _) = self.bounds();
+        let baseline = top + font.ascender * self.size;
+        let y = baseline + crate::font::decoration_offset(font, self.size, decoration);
+        Some(((x, y), (x + w, y)))
+    }
+
+    /// Get detailed text metrics, with letter and word spacing in the width
     pub fn metrics(&self) -> crate::font::TextMetrics {
-        crate::font::measure_text(&self.content, &self.font, self.size)
+        let font = crate::font::get_metrics(&self.font);
+        crate::font::TextMetrics {
+            width: font.measure_width_spaced(&self.content, self.size, self.letter_spacing, self.word_spacing),
+            ..font.measure(&self.content, self.size)
+        }
     }
 }
 
@@ -321,26 +388,58 @@ impl Text {
 #[cfg_attr(feature = "python", pyclass(get_all, set_all))]
 pub struct Image {
     pub x: f32, pub y: f32, pub w: f32, pub h: f32, pub href: String, pub transform: Option<String>,
+    /// Fit mode: `cover` (crop to fill) or `contain` (letterbox)
+    #[serde(default)]
+    pub fit: Option<String>,
+    #[serde(default)]
+    pub hidden: bool,
+    #[serde(default)]
+    pub layer: Option<String>,
+    #[serde(default)]
+    pub z: i32,
 }
 
 #[cfg(feature = "python")]
 #[pymethods]
 impl Image {
     #[new]
-    #[pyo3(signature = (x, y, w, h, href, transform=None))]
-    fn py_new(x: f32, y: f32, w: f32, h: f32, href: String, transform: Option<String>) -> Self {
-        Self { x, y, w, h, href, transform }
+    #[pyo3(signature = (x, y, w, h, href, transform=None, fit=None))]
+    fn py_new(x: f32, y: f32, w: f32, h: f32, href: String, transform: Option<String>, fit: Option<String>) -> Self {
+        Self { x, y, w, h, href, transform, fit, hidden: false, layer: None, z: 0 }
     }
 }
 
 impl Image {
     pub fn to_svg(&self) -> String {
-        format!(r#"<image x="{}" y="{}" width="{}" height="{}" href="{}"{}/>"#, self.x, self.y, self.w, self.h, html_escape(&self.href), transform_attr(&self.transform))
+        let par = self.fit.as_deref().and_then(preserve_aspect_ratio)
+            .map_or(String::new(), |p| format!(r#" preserveAspectRatio="{}""#, p));
+        format!(r#"<image x="{}" y="{}" width="{}" height="{}" href="{}"{}{}/>"#, num(self.x), num(self.y), num(self.w), num(self.h), html_escape(&self.href), par, transform_attr(&self.transform))
     }
     pub fn bounds(&self) -> (f32, f32, f32, f32) { (self.x, self.y, self.w, self.h) }
 }
 
-fn html_escape(s: &str) -> String { s.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;") }
+pub(crate) fn html_escape(s: &str) -> String { s.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;") }
+/// `svg` with `display="none"` added to its outermost tag when `hidden`; markup with
+/// several top-level tags (an edge and its label) is wrapped in one hidden `<g>`
+pub(crate) fn display_svg(svg: String, hidden: bool) -> String {
+    if !hidden { return svg; }
+    if !single_element(&svg) { return format!(r#"<g display="none">{}</g>"#, svg); }
+    let at = svg[1..].find([' ', '/', '>']).map_or(svg.len(), |i| i + 1);
+    format!(r#"{} display="none"{}"#, &svg[..at], &svg[at..])
+}
+
+/// Whether `svg` is one element: its first tag closes at the very end
+fn single_element(svg: &str) -> bool {
+    let (mut depth, mut rest) = (0, svg);
+    while let Some(open) = rest.find('<') {
+        let close = rest[open..].find('>').map_or(rest.len(), |i| open + i + 1);
+        let tag = &rest[open..close];
+        if tag.starts_with("</") { depth -= 1; } else if !tag.ends_with("/>") { depth += 1; }
+        rest = &rest[close..];
+        if depth <= 0 { return rest.trim().is_empty(); }
+    }
+    true
+}
 #[inline] fn transform_attr(tf: &Option<String>) -> String { tf.as_ref().map_or(String::new(), |t| format!(r#" transform="{}""#, t)) }
 
 /// Diamond primitive (rotated rect for flowcharts)
@@ -364,12 +463,11 @@ impl Diamond {
 
 impl Diamond {
     pub fn to_svg(&self) -> String {
-        let pts = format!("{},{} {},{} {},{} {},{}",
-            self.cx, self.cy - self.h / 2.0,
-            self.cx + self.w / 2.0, self.cy,
-            self.cx, self.cy + self.h / 2.0,
-            self.cx - self.w / 2.0, self.cy);
-        format!(r#"<polygon points="{}"{}{}/>"#, pts, self.style.to_svg_attrs(), transform_attr(&self.transform))
+        let (hw, hh) = (self.w / 2.0, self.h / 2.0);
+        Polygon {
+            points: vec![(self.cx, self.cy - hh), (self.cx + hw, self.cy), (self.cx, self.cy + hh), (self.cx - hw, self.cy)],
+            style: self.style.clone(), transform: self.transform.clone(),
+        }.to_svg()
     }
     pub fn bounds(&self) -> (f32, f32, f32, f32) { (self.cx - self.w / 2.0, self.cy - self.h / 2.0, self.w, self.h) }
 }
@@ -398,51 +496,64 @@ impl Node {
     }
 }
 
+/// Font size used for node labels
+const NODE_LABEL_SIZE: f32 = 16.0;
+
 impl Node {
     pub fn to_svg(&self) -> String {
         let shape_svg = match self.shape.as_str() {
             "circle" => {
                 let r = self.w.min(self.h) / 2.0;
-                format!(r#"<circle cx="{}" cy="{}" r="{}"{}/>"#, self.cx, self.cy, r, self.style.to_svg_attrs())
+                format!(r#"<circle cx="{}" cy="{}" r="{}"{}/>"#, num(self.cx), num(self.cy), num(r), self.style.to_svg_attrs())
             }
             "ellipse" => {
-                format!(r#"<ellipse cx="{}" cy="{}" rx="{}" ry="{}"{}/>"#, self.cx, self.cy, self.w / 2.0, self.h / 2.0, self.style.to_svg_attrs())
+                format!(r#"<ellipse cx="{}" cy="{}" rx="{}" ry="{}"{}/>"#, num(self.cx), num(self.cy), num(self.w / 2.0), num(self.h / 2.0), self.style.to_svg_attrs())
             }
             "diamond" => {
-                let pts = format!("{},{} {},{} {},{} {},{}",
-                    self.cx, self.cy - self.h / 2.0,
-                    self.cx + self.w / 2.0, self.cy,
-                    self.cx, self.cy + self.h / 2.0,
-                    self.cx - self.w / 2.0, self.cy);
+                let pts = self.outline().iter().map(|(x, y)| format!("{},{}", num(*x), num(*y))).collect::<Vec<_>>().join(" ");
                 format!(r#"<polygon points="{}"{}/>"#, pts, self.style.to_svg_attrs())
             }
             _ => { // rect
                 let x = self.cx - self.w / 2.0;
                 let y = self.cy - self.h / 2.0;
-                format!(r#"<rect x="{}" y="{}" width="{}" height="{}"{}/>"#, x, y, self.w, self.h, self.style.to_svg_attrs())
+                format!(r#"<rect x="{}" y="{}" width="{}" height="{}"{}/>"#, num(x), num(y), num(self.w), num(self.h), self.style.to_svg_attrs())
             }
         };
         
         let label_svg = self.label.as_ref().map_or(String::new(), |lbl| {
             let fill = self.label_style.fill.as_deref().unwrap_or("#000");
-            format!(r#"<text x="{}" y="{}" text-anchor="middle" dominant-baseline="middle" fill="{}">{}</text>"#, 
-                self.cx, self.cy, fill, html_escape(lbl))
+            let (x, y) = self.label_anchor();
+            // Shift by font metrics rather than dominant-baseline, which renderers interpret differently
+            let y = y + crate::font::baseline_offset(crate::font::get_metrics("system-ui"), NODE_LABEL_SIZE, crate::font::Baseline::Middle);
+            format!(r#"<text x="{}" y="{}" text-anchor="middle" font-size="{}" fill="{}">{}</text>"#, 
+                num(x), num(y), num(NODE_LABEL_SIZE), fill, html_escape(lbl))
         });
         
         format!(r#"<g id="node-{}"{}>{}{}</g>"#, html_escape(&self.id), transform_attr(&self.transform), shape_svg, label_svg)
     }
     
     pub fn bounds(&self) -> (f32, f32, f32, f32) { (self.cx - self.w / 2.0, self.cy - self.h / 2.0, self.w, self.h) }
+
+    /// Vertices of a polygonal node shape, clockwise from the top; empty for
+    /// rects and round shapes
+    pub fn outline(&self) -> Vec<(f32, f32)> {
+        let (hw, hh) = (self.w / 2.0, self.h / 2.0);
+        match self.shape.as_str() {
+            "diamond" => vec![(self.cx, self.cy - hh), (self.cx + hw, self.cy), (self.cx, self.cy + hh), (self.cx - hw, self.cy)],
+            _ => Vec::new(),
+        }
+    }
+
+    /// Center of the label: the outline's centroid for polygonal shapes,
+    /// otherwise the node center
+    pub fn label_anchor(&self) -> (f32, f32) {
+        let outline = self.outline();
+        if outline.is_empty() { (self.cx, self.cy) } else { crate::path::label_anchor(&outline) }
+    }
     
-    /// Get anchor point for edges (center of specified side)
+    /// Get anchor point for edges (center of specified side, or a corner)
     pub fn anchor(&self, side: &str) -> (f32, f32) {
-        match side {
-            "top" | "n" => (self.cx, self.cy - self.h / 2.0),
-            "bottom" | "s" => (self.cx, self.cy + self.h / 2.0),
-            "left" | "w" => (self.cx - self.w / 2.0, self.cy),
-            "right" | "e" => (self.cx + self.w / 2.0, self.cy),
-            _ => (self.cx, self.cy), // center
-        }
+        crate::anchor::anchor_point((self.cx, self.cy, self.w, self.h), side)
     }
 }
 
@@ -475,7 +586,13 @@ pub struct Edge {
     pub to_pt: (f32, f32),
     pub edge_style: String,
     pub arrow: String,
+    /// Arrowhead outline: `triangle`, `open`, `diamond`, or `circle`
+    pub arrow_head: String,
+    /// Arrowhead marker width in pixels
+    pub arrow_size: f32,
     pub label: Option<String>,
+    /// Fill of a rounded rect drawn behind the label
+    pub label_bg: Option<String>,
     pub style: Style,
 }
 
@@ -488,11 +605,25 @@ impl Edge {
         let mut s = style.unwrap_or_default();
         if s.stroke.is_none() { s.stroke = Some("#333".into()); }
         if s.stroke_width == 0.0 { s.stroke_width = 2.0; }
-        Self { from_id, to_id, from_pt, to_pt, edge_style, arrow, label, style: s }
+        Self {
+            from_id, to_id, from_pt, to_pt, edge_style, arrow, arrow_head: ARROW_HEADS[0].into(), arrow_size: ARROW_SIZE, label, label_bg: None, style: s,
+        }
     }
 }
 
 impl Edge {
+    /// Start and end marker ids for this edge's arrowhead among the defs
+    /// emitted under `prefix`
+    pub fn marker_ids(&self, prefix: &str) -> (String, String) {
+        let suffix = arrow_marker_suffix(&self.arrow_head, self.arrow_size);
+        (format!("{}-arrow{}-start", prefix, suffix), format!("{}-arrow{}-end", prefix, suffix))
+    }
+
+    /// Whether the arrowhead needs its own markers beside the default ones
+    pub(crate) fn has_custom_head(&self) -> bool {
+        self.arrow != "none" && !arrow_marker_suffix(&self.arrow_head, self.arrow_size).is_empty()
+    }
+
     pub fn to_svg(&self, marker_ids: (&str, &str)) -> String {
         let (x1, y1) = self.from_pt;
         let (x2, y2) = self.to_pt;
@@ -506,16 +637,16 @@ impl Edge {
                 let dy = (y2 - y1).abs();
                 let ctrl_offset = (dx.max(dy)) * 0.3;
                 if (y2 - y1).abs() > (x2 - x1).abs() {
-                    format!("M{},{} C{},{} {},{} {},{}", x1, y1, x1, my, x2, my, x2, y2)
+                    format!("M{},{} C{},{} {},{} {},{}", num(x1), num(y1), num(x1), num(my), num(x2), num(my), num(x2), num(y2))
                 } else {
-                    format!("M{},{} C{},{} {},{} {},{}", x1, y1, mx, y1 + ctrl_offset, mx, y2 - ctrl_offset, x2, y2)
+                    format!("M{},{} C{},{} {},{} {},{}", num(x1), num(y1), num(mx), num(y1 + ctrl_offset), num(mx), num(y2 - ctrl_offset), num(x2), num(y2))
                 }
             }
             "orthogonal" => {
                 let mx = (x1 + x2) / 2.0;
-                format!("M{},{} L{},{} L{},{} L{},{}", x1, y1, mx, y1, mx, y2, x2, y2)
+                format!("M{},{} L{},{} L{},{} L{},{}", num(x1), num(y1), num(mx), num(y1), num(mx), num(y2), num(x2), num(y2))
             }
-            _ => format!("M{},{} L{},{}", x1, y1, x2, y2), // straight
+            _ => format!("M{},{} L{},{}", num(x1), num(y1), num(x2), num(y2)), // straight
         };
         
         let markers = match self.arrow.as_str() {
@@ -527,12 +658,17 @@ impl Edge {
         
         let label_svg = self.label.as_ref().map_or(String::new(), |lbl| {
             let mx = (x1 + x2) / 2.0;
-            let my = (y1 + y2) / 2.0;
-            format!(r##"<text x="{}" y="{}" text-anchor="middle" dominant-baseline="middle" font-size="12" fill="#666">{}</text>"##, mx, my - 8.0, html_escape(lbl))
+            let my = (y1 + y2) / 2.0 - 8.0;
+            let bg = self.label_bg.as_ref().map_or(String::new(), |bg| {
+                let m = crate::font::measure_text(lbl, "sans-serif", EDGE_LABEL_SIZE);
+                let (w, h) = (m.width + 2.0 * EDGE_LABEL_PADDING, m.height + 2.0 * EDGE_LABEL_PADDING);
+                format!(r#"<rect x="{}" y="{}" width="{}" height="{}" rx="3" fill="{}"/>"#, num(mx - w / 2.0), num(my - h / 2.0), num(w), num(h), html_escape(bg))
+            });
+            format!(r##"{}<text x="{}" y="{}" text-anchor="middle" dominant-baseline="middle" font-size="{}" fill="#666">{}</text>"##, bg, num(mx), num(my), num(EDGE_LABEL_SIZE), html_escape(lbl))
         });
         
         format!(r#"<path d="{}" fill="none" stroke="{}" stroke-width="{}"{}/>{}"#, 
-            path_d, stroke, self.style.stroke_width, markers, label_svg)
+            path_d, stroke, num(self.style.stroke_width), markers, label_svg)
     }
     
     pub fn bounds(&self) -> (f32, f32, f32, f32) {
@@ -542,12 +678,25 @@ impl Edge {
     }
 }
 
+/// Marker id prefix for arrowheads on standalone lines and paths
+pub const LINE_MARKER_PREFIX: &str = "line";
+
+/// Marker id prefix for line and path arrowheads drawn in `color`, one marker pair per stroke color
+pub fn line_marker_prefix(color: &str) -> String {
+    let key: String = color.trim_start_matches('#').chars().map(|c| if c.is_ascii_alphanumeric() { c } else { '_' }).collect();
+    format!("{}-{}", LINE_MARKER_PREFIX, key)
+}
+
 /// Generate SVG defs for arrow markers
 pub fn arrow_marker_defs(id_prefix: &str, color: &str) -> String {
-    format!(
-        r#"<marker id="{id_prefix}-arrow-start" markerWidth="10" markerHeight="7" refX="0" refY="3.5" orient="auto-start-reverse"><polygon points="10 0, 10 7, 0 3.5" fill="{color}"/></marker><marker id="{id_prefix}-arrow-end" markerWidth="10" markerHeight="7" refX="10" refY="3.5" orient="auto"><polygon points="0 0, 10 3.5, 0 7" fill="{color}"/></marker>"#,
-        id_prefix = id_prefix, color = color
-    )
+    arrowhead_marker_defs(id_prefix, color, ARROW_HEADS[0], ARROW_SIZE)
+}
+
+/// Generate SVG defs for one arrowhead outline and size, with the ids
+/// [`Edge::marker_ids`] refers to
+pub fn arrowhead_marker_defs(id_prefix: &str, color: &str, head: &str, size: f32) -> String {
+    let suffix = arrow_marker_suffix(head, size);
+    arrow_markers(&format!("{}-arrow{}-start", id_prefix, suffix), &format!("{}-arrow{}-end", id_prefix, suffix), color, head, size)
 }
 
 /// Symbol definition for reusable components (SVG <symbol>)
@@ -579,7 +728,7 @@ impl Symbol {
 impl Symbol {
     pub fn to_svg_def(&self) -> String {
         let viewbox = self.viewbox.map_or(String::new(), |(x, y, w, h)| 
-            format!(r#" viewBox="{} {} {} {}""#, x, y, w, h));
+            format!(r#" viewBox="{} {} {} {}""#, num(x), num(y), num(w), num(h)));
         let inner: String = self.children.iter().map(|e| e.to_svg()).collect();
         format!(r#"<symbol id="{}"{}>{}</symbol>"#, html_escape(&self.id), viewbox, inner)
     }
@@ -624,15 +773,31 @@ impl Use {
 impl Use {
     pub fn to_svg(&self) -> String {
         let size = match (self.width, self.height) {
-            (Some(w), Some(h)) => format!(r#" width="{}" height="{}""#, w, h),
-            (Some(w), None) => format!(r#" width="{}""#, w),
-            (None, Some(h)) => format!(r#" height="{}""#, h),
+            (Some(w), Some(h)) => format!(r#" width="{}" height="{}""#, num(w), num(h)),
+            (Some(w), None) => format!(r#" width="{}""#, num(w)),
+            (None, Some(h)) => format!(r#" height="{}""#, num(h)),
             _ => String::new(),
         };
         format!("<use href=\"#{}\" x=\"{}\" y=\"{}\"{}{}{}/>" , 
-            html_escape(&self.href), self.x, self.y, size, self.style.to_svg_attrs(), transform_attr(&self.transform))
+            html_escape(&self.href), num(self.x), num(self.y), size, self.style.to_svg_attrs(), transform_attr(&self.transform))
     }
     
+    /// Transform that places a symbol's children where this `<use>` would render them.
+    /// With a viewBox the content is fitted `xMidYMid meet`, matching SVG's default.
+    pub fn expansion_transform(&self, sym: &Symbol) -> String {
+        let placement = match sym.viewbox {
+            Some((vx, vy, vw, vh)) if vw > 0.0 && vh > 0.0 => {
+                let (w, h) = (self.width.unwrap_or(vw), self.height.unwrap_or(vh));
+                let s = (w / vw).min(h / vh);
+                let tx = self.x + (w - vw * s) / 2.0 - vx * s;
+                let ty = self.y + (h - vh * s) / 2.0 - vy * s;
+                format!("translate({} {}) scale({})", num(tx), num(ty), num(s))
+            }
+            _ => format!("translate({} {})", num(self.x), num(self.y)),
+        };
+        match &self.transform { Some(tf) => format!("{} {}", tf, placement), None => placement }
+    }
+
     pub fn bounds(&self) -> (f32, f32, f32, f32) {
         (self.x, self.y, self.width.unwrap_or(0.0), self.height.unwrap_or(0.0))
     }
@@ -643,4 +808,87 @@ mod tests {
     use super::*;
     #[test] fn test_rect_bounds() { assert_eq!(Rect { x: 10.0, y: 20.0, w: 100.0, h: 50.0, rx: 0.0, style: Style::default(), transform: None }.bounds(), (10.0, 20.0, 100.0, 50.0)); }
     #[test] fn test_circle_bounds() { assert_eq!(Circle { cx: 100.0, cy: 100.0, r: 50.0, style: Style::default(), transform: None }.bounds(), (50.0, 50.0, 100.0, 100.0)); }
+    #[test] fn test_image_fit() {
+        let img = Image { x: 0.0, y: 0.0, w: 10.0, h: 10.0, href: "a.png".into(), transform: None, fit: Some("cover".into()), hidden: false, layer: None, z: 0 };
+        assert!(img.to_svg().contains(r#"preserveAspectRatio="xMidYMid slice""#));
+        assert!(!Image { fit: None, ..img }.to_svg().contains("preserveAspectRatio"));
+    }
+    #[test] fn test_text_baseline() {
+        let t = Text { x: 0.0, y: 20.0, content: "Hi".into(), font: "Arial".into(), size: 10.0, weight: "normal".into(), anchor: "start".into(), style: Style::default(), transform: None, baseline: Some("middle".into()), letter_spacing: 0.0, word_spacing: 0.0, decoration: None, direction: None };
+        assert!(t.to_svg().contains(r#"dominant-baseline="middle""#));
+        assert!(!Text { baseline: None, ..t.clone() }.to_svg().contains("dominant-baseline"));
+        assert!(t.bounds().1 > Text { baseline: None, ..t }.bounds().1);
+    }
+    #[test] fn test_text_spacing() {
+        let t = Text { x: 0.0, y: 20.0, content: "Hi all".into(), font: "Arial".into(), size: 10.0, weight: "normal".into(), anchor: "start".into(), style: Style::default(), transform: None, baseline: None, letter_spacing: 0.0, word_spacing: 0.0, decoration: None, direction: None };
+        assert!(!t.to_svg().contains("spacing"));
+        let spaced = Text { letter_spacing: 1.5, word_spacing: 4.0, ..t.clone() };
+        assert!(spaced.to_svg().contains(r#" letter-spacing="1.5" word-spacing="4""#), "{}", spaced.to_svg());
+        assert!((spaced.bounds().2 - (t.bounds().2 + 5.0 * 1.5 + 4.0)).abs() < 1e-4);
+    }
+    #[test] fn test_text_decoration() {
+        let t = Text { x: 0.0, y: 20.0, content: "Hi".into(), font: "Arial".into(), size: 10.0, weight: "normal".into(), anchor: "start".into(), style: Style::default(), transform: None, baseline: None, letter_spacing: 0.0, word_spacing: 0.0, decoration: Some("underline".into()), direction: None };
+        assert!(t.to_svg().contains(r#" text-decoration="underline""#), "{}", t.to_svg());
+        let ((x1, under), (x2, _)) = t.decoration_line().unwrap();
+        assert_eq!((x1, x2), (0.0, t.bounds().2));
+        assert!(under > 20.0);
+        let ((_, strike), _) = Text { decoration: Some("line-through".into()), ..t.clone() }.decoration_line().unwrap();
+        assert!(strike < 20.0 && strike > 20.0 - 10.0 * 0.52);
+        assert!(Text { decoration: None, ..t }.decoration_line().is_none());
+    }
+    #[test] fn test_text_rtl_anchor() {
+        let t = Text { x: 50.0, y: 20.0, content: "שלום".into(), font: "Arial".into(), size: 10.0, weight: "normal".into(), anchor: "start".into(), style: Style::default(), transform: None, baseline: None, letter_spacing: 0.0, word_spacing: 0.0, decoration: None, direction: Some("rtl".into()) };
+        assert!(t.to_svg().contains(r#" direction="rtl""#));
+        let (x, _, w, _) = t.bounds();
+        assert!((x + w - 50.0).abs() < 1e-4 && w > 0.0);
+        let end = Text { anchor: "end".into(), ..t.clone() }.bounds();
+        assert_eq!(end.0, 50.0);
+    }
+    #[test] fn test_hidden_edge_hides_label() {
+        let edge = Edge {
+            from_id: "a".into(), to_id: "b".into(), from_pt: (0.0, 0.0), to_pt: (100.0, 0.0), edge_style: "straight".into(),
+            arrow: "none".into(), arrow_head: ARROW_HEADS[0].into(), arrow_size: ARROW_SIZE, label: Some("yes".into()),
+            label_bg: Some("#fff".into()), style: Style { stroke: Some("#333".into()), ..Style::default() },
+        };
+        let svg = display_svg(edge.to_svg(("s", "e")), true);
+        assert!(svg.starts_with(r#"<g display="none"><path"#) && svg.ends_with("</text></g>"), "{}", svg);
+        assert_eq!(svg.matches("display=").count(), 1);
+        let rect = display_svg(Rect { x: 0.0, y: 0.0, w: 1.0, h: 1.0, rx: 0.0, style: Style::default(), transform: None }.to_svg(), true);
+        assert!(rect.starts_with(r#"<rect display="none""#), "{}", rect);
+    }
+    #[test] fn test_line_dash() {
+        let style = Style { stroke: Some("#000".into()), dash: Some("120".into()), dash_offset: 120.0, ..Style::default() };
+        let l = Line { x1: 0.0, y1: 0.0, x2: 100.0, y2: 0.0, style, transform: None };
+        assert!(l.to_svg().contains(r#"stroke-dasharray="120" stroke-dashoffset="120""#));
+    }
+    #[test] fn test_non_terminating_coords_rounded() {
+        let third = 1.0_f32 / 3.0;
+        let style = Style { stroke: Some("#000".into()), stroke_width: third, ..Style::default() };
+        let l = Line { x1: third, y1: 0.0, x2: 100.0, y2: 2.0 * third, style, transform: None };
+        assert_eq!(l.to_svg(), r##"<line x1="0.333" y1="0" x2="100" y2="0.667" stroke="#000" stroke-width="0.333"/>"##);
+    }
+    #[test] fn test_canonical_attr_order() {
+        let style = Style { fill: Some("#f00".into()), stroke: Some("#000".into()), stroke_width: 2.0, opacity: 0.5, filter: Some("s".into()), dash: Some("4 2".into()), ..Style::default() };
+        let p = Polygon { points: vec![(0.0, 0.0), (10.0, 0.0), (5.0, 8.0)], style: style.clone(), transform: Some("rotate(45)".into()) };
+        // Byte-identical to the WASM `polygon_svg` for the same inputs (see its tests)
+        assert_eq!(p.to_svg(), r##"<polygon points="0,0 10,0 5,8" fill="#f00" stroke="#000" stroke-width="2" stroke-dasharray="4 2" opacity="0.5" filter="url(#s)" transform="rotate(45)"/>"##);
+        // A node's rect and a standalone rect render through different paths
+        let n = Node { id: "a".into(), shape: "rect".into(), cx: 30.0, cy: 20.0, w: 60.0, h: 40.0, label: None, style: style.clone(), label_style: Style::default(), transform: None };
+        let r = Rect { x: 0.0, y: 0.0, w: 60.0, h: 40.0, rx: 0.0, style, transform: None };
+        assert_eq!(n.to_svg(), format!(r#"<g id="node-a">{}</g>"#, r.to_svg()));
+    }
+    #[test] fn test_node_label_centered() {
+        let n = Node { id: "a".into(), shape: "rect".into(), cx: 50.0, cy: 40.0, w: 60.0, h: 30.0, label: Some("A".into()), style: Style::default(), label_style: Style::default(), transform: None };
+        let m = crate::font::get_metrics("system-ui");
+        let y = 40.0 + m.cap_height * NODE_LABEL_SIZE / 2.0;
+        assert!(n.to_svg().contains(&format!(r#"<text x="50" y="{}""#, num(y))));
+    }
+
+    #[test] fn test_diamond_label_at_centroid() {
+        let n = Node { id: "d".into(), shape: "diamond".into(), cx: 50.0, cy: 40.0, w: 60.0, h: 30.0, label: Some("D".into()), style: Style::default(), label_style: Style::default(), transform: None };
+        assert_eq!(n.outline().len(), 4);
+        let (x, y) = n.label_anchor();
+        assert!((x - 50.0).abs() < 1e-4 && (y - 40.0).abs() < 1e-4, "{} {}", x, y);
+        assert!(n.to_svg().contains(r#"<polygon points="50,25 80,40 50,55 20,40""#), "{}", n.to_svg());
+    }
 }
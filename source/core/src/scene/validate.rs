@@ -0,0 +1,79 @@
+//! Checks on a built scene that need resolved geometry
+//!
+//! The parser can't tell where content ends up once transforms and layout apply,
+//! so these run on the scene itself and point at elements by index and diff id.
+
+use crate::dsl::ErrorSeverity;
+use crate::hash::ElementId;
+use crate::render::{element_kind, IndexedScene};
+use super::scene::Scene;
+
+/// A problem with one top-level element of a scene
+#[derive(Clone, Debug, PartialEq)]
+pub struct Diagnostic {
+    /// Position in [`Scene::elements`]
+    pub index: usize,
+    /// Stable id, matching the ids in [`Scene::diff`] output
+    pub id: ElementId,
+    pub severity: ErrorSeverity,
+    pub message: String,
+}
+
+impl Scene {
+    /// Warn about elements that lie entirely outside the canvas
+    pub fn validate(&self) -> Vec<Diagnostic> { self.validate_with_margin(0.0) }
+
+    /// Like [`Self::validate`], but tolerate elements within `margin` pixels of the
+    /// canvas edge, for content that is clipped on purpose
+    pub fn validate_with_margin(&self, margin: f32) -> Vec<Diagnostic> {
+        let (cw, ch) = self.dimensions();
+        let (cw, ch) = (cw as f32, ch as f32);
+        let index = IndexedScene::from_scene(self);
+        self.elements().iter().zip(&index.elements).filter_map(|(el, indexed)| {
+            let (x, y, w, h) = el.transformed_bounds();
+            let outside = x > cw + margin || y > ch + margin || x + w < -margin || y + h < -margin;
+            outside.then(|| Diagnostic {
+                index: indexed.index,
+                id: indexed.id,
+                severity: ErrorSeverity::Warning,
+                message: format!(
+                    "{} at ({}, {}) lies outside the {}x{} canvas", element_kind(el).name(), x, y, cw, ch,
+                ),
+            })
+        }).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scene::{Element, Rect, Style};
+    use crate::CanvasSize;
+
+    fn rect(x: f32, y: f32, transform: Option<&str>) -> Element {
+        Element::Rect(Rect { x, y, w: 10.0, h: 10.0, rx: 0.0, style: Style::default(), transform: transform.map(Into::into) })
+    }
+
+    #[test]
+    fn test_out_of_canvas_warning() {
+        let mut scene = Scene::new(CanvasSize::Medium, "#fff".into());
+        scene.push(rect(10.0, 10.0, None));
+        scene.push(rect(1000.0, 1000.0, None));
+        // Moved back into view by its transform
+        scene.push(rect(1000.0, 1000.0, Some("translate(-990 -990)")));
+        let diagnostics = scene.validate();
+        assert_eq!(diagnostics.len(), 1, "{:?}", diagnostics);
+        assert_eq!(diagnostics[0].index, 1);
+        assert_eq!(diagnostics[0].severity, ErrorSeverity::Warning);
+        assert_eq!(diagnostics[0].message, "rect at (1000, 1000) lies outside the 64x64 canvas");
+        assert_eq!(diagnostics[0].id, IndexedScene::from_scene(&scene).elements[1].id);
+    }
+
+    #[test]
+    fn test_margin_tolerates_clipped_content() {
+        let mut scene = Scene::new(CanvasSize::Medium, "#fff".into());
+        scene.push(rect(-15.0, 0.0, None));
+        assert_eq!(scene.validate().len(), 1);
+        assert!(scene.validate_with_margin(8.0).is_empty());
+    }
+}
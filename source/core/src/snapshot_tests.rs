@@ -1,7 +1,8 @@
 //! Snapshot tests for SVG output using insta
 //!
 //! These tests verify that rendered SVG output matches expected snapshots.
-//! Uses direct Scene construction to test the rendering pipeline.
+//! Most build a Scene directly to test rendering; the golden tests at the end
+//! run DSL source through the full pipeline via `render::render_dsl_to_svg`.
 
 #![cfg(all(test, any(feature = "python", feature = "bench")))]
 
@@ -80,7 +81,7 @@ fn snapshot_basic_text() {
     scene.push(Element::Text(Text {
         x: 32.0, y: 32.0, content: "Hello".into(),
         font: "sans-serif".into(), size: 14.0, weight: "normal".into(), anchor: "start".into(),
-        style: Style::with_fill("#333"), transform: None,
+        style: Style::with_fill("#333"), transform: None, baseline: None, letter_spacing: 0.0, word_spacing: 0.0, decoration: None, direction: None,
     }));
     assert_snapshot!("basic_text", scene.render_svg());
 }
@@ -115,6 +116,7 @@ fn snapshot_gradient_rect() {
         from_color: "#ff6b6b".into(),
         to_color: "#4ecdc4".into(),
         angle: 45.0,
+        shared: false,
     });
     scene.push(Element::Rect(Rect {
         x: 8.0, y: 8.0, w: 48.0, h: 48.0, rx: 4.0,
@@ -150,6 +152,7 @@ fn snapshot_radial_gradient() {
         from_color: "#fff".into(),
         to_color: "#000".into(),
         angle: 0.0,
+        shared: false,
     });
     scene.push(Element::Circle(Circle {
         cx: 32.0, cy: 32.0, r: 24.0,
@@ -230,7 +233,7 @@ fn snapshot_nested_group() {
             cx: 32.0, cy: 32.0, r: 16.0,
             style: Style::with_fill("#3b82f6"), transform: None,
         }),
-    ], None));
+    ], None, 1.0));
     assert_snapshot!("nested_group", scene.render_svg());
 }
 
@@ -284,7 +287,7 @@ fn snapshot_text_bold() {
     scene.push(Element::Text(Text {
         x: 32.0, y: 32.0, content: "Bold".into(),
         font: "sans-serif".into(), size: 16.0, weight: "bold".into(), anchor: "middle".into(),
-        style: Style::with_fill("#1f2937"), transform: None,
+        style: Style::with_fill("#1f2937"), transform: None, baseline: None, letter_spacing: 0.0, word_spacing: 0.0, decoration: None, direction: None,
     }));
     assert_snapshot!("text_bold", scene.render_svg());
 }
@@ -295,17 +298,17 @@ fn snapshot_text_anchors() {
     scene.push(Element::Text(Text {
         x: 8.0, y: 24.0, content: "Start".into(),
         font: "sans-serif".into(), size: 12.0, weight: "normal".into(), anchor: "start".into(),
-        style: Style::with_fill("#333"), transform: None,
+        style: Style::with_fill("#333"), transform: None, baseline: None, letter_spacing: 0.0, word_spacing: 0.0, decoration: None, direction: None,
     }));
     scene.push(Element::Text(Text {
         x: 48.0, y: 48.0, content: "Center".into(),
         font: "sans-serif".into(), size: 12.0, weight: "normal".into(), anchor: "middle".into(),
-        style: Style::with_fill("#333"), transform: None,
+        style: Style::with_fill("#333"), transform: None, baseline: None, letter_spacing: 0.0, word_spacing: 0.0, decoration: None, direction: None,
     }));
     scene.push(Element::Text(Text {
         x: 88.0, y: 72.0, content: "End".into(),
         font: "sans-serif".into(), size: 12.0, weight: "normal".into(), anchor: "end".into(),
-        style: Style::with_fill("#333"), transform: None,
+        style: Style::with_fill("#333"), transform: None, baseline: None, letter_spacing: 0.0, word_spacing: 0.0, decoration: None, direction: None,
     }));
     assert_snapshot!("text_anchors", scene.render_svg());
 }
@@ -403,3 +406,34 @@ fn snapshot_opacity_layers() {
     }));
     assert_snapshot!("opacity_layers", scene.render_svg());
 }
+
+// ─────────────────────────────────────────────────────────────────────────────
+// DSL Golden Snapshots (full pipeline)
+// ─────────────────────────────────────────────────────────────────────────────
+
+/// SVG for `source`, which must parse cleanly
+fn golden(source: &str) -> String {
+    let (svg, errors) = crate::render::render_dsl_to_svg(source);
+    assert!(errors.is_empty(), "{:?}", errors);
+    svg
+}
+
+#[test]
+fn golden_rect() {
+    assert_snapshot!("golden_rect", golden("canvas medium fill #fff\nrect at 8,8 size 48x32 #3b82f6\n  corner 4\n  stroke #1e40af 2"));
+}
+
+#[test]
+fn golden_gradient() {
+    assert_snapshot!("golden_gradient", golden("canvas medium\ncircle at 32,32 radius 24\n  gradient linear #f59e0b #ef4444 45"));
+}
+
+#[test]
+fn golden_graph() {
+    assert_snapshot!("golden_graph", golden("canvas giant\ngraph\n  node \"a\" at 100,100\n    label \"Start\"\n  node \"b\" at 300,100\n    label \"End\"\n  edge \"a\" -> \"b\""));
+}
+
+#[test]
+fn golden_text() {
+    assert_snapshot!("golden_text", golden("canvas large\ntext \"Hello\" at 48,48 #111\n  font \"Georgia\" 18\n  bold\n  center"));
+}
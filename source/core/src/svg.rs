@@ -0,0 +1,149 @@
+//! Canonical SVG attribute order and shared marker geometry
+//!
+//! Scene shapes and the WASM primitives both write presentation attributes
+//! through `Attrs`, so an element serializes to the same bytes whichever path
+//! built it and its content hash survives refactors of either. Arrowhead
+//! markers, gradient vectors, image fit and the canvas background are generated
+//! here for the same reason.
+
+pub(crate) use crate::dsl::ARROW_HEADS;
+use crate::fmt::num;
+
+/// Presentation attributes in output order. Anything unlisted (geometry) sorts
+/// ahead of them, in the order it was set.
+pub(crate) const ATTR_ORDER: [&str; 12] = [
+    "fill", "fill-rule", "stroke", "stroke-width", "stroke-dasharray", "stroke-dashoffset",
+    "opacity", "filter", "class", "marker-start", "marker-end", "transform",
+];
+
+/// Attribute list rendered in `ATTR_ORDER`
+#[derive(Debug, Default)]
+pub(crate) struct Attrs(Vec<(&'static str, String)>);
+
+impl Attrs {
+    pub fn new() -> Self { Self::default() }
+
+    pub fn set(&mut self, name: &'static str, value: impl std::fmt::Display) -> &mut Self {
+        self.0.push((name, value.to_string()));
+        self
+    }
+
+    /// Each attribute with a leading space, in canonical order
+    pub fn render(&self) -> String {
+        let mut attrs: Vec<_> = self.0.iter().collect();
+        attrs.sort_by_key(|(name, _)| rank(name));
+        attrs.iter().map(|(name, value)| format!(r#" {}="{}""#, name, value)).collect()
+    }
+}
+
+fn rank(name: &str) -> usize { ATTR_ORDER.iter().position(|a| *a == name).map_or(0, |i| i + 1) }
+
+/// Opening of the background `<rect>`; shapes always carry `x`/`y` first, so
+/// markup starting with this is the background
+pub(crate) const BACKGROUND_RECT_OPEN: &str = r#"<rect width="100%" height="100%""#;
+
+/// Full-canvas background `<rect>`, or nothing when `fill` is `none` or
+/// `transparent` and the canvas should stay see-through
+pub(crate) fn background_rect(fill: &str) -> String {
+    if fill.eq_ignore_ascii_case("none") || fill.eq_ignore_ascii_case("transparent") { return String::new(); }
+    format!(r#"{} fill="{}"/>"#, BACKGROUND_RECT_OPEN, fill)
+}
+
+/// Linear gradient vector through the center of `(x, y, w, h)`, rounded to a
+/// tenth: 0° runs left to right, 90° top to bottom
+pub(crate) fn gradient_vector(angle: f32, (x, y, w, h): (f32, f32, f32, f32)) -> (f32, f32, f32, f32) {
+    let rad = angle.to_radians();
+    let at = |v: f32| (v * 10.0).round() / 10.0 + 0.0;
+    let (cx, cy, dx, dy) = (x + w / 2.0, y + h / 2.0, w / 2.0 * rad.cos(), h / 2.0 * rad.sin());
+    (at(cx - dx), at(cy - dy), at(cx + dx), at(cy + dy))
+}
+
+/// Map an image fit mode to its SVG `preserveAspectRatio` value
+pub fn preserve_aspect_ratio(fit: &str) -> Option<&'static str> {
+    match fit {
+        "cover" => Some("xMidYMid slice"),
+        "contain" => Some("xMidYMid meet"),
+        _ => None,
+    }
+}
+
+/// Font size of edge labels
+pub(crate) const EDGE_LABEL_SIZE: f32 = 12.0;
+
+/// Space between an edge label and the edge of its background
+pub(crate) const EDGE_LABEL_PADDING: f32 = 4.0;
+
+/// Default arrowhead width; heads are 7/10 as tall as they are wide
+pub(crate) const ARROW_SIZE: f32 = 10.0;
+
+/// Marker id suffix telling arrowheads apart: empty for the default triangle,
+/// so plain arrows keep their `-arrow-start` and `-arrow-end` ids
+pub(crate) fn arrow_marker_suffix(head: &str, size: f32) -> String {
+    if head == ARROW_HEADS[0] && size == ARROW_SIZE { String::new() } else { format!("-{}-{}", head, size) }
+}
+
+/// Start and end `<marker>` defs for one arrowhead. The start head is the end
+/// one mirrored, pointing back along the line.
+pub(crate) fn arrow_markers(start_id: &str, end_id: &str, color: &str, head: &str, size: f32) -> String {
+    let (wf, hf) = (size, size * 7.0 / 10.0);
+    let (w, h, hh, mid) = (num(wf), num(hf), num(hf / 2.0), num(wf / 2.0));
+    let (start, end, ref_x) = match head {
+        "open" => (
+            format!(r#"<path d="M{w} 0 L0 {hh} L{w} {h}" fill="none" stroke="{color}" stroke-width="1.5"/>"#),
+            format!(r#"<path d="M0 0 L{w} {hh} L0 {h}" fill="none" stroke="{color}" stroke-width="1.5"/>"#),
+            wf,
+        ),
+        "diamond" => {
+            let diamond = format!(r#"<polygon points="0 {hh}, {mid} 0, {w} {hh}, {mid} {h}" fill="{color}"/>"#);
+            (diamond.clone(), diamond, wf)
+        }
+        "circle" => {
+            let circle = format!(r#"<circle cx="{mid}" cy="{hh}" r="{hh}" fill="{color}"/>"#);
+            (circle.clone(), circle, wf / 2.0)
+        }
+        _ => (
+            format!(r#"<polygon points="{w} 0, {w} {h}, 0 {hh}" fill="{color}"/>"#),
+            format!(r#"<polygon points="0 0, {w} {hh}, 0 {h}" fill="{color}"/>"#),
+            wf,
+        ),
+    };
+    format!(
+        r#"<marker id="{start_id}" markerWidth="{w}" markerHeight="{h}" refX="{}" refY="{hh}" orient="auto-start-reverse">{start}</marker><marker id="{end_id}" markerWidth="{w}" markerHeight="{h}" refX="{}" refY="{hh}" orient="auto">{end}</marker>"#,
+        num(wf - ref_x), num(ref_x),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_order_ignores_insertion_order() {
+        let mut a = Attrs::new();
+        a.set("transform", "rotate(45)").set("opacity", 0.5).set("fill", "#f00").set("x", 1).set("stroke-width", 2).set("stroke", "#000");
+        let mut b = Attrs::new();
+        b.set("x", 1).set("stroke", "#000").set("fill", "#f00").set("transform", "rotate(45)").set("stroke-width", 2).set("opacity", 0.5);
+        assert_eq!(a.render(), b.render());
+        assert_eq!(a.render(), r##" x="1" fill="#f00" stroke="#000" stroke-width="2" opacity="0.5" transform="rotate(45)""##);
+        assert_eq!(Attrs::new().render(), "");
+    }
+
+    #[test]
+    fn test_arrow_marker_heads() {
+        // The default head is the original 10x7 triangle
+        assert_eq!(
+            arrow_markers("a-start", "a-end", "#333", "triangle", ARROW_SIZE),
+            r##"<marker id="a-start" markerWidth="10" markerHeight="7" refX="0" refY="3.5" orient="auto-start-reverse"><polygon points="10 0, 10 7, 0 3.5" fill="#333"/></marker><marker id="a-end" markerWidth="10" markerHeight="7" refX="10" refY="3.5" orient="auto"><polygon points="0 0, 10 3.5, 0 7" fill="#333"/></marker>"##,
+        );
+        let open = arrow_markers("a-start", "a-end", "#333", "open", 12.0);
+        assert_eq!(open.matches(r#"<path d="#).count(), 2, "{}", open);
+        assert!(open.contains(r#"markerWidth="12" markerHeight="8.4""#), "{}", open);
+        assert!(!open.contains("<polygon"), "{}", open);
+
+        let diamond = arrow_markers("a-start", "a-end", "#333", "diamond", ARROW_SIZE);
+        let points: Vec<&str> = diamond.split(r#"points=""#).skip(1).map(|p| &p[..p.find('"').unwrap()]).collect();
+        assert_eq!(points, ["0 3.5, 5 0, 10 3.5, 5 7"; 2]);
+        assert_eq!(arrow_marker_suffix("triangle", ARROW_SIZE), "");
+        assert_eq!(arrow_marker_suffix("diamond", 12.0), "-diamond-12");
+    }
+}
@@ -16,7 +16,7 @@ mod tests {
         // Use public re-exports from crate root
         use crate::{
             // Lexer types
-            CanvasSize, Token, TokenType, TokenValue,
+            AspectRatio, CanvasSize, Token, TokenType, TokenValue,
             // AST types
             AstCanvas, AstGraph, AstNode, AstShape, AstStyle, AstTransform,
             ErrorKind, ErrorSeverity, FullStyle, GradientDef, GraphEdge, GraphNode,
@@ -65,6 +65,7 @@ r#"// ════════════════════════
 "#);
         output.push_str(&get_type_def!(TokenType)); output.push_str("\n\n");
         output.push_str(&get_type_def!(CanvasSize)); output.push_str("\n\n");
+        output.push_str(&get_type_def!(AspectRatio)); output.push_str("\n\n");
         output.push_str(&get_type_def!(TokenValue)); output.push_str("\n\n");
         output.push_str(&get_type_def!(Token)); output.push_str("\n\n");
 
@@ -1,9 +1,10 @@
 """Interpreter for the visual DSL using Rust core for lexing, parsing, and rendering."""
 
 import logging
+import re
 from dataclasses import dataclass, field
 from .types import Node, Canvas, Shape, Style, Transform, CANVAS_SIZES
-from .errors import ErrorCode, ErrorInfo, ErrorList, RenderError
+from .errors import ErrorCode, ErrorInfo, ErrorList, RenderError, Severity
 
 logger = logging.getLogger(__name__)
 
@@ -22,6 +23,41 @@ for attr in ['Lexer', 'Parser', 'Scene']:
         raise ImportError(f"Rust core module is incomplete - missing {attr} class")
 
 
+def _severity(sev) -> Severity:
+    """Map a Rust ErrorSeverity onto the Python severity scale."""
+    if sev == rust.ErrorSeverity.Hint:
+        return Severity.INFO
+    if sev == rust.ErrorSeverity.Warning:
+        return Severity.WARNING
+    return Severity.ERROR
+
+
+# Edge anchor candidates and the direction an edge leaves a node at each
+_ANCHOR_SIDES = ('top', 'right', 'bottom', 'left')
+_ANCHOR_CORNERS = ('top-right', 'bottom-right', 'bottom-left', 'top-left')
+_ANCHOR_OUTWARD = {
+    'top': (0, -1), 'bottom': (0, 1), 'left': (-1, 0), 'right': (1, 0),
+    'top-right': (1, -1), 'bottom-right': (1, 1), 'bottom-left': (-1, 1), 'top-left': (-1, -1),
+}
+# Extra length charged per edge already attached at an anchor
+_ANCHOR_REUSE_PENALTY = 12.0
+
+
+def _anchor_point(node: dict, anchor: str) -> tuple:
+    """Point on a node's box edge for a side or corner anchor."""
+    dx, dy = _ANCHOR_OUTWARD.get(anchor, (0, 0))
+    return (node['cx'] + dx * node['w'] / 2, node['cy'] + dy * node['h'] / 2)
+
+
+def _axis_anchors(from_node: dict, to_node: dict) -> tuple:
+    """Facing side midpoints along the axis the nodes are furthest apart on."""
+    dx = to_node['cx'] - from_node['cx']
+    dy = to_node['cy'] - from_node['cy']
+    if abs(dy) > abs(dx):
+        return ('bottom', 'top') if dy > 0 else ('top', 'bottom')
+    return ('right', 'left') if dx > 0 else ('left', 'right')
+
+
 @dataclass(slots=True)
 class SceneState:
     """Evaluated scene state."""
@@ -37,9 +73,10 @@ class SceneState:
         self._def_id += 1
         return f"d{self._def_id}"
     
-    def add_error(self, code: ErrorCode, msg: str, line: int = 0, col: int = 0):
+    def add_error(self, code: ErrorCode, msg: str, line: int = 0, col: int = 0,
+                  severity: Severity = Severity.ERROR):
         """Add a structured error to the scene state."""
-        self.error_infos.append(ErrorInfo(code, msg, line, col))
+        self.error_infos.append(ErrorInfo(code, msg, line, col, severity))
 
     def to_svg(self) -> str:
         """Render scene to SVG using Rust core."""
@@ -49,6 +86,13 @@ class SceneState:
             if size is None:
                 size = rust.CanvasSize.from_name(self.canvas.size) or rust.CanvasSize.Medium
             scene = rust.Scene(size, self.canvas.fill)
+            scene.aspect = self.canvas.aspect
+            if grad := self.canvas.gradient:
+                scene.background_gradient = rust.Gradient(
+                    '', grad.get('gtype', 'linear'), grad.get('from', '#fff'),
+                    grad.get('to', '#000'), float(grad.get('angle', 0.0))
+                )
+            scene.background_image = self.canvas.image
             
             # Add shapes first (this populates _gradients and _filters)
             for s in self.shapes:
@@ -59,7 +103,7 @@ class SceneState:
                 scene.add_gradient(rust.Gradient(
                     gid, grad.get('type', 'linear'),
                     grad.get('from', '#fff'), grad.get('to', '#000'),
-                    float(grad.get('angle', 90.0))
+                    float(grad.get('angle', 0.0)), bool(grad.get('shared', False))
                 ))
             
             for fid, filt in self._filters:
@@ -97,6 +141,10 @@ class SceneState:
         x, y = float(x + offset[0]), float(y + offset[1])
         
         rust_style = self._make_style(style)
+        if props.get('arrow', 'none') != 'none':
+            rust_style.arrow = props['arrow']
+        if s.get('meta'):
+            rust_style.meta = s['meta']
         
         match kind:
             case 'rect':
@@ -129,20 +177,45 @@ class SceneState:
                 content = str(props.get('content', ''))
                 font = str(style.get('font') or 'system-ui')
                 size = float(style.get('font_size', 16))
+                if (truncate := props.get('truncate')) is not None:
+                    content = rust.truncate_to_width(content, font, size, float(truncate))
                 weight = str(style.get('font_weight', 'normal'))
                 anchor = str(style.get('text_anchor', 'start'))
-                scene.add_text(rust.Text(x, y, content, font, size, weight, anchor, rust_style, transform))
+                baseline = style.get('baseline')
+                letter_spacing = float(style.get('letter_spacing', 0.0))
+                word_spacing = float(style.get('word_spacing', 0.0))
+                decoration = style.get('decoration')
+                direction = style.get('direction')
+                scene.add_text(rust.Text(x, y, content, font, size, weight, anchor, rust_style, transform, baseline, letter_spacing, word_spacing, decoration, direction))
             case 'image':
                 w, h = props.get('size', (100, 100))
                 href = str(props.get('href', ''))
-                scene.add_image(rust.Image(x, y, float(w), float(h), href, transform))
+                fit = props.get('fit')
+                scene.add_image(rust.Image(x, y, float(w), float(h), href, transform, fit))
+            case 'group' | 'layout' if (opacity := float(style.get('opacity', 1.0))) < 1.0:
+                # Fade the subtree as one layer so overlapping children don't double-darken
+                layer = rust.Scene()
+                self._add_shape(layer, {**s, 'style': {**style, 'opacity': 1.0}}, offset)
+                scene.add_group(layer, None, opacity)
+                return
             case 'group':
                 for c in children:
                     self._add_shape(scene, c, (0, 0))
+                return
             case 'layout':
                 self._add_layout(scene, props, children)
+                return
             case 'graph':
                 self._add_graph_to_scene(scene, s)
+                return
+            case _:
+                return
+        if origin_pct := s.get('transform', {}).get('origin_pct'):
+            # Percent origins resolve against the shape's own bounds, as in the Rust build
+            el = scene.elements()[-1]
+            bx, by, bw, bh = el.bounds
+            px, py = origin_pct
+            el.transform = self._make_transform({**s['transform'], 'origin': (bx + bw * px / 100, by + bh * py / 100)})
 
     def _make_style(self, style: dict) -> 'rust.Style':
         """Convert style dict to Rust Style."""
@@ -151,8 +224,11 @@ class SceneState:
         
         # Handle gradient - register it and use url reference
         if grad := style.get('gradient'):
-            gid = self.next_id()
-            self._gradients.append((gid, grad))
+            # Shapes naming the same shared gradient reference a single def
+            gid = next((g for g, d in self._gradients if grad.get('shared') and d == grad), None)
+            if gid is None:
+                gid = self.next_id()
+                self._gradients.append((gid, grad))
             fill = f"url(#{gid})"
         
         # Handle shadow filter
@@ -160,13 +236,18 @@ class SceneState:
             fid = self.next_id()
             self._filters.append((fid, {'kind': 'shadow', **shadow}))
         
-        return rust.Style(
+        rust_style = rust.Style(
             fill=fill,
             stroke=stroke,
             stroke_width=float(style.get('stroke_width', 1.0)),
             opacity=float(style.get('opacity', 1.0)),
             corner=float(style.get('corner', 0.0))
         )
+        if dash := style.get('dash'):
+            rust_style.dash = ' '.join(f'{d:g}' for d in dash)
+        rust_style.dash_offset = float(style.get('dash_offset', 0.0))
+        rust_style.fill_rule = style.get('fill_rule')
+        return rust_style
 
     def _make_transform(self, transform: dict) -> str | None:
         """Convert transform dict to SVG transform string."""
@@ -184,7 +265,12 @@ class SceneState:
                 parts.append(f"rotate({rotate})")
         if scale := transform.get('scale'):
             sx, sy = (scale, scale) if isinstance(scale, (int, float)) else scale
-            parts.append(f"scale({sx} {sy})")
+            # Scale about the origin so negative factors mirror in place
+            ox, oy = transform.get('origin') or (0, 0)
+            if ox or oy:
+                parts.append(f"translate({ox} {oy}) scale({sx} {sy}) translate({0 - ox} {0 - oy})")
+            else:
+                parts.append(f"scale({sx} {sy})")
         return ' '.join(parts) if parts else None
 
     def _add_layout(self, scene, props: dict, children: list):
@@ -267,8 +353,17 @@ class SceneState:
             
             # Add label
             if label:
-                label_style = rust.Style(fill='#fff', opacity=1.0)
-                scene.add_text(rust.Text(cx, cy, str(label), 'system-ui', 13.0, 'normal', 'middle', label_style, None))
+                label_style = rust.Style(fill=self._label_fill(node_style.fill), opacity=1.0)
+                scene.add_text(rust.Text(cx, cy, str(label), 'system-ui', 13.0, 'normal', 'middle', label_style, None, 'middle'))
+
+    @staticmethod
+    def _label_fill(fill) -> str:
+        """White or black label, whichever reads better on a hex node fill."""
+        if not (isinstance(fill, str) and re.fullmatch(r'#([0-9a-fA-F]{3}|[0-9a-fA-F]{6})', fill)):
+            return '#fff'
+        choices = [rust.Color.from_hex('#fff'), rust.Color.from_hex('#000')]
+        best = rust.Color.from_hex(fill).best_text_color(choices)
+        return '#000' if best.luminance() < 0.5 else '#fff'
 
     def _compute_edge_path(self, from_pt: tuple, to_pt: tuple, edge_style: str) -> str:
         """Compute SVG path data for an edge."""
@@ -342,16 +437,17 @@ class Interpreter:
         tokens = lexer.py_tokenize()
         
         # Parse with Rust parser - returns native Python objects directly
-        parser = rust.Parser(tokens)
+        parser = rust.Parser(tokens, geometry_warnings=True)
         ast = parser.parse_py()
         
-        # Collect parse errors
-        for err in parser.get_errors():
+        # Collect lexer (indentation) and parse errors
+        for err in lexer.get_errors() + parser.get_errors():
             self.state.add_error(
                 ErrorCode.PARSE_UNEXPECTED_TOKEN,
                 err.message,
                 err.line,
-                err.col
+                err.col,
+                _severity(err.severity)
             )
         
         # Evaluate the AST
@@ -370,7 +466,10 @@ class Interpreter:
                 self._eval_ast(child)
         elif 'Canvas' in ast:
             c = ast['Canvas']
-            self.state.canvas = Canvas(size=c.get('size', 'medium'), fill=c['fill'])
+            self.state.canvas = Canvas(
+                size=c.get('size', 'medium'), fill=c['fill'], aspect=tuple(c.get('aspect', (1, 1))),
+                gradient=c.get('gradient'), image=c.get('image'),
+            )
         elif 'Shape' in ast:
             self._add_shape(ast['Shape'])
         elif 'Graph' in ast:
@@ -420,12 +519,14 @@ class Interpreter:
         
         # Convert edges - resolve node references to coordinates
         node_map = {n['id']: n for n in nodes}
+        anchors = graph.get('anchors', 'axis')
+        used = {}
         edges = []
         for e in graph.get('edges', []):
             from_node = node_map.get(e['from'])
             to_node = node_map.get(e['to'])
             if from_node and to_node:
-                from_pt, to_pt = self._compute_edge_anchors(from_node, to_node)
+                from_pt, to_pt = self._compute_edge_anchors(from_node, to_node, anchors, used)
                 edges.append({
                     'from_id': e['from'],
                     'to_id': e['to'],
@@ -433,7 +534,10 @@ class Interpreter:
                     'to_pt': to_pt,
                     'edge_style': e.get('style', 'straight'),
                     'arrow': e.get('arrow', 'forward'),
+                    'arrow_head': e.get('arrow_head', 'triangle'),
+                    'arrow_size': float(e.get('arrow_size', 10.0)),
                     'label': e.get('label'),
+                    'label_bg': e.get('label_bg'),
                     'stroke': e.get('stroke', '#333'),
                     'stroke_width': float(e.get('stroke_width', 2.0)),
                 })
@@ -472,27 +576,26 @@ class Interpreter:
         
         return nodes
 
-    def _compute_edge_anchors(self, from_node: dict, to_node: dict) -> tuple:
-        """Compute best anchor points for an edge between two nodes."""
-        dx = to_node['cx'] - from_node['cx']
-        dy = to_node['cy'] - from_node['cy']
-        
-        if abs(dy) > abs(dx):
-            if dy > 0:
-                from_pt = (from_node['cx'], from_node['cy'] + from_node['h'] / 2)
-                to_pt = (to_node['cx'], to_node['cy'] - to_node['h'] / 2)
-            else:
-                from_pt = (from_node['cx'], from_node['cy'] - from_node['h'] / 2)
-                to_pt = (to_node['cx'], to_node['cy'] + to_node['h'] / 2)
-        else:
-            if dx > 0:
-                from_pt = (from_node['cx'] + from_node['w'] / 2, from_node['cy'])
-                to_pt = (to_node['cx'] - to_node['w'] / 2, to_node['cy'])
-            else:
-                from_pt = (from_node['cx'] - from_node['w'] / 2, from_node['cy'])
-                to_pt = (to_node['cx'] + to_node['w'] / 2, to_node['cy'])
-        
-        return from_pt, to_pt
+    def _compute_edge_anchors(self, from_node: dict, to_node: dict, mode: str, used: dict) -> tuple:
+        """Compute anchor points for an edge between two nodes (mirrors anchor::best_anchors)."""
+        boxes = (from_node, to_node)
+        candidates = _ANCHOR_SIDES + _ANCHOR_CORNERS if mode == 'corners' else _ANCHOR_SIDES if mode == 'sides' else ()
+        best = None
+        for fa in candidates:
+            for ta in candidates:
+                p, q = _anchor_point(from_node, fa), _anchor_point(to_node, ta)
+                dx, dy = q[0] - p[0], q[1] - p[1]
+                (fx, fy), (tx, ty) = _ANCHOR_OUTWARD[fa], _ANCHOR_OUTWARD[ta]
+                if dx * fx + dy * fy <= 0 or -dx * tx - dy * ty <= 0:
+                    continue
+                uses = used.get((from_node['id'], fa), 0) + used.get((to_node['id'], ta), 0)
+                cost = (dx * dx + dy * dy) ** 0.5 + _ANCHOR_REUSE_PENALTY * uses
+                if best is None or cost < best[0]:
+                    best = (cost, fa, ta)
+        sides = best[1:] if best else _axis_anchors(from_node, to_node)
+        for node, side in zip(boxes, sides):
+            used[(node['id'], side)] = used.get((node['id'], side), 0) + 1
+        return _anchor_point(from_node, sides[0]), _anchor_point(to_node, sides[1])
 
     def _shape_to_dict(self, shape: dict) -> dict:
         """Convert Rust AST Shape to dict for rendering."""
@@ -518,6 +621,14 @@ class Interpreter:
                 'font_size': style.get('font_size', 16.0),
                 'font_weight': style.get('font_weight', 'normal'),
                 'text_anchor': style.get('text_anchor', 'start'),
+                'baseline': style.get('baseline'),
+                'letter_spacing': style.get('letter_spacing', 0.0),
+                'word_spacing': style.get('word_spacing', 0.0),
+                'decoration': style.get('decoration'),
+                'direction': style.get('direction'),
+                'dash': style.get('dash'),
+                'dash_offset': style.get('dash_offset', 0.0),
+                'fill_rule': style.get('fill_rule'),
                 'shadow': shape.get('shadow'),
                 'gradient': shape.get('gradient'),
             },
@@ -526,6 +637,7 @@ class Interpreter:
                 'rotate': transform.get('rotate', 0.0),
                 'scale': transform.get('scale'),
                 'origin': transform.get('origin'),
+                'origin_pct': transform.get('origin_pct'),
             },
             'children': [self._shape_to_dict(c) for c in shape.get('children', [])],
         }
@@ -63,14 +63,19 @@ class Canvas:
     """Canvas definition using standardized sizes. Wraps Rust AstCanvas."""
     size: str = "medium"  # nano|micro|tiny|small|medium|large|xlarge|huge|massive|giant
     fill: str = "#fff"
+    aspect: tuple[int, int] = (1, 1)  # w:h, size tier is the shorter side
+    gradient: dict | None = None  # {gtype, from, to, angle}, painted over fill
+    image: str | None = None  # href scaled to cover the canvas
     
     @property
     def width(self) -> int:
-        return CANVAS_SIZES.get(self.size, 64)
+        base, (w, h) = CANVAS_SIZES.get(self.size, 64), self.aspect
+        return (base * w + h // 2) // h if w >= h else base
     
     @property
     def height(self) -> int:
-        return CANVAS_SIZES.get(self.size, 64)
+        base, (w, h) = CANVAS_SIZES.get(self.size, 64), self.aspect
+        return base if w >= h else (base * h + w // 2) // w
 
 
 @dataclass(slots=True)
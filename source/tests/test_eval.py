@@ -208,6 +208,15 @@ text "Test" at 20,50
         assert '<text' in svg
         assert 'Test' in svg
 
+    def test_to_svg_text_truncate(self):
+        """Truncated text is cut to its width with an ellipsis."""
+        state = Interpreter().eval("""
+text "Quarterly revenue summary for the northern region" at 0,20 truncate 120
+""")
+        svg = state.to_svg()
+        assert '…</text>' in svg
+        assert 'northern region' not in svg
+
     def test_to_svg_line(self):
         """Line SVG output."""
         state = Interpreter().eval("""
@@ -228,6 +237,14 @@ path "M10 10 L190 10 L100 180 Z"
         svg = state.to_svg()
         assert '<path' in svg
 
+    def test_to_svg_fill_rule(self):
+        """Fill rule reaches the path."""
+        state = Interpreter().eval("""
+path "M0 0 H20 V20 H0 Z M5 5 H15 V15 H5 Z"
+    fill-rule evenodd
+""")
+        assert 'fill-rule="evenodd"' in state.to_svg()
+
     def test_to_svg_polygon(self):
         """Polygon SVG output."""
         state = Interpreter().eval("""
@@ -249,6 +266,27 @@ rect 180x180 at 10,10
         assert '<defs>' in svg
         assert 'linearGradient' in svg
 
+    def test_to_svg_shared_gradient(self):
+        """Shared gradients span the union of their shapes in user space."""
+        state = Interpreter().eval("""
+rect at 10,10 size 20x20
+    gradient linear #000 #fff 0 shared
+rect at 50,30 size 20x20
+    gradient linear #000 #fff 0 shared
+""")
+        svg = state.to_svg()
+        assert svg.count('<linearGradient') == 1
+        assert 'gradientUnits="userSpaceOnUse" x1="10" y1="30" x2="70" y2="30"' in svg
+
+    def test_to_svg_percent_origin(self):
+        """Percent origins pivot on the shape's own bounds."""
+        state = Interpreter().eval("""
+rect at 20,20 size 40x20
+    rotate 45
+    origin 50% 50%
+""")
+        assert 'transform="rotate(45.0 40.0 30.0)"' in state.to_svg()
+
     def test_to_svg_shadow(self):
         """Shadow filter in SVG."""
         state = Interpreter().eval("""
@@ -363,6 +401,20 @@ stack gap 10 at 20,20
         assert '<svg' in svg
         assert svg.count('<rect') >= 3
 
+    def test_group_opacity_wraps_children(self):
+        """Group opacity becomes a single wrapping <g>."""
+        state = Interpreter().eval("""
+canvas massive
+group
+    opacity 0.5
+    rect 40x40 at 10,10
+    rect 40x40 at 30,30
+""")
+        svg = state.to_svg()
+        assert svg.count('<g opacity="0.5">') == 1
+        assert svg.count('opacity=') == 1
+        assert svg.index('<g opacity="0.5">') < svg.index('<rect x=') < svg.index('</g>')
+
     def test_row_layout(self):
         """Horizontal row layout."""
         state = Interpreter().eval("""
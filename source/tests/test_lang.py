@@ -42,6 +42,25 @@ class TestLexer:
         assert tokens[1].value == "Hello World"
 
 
+class TestTokenPython:
+    """Token/TokenType ergonomics for debugging lexer output."""
+
+    def test_token_repr(self):
+        tokens = rust.Lexer('rect at 1,2 #f00').py_tokenize()
+        assert repr(tokens[0]) == 'Token(Ident "rect" @1:1)'
+        assert repr(tokens[2]) == 'Token(Pair 1,2 @1:9)'
+        assert repr(tokens[3].ttype) == "Color" and tokens[3].ttype.name == "Color"
+
+    def test_token_equality(self):
+        a = rust.Lexer("fill #fff").py_tokenize()
+        b = rust.Lexer("fill #fff").py_tokenize()
+        assert a[1] == b[1] and hash(a[1]) == hash(b[1])
+        assert a[0] != a[1]
+        assert a[0] != rust.Lexer("rect fill").py_tokenize()[1]  # same text, different column
+        assert len({t.ttype for t in a + b}) == len({t.ttype for t in a})
+        assert {rust.TokenType.Ident: "word"}[a[0].ttype] == "word"
+
+
 class TestParser:
     """Test Rust parser via Python bindings."""
     
@@ -86,6 +105,16 @@ class TestParser:
         assert shape['style']['stroke_width'] == 2.0
         assert shape['style']['corner'] == 8.0
 
+    def test_parse_stack_layout(self):
+        ast = self._parse("stack gap 12 justify space-between align center width 50%")
+        props = ast['Scene'][0]['Shape']['props']
+        layout = props['_layout']
+        assert layout['gap'] == {'type': 'px', 'value': 12.0}
+        assert layout['justify'] == "space-between"
+        assert layout['align'] == "center"
+        assert layout['padding'] is None and layout['constraints'] == []
+        assert props['width'] == {'type': 'percent', 'value': 50.0}
+
 
 class TestInterpreter:
     """Test full evaluation pipeline."""
@@ -0,0 +1,102 @@
+"""Tests for walking and editing a Rust Scene from Python."""
+
+import pytest
+
+rust = pytest.importorskip("iconoglott_core")
+
+
+@pytest.fixture
+def scene():
+    s = rust.Scene(rust.CanvasSize.Medium, "#fff")
+    s.add_rect(rust.Rect(0, 0, 10, 10, 0, rust.Style(fill="#000")))
+    s.add_image(rust.Image(0, 0, 10, 10, "a.png"))
+    s.add_node(rust.Node("start", label="Start", style=rust.Style(fill="#eee")))
+    return s
+
+
+class TestSceneElements:
+    def test_elements_in_order(self, scene):
+        assert [e.kind for e in scene.elements()] == ["rect", "image", "node"]
+
+    def test_style_edit_rerenders(self, scene):
+        rect = scene.elements()[0]
+        style = rect.style
+        style.fill = "#e94560"
+        rect.style = style
+        assert 'fill="#e94560"' in scene.to_svg()
+        assert 'fill="#000"' not in rect.to_svg()
+
+    def test_unstyled_element(self, scene):
+        image = scene.elements()[1]
+        assert image.style is None
+        with pytest.raises(ValueError):
+            image.style = rust.Style()
+
+    def test_visibility_toggle(self, scene):
+        image = scene.elements()[1]
+        assert image.visible
+        image.visible = False
+        assert 'display="none"' in image.to_svg()
+        assert len(scene.elements()) == 3
+        image.visible = True
+        assert "display" not in scene.to_svg()
+
+    def test_layer_toggle(self, scene):
+        style = rust.Style(fill="#f00")
+        style.layer = "fg"
+        scene.add_circle(rust.Circle(5, 5, 2, style))
+        assert scene.layers() == ["fg"]
+        assert '<g id="layer-fg">' in scene.to_svg()
+        scene.set_layer_visible("fg", False)
+        assert "<circle" not in scene.to_svg()
+        assert len(scene.elements()) == 4
+
+    def test_ordered_elements_follow_z(self):
+        s = rust.Scene(rust.CanvasSize.Medium, "#fff")
+        for x, z in [(0, 0), (10, 0), (20, 0)]:
+            style = rust.Style(fill="#000")
+            style.z = z
+            s.add_rect(rust.Rect(x, 0, 5, 5, 0, style))
+        # Equal z keeps declaration order
+        assert [e.to_svg() for e in s.ordered_elements()] == [e.to_svg() for e in s.elements()]
+        first = s.elements()[0]
+        style = first.style
+        style.z = 1
+        first.style = style
+        order = [e.to_svg() for e in s.ordered_elements()]
+        assert 'x="10"' in order[0] and 'x="20"' in order[1] and 'x="0"' in order[2]
+        assert s.to_svg().index('x="20"') < s.to_svg().index('x="0"')
+
+    def test_find_by_id(self, scene):
+        node = scene.find_by_id("start")
+        assert node.kind == "node" and node.id == "start"
+        node.id = "begin"
+        assert scene.find_by_id("start") is None
+        assert scene.find_by_id("begin").style.fill == "#eee"
+
+    def test_stale_handle(self, scene):
+        rect = scene.elements()[0]
+        scene.clear()
+        with pytest.raises(IndexError):
+            rect.style
+
+
+class TestCompile:
+    def test_compile_rect(self):
+        svg = rust.compile("canvas medium\nrect at 0,0 size 10x10 #f00")
+        assert 'width="64"' in svg and 'fill="#f00"' in svg
+
+    def test_size_override(self):
+        assert 'width="48"' in rust.compile("rect size 10x10", "small")
+        with pytest.raises(ValueError):
+            rust.compile("rect", "bogus")
+
+    def test_diagnostics(self):
+        svg, errors = rust.compile_with_diagnostics("foobar\nrect size 10x10")
+        assert "<rect" in svg
+        assert [e.code for e in errors] == ["E002"]
+
+    def test_validate(self):
+        errors = rust.validate('$accent = #f00\nuse "star" at 10,10')
+        assert [(e.code, e.severity) for e in errors] == [("E014", rust.ErrorSeverity.Hint), ("E011", rust.ErrorSeverity.Error)]
+        assert rust.validate("rect size 10x10") == []